@@ -2,7 +2,8 @@ use crate::utils::MUTEZ_PER_TEZ;
 use crate::{
     config::{Account, Config, NetworkName, SmartFunction, User},
     error::{bail_user_error, user_error, Result},
-    utils::AddressOrAlias,
+    signer::{self, EncryptedSecretKey, ExternalSigner, KeySource},
+    utils::{read_file_or_input_or_piped, AddressOrAlias},
 };
 use anyhow::Context;
 use bip39::{Language, Mnemonic};
@@ -11,9 +12,11 @@ use dialoguer::{Confirm, Input};
 use jstz_crypto::hash::Hash;
 use jstz_crypto::keypair_from_secret_key;
 use jstz_crypto::smart_function_hash::SmartFunctionHash;
-use jstz_crypto::{keypair_from_mnemonic, public_key_hash::PublicKeyHash};
+use jstz_crypto::{
+    keypair_from_mnemonic, public_key_hash::PublicKeyHash, signature::Signature,
+};
 use log::{debug, info, warn};
-use std::collections::hash_map::Entry;
+use url::Url;
 
 fn generate_mnemonic() -> String {
     // unwrap is okay here because we are using a fixed value for word count and it's always
@@ -31,8 +34,8 @@ impl User {
 
         Ok(Self {
             address,
-            secret_key: sk,
             public_key: pk,
+            key: KeySource::Plain { secret_key: sk },
         })
     }
 
@@ -42,14 +45,85 @@ impl User {
         Ok(Self {
             public_key,
             address: PublicKeyHash::from_base58(&address)?,
-            secret_key,
+            key: KeySource::Plain { secret_key },
         })
     }
+
+    pub async fn from_external_signer(
+        signer: ExternalSigner,
+        address: Option<PublicKeyHash>,
+    ) -> Result<Self> {
+        let public_key = signer.public_key(address.as_ref()).await?;
+        let derived_address = PublicKeyHash::from(&public_key);
+        if let Some(address) = address {
+            if address != derived_address {
+                bail_user_error!(
+                    "The signer '{}' returned the public key of {} instead of {}.",
+                    signer,
+                    derived_address,
+                    address
+                );
+            }
+        }
+
+        Ok(Self {
+            address: derived_address,
+            public_key,
+            key: KeySource::External { signer },
+        })
+    }
+
+    /// Encrypts the plain text secret key of the user with `password`.
+    pub fn encrypt(&mut self, password: &str) -> Result<()> {
+        let KeySource::Plain { secret_key } = &self.key else {
+            bail_user_error!(
+                "The secret key of {} is not stored in plain text.",
+                self.address
+            );
+        };
+
+        self.key = KeySource::Encrypted {
+            encrypted_secret_key: EncryptedSecretKey::encrypt(
+                secret_key,
+                &self.address,
+                password,
+            )?,
+        };
+
+        Ok(())
+    }
+
+    pub async fn sign(&self, message: impl AsRef<[u8]>) -> Result<Signature> {
+        self.key
+            .sign(&self.address, &self.public_key, message.as_ref())
+            .await
+    }
+}
+
+fn warn_if_plain(alias: &str, user: &User) {
+    if let KeySource::Plain { .. } = user.key {
+        warn!("The secret key of '{alias}' is stored unencrypted. Run `jstz account encrypt {alias}` to protect it with a password.");
+    }
+}
+
+/// Encrypts the secret key of a new account unless the user opted out
+fn protect_new_key(alias: &str, user: &mut User, encrypt: bool) -> Result<()> {
+    if encrypt {
+        user.encrypt(&signer::new_password()?)?;
+    }
+    warn_if_plain(alias, user);
+    Ok(())
 }
 
-async fn add_smart_function(alias: String, address: SmartFunctionHash) -> Result<()> {
+async fn add_smart_function(
+    alias: String,
+    address: SmartFunctionHash,
+    network: Option<NetworkName>,
+) -> Result<()> {
     let mut cfg = Config::load().await?;
-    if cfg.accounts.contains(&alias) {
+    // Smart functions only exist on the network they were deployed to
+    let network = Some(cfg.network_name(&network)?);
+    if cfg.accounts(&network).contains(&alias) {
         bail_user_error!(
             "The smart function '{}' already exists. Please choose another name.",
             alias
@@ -57,20 +131,42 @@ async fn add_smart_function(alias: String, address: SmartFunctionHash) -> Result
     }
 
     info!("Added smart function: {} -> {}", alias, address);
-    cfg.accounts.insert(alias, SmartFunction { address });
+    cfg.accounts_mut(&network)
+        .insert(alias, SmartFunction { address });
 
     cfg.save()?;
 
     Ok(())
 }
 
-async fn create_account(alias: String, force: bool) -> Result<()> {
+/// Adds `user` to the accounts shared by all networks, since keys are not tied to a
+/// network, or to the accounts of `network` if it is given explicitly.
+fn insert_user(
+    cfg: &mut Config,
+    network: &Option<NetworkName>,
+    alias: String,
+    user: User,
+) {
+    let accounts = match network {
+        Some(_) => cfg.accounts_mut(network),
+        None => &mut cfg.shared_accounts,
+    };
+    accounts.insert(alias, user);
+}
+
+async fn create_account(
+    alias: String,
+    force: bool,
+    encrypt: bool,
+    network: Option<NetworkName>,
+) -> Result<()> {
     let mut cfg = Config::load().await?;
-    check_alias_uniqueness(&cfg, &alias, force)?;
+    check_alias_uniqueness(&cfg, &network, &alias, force)?;
 
-    let user = _create_account()?;
+    let mut user = _create_account()?;
+    protect_new_key(&alias, &mut user, encrypt)?;
 
-    cfg.accounts.insert(alias, user);
+    insert_user(&mut cfg, &network, alias, user);
     cfg.save()?;
 
     Ok(())
@@ -92,8 +188,13 @@ fn _create_account() -> Result<User> {
     Ok(user)
 }
 
-fn check_alias_uniqueness(cfg: &Config, alias: &str, force: bool) -> Result<()> {
-    if cfg.accounts.contains(alias) && !force {
+fn check_alias_uniqueness(
+    cfg: &Config,
+    network: &Option<NetworkName>,
+    alias: &str,
+    force: bool,
+) -> Result<()> {
+    if cfg.accounts(network).contains(alias) && !force {
         bail_user_error!(
             "The account '{}' already exists. Please choose another name or specify the `--force` flag to overwrite the account.",
             alias
@@ -102,9 +203,25 @@ fn check_alias_uniqueness(cfg: &Config, alias: &str, force: bool) -> Result<()>
     Ok(())
 }
 
-async fn import_account(alias: String, force: bool) -> Result<()> {
+async fn import_account(
+    alias: String,
+    force: bool,
+    encrypt: bool,
+    remote_signer: Option<Url>,
+    signer_command: Option<String>,
+    network: Option<NetworkName>,
+) -> Result<()> {
     let mut cfg = Config::load().await?;
-    check_alias_uniqueness(&cfg, &alias, force)?;
+    check_alias_uniqueness(&cfg, &network, &alias, force)?;
+
+    if let Some(uri) = remote_signer {
+        let (signer, address) = ExternalSigner::from_remote_uri(&uri)?;
+        return import_external_signer(cfg, network, alias, signer, Some(address)).await;
+    }
+    if let Some(command_line) = signer_command {
+        let signer = ExternalSigner::from_command_line(&command_line)?;
+        return import_external_signer(cfg, network, alias, signer, None).await;
+    }
 
     let secret_key_str: String = Input::new()
         .with_prompt("Enter the secret key of your account")
@@ -117,26 +234,63 @@ async fn import_account(alias: String, force: bool) -> Result<()> {
 
     // Only from secret keys for now
     // https://linear.app/tezos/issue/JSTZ-494/figure-out-how-to-align-differences-in-key-derivation
-    let user =
+    let mut user =
         User::from_secret_key(&secret_key_str).context("Failed to process secret key")?;
+    protect_new_key(&alias, &mut user, encrypt)?;
 
     debug!("User imported: {:?}", user);
     info!("User {} imported with address: {}", alias, user.address);
 
-    cfg.accounts.insert(alias, user);
+    insert_user(&mut cfg, &network, alias, user);
     cfg.save()?;
 
     Ok(())
 }
 
-async fn delete_account(alias: String) -> Result<()> {
+async fn import_external_signer(
+    mut cfg: Config,
+    network: Option<NetworkName>,
+    alias: String,
+    signer: ExternalSigner,
+    address: Option<PublicKeyHash>,
+) -> Result<()> {
+    let user = User::from_external_signer(signer, address).await?;
+
+    debug!("User imported: {:?}", user);
+    info!("User {} imported with address: {}", alias, user.address);
+
+    insert_user(&mut cfg, &network, alias, user);
+    cfg.save()?;
+
+    Ok(())
+}
+
+async fn encrypt_account(alias: String, network: Option<NetworkName>) -> Result<()> {
+    let mut cfg = Config::load().await?;
+
+    let user = match cfg.account_mut(&network, &alias) {
+        Some(Account::User(user)) => user,
+        Some(Account::SmartFunction(_)) => {
+            bail_user_error!("Cannot encrypt '{}', it is a smart function.", alias)
+        }
+        None => bail_user_error!("The account '{}' does not exist.", alias),
+    };
+
+    user.encrypt(&signer::new_password()?)?;
+    cfg.save()?;
+
+    info!("The secret key of '{}' is now encrypted.", alias);
+    Ok(())
+}
+
+async fn delete_account(alias: String, network: Option<NetworkName>) -> Result<()> {
     let mut cfg = Config::load().await?;
 
-    if !cfg.accounts.contains(&alias) {
+    if !cfg.accounts(&network).contains(&alias) {
         bail_user_error!("The account '{}' does not exist.", alias);
     }
 
-    if cfg.accounts.current_alias() == Some(&alias) {
+    if cfg.accounts(&network).current_alias() == Some(&alias) {
         warn!("You are currently logged into the account: {}.", alias);
     }
 
@@ -148,17 +302,21 @@ async fn delete_account(alias: String) -> Result<()> {
         bail_user_error!("Account deletion aborted.");
     }
 
-    cfg.accounts.remove(&alias);
+    cfg.remove_account(&network, &alias);
     cfg.save()?;
 
     info!("Account '{}' successfully deleted.", alias);
     Ok(())
 }
 
-pub async fn login(alias: String) -> Result<()> {
+pub async fn login(
+    alias: String,
+    encrypt: bool,
+    network: Option<NetworkName>,
+) -> Result<()> {
     let mut cfg = Config::load().await?;
 
-    if cfg.accounts.current_alias().is_some()
+    if cfg.accounts(&network).current_alias().is_some()
         && !Confirm::new()
             .with_prompt(format!(
                 "You are already logged in. Do you want to logout and login in to {alias}?"
@@ -169,9 +327,9 @@ pub async fn login(alias: String) -> Result<()> {
         bail_user_error!("Login aborted");
     }
 
-    let account: &Account = match cfg.accounts.entry(alias.clone()) {
-        Entry::Occupied(entry) => entry.into_mut(),
-        Entry::Vacant(entry) => {
+    let account: Account = match cfg.accounts(&network).get(&alias) {
+        Some(account) => account.clone(),
+        None => {
             if !Confirm::new()
                 .with_prompt("Account not found. Do you want to create it?")
                 .interact()?
@@ -179,8 +337,10 @@ pub async fn login(alias: String) -> Result<()> {
                 bail_user_error!("Login aborted");
             }
 
-            let user = _create_account()?;
-            entry.insert(user.into())
+            let mut user = _create_account()?;
+            protect_new_key(&alias, &mut user, encrypt)?;
+            insert_user(&mut cfg, &network, alias.clone(), user.clone());
+            user.into()
         }
     };
 
@@ -196,7 +356,7 @@ pub async fn login(alias: String) -> Result<()> {
                 alias, user.address
             );
 
-            cfg.accounts.set_current_alias(Some(alias))?;
+            cfg.set_current_alias(&network, Some(alias))?;
             cfg.save()?;
 
             Ok(())
@@ -204,26 +364,26 @@ pub async fn login(alias: String) -> Result<()> {
     }
 }
 
-pub async fn login_quick(cfg: &mut Config) -> Result<()> {
-    if cfg.accounts.current_user().is_none() {
+pub async fn login_quick(cfg: &mut Config, network: &Option<NetworkName>) -> Result<()> {
+    if cfg.accounts(network).current_user().is_none() {
         let account_alias: String = Input::new()
                 .with_prompt("You are not logged in. Please type the account name that you want to log into or create as new")
                 .interact()?;
 
-        login(account_alias).await?;
+        login(account_alias, true, network.clone()).await?;
         info!("");
     }
     Ok(())
 }
 
-pub async fn logout() -> Result<()> {
+pub async fn logout(network: Option<NetworkName>) -> Result<()> {
     let mut cfg = Config::load().await?;
 
-    if cfg.accounts.current_alias().is_none() {
+    if cfg.accounts(&network).current_alias().is_none() {
         bail_user_error!("You are not logged in. Please run `jstz login`.");
     }
 
-    cfg.accounts.set_current_alias(None)?;
+    cfg.set_current_alias(&network, None)?;
     cfg.save()?;
 
     info!("You have been logged out.");
@@ -231,10 +391,10 @@ pub async fn logout() -> Result<()> {
     Ok(())
 }
 
-pub async fn whoami() -> Result<()> {
+pub async fn whoami(network: Option<NetworkName>) -> Result<()> {
     let cfg = Config::load().await?;
 
-    let (alias, user) = cfg.accounts.current_user().ok_or(user_error!(
+    let (alias, user) = cfg.accounts(&network).current_user().ok_or(user_error!(
         "You are not logged in. Please run `jstz login`."
     ))?;
 
@@ -248,32 +408,48 @@ pub async fn whoami() -> Result<()> {
     Ok(())
 }
 
-async fn list_accounts(long: bool) -> Result<()> {
-    let cfg = Config::load().await?;
-
-    info!("Accounts:");
-    for (alias, account) in cfg.accounts.iter() {
-        if long {
-            info!("Alias: {}", alias);
-            match account {
-                Account::User(User {
-                    address,
-                    secret_key,
-                    public_key,
-                }) => {
-                    info!("  Type: User");
-                    info!("  Address: {}", address);
-                    info!("  Public Key: {}", public_key.to_string());
-                    info!("  Secret Key: {}", secret_key.to_string());
-                }
-                Account::SmartFunction(SmartFunction { address, .. }) => {
-                    info!("  Type: Smart Function");
-                    info!("  Address: {}", address);
+fn print_account(alias: &str, account: &Account, long: bool) {
+    if long {
+        info!("Alias: {}", alias);
+        match account {
+            Account::User(User {
+                address,
+                public_key,
+                key,
+            }) => {
+                info!("  Type: User");
+                info!("  Address: {}", address);
+                info!("  Public Key: {}", public_key.to_string());
+                match key {
+                    KeySource::Plain { .. } => info!("  Secret Key: <unencrypted>"),
+                    KeySource::Encrypted { .. } => info!("  Secret Key: <encrypted>"),
+                    KeySource::External { signer } => info!("  Signer: {}", signer),
                 }
             }
-        } else {
-            info!("{}: {}", alias, account.address());
+            Account::SmartFunction(SmartFunction { address, .. }) => {
+                info!("  Type: Smart Function");
+                info!("  Address: {}", address);
+            }
         }
+    } else {
+        info!("{}: {}", alias, account.address());
+    }
+}
+
+async fn list_accounts(long: bool, network: Option<NetworkName>) -> Result<()> {
+    let cfg = Config::load().await?;
+    let accounts = cfg.accounts(&network);
+
+    if let Ok(network) = cfg.network_name(&network) {
+        info!("Accounts on network '{}':", network);
+        for (alias, account) in accounts.network().iter().flat_map(|a| a.iter()) {
+            print_account(alias, account, long);
+        }
+    }
+
+    info!("Shared accounts:");
+    for (alias, account) in accounts.shared() {
+        print_account(alias, account, long);
     }
 
     Ok(())
@@ -287,7 +463,7 @@ async fn get_code(
 
     debug!("Getting code.. {:?}.", network);
 
-    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg)?;
+    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg, &network)?;
     let sf_address = address
         .as_smart_function()
         .ok_or(user_error!("Address is not a smart function"))?;
@@ -309,7 +485,7 @@ async fn get_balance(
 ) -> Result<()> {
     let cfg = Config::load().await?;
 
-    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg)?;
+    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg, &network)?;
     debug!("resolved `account` -> {:?}", address);
 
     let balance = cfg.jstz_client(&network)?.get_balance(&address).await?;
@@ -330,27 +506,67 @@ pub enum Command {
         /// Overwrites an existing alias.
         #[arg(short, long)]
         force: bool,
+        /// Stores the secret key unencrypted instead of encrypting it with a password.
+        #[arg(long)]
+        no_encrypt: bool,
+        /// Adds the account to the accounts of the network from the config file instead of the
+        /// accounts shared by all networks. Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
-    /// 📥 Imports a user account from a secret key.
+    /// 📥 Imports a user account from a secret key or an external signer.
     Import {
         #[arg(value_name = "ALIAS")]
         alias: String,
         /// Overwrites an existing alias.
         #[arg(short, long)]
         force: bool,
+        /// Stores the secret key unencrypted instead of encrypting it with a password.
+        #[arg(long, conflicts_with_all = ["remote_signer", "signer_command"])]
+        no_encrypt: bool,
+        /// Delegates signing to an `octez-signer` compatible remote signer,
+        /// e.g. `http://localhost:6732/tz1...`.
+        #[arg(long, value_name = "URI", conflicts_with = "signer_command")]
+        remote_signer: Option<Url>,
+        /// Delegates signing to an external program, invoked as `<COMMAND> public-key`
+        /// and `<COMMAND> sign <HEX BYTES>`.
+        #[arg(long, value_name = "COMMAND")]
+        signer_command: Option<String>,
+        /// Adds the account to the accounts of the network from the config file instead of the
+        /// accounts shared by all networks. Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
+    /// 🔒 Encrypts the secret key of a user account with a password.
+    Encrypt {
+        /// User alias.
+        #[arg(value_name = "ALIAS")]
+        alias: String,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
     /// ❌ Deletes an account (user or smart function).
     Delete {
         /// User or smart function alias to be deleted.
         #[arg(value_name = "ALIAS")]
         alias: String,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
-    /// 📋 Lists all accounts.
+    /// 📋 Lists the accounts of a network and the shared accounts.
     #[clap(alias = "ls")]
     List {
         /// Flag for long format output.
         #[arg(short, long)]
         long: bool,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
     /// 💻 Outputs the deployed code for an account.
     Code {
@@ -372,6 +588,26 @@ pub enum Command {
         #[arg(short, long, default_value = None)]
         network: Option<NetworkName>,
     },
+    /// 📍 Outputs the address of an account, or predicts the address of a smart function
+    /// deployed with `jstz deploy --salt`.
+    Address {
+        /// Address or alias of the account, or of the deployer with `--predict`.
+        #[arg(short, long, value_name = "ALIAS|ADDRESS")]
+        account: Option<AddressOrAlias>,
+        /// Predicts the address of the smart function deployed with the given salt and code.
+        #[arg(long, requires = "salt")]
+        predict: bool,
+        /// Salt of the deployment.
+        #[arg(long, requires = "predict", default_value = None)]
+        salt: Option<String>,
+        /// Function code of the deployment.
+        #[arg(value_name = "CODE|PATH", requires = "predict", default_value = None, value_hint = clap::ValueHint::FilePath)]
+        code: Option<String>,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
     /// 🔄 Creates alias for a deployed smart function.
     Alias {
         /// Alias of the smart function.
@@ -380,18 +616,84 @@ pub enum Command {
         /// Address of the smart function.
         #[arg(value_name = "KT1 ADDRESS")]
         address: SmartFunctionHash,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
 }
 
+async fn get_address(
+    account: Option<AddressOrAlias>,
+    predict: bool,
+    salt: Option<String>,
+    code: Option<String>,
+    network: Option<NetworkName>,
+) -> Result<()> {
+    let cfg = Config::load().await?;
+
+    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg, &network)?;
+    debug!("resolved `account` -> {:?}", address);
+    if !predict {
+        info!("{}", address);
+        return Ok(());
+    }
+
+    let salt = salt.ok_or(user_error!("No salt supplied. Please provide `--salt`."))?;
+    let code = read_file_or_input_or_piped(code)?
+        .ok_or(user_error!("No function code supplied. Please provide a filename or pipe the file contents into stdin."))?;
+    let predicted =
+        jstz_proto::context::account::Account::salted_address(&address, &salt, &code)
+            .map_err(|e| user_error!("{}", e))?;
+
+    info!("{}", predicted);
+
+    Ok(())
+}
+
 pub async fn exec(command: Command) -> Result<()> {
     match command {
-        Command::Alias { alias, address } => add_smart_function(alias, address).await,
-        Command::Import { alias, force } => import_account(alias, force).await,
-        Command::Create { alias, force } => create_account(alias, force).await,
-        Command::Delete { alias } => delete_account(alias).await,
-        Command::List { long } => list_accounts(long).await,
+        Command::Alias {
+            alias,
+            address,
+            network,
+        } => add_smart_function(alias, address, network).await,
+        Command::Import {
+            alias,
+            force,
+            no_encrypt,
+            remote_signer,
+            signer_command,
+            network,
+        } => {
+            import_account(
+                alias,
+                force,
+                !no_encrypt,
+                remote_signer,
+                signer_command,
+                network,
+            )
+            .await
+        }
+        Command::Create {
+            alias,
+            force,
+            no_encrypt,
+            network,
+        } => create_account(alias, force, !no_encrypt, network).await,
+        Command::Encrypt { alias, network } => encrypt_account(alias, network).await,
+        Command::Delete { alias, network } => delete_account(alias, network).await,
+        Command::List { long, network } => list_accounts(long, network).await,
         Command::Code { account, network } => get_code(account, network).await,
         Command::Balance { account, network } => get_balance(account, network).await,
+        Command::Address {
+            account,
+            predict,
+            salt,
+            code,
+            network,
+        } => get_address(account, predict, salt, code, network).await,
     }
 }
 
@@ -0,0 +1,275 @@
+//! Agent keeping unlocked secret keys in memory across `jstz` invocations.
+//!
+//! Like `ssh-agent`, the agent is a background `jstz` process listening on a
+//! Unix socket in a directory of the jstz home directory that only the user can
+//! access. The agent signs messages with the keys it holds but never hands them
+//! out. Keys are forgotten [`UNLOCK_TIMEOUT`] after they were unlocked and the
+//! agent exits once it has held no keys for [`IDLE_TIMEOUT`].
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+        process::CommandExt,
+    },
+    path::PathBuf,
+    process::{Command as ProcessCommand, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use jstz_crypto::{
+    public_key_hash::PublicKeyHash, secret_key::SecretKey, signature::Signature,
+};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as};
+
+use crate::{
+    config::jstz_home_dir,
+    error::{anyhow, Result},
+    signer::UNLOCK_TIMEOUT,
+};
+
+/// How long the agent keeps running without holding any key.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SOCKET_DIR: &str = "agent";
+const SOCKET_FILE: &str = "agent.sock";
+const START_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a client may take to send a request or read the response. The agent
+/// serves one request at a time, so a stalled client must not block it.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "request", rename_all = "snake_case")]
+enum Request {
+    Sign {
+        address: PublicKeyHash,
+        #[serde_as(as = "Base64")]
+        message: Vec<u8>,
+    },
+    Add {
+        address: PublicKeyHash,
+        secret_key: SecretKey,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Response {
+    signature: Option<Signature>,
+}
+
+fn socket_dir() -> PathBuf {
+    jstz_home_dir().join(SOCKET_DIR)
+}
+
+fn socket_path() -> PathBuf {
+    socket_dir().join(SOCKET_FILE)
+}
+
+fn connect() -> Result<UnixStream> {
+    let stream = UnixStream::connect(socket_path())?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    Ok(stream)
+}
+
+fn send(request: &Request) -> Result<Response> {
+    let mut stream = connect()?;
+    stream.write_all(&serde_json::to_vec(request)?)?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(serde_json::from_slice(&response)?)
+}
+
+/// Signs `message` with the secret key of `address` if the agent holds it.
+pub fn sign(address: &PublicKeyHash, message: &[u8]) -> Option<Signature> {
+    send(&Request::Sign {
+        address: address.clone(),
+        message: message.to_vec(),
+    })
+    .map_err(|e| debug!("Failed to query the key agent: {e}"))
+    .ok()
+    .and_then(|response| response.signature)
+}
+
+/// Hands the unlocked secret key of `address` to the agent, starting the agent
+/// if it is not running. Failures only mean that the password is asked again.
+pub fn add(address: &PublicKeyHash, secret_key: &SecretKey) {
+    let request = Request::Add {
+        address: address.clone(),
+        secret_key: secret_key.clone(),
+    };
+    if send(&request).is_ok() {
+        return;
+    }
+    if let Err(e) = start().and_then(|_| send(&request)) {
+        debug!("Failed to hand the key to the key agent: {e}");
+    }
+}
+
+fn start() -> Result<()> {
+    ProcessCommand::new(std::env::current_exe()?)
+        .arg("key-agent")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Keep the agent out of the terminal's process group so that Ctrl-C
+        // in the shell does not stop it
+        .process_group(0)
+        .spawn()?;
+
+    let started_at = Instant::now();
+    while started_at.elapsed() < START_TIMEOUT {
+        if UnixStream::connect(socket_path()).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    Err(anyhow!("The key agent did not start"))
+}
+
+type Keys = Arc<Mutex<HashMap<PublicKeyHash, (SecretKey, Instant)>>>;
+
+/// Runs the agent until it has held no keys for [`IDLE_TIMEOUT`].
+pub fn run() -> Result<()> {
+    let path = socket_path();
+    if UnixStream::connect(&path).is_ok() {
+        // Another agent is already running
+        return Ok(());
+    }
+    // Remove the socket of an agent that did not exit cleanly
+    let _ = std::fs::remove_file(&path);
+    // The socket is only reachable through a directory that only the user can
+    // access, so it is never exposed between `bind` and setting its permissions
+    let dir = socket_dir();
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let keys = Keys::default();
+    let last_held = Arc::new(Mutex::new(Instant::now()));
+    {
+        let keys = keys.clone();
+        let last_held = last_held.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            let mut keys = keys.lock().unwrap();
+            keys.retain(|_, (_, unlocked_at)| unlocked_at.elapsed() < UNLOCK_TIMEOUT);
+            let mut last_held = last_held.lock().unwrap();
+            if !keys.is_empty() {
+                *last_held = Instant::now();
+            } else if last_held.elapsed() >= IDLE_TIMEOUT {
+                let _ = std::fs::remove_file(socket_path());
+                std::process::exit(0);
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let timeouts = stream
+            .set_read_timeout(Some(REQUEST_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(REQUEST_TIMEOUT)));
+        if let Err(e) = timeouts {
+            debug!("Failed to set the timeouts of a key agent request: {e}");
+            continue;
+        }
+        if let Err(e) = handle(&keys, &mut stream) {
+            debug!("Failed to handle a key agent request: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn handle(keys: &Keys, stream: &mut UnixStream) -> Result<()> {
+    let mut request = Vec::new();
+    stream.read_to_end(&mut request)?;
+    let mut keys = keys
+        .lock()
+        .map_err(|_| anyhow!("Key agent cache is poisoned"))?;
+
+    let response = match serde_json::from_slice(&request)? {
+        Request::Sign { address, message } => Response {
+            signature: keys
+                .get(&address)
+                .filter(|(_, unlocked_at)| unlocked_at.elapsed() < UNLOCK_TIMEOUT)
+                .map(|(secret_key, _)| secret_key.sign(&message))
+                .transpose()?,
+        },
+        Request::Add {
+            address,
+            secret_key,
+        } => {
+            keys.insert(address, (secret_key, Instant::now()));
+            Response::default()
+        }
+    };
+    stream.write_all(&serde_json::to_vec(&response)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixStream, thread};
+
+    use jstz_crypto::{public_key_hash::PublicKeyHash, secret_key::SecretKey};
+
+    use super::{handle, Keys, Request, Response};
+
+    const SECRET_KEY: &str = "edsk3AbxMYLgdY71xPEjWjXi5JCx6tSS8jhQ2mc1KczZ1JfPrTqSgM";
+
+    fn exchange(keys: &Keys, request: Request) -> Response {
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let keys = keys.clone();
+        let agent = thread::spawn(move || handle(&keys, &mut server).unwrap());
+        std::io::Write::write_all(&mut client, &serde_json::to_vec(&request).unwrap())
+            .unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = Vec::new();
+        std::io::Read::read_to_end(&mut client, &mut response).unwrap();
+        agent.join().unwrap();
+        serde_json::from_slice(&response).unwrap()
+    }
+
+    #[test]
+    fn agent_signs_with_added_keys() {
+        let keys = Keys::default();
+        let address =
+            PublicKeyHash::from_base58("tz1ficxJFv7MUtsCimF8bmT9SYPDok52ySg6").unwrap();
+        let secret_key = SecretKey::from_base58(SECRET_KEY).unwrap();
+        let sign = |keys: &Keys| {
+            exchange(
+                keys,
+                Request::Sign {
+                    address: address.clone(),
+                    message: b"message".to_vec(),
+                },
+            )
+        };
+
+        assert!(sign(&keys).signature.is_none());
+
+        exchange(
+            &keys,
+            Request::Add {
+                address: address.clone(),
+                secret_key: secret_key.clone(),
+            },
+        );
+        assert_eq!(
+            sign(&keys).signature,
+            Some(secret_key.sign(b"message").unwrap())
+        );
+    }
+}
@@ -109,7 +109,7 @@ impl DeployBridge {
 
         // 1. Resolve addresses
         let source = source.resolve_l1(&cfg, &network)?;
-        let jstz_fa_token_address = jstz_fa_token.resolve(&cfg)?;
+        let jstz_fa_token_address = jstz_fa_token.resolve(&cfg, &network)?;
         let fa_token_address = tezos_fa_token.resolve(&cfg, &network)?;
         let fa_token_object = FaToken::from(&fa_token_address, fa_token_id);
 
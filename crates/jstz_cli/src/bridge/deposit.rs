@@ -29,7 +29,7 @@ pub async fn exec(
         assert_sandbox_running(JSTZD_SERVER_BASE_URL).await?;
     }
 
-    let to_pkh = to.resolve(&cfg)?;
+    let to_pkh = to.resolve(&cfg, &network)?;
 
     // Check if trying to deposit to a bootsrap account.
     if let Some(bootstrap_account) = SANDBOX_BOOTSTRAP_ACCOUNTS
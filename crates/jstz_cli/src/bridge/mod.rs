@@ -3,6 +3,7 @@ use deploy::DeployBridge;
 
 pub mod deploy;
 mod deposit;
+mod outbox;
 mod withdraw;
 
 use crate::{
@@ -40,6 +41,22 @@ pub enum Command {
         /// The amount in XTZ to transfer.
         #[arg(short, long)]
         amount: Tez,
+        /// Wait until the withdrawal's commitment is cemented then execute the outbox message
+        /// on L1 with the receiver's account to complete the transfer.
+        #[arg(long)]
+        execute: bool,
+        /// Maximum fees in XTZ burnt to execute the withdrawal on L1, defaulting to octez-client's.
+        #[arg(long, requires = "execute", default_value = None)]
+        burn_cap: Option<Tez>,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
+    /// 📤 Shows the backlog of withdrawals waiting for space in the rollup outbox. The rollup
+    /// outbox holds a limited number of messages per level and withdrawals that do not fit
+    /// are written at a later level.
+    Outbox {
         /// Specifies the network from the config file, defaulting to the configured default network.
         /// Use `dev` for the local sandbox.
         #[arg(short, long, default_value = None)]
@@ -67,8 +84,11 @@ pub async fn exec(command: Command) -> Result<()> {
         Command::Withdraw {
             to,
             amount,
+            execute,
+            burn_cap,
             network,
-        } => withdraw::exec(to, amount, network).await,
+        } => withdraw::exec(to, amount, execute, burn_cap, network).await,
+        Command::Outbox { network } => outbox::exec(network).await,
         Command::FaDeploy(deploy) => {
             let _ = deploy.exec().await?;
             Ok(())
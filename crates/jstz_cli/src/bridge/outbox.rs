@@ -0,0 +1,75 @@
+use jstz_core::kv::outbox::OutboxQueueStatus;
+use log::info;
+
+use crate::{
+    config::{Config, NetworkName},
+    error::Result,
+    sandbox::{assert_sandbox_running, JSTZD_SERVER_BASE_URL},
+};
+
+pub async fn exec(network: Option<NetworkName>) -> Result<()> {
+    let cfg = Config::load().await?;
+    if cfg.network_name(&network)? == NetworkName::Dev {
+        assert_sandbox_running(JSTZD_SERVER_BASE_URL).await?;
+    }
+
+    let status = cfg.jstz_client(&network)?.get_outbox_queue_status().await?;
+    for line in describe(&status) {
+        info!("{}", line);
+    }
+    Ok(())
+}
+
+fn describe(status: &OutboxQueueStatus) -> Vec<String> {
+    if status.len == 0 {
+        return vec![
+            "The outbox queue is empty. New withdrawals are written to the rollup outbox at the level they are made."
+                .to_string(),
+        ];
+    }
+    let mut lines = vec![
+        format!("Pending outbox messages: {}/{}", status.len, status.max),
+        "Pending withdrawals have been debited on jstz and will be written to the rollup outbox at a later level."
+            .to_string(),
+    ];
+    if let Some(level) = status.oldest_pending_level {
+        lines.push(format!("Oldest pending message produced at level: {level}"));
+    }
+    if let Some(level) = status.estimated_flush_level {
+        lines.push(format!(
+            "Estimated to be written to the outbox by level: {level}"
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use jstz_core::kv::outbox::OutboxQueueStatus;
+
+    use super::describe;
+
+    #[test]
+    fn describe_outbox_queue_status() {
+        let mut status = OutboxQueueStatus {
+            len: 0,
+            max: 65535,
+            oldest_pending_level: None,
+            estimated_flush_level: None,
+        };
+        assert_eq!(describe(&status).len(), 1);
+
+        status.len = 120;
+        status.oldest_pending_level = Some(5);
+        status.estimated_flush_level = Some(7);
+        assert_eq!(
+            describe(&status),
+            vec![
+                "Pending outbox messages: 120/65535".to_string(),
+                "Pending withdrawals have been debited on jstz and will be written to the rollup outbox at a later level.".to_string(),
+                "Oldest pending message produced at level: 5".to_string(),
+                "Estimated to be written to the outbox by level: 7".to_string(),
+            ]
+        );
+    }
+}
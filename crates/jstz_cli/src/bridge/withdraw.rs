@@ -1,25 +1,38 @@
+use std::{ops::Deref, time::Duration};
+
 use crate::{
     config::{Config, NetworkName},
     error::{bail_user_error, Result},
+    jstz::JstzClient,
     run::{self, RunArgs},
     sandbox::{assert_sandbox_running, JSTZD_SERVER_BASE_URL},
     utils::{AddressOrAlias, Tez},
 };
 use anyhow::Context;
+use jstz_core::kv::outbox::OutboxProof;
 use jstz_crypto::{hash::Hash, public_key_hash::PublicKeyHash};
 use jstz_proto::context::account::{Address, Addressable};
-use log::debug;
+use log::{debug, info};
 use reqwest::StatusCode;
+use tokio::time::sleep;
+
+// Interval between checks for the cementation of the withdrawal's commitment
+const OUTBOX_PROOF_POLL_INTERVAL: Duration = Duration::from_secs(10);
+// Number of checks before giving up, about an hour at the poll interval
+const OUTBOX_PROOF_MAX_ATTEMPTS: u32 = 360;
 
 pub async fn exec(
     to: AddressOrAlias,
     amount: Tez,
+    execute: bool,
+    burn_cap: Option<Tez>,
     network: Option<NetworkName>,
 ) -> Result<()> {
     let cfg = Config::load().await?;
 
     // Check network
-    let receiver = if cfg.network_name(&network)? == NetworkName::Dev {
+    let use_sandbox = cfg.network_name(&network)? == NetworkName::Dev;
+    let receiver = if use_sandbox {
         assert_sandbox_running(JSTZD_SERVER_BASE_URL).await?;
         sandbox_resolve_l1(to, JSTZD_SERVER_BASE_URL).await?
     } else {
@@ -28,14 +41,107 @@ pub async fn exec(
 
     debug!("resolved `to` -> {}", &receiver.to_base58());
 
-    let amount = amount.to_mutez();
     let url = "jstz://jstz/withdraw".to_string();
     let http_method = "POST".to_string();
     let gas_limit = 10; // TODO: set proper gas limit
-    let withdraw = jstz_proto::executor::withdraw::Withdrawal { amount, receiver };
+    let withdraw = jstz_proto::executor::withdraw::Withdrawal {
+        amount: amount.to_mutez(),
+        receiver: receiver.clone(),
+    };
     let json_data = serde_json::to_string(&withdraw)?;
     let args = RunArgs::new(url, http_method, gas_limit);
-    run::exec(args.set_json_data(Some(json_data)).set_network(network)).await
+    let receipt = run::exec_with_receipt(
+        args.set_json_data(Some(json_data))
+            .set_network(network.clone()),
+    )
+    .await?;
+    let outbox_message_id = match receipt.withdrawals.first() {
+        Some(withdrawal) if withdrawal.outbox_message_id.is_none() => {
+            info!(
+                "The withdrawal could not be assigned a message in the rollup outbox of this level. It has been queued and will be written to the outbox at a later level. Run `jstz bridge outbox` to check the queue."
+            );
+            None
+        }
+        withdrawal => withdrawal.and_then(|withdrawal| withdrawal.outbox_message_id),
+    };
+    if !execute {
+        return Ok(());
+    }
+
+    let Some(id) = outbox_message_id else {
+        bail_user_error!(
+            "The withdrawal was deferred to a later level of the rollup outbox. It cannot be executed automatically."
+        );
+    };
+    info!(
+        "Waiting for the commitment of level {} to be cemented. This can take a while...",
+        id.level
+    );
+    let proof = wait_for_outbox_proof(
+        &cfg.jstz_client(&network)?,
+        id.level,
+        id.index,
+        OUTBOX_PROOF_MAX_ATTEMPTS,
+    )
+    .await?;
+    if use_sandbox {
+        exec_sandbox(JSTZD_SERVER_BASE_URL, &proof, burn_cap).await?;
+    } else {
+        cfg.octez_client(&network)?
+            .execute_outbox_message(
+                &proof.rollup_address,
+                &receiver.to_base58(),
+                &proof.commitment,
+                &proof.proof,
+                burn_cap.as_ref().map(Deref::deref),
+            )
+            .context("Failed to execute the withdrawal on L1")?;
+    }
+    info!("Executed the withdrawal of {} XTZ on L1", amount);
+    Ok(())
+}
+
+async fn wait_for_outbox_proof(
+    jstz_client: &JstzClient,
+    level: u32,
+    index: u32,
+    max_attempts: u32,
+) -> Result<OutboxProof> {
+    for attempt in 1..=max_attempts {
+        if let Some(proof) = jstz_client.get_outbox_proof(level, index).await? {
+            return Ok(proof);
+        }
+        if attempt < max_attempts {
+            sleep(OUTBOX_PROOF_POLL_INTERVAL).await;
+        }
+    }
+    bail_user_error!(
+        "The commitment of level {} is not cemented yet. Execute the withdrawal later with `octez-client execute outbox message`.",
+        level
+    )
+}
+
+async fn exec_sandbox(
+    jstzd_server_base_url: &str,
+    proof: &OutboxProof,
+    burn_cap: Option<Tez>,
+) -> Result<()> {
+    // go through jstzd server even when the sandbox is not in a container for simplicity
+    let client = reqwest::Client::new();
+    let burn_cap: Option<f64> = burn_cap.map(|v| v.to_string().parse().unwrap());
+    let res = client
+        .post(format!("{jstzd_server_base_url}/execute_outbox_message"))
+        .json(&serde_json::json!({
+            "commitment": proof.commitment,
+            "proof": proof.proof,
+            "burn_cap": burn_cap,
+        }))
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        bail_user_error!("Failed to execute the withdrawal on L1.");
+    }
+    Ok(())
 }
 
 async fn sandbox_resolve_l1(
@@ -71,10 +177,74 @@ async fn sandbox_resolve_l1(
 
 #[cfg(test)]
 mod tests {
-    use super::sandbox_resolve_l1;
-    use crate::utils::AddressOrAlias;
+    use super::{exec_sandbox, sandbox_resolve_l1, wait_for_outbox_proof};
+    use crate::{
+        jstz::JstzClient,
+        utils::{AddressOrAlias, Tez},
+    };
+    use jstz_core::kv::outbox::OutboxProof;
+    use rust_decimal::Decimal;
     use std::str::FromStr;
 
+    fn dummy_proof() -> OutboxProof {
+        OutboxProof {
+            rollup_address: "sr1PuFMgaRUN12rKQ3J2ae5psNtwCxPNmGNK".to_string(),
+            commitment: "src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu"
+                .to_string(),
+            proof: "0300".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn exec_sandbox_ok() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/execute_outbox_message")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "commitment": "src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu",
+                "proof": "0300",
+                "burn_cap": 1.5,
+            })))
+            .create();
+
+        assert!(exec_sandbox(
+            &server.url(),
+            &dummy_proof(),
+            Some(Tez::try_from(Decimal::new(15, 1)).unwrap())
+        )
+        .await
+        .is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn exec_sandbox_failed_to_send_request() {
+        assert_eq!(
+            exec_sandbox("bad url", &dummy_proof(), None)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "builder error: relative URL without a base"
+        );
+    }
+
+    #[tokio::test]
+    async fn exec_sandbox_bad_request() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/execute_outbox_message")
+            .with_status(400)
+            .create();
+
+        assert_eq!(
+            exec_sandbox(&server.url(), &dummy_proof(), None)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Failed to execute the withdrawal on L1."
+        );
+    }
+
     #[tokio::test]
     async fn sandbox_resolve_l1_address() {
         assert_eq!(
@@ -163,4 +333,55 @@ mod tests {
             "Failed to resolve L1 address aliases in the sandbox."
         );
     }
+
+    #[tokio::test]
+    async fn wait_for_outbox_proof_ok() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/outbox/12/3")
+            .with_body(r#"{"rollupAddress":"sr1PuFMgaRUN12rKQ3J2ae5psNtwCxPNmGNK","commitment":"src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu","proof":"0300"}"#)
+            .create();
+        assert_eq!(
+            wait_for_outbox_proof(&JstzClient::new(server.url()), 12, 3, 1)
+                .await
+                .unwrap(),
+            OutboxProof {
+                rollup_address: "sr1PuFMgaRUN12rKQ3J2ae5psNtwCxPNmGNK".to_string(),
+                commitment: "src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu"
+                    .to_string(),
+                proof: "0300".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_outbox_proof_err() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/outbox/12/3").with_status(400).create();
+        assert_eq!(
+            wait_for_outbox_proof(&JstzClient::new(server.url()), 12, 3, 1)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Failed to get outbox proof. Status: 400 Bad Request"
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_outbox_proof_gives_up() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/outbox/12/3")
+            .with_status(404)
+            .expect(1)
+            .create();
+        assert_eq!(
+            wait_for_outbox_proof(&JstzClient::new(server.url()), 12, 3, 1)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "The commitment of level 12 is not cemented yet. Execute the withdrawal later with `octez-client execute outbox message`."
+        );
+        mock.assert();
+    }
 }
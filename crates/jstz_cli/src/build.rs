@@ -0,0 +1,174 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use jstz_core::reveal_data::DEFAULT_MAX_REVEAL_SIZE;
+use jstz_proto::runtime::ParsedCode;
+use log::{debug, info, warn};
+
+use crate::{
+    config::{Config, NetworkName},
+    error::{bail_user_error, user_error, Result},
+    term::styles,
+};
+
+pub const DEFAULT_ENTRY: &str = "index.ts";
+pub const DEFAULT_OUTFILE: &str = "dist/index.js";
+
+pub async fn exec(
+    path: &Path,
+    entry: PathBuf,
+    outfile: PathBuf,
+    network: Option<NetworkName>,
+) -> Result<()> {
+    if !path.join(&entry).exists() {
+        bail_user_error!(
+            "Entry point '{}' not found. Please run `{}` to create a smart function project or specify the entry point with `--entry`.",
+            path.join(&entry).display(),
+            styles::command("jstz init")
+        );
+    }
+
+    bundle(path, &entry, &outfile)?;
+
+    let outfile = path.join(outfile);
+    let code = fs::read_to_string(&outfile)?;
+    let max_size = max_reveal_size(&network).await.unwrap_or_else(|e| {
+        warn!(
+            "Failed to get the reveal limit of the network, using the default limit: {e}"
+        );
+        DEFAULT_MAX_REVEAL_SIZE
+    });
+    let size = validate(code, max_size)?;
+
+    info!(
+        "Built {} ({}, {:.1}% of the reveal limit of {}).",
+        outfile.display(),
+        format_size(size),
+        size as f64 * 100.0 / max_size as f64,
+        format_size(max_size)
+    );
+    info!(
+        "Run `{}` to deploy it.",
+        styles::command(format!("jstz deploy {}", outfile.display()))
+    );
+
+    Ok(())
+}
+
+/// Bundles the entry point and its dependencies into a single ES module
+/// with the esbuild installed in the project.
+fn bundle(path: &Path, entry: &Path, outfile: &Path) -> Result<()> {
+    let mut command = Command::new("npx");
+    command
+        .current_dir(path)
+        .args(["--no-install", "esbuild"])
+        .arg(entry)
+        .args(["--bundle", "--format=esm", "--target=esnext", "--minify"])
+        .arg(format!("--outfile={}", outfile.display()));
+    debug!("Running {:?}", command);
+
+    let status = command.status().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => user_error!(
+            "`npx` not found. Please install Node.js to build smart functions."
+        ),
+        _ => e.into(),
+    })?;
+    if !status.success() {
+        bail_user_error!(
+            "Failed to bundle '{}'. If esbuild is missing, run `npm install` in '{}'.",
+            entry.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Maximum size of a smart function that can be deployed to the network, as set
+/// by its protocol parameters.
+async fn max_reveal_size(network: &Option<NetworkName>) -> Result<usize> {
+    let cfg = Config::load().await?;
+    let params = cfg.jstz_client(network)?.get_protocol_params().await?;
+    Ok(params.max_reveal_size())
+}
+
+/// Checks that the bundled code can be deployed as a smart function to a network
+/// with the reveal limit `max_size`, returning its size in bytes.
+fn validate(code: String, max_size: usize) -> Result<usize> {
+    let size = code.len();
+    if size > max_size {
+        bail_user_error!(
+            "The smart function is {}, which exceeds the maximum reveal size of {}.",
+            format_size(size),
+            format_size(max_size)
+        );
+    }
+    ParsedCode::try_from(code)
+        .map_err(|e| user_error!("The bundled smart function is invalid: {}", e))?;
+    Ok(size)
+}
+
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use jstz_core::reveal_data::MAX_REVEAL_SIZE;
+
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KB");
+        assert_eq!(format_size(DEFAULT_MAX_REVEAL_SIZE), "10.0 MB");
+        assert_eq!(format_size(MAX_REVEAL_SIZE), "128.0 MB");
+    }
+
+    #[test]
+    fn test_validate() {
+        let code = "export default (request) => new Response('Hello world!')";
+        assert_eq!(
+            validate(code.to_string(), DEFAULT_MAX_REVEAL_SIZE).unwrap(),
+            code.len()
+        );
+
+        let err = validate(
+            "export default (request) =>".to_string(),
+            DEFAULT_MAX_REVEAL_SIZE,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid"));
+
+        let err = validate(code.to_string(), code.len() - 1).unwrap_err();
+        assert!(err.to_string().contains("exceeds the maximum reveal size"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_entry_point() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = exec(
+            dir.path(),
+            PathBuf::from(DEFAULT_ENTRY),
+            PathBuf::from(DEFAULT_OUTFILE),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
@@ -1,6 +1,6 @@
 use derive_more::{From, TryInto};
 use jstz_crypto::{
-    public_key::PublicKey, public_key_hash::PublicKeyHash, secret_key::SecretKey,
+    public_key::PublicKey, public_key_hash::PublicKeyHash,
     smart_function_hash::SmartFunctionHash,
 };
 use jstz_proto::context::account::Address;
@@ -22,6 +22,7 @@ use crate::{
         JSTZD_SERVER_BASE_URL, SANDBOX_JSTZ_NODE_PORT, SANDBOX_LOCAL_HOST_ADDR,
         SANDBOX_OCTEZ_NODE_RPC_PORT,
     },
+    signer::KeySource,
     utils::AddressOrAlias,
 };
 
@@ -62,8 +63,9 @@ impl Account {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     pub address: PublicKeyHash,
-    pub secret_key: SecretKey,
     pub public_key: PublicKey,
+    #[serde(flatten)]
+    pub key: KeySource,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -150,12 +152,17 @@ impl AccountConfig {
 }
 
 impl AddressOrAlias {
-    pub fn resolve(&self, cfg: &Config) -> Result<Address> {
+    /// Resolves the alias against the accounts visible on `network`.
+    pub fn resolve(
+        &self,
+        cfg: &Config,
+        network: &Option<NetworkName>,
+    ) -> Result<Address> {
         match self {
             AddressOrAlias::Address(address) => Ok(address.clone()),
             AddressOrAlias::Alias(alias) => {
                 let account = cfg
-                    .accounts
+                    .accounts(network)
                     .get(alias)
                     .ok_or_else(|| user_error!("User/smart function '{}' not found. Please provide a valid address or alias.", alias))?;
 
@@ -167,11 +174,12 @@ impl AddressOrAlias {
     pub fn resolve_or_use_current_user(
         account: Option<AddressOrAlias>,
         cfg: &Config,
+        network: &Option<NetworkName>,
     ) -> Result<Address> {
         match account {
-            Some(account) => account.resolve(cfg),
+            Some(account) => account.resolve(cfg, network),
             None => cfg
-                .accounts
+                .accounts(network)
                 .current_user()
                 .ok_or(user_error!(
                     "You are not logged in. Please run `jstz login`."
@@ -206,6 +214,53 @@ impl AddressOrAlias {
     }
 }
 
+/// The accounts visible on a network: the accounts of the network, then the shared
+/// accounts. Accounts of the network shadow shared accounts with the same alias.
+#[derive(Debug, Clone, Copy)]
+pub struct Accounts<'a> {
+    network: Option<&'a AccountConfig>,
+    shared: &'a AccountConfig,
+}
+
+impl<'a> Accounts<'a> {
+    pub fn current_alias(&self) -> Option<&'a str> {
+        self.network
+            .and_then(AccountConfig::current_alias)
+            .or_else(|| self.shared.current_alias())
+    }
+
+    pub fn current_user(&self) -> Option<(&'a str, &'a User)> {
+        let alias = self.current_alias()?;
+        match self.get(alias)? {
+            Account::User(user) => Some((alias, user)),
+            Account::SmartFunction(_) => None,
+        }
+    }
+
+    pub fn contains(&self, alias: &str) -> bool {
+        self.get(alias).is_some()
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&'a Account> {
+        self.network
+            .and_then(|accounts| accounts.get(alias))
+            .or_else(|| self.shared.get(alias))
+    }
+
+    /// Accounts of the network.
+    pub fn network(&self) -> Option<&'a AccountConfig> {
+        self.network
+    }
+
+    /// Shared accounts, without those shadowed by accounts of the network.
+    pub fn shared(&self) -> impl Iterator<Item = (&'a String, &'a Account)> + 'a {
+        let network = self.network;
+        self.shared.iter().filter(move |(alias, _)| {
+            !network.is_some_and(|accounts| accounts.contains(alias))
+        })
+    }
+}
+
 pub struct AccountsIter<'a> {
     inner: hash_map::Iter<'a, String, Account>,
 }
@@ -251,9 +306,12 @@ pub struct Config {
     /// The octez client directory to use
     #[serde(skip_serializing_if = "Option::is_none")]
     pub octez_client_dir: Option<PathBuf>,
-    /// List of accounts
+    /// Accounts shared by all networks
     #[serde(flatten)]
-    pub accounts: AccountConfig,
+    pub shared_accounts: AccountConfig,
+    /// Accounts of each network
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub network_accounts: HashMap<NetworkName, AccountConfig>,
     /// Available networks
     #[serde(flatten)]
     pub networks: NetworkConfig,
@@ -309,13 +367,14 @@ pub struct NetworkConfig {
 impl Config {
     pub fn new(
         octez_client_dir: Option<PathBuf>,
-        accounts: AccountConfig,
+        shared_accounts: AccountConfig,
         networks: NetworkConfig,
     ) -> Self {
         Self {
             octez_path: None,
             octez_client_dir,
-            accounts,
+            shared_accounts,
+            network_accounts: HashMap::new(),
             networks,
             sandbox_logs_dir: None,
             jstzd_config: None,
@@ -423,6 +482,108 @@ impl Config {
         Ok(JstzClient::new(network.jstz_node_endpoint.clone()))
     }
 
+    /// Network whose accounts are used with `name`: the given network, or the default
+    /// network. Without either, only the shared accounts are used.
+    fn accounts_network(&self, name: &Option<NetworkName>) -> Option<NetworkName> {
+        name.clone()
+            .or_else(|| self.networks.default_network.clone())
+    }
+
+    /// Accounts visible on the network `name`.
+    pub fn accounts(&self, name: &Option<NetworkName>) -> Accounts<'_> {
+        Accounts {
+            network: self
+                .accounts_network(name)
+                .and_then(|name| self.network_accounts.get(&name)),
+            shared: &self.shared_accounts,
+        }
+    }
+
+    /// Accounts of the network `name`, where new accounts and aliases are added.
+    pub fn accounts_mut(&mut self, name: &Option<NetworkName>) -> &mut AccountConfig {
+        match self.accounts_network(name) {
+            Some(name) => self.network_accounts.entry(name).or_default(),
+            None => &mut self.shared_accounts,
+        }
+    }
+
+    /// Account `alias` visible on the network `name`, for modification.
+    pub fn account_mut(
+        &mut self,
+        name: &Option<NetworkName>,
+        alias: &str,
+    ) -> Option<&mut Account> {
+        let network = self.accounts_network(name);
+        match network.and_then(|name| self.network_accounts.get_mut(&name)) {
+            Some(accounts) if accounts.contains(alias) => {
+                accounts.accounts.get_mut(alias)
+            }
+            _ => self.shared_accounts.accounts.get_mut(alias),
+        }
+    }
+
+    /// Removes the account `alias` visible on the network `name`.
+    pub fn remove_account(
+        &mut self,
+        name: &Option<NetworkName>,
+        alias: &str,
+    ) -> Option<Account> {
+        let network = self.accounts_network(name);
+        match network.and_then(|name| self.network_accounts.get_mut(&name)) {
+            Some(accounts) if accounts.contains(alias) => accounts.remove(alias),
+            _ => self.shared_accounts.remove(alias),
+        }
+    }
+
+    /// Sets the current account on the network `name`. Logging out only logs out of
+    /// `name`: the other networks keep the shared current account they fall back to.
+    pub fn set_current_alias(
+        &mut self,
+        name: &Option<NetworkName>,
+        alias: Option<String>,
+    ) -> Result<()> {
+        match alias.as_deref() {
+            Some(alias) => match self.accounts(name).get(alias) {
+                None => bail!(
+                    "Cannot set current account to '{}', account not found.",
+                    alias
+                ),
+                Some(Account::SmartFunction(_)) => bail!(
+                    "Cannot set current account to '{}', it is a smart function.",
+                    alias
+                ),
+                Some(Account::User(_)) => (),
+            },
+            None => self.pin_shared_current_alias(name),
+        }
+        self.accounts_mut(name).current_alias = alias;
+        Ok(())
+    }
+
+    /// Clears the shared current account, making it the current account of every
+    /// network other than `name` that falls back to it.
+    fn pin_shared_current_alias(&mut self, name: &Option<NetworkName>) {
+        let Some(alias) = self.shared_accounts.current_alias.take() else {
+            return;
+        };
+        let network = self.accounts_network(name);
+        let others: Vec<_> = self
+            .networks
+            .networks
+            .keys()
+            .map(|name| NetworkName::Custom(name.clone()))
+            .chain([NetworkName::Dev])
+            .chain(self.network_accounts.keys().cloned())
+            .filter(|other| Some(other) != network.as_ref())
+            .collect();
+        for other in others {
+            let accounts = self.network_accounts.entry(other).or_default();
+            if accounts.current_alias.is_none() {
+                accounts.current_alias = Some(alias.clone());
+            }
+        }
+    }
+
     fn network(&self, name: &Option<NetworkName>) -> Result<Network> {
         let network = match name {
             Some(name) => self.lookup_network(name),
@@ -485,10 +646,13 @@ impl Config {
 mod tests {
     use std::collections::HashMap;
 
+    use jstz_crypto::smart_function_hash::SmartFunctionHash;
+
     use super::{
-        Config, JstzNodeConfig, JstzdConfig, Network, NetworkConfig, NetworkName,
-        OctezClientConfig, OctezNodeConfig,
+        Account, Config, JstzNodeConfig, JstzdConfig, Network, NetworkConfig,
+        NetworkName, OctezClientConfig, OctezNodeConfig, SmartFunction, User,
     };
+    use crate::utils::AddressOrAlias;
 
     fn dummy_jstzd_config() -> JstzdConfig {
         JstzdConfig {
@@ -504,6 +668,111 @@ mod tests {
         }
     }
 
+    fn smart_function(address: &str) -> SmartFunction {
+        SmartFunction {
+            address: SmartFunctionHash::from_base58(address).unwrap(),
+        }
+    }
+
+    fn user() -> User {
+        User::from_secret_key("edsk4YBTjLtZgLNWKUN95unbAZ6cfq2eXhRveVt4J5oFPYHMzadpc8")
+            .unwrap()
+    }
+
+    #[test]
+    fn network_accounts() {
+        let dev = Some(NetworkName::Dev);
+        let testnet = Some(NetworkName::Custom("testnet".to_owned()));
+        let mut config = Config::default();
+        config.networks.default_network = testnet.clone();
+        config.shared_accounts.insert("alice".to_owned(), user());
+        config.accounts_mut(&dev).insert(
+            "counter".to_owned(),
+            smart_function("KT1RJ2P9C6cp6hVnTpNp4p5T5oJ3G2ySEtEf"),
+        );
+        config.accounts_mut(&None).insert(
+            "counter".to_owned(),
+            smart_function("KT1KRj5VMNmhxobTJBPq7u2kacqbxu9Cntx6"),
+        );
+
+        let alias = AddressOrAlias::Alias("counter".to_owned());
+        assert_eq!(
+            alias.resolve(&config, &dev).unwrap().to_string(),
+            "KT1RJ2P9C6cp6hVnTpNp4p5T5oJ3G2ySEtEf"
+        );
+        assert_eq!(
+            alias.resolve(&config, &None).unwrap().to_string(),
+            "KT1KRj5VMNmhxobTJBPq7u2kacqbxu9Cntx6"
+        );
+        assert!(alias
+            .resolve(&config, &Some(NetworkName::Custom("other".to_owned())))
+            .is_err());
+
+        // Shared accounts are visible on every network
+        assert!(config.accounts(&dev).contains("alice"));
+        assert!(config.accounts(&testnet).contains("alice"));
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json["accounts"]["alice"].is_object());
+        assert!(json["network_accounts"]["dev"]["accounts"]["counter"].is_object());
+        let config: Config = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            alias.resolve(&config, &dev).unwrap().to_string(),
+            "KT1RJ2P9C6cp6hVnTpNp4p5T5oJ3G2ySEtEf"
+        );
+    }
+
+    #[test]
+    fn network_current_alias() {
+        let dev = Some(NetworkName::Dev);
+        let testnet = Some(NetworkName::Custom("testnet".to_owned()));
+        let mut config = Config::default();
+        config.networks.networks.insert(
+            "testnet".to_owned(),
+            Network {
+                octez_node_rpc_endpoint: "http://127.0.0.1:8732".to_owned(),
+                jstz_node_endpoint: "http://127.0.0.1:8933".to_owned(),
+            },
+        );
+        config.shared_accounts.insert("alice".to_owned(), user());
+        config.accounts_mut(&dev).insert(
+            "counter".to_owned(),
+            smart_function("KT1RJ2P9C6cp6hVnTpNp4p5T5oJ3G2ySEtEf"),
+        );
+
+        config
+            .set_current_alias(&dev, Some("alice".to_owned()))
+            .unwrap();
+        assert_eq!(config.accounts(&dev).current_user().unwrap().0, "alice");
+        assert!(config.accounts(&testnet).current_alias().is_none());
+        assert!(config
+            .set_current_alias(&dev, Some("counter".to_owned()))
+            .is_err());
+        assert!(config
+            .set_current_alias(&testnet, Some("bob".to_owned()))
+            .is_err());
+
+        // Networks without a current account fall back to the shared one
+        config.shared_accounts.current_alias = Some("alice".to_owned());
+        assert_eq!(config.accounts(&testnet).current_alias(), Some("alice"));
+        // Logging out of a network keeps the other networks logged in
+        config.set_current_alias(&dev, None).unwrap();
+        assert!(config.accounts(&dev).current_alias().is_none());
+        assert_eq!(config.accounts(&testnet).current_alias(), Some("alice"));
+        assert!(config.shared_accounts.current_alias().is_none());
+        config.set_current_alias(&testnet, None).unwrap();
+        assert!(config.accounts(&testnet).current_alias().is_none());
+        config
+            .set_current_alias(&dev, Some("alice".to_owned()))
+            .unwrap();
+
+        assert!(matches!(
+            config.remove_account(&dev, "alice"),
+            Some(Account::User(_))
+        ));
+        assert!(config.accounts(&dev).current_user().is_none());
+    }
+
     #[test]
     fn lookup_network_with_jstzd() {
         let mut config = Config::default();
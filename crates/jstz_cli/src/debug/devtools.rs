@@ -0,0 +1,235 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, State, WebSocketUpgrade,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use deno_core::{
+    futures::{
+        channel::mpsc::{self, UnboundedSender},
+        SinkExt, StreamExt,
+    },
+    v8, InspectorMsg, InspectorSessionKind, InspectorSessionProxy,
+};
+use log::debug;
+use serde::Serialize;
+use tokio::net::TcpListener;
+
+use crate::error::{user_error, Result};
+
+/// Smart function call which DevTools can connect to
+struct Target {
+    id: String,
+    title: String,
+    sessions: UnboundedSender<InspectorSessionProxy>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetInfo {
+    id: String,
+    title: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    url: String,
+    web_socket_debugger_url: String,
+    devtools_frontend_url: String,
+}
+
+/// Server of the Chrome DevTools protocol, listing the smart function calls waiting
+/// for a debugger on `/json/list` and connecting sessions to them on `/{id}`
+#[derive(Clone)]
+pub struct DevToolsServer {
+    port: u16,
+    targets: Arc<Mutex<Vec<Target>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl DevToolsServer {
+    /// Starts the server on `127.0.0.1:{port}` in the background
+    pub async fn start(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| user_error!("Failed to listen on port {}: {}", port, e))?;
+        let server = Self {
+            port: listener.local_addr()?.port(),
+            targets: Default::default(),
+            next_id: Default::default(),
+        };
+        let router = Router::new()
+            .route("/json/version", get(version))
+            .route("/json", get(list))
+            .route("/json/list", get(list))
+            .route("/:id", get(connect))
+            .with_state(server.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                debug!("DevTools server stopped: {e}");
+            }
+        });
+        Ok(server)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Registers a call to the smart function `title` whose inspector accepts
+    /// sessions on `sessions`, returning its websocket URL
+    pub fn add_target(
+        &self,
+        title: String,
+        sessions: UnboundedSender<InspectorSessionProxy>,
+    ) -> String {
+        let mut targets = self.targets.lock().unwrap();
+        // Targets are removed once their runtime, and so their inspector, is dropped
+        targets.retain(|target| !target.sessions.is_closed());
+        let id = format!("{}-{}", title, self.next_id.fetch_add(1, Ordering::Relaxed));
+        let url = self.web_socket_url(&id);
+        targets.push(Target {
+            id,
+            title,
+            sessions,
+        });
+        url
+    }
+
+    fn web_socket_url(&self, id: &str) -> String {
+        format!("ws://127.0.0.1:{}/{}", self.port, id)
+    }
+
+    fn target_infos(&self) -> Vec<TargetInfo> {
+        let targets = self.targets.lock().unwrap();
+        targets
+            .iter()
+            .filter(|target| !target.sessions.is_closed())
+            .map(|target| TargetInfo {
+                id: target.id.clone(),
+                title: target.title.clone(),
+                kind: "node",
+                url: format!("jstz://{}", target.title),
+                web_socket_debugger_url: self.web_socket_url(&target.id),
+                devtools_frontend_url: format!(
+                    "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws=127.0.0.1:{}/{}",
+                    self.port, target.id
+                ),
+            })
+            .collect()
+    }
+
+    fn sessions(&self, id: &str) -> Option<UnboundedSender<InspectorSessionProxy>> {
+        let targets = self.targets.lock().unwrap();
+        targets
+            .iter()
+            .find(|target| target.id == id)
+            .map(|target| target.sessions.clone())
+    }
+}
+
+async fn version() -> impl IntoResponse {
+    Json(serde_json::json!({
+        "Browser": format!("jstz/{}", env!("CARGO_PKG_VERSION")),
+        "Protocol-Version": "1.3",
+        "V8-Version": v8::V8::get_version(),
+    }))
+}
+
+async fn list(State(server): State<DevToolsServer>) -> impl IntoResponse {
+    Json(server.target_infos())
+}
+
+async fn connect(
+    State(server): State<DevToolsServer>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    match server.sessions(&id) {
+        Some(sessions) => ws.on_upgrade(move |socket| bridge(socket, sessions)),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Forwards the messages of a DevTools websocket to a new inspector session and back
+async fn bridge(socket: WebSocket, sessions: UnboundedSender<InspectorSessionProxy>) {
+    let (outbound_tx, mut outbound_rx) = mpsc::unbounded::<InspectorMsg>();
+    let (mut inbound_tx, inbound_rx) = mpsc::unbounded::<String>();
+    let session = InspectorSessionProxy {
+        tx: outbound_tx,
+        rx: inbound_rx,
+        kind: InspectorSessionKind::NonBlocking {
+            wait_for_disconnect: false,
+        },
+    };
+    if sessions.unbounded_send(session).is_err() {
+        debug!("The smart function call finished before DevTools connected");
+        return;
+    }
+
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let inbound = async {
+        while let Some(Ok(message)) = ws_rx.next().await {
+            match message {
+                Message::Text(text) => {
+                    if inbound_tx.send(text).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+    };
+    let outbound = async {
+        while let Some(message) = outbound_rx.next().await {
+            if ws_tx.send(Message::Text(message.content)).await.is_err() {
+                break;
+            }
+        }
+    };
+    // The session ends when either DevTools disconnects or the call finishes
+    tokio::select! {
+        _ = inbound => {}
+        _ = outbound => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use deno_core::futures::channel::mpsc;
+    use serde_json::Value;
+
+    use super::DevToolsServer;
+
+    #[tokio::test]
+    async fn lists_waiting_targets() {
+        let server = DevToolsServer::start(0).await.unwrap();
+        let (sessions, receiver) = mpsc::unbounded();
+        let url = server
+            .add_target("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton".to_string(), sessions);
+
+        let list_url = format!("http://127.0.0.1:{}/json/list", server.port());
+        let targets: Vec<Value> =
+            reqwest::get(&list_url).await.unwrap().json().await.unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0]["webSocketDebuggerUrl"], url);
+        assert_eq!(
+            targets[0]["url"],
+            "jstz://KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton"
+        );
+
+        // Calls are no longer listed once their inspector is dropped
+        drop(receiver);
+        let targets: Vec<Value> =
+            reqwest::get(&list_url).await.unwrap().json().await.unwrap();
+        assert!(targets.is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use std::rc::Rc;
+
+use jstz_core::kv::Transaction;
+use jstz_crypto::{hash::Blake2b, smart_function_hash::SmartFunctionHash};
+use jstz_proto::{
+    context::account::{Account, Address},
+    operation::Content,
+    runtime::v2::run_toplevel_fetch,
+};
+use jstz_runtime::{debugger, JstzRuntime};
+use log::{debug, info};
+use tezos_smart_rollup_mock::MockHost;
+
+use crate::{
+    config::{Config, NetworkName},
+    error::{bail_user_error, user_error, Result},
+    repl::load_smart_function,
+    utils::AddressOrAlias,
+};
+
+mod devtools;
+
+use devtools::DevToolsServer;
+
+pub const DEFAULT_PORT: u16 = 9229;
+
+/// Pauses every smart function call until DevTools connects to it
+struct DevToolsDebugger(DevToolsServer);
+
+impl debugger::Debugger for DevToolsDebugger {
+    fn attach(&self, runtime: &mut JstzRuntime, address: &SmartFunctionHash) {
+        let sessions = runtime.inspector().borrow().get_session_sender();
+        let url = self.0.add_target(address.to_string(), sessions);
+        info!("Debugger listening on {url} for {address}");
+        runtime.wait_for_debugger();
+    }
+}
+
+pub async fn exec(
+    hash: String,
+    load: Vec<AddressOrAlias>,
+    port: u16,
+    network: Option<NetworkName>,
+) -> Result<()> {
+    let cfg = Config::load().await?;
+    let client = cfg.jstz_client(&network)?;
+
+    // 1. Fetch the operation
+    let hash =
+        Blake2b::try_parse(hash).map_err(|_| user_error!("Invalid operation hash."))?;
+    let operation = client.get_operation(&hash).await?.ok_or(user_error!(
+        "Operation '{}' was not injected through this node.",
+        hash
+    ))?;
+    let Content::RunFunction(run_function) = operation.content().clone() else {
+        bail_user_error!("Only `RunFunction` operations can be debugged.");
+    };
+    let address = run_function
+        .uri
+        .host()
+        .and_then(|host| SmartFunctionHash::from_base58(host).ok())
+        .ok_or(user_error!("The operation does not call a smart function."))?;
+    debug!("Replaying {} on {}", hash, address);
+
+    // 2. Load the state of the called smart functions from the node
+    let mut host = MockHost::default();
+    let mut tx = Transaction::default();
+    tx.begin();
+    load_smart_function(&client, &address, &host, &mut tx).await?;
+    for account in load {
+        let address = account
+            .resolve(&cfg, &network)?
+            .as_smart_function()
+            .cloned()
+            .ok_or(user_error!("Only smart functions can be loaded."))?;
+        load_smart_function(&client, &address, &host, &mut tx).await?;
+    }
+    let source = Address::User(operation.source());
+    let balance = client.get_balance(&source).await?;
+    Account::set_balance(&host, &mut tx, &source, balance)?;
+
+    // 3. Replay the operation with the debugger attached to every call
+    let server = DevToolsServer::start(port).await?;
+    debugger::set_debugger(Some(Rc::new(DevToolsDebugger(server))));
+    let receipt =
+        run_toplevel_fetch(&mut host, &mut tx, &source, run_function, hash).await;
+    debugger::set_debugger(None);
+
+    match receipt {
+        Ok(receipt) => {
+            info!("{}", receipt.status_code);
+            if let Some(body) = &receipt.body.0 {
+                info!("{}", String::from_utf8_lossy(body));
+            }
+            Ok(())
+        }
+        Err(err) => bail_user_error!("{err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, time::Duration};
+
+    use futures::{SinkExt, StreamExt};
+    use http::{HeaderMap, Method};
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{
+        hash::{Blake2b, Hash},
+        smart_function_hash::SmartFunctionHash,
+    };
+    use jstz_proto::{
+        context::account::Address,
+        operation::RunFunction,
+        runtime::{v2::run_toplevel_fetch, RuntimeVersion},
+        HttpBody,
+    };
+    use jstz_runtime::debugger;
+    use serde_json::Value;
+    use tezos_smart_rollup_mock::MockHost;
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::{DevToolsDebugger, DevToolsServer};
+    use crate::{
+        repl::{set_code, DEFAULT_SMART_FUNCTION_ADDRESS},
+        run::DEFAULT_GAS_LIMIT,
+    };
+
+    /// Plays DevTools: connects to the first call waiting for a debugger and resumes
+    /// it every time it pauses, returning the number of pauses
+    async fn resume_first_target(port: u16) -> usize {
+        let list_url = format!("http://127.0.0.1:{port}/json/list");
+        let url = loop {
+            let targets: Vec<Value> =
+                reqwest::get(&list_url).await.unwrap().json().await.unwrap();
+            if let Some(target) = targets.first() {
+                break target["webSocketDebuggerUrl"].as_str().unwrap().to_string();
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        for message in [
+            r#"{"id":1,"method":"Debugger.enable"}"#,
+            r#"{"id":2,"method":"Runtime.runIfWaitingForDebugger"}"#,
+        ] {
+            socket
+                .send(Message::Text(message.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let mut pauses = 0;
+        // The session is closed once the call finishes
+        while let Some(Ok(message)) = socket.next().await {
+            if let Message::Text(text) = message {
+                if text.contains(r#""method":"Debugger.paused""#) {
+                    pauses += 1;
+                    let resume = r#"{"id":3,"method":"Debugger.resume"}"#;
+                    socket
+                        .send(Message::Text(resume.to_string()))
+                        .await
+                        .unwrap();
+                }
+            }
+        }
+        pauses
+    }
+
+    #[test]
+    fn pauses_calls_until_devtools_resumes() {
+        // The call blocks this thread until DevTools connects, so the server and
+        // DevTools run on another runtime
+        let background = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let server = background.block_on(DevToolsServer::start(0)).unwrap();
+        let devtools = background.spawn(resume_first_target(server.port()));
+
+        let address =
+            SmartFunctionHash::from_base58(DEFAULT_SMART_FUNCTION_ADDRESS).unwrap();
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        set_code(
+            &host,
+            &mut tx,
+            &address,
+            "export default () => new Response('debugged')".to_string(),
+            RuntimeVersion::CURRENT,
+        )
+        .unwrap();
+        let run_function = RunFunction {
+            uri: format!("jstz://{address}/").parse().unwrap(),
+            method: Method::GET,
+            headers: HeaderMap::new(),
+            body: HttpBody::empty(),
+            gas_limit: DEFAULT_GAS_LIMIT as usize,
+        };
+
+        debugger::set_debugger(Some(Rc::new(DevToolsDebugger(server))));
+        let receipt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run_toplevel_fetch(
+                &mut host,
+                &mut tx,
+                &Address::User(jstz_mock::account1()),
+                run_function,
+                Blake2b::from(b"op_hash".as_ref()),
+            ))
+            .unwrap();
+        debugger::set_debugger(None);
+
+        assert_eq!(receipt.body.0.as_deref(), Some(b"debugged".as_ref()));
+        assert_eq!(background.block_on(devtools).unwrap(), 1);
+    }
+}
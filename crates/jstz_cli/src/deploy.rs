@@ -1,6 +1,7 @@
-use jstz_core::reveal_data::MAX_REVEAL_SIZE;
 use jstz_proto::{
-    operation::{Content, DeployFunction, Operation, SignedOperation},
+    operation::{
+        Content, DeployFunction, DeploySaltedFunction, Operation, SignedOperation,
+    },
     receipt::{ReceiptContent, ReceiptResult},
 };
 use log::{debug, info};
@@ -19,6 +20,7 @@ pub async fn exec(
     code_op: Option<String>,
     balance: Option<Tez>,
     name: Option<String>,
+    salt: Option<String>,
     network: Option<NetworkName>,
     force: bool,
     config_path: Option<PathBuf>,
@@ -31,16 +33,16 @@ pub async fn exec(
     }
 
     // Get the current user and check if we are logged in
-    account::login_quick(&mut cfg).await?;
+    account::login_quick(&mut cfg, &network).await?;
     cfg.reload_path(config_path.clone()).await?;
-    let (user_name, user) = cfg.accounts.current_user().ok_or(anyhow!(
+    let (user_name, user) = cfg.accounts(&network).current_user().ok_or(anyhow!(
         "Failed to setup the account. Please run `{}`.",
         styles::command("jstz login")
     ))?;
 
     // 1. Check if the name already exists
     if let Some(name) = &name {
-        if cfg.accounts.contains(name) && !force {
+        if cfg.accounts(&network).contains(name) && !force {
             bail_user_error!(
                 "The name '{}' is already used by another smart function or a user account. Please choose another name or specify the `--force` flag to overwrite the name.",
                 name
@@ -58,21 +60,30 @@ pub async fn exec(
     let code = read_file_or_input_or_piped(code_op)?
         .ok_or(user_error!("No function code supplied. Please provide a filename or pipe the file contents into stdin."))?;
 
-    if code.len() > MAX_REVEAL_SIZE {
-        bail_user_error!(
-            "Smart functions are currently restricted to {MAX_REVEAL_SIZE} bytes"
-        );
+    let max_size = jstz_client.get_protocol_params().await?.max_reveal_size();
+    if code.len() > max_size {
+        bail_user_error!("Smart functions are currently restricted to {max_size} bytes");
     }
 
     debug!("Code: {}", code);
 
+    let account_credit = balance.map(|b| b.to_mutez()).unwrap_or(0);
+    let content = match salt {
+        Some(salt) => Content::DeploySaltedFunction(DeploySaltedFunction {
+            function_code: code,
+            account_credit,
+            salt,
+        }),
+        None => Content::DeployFunction(DeployFunction {
+            function_code: code,
+            account_credit,
+        }),
+    };
+
     let op = Operation {
         public_key: user.public_key.clone(),
         nonce,
-        content: Content::DeployFunction(DeployFunction {
-            function_code: code,
-            account_credit: balance.map(|b| b.to_mutez()).unwrap_or(0),
-        }),
+        content,
     };
 
     debug!("Operation: {:?}", op);
@@ -81,7 +92,7 @@ pub async fn exec(
 
     debug!("Operation hash: {}", hash.to_string());
 
-    let signed_op = SignedOperation::new(user.secret_key.sign(&hash)?, op);
+    let signed_op = SignedOperation::new(user.sign(&hash).await?, op);
 
     debug!("Signed operation: {:?}", signed_op);
 
@@ -108,7 +119,7 @@ pub async fn exec(
 
     // Show message showing how to run the smart function
     // TODO: add --trace flag
-    let network_flag = match network {
+    let network_flag = match &network {
         Some(network) => format!(" --network {network}"),
         None => "".to_string(),
     };
@@ -120,10 +131,95 @@ pub async fn exec(
     );
 
     if let Some(name) = name {
-        cfg.accounts.insert(name, SmartFunction { address });
+        cfg.accounts_mut(&network)
+            .insert(name, SmartFunction { address });
     }
 
     cfg.save_to_path(config_path)?;
 
     Ok(())
 }
+
+#[cfg(feature = "v2_runtime")]
+pub async fn exec_library(
+    code_op: Option<String>,
+    network: Option<NetworkName>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    use jstz_proto::operation::DeployLibrary;
+
+    let mut cfg = Config::load_path(config_path.clone()).await?;
+
+    // Load sandbox if the selected network is Dev and sandbox is not already loaded
+    if cfg.network_name(&network)? == NetworkName::Dev {
+        assert_sandbox_running(JSTZD_SERVER_BASE_URL).await?;
+    }
+
+    // Get the current user and check if we are logged in
+    account::login_quick(&mut cfg, &network).await?;
+    cfg.reload_path(config_path).await?;
+    let (user_name, user) = cfg.accounts(&network).current_user().ok_or(anyhow!(
+        "Failed to setup the account. Please run `{}`.",
+        styles::command("jstz login")
+    ))?;
+
+    // 1. Construct operation
+    let jstz_client = cfg.jstz_client(&network)?;
+
+    let nonce = jstz_client.get_nonce(&user.address.clone().into()).await?;
+
+    debug!("Nonce: {:?}", nonce);
+
+    let code = read_file_or_input_or_piped(code_op)?
+        .ok_or(user_error!("No library code supplied. Please provide a filename or pipe the file contents into stdin."))?;
+
+    let max_size = jstz_client.get_protocol_params().await?.max_reveal_size();
+    if code.len() > max_size {
+        bail_user_error!("Libraries are currently restricted to {max_size} bytes");
+    }
+
+    debug!("Code: {}", code);
+
+    let op = Operation {
+        public_key: user.public_key.clone(),
+        nonce,
+        content: Content::DeployLibrary(DeployLibrary { library_code: code }),
+    };
+
+    debug!("Operation: {:?}", op);
+
+    let hash = op.hash();
+
+    debug!("Operation hash: {}", hash.to_string());
+
+    let signed_op = SignedOperation::new(user.sign(&hash).await?, op);
+
+    debug!("Signed operation: {:?}", signed_op);
+
+    // 2. Send operation to jstz-node
+    jstz_client.post_operation(&signed_op).await?;
+    let receipt = jstz_client.wait_for_operation_receipt(&hash).await?;
+
+    debug!("Receipt: {:?}", receipt);
+
+    let library_hash = match receipt.result {
+        ReceiptResult::Success(ReceiptContent::DeployLibrary(deploy)) => deploy.hash,
+        ReceiptResult::Success(_) => {
+            bail!("Expected a `DeployLibrary` receipt, but got something else.")
+        }
+        ReceiptResult::Failed(err) => {
+            bail_user_error!("Failed to deploy library with error {err:?}.")
+        }
+    };
+
+    info!(
+        "Library deployed by {} with hash: {}",
+        user_name, library_hash
+    );
+    info!(
+        "Import with `{}`",
+        styles::command(format!("import ... from \"jstz-lib:{library_hash}\""))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::{fs, path::Path};
+
+use log::info;
+
+use crate::{
+    error::{bail_user_error, Result},
+    term::styles,
+};
+
+const PACKAGE_JSON: &str = include_str!("template/package.json");
+const TSCONFIG_JSON: &str = include_str!("template/tsconfig.json");
+const INDEX_TS: &str = include_str!("template/index.ts");
+const GITIGNORE: &str = include_str!("template/gitignore");
+
+/// Files of a new smart function project, relative to the project directory.
+fn template(name: &str) -> [(&'static str, String); 4] {
+    [
+        ("package.json", PACKAGE_JSON.replace("{{name}}", name)),
+        ("tsconfig.json", TSCONFIG_JSON.to_string()),
+        ("index.ts", INDEX_TS.to_string()),
+        (".gitignore", GITIGNORE.to_string()),
+    ]
+}
+
+/// npm package names are lowercase and url-safe.
+fn package_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches(|c| c == '-' || c == '.' || c == '_');
+    if name.is_empty() {
+        "smart-function".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn exec(path: &Path, name: Option<String>, force: bool) -> Result<()> {
+    let name = package_name(&match name {
+        Some(name) => name,
+        None => fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    });
+
+    let files = template(&name);
+    if !force {
+        if let Some((file, _)) = files.iter().find(|(file, _)| path.join(file).exists()) {
+            bail_user_error!(
+                "'{}' already exists. Please choose another directory or specify the `--force` flag to overwrite it.",
+                path.join(file).display()
+            );
+        }
+    }
+
+    fs::create_dir_all(path)?;
+    for (file, contents) in files {
+        fs::write(path.join(file), contents)?;
+        info!("Created {}", path.join(file).display());
+    }
+
+    info!(
+        "\nSmart function project '{}' created. To build it, run:\n\n  cd {}\n  npm install\n  {}\n",
+        name,
+        path.display(),
+        styles::command("jstz build")
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(package_name("my-function"), "my-function");
+        assert_eq!(package_name("My Function!"), "my-function");
+        assert_eq!(package_name("  _counter_ "), "counter");
+        assert_eq!(package_name("!!!"), "smart-function");
+    }
+
+    #[test]
+    fn test_init_scaffolds_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Counter");
+
+        exec(&path, None, false).unwrap();
+
+        let package_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.join("package.json")).unwrap())
+                .unwrap();
+        assert_eq!(package_json["name"], "counter");
+        assert_eq!(package_json["devDependencies"]["@jstz-dev/types"], "0.x");
+        assert!(package_json.get("dependencies").is_none());
+
+        let tsconfig: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(path.join("tsconfig.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            tsconfig["compilerOptions"]["types"],
+            serde_json::json!(["@jstz-dev/types"])
+        );
+
+        assert!(path.join("index.ts").exists());
+        assert!(path.join(".gitignore").exists());
+    }
+
+    #[test]
+    fn test_init_does_not_overwrite_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("index.ts"), "// my code").unwrap();
+
+        let err = exec(dir.path(), Some("counter".to_string()), false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("index.ts")).unwrap(),
+            "// my code"
+        );
+
+        exec(dir.path(), Some("counter".to_string()), true).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("index.ts")).unwrap(),
+            INDEX_TS
+        );
+    }
+}
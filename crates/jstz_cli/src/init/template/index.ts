@@ -0,0 +1,14 @@
+// Count the requests made to this smart function
+const handler = async (request: Request): Promise<Response> => {
+  const requester = request.headers.get("Referer") as Address;
+  const count = (Kv.get<number>("count") ?? 0) + 1;
+  Kv.set("count", count);
+
+  console.log(`${requester} made request #${count}`);
+
+  return new Response(JSON.stringify({ requester, count }), {
+    headers: { "Content-Type": "application/json" },
+  });
+};
+
+export default handler;
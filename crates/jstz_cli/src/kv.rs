@@ -1,4 +1,5 @@
 use clap::Subcommand;
+use jstz_proto::runtime::KvValue;
 use log::{debug, info};
 
 use crate::{
@@ -14,7 +15,7 @@ async fn get(
 ) -> Result<()> {
     let cfg = Config::load().await?;
 
-    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg)?;
+    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg, &network)?;
     debug!("resolved `account` -> {:?}", address);
 
     let value = cfg
@@ -24,7 +25,10 @@ async fn get(
 
     // Print value
     match value {
-        Some(value) => info!("{}", serde_json::to_string_pretty(&value).unwrap()),
+        Some(KvValue::Json(value)) => {
+            info!("{}", serde_json::to_string_pretty(&value).unwrap())
+        }
+        Some(KvValue::Bytes(bytes)) => info!("0x{}", hex::encode(bytes)),
         None => bail_user_error!("No value found"),
     }
 
@@ -38,7 +42,7 @@ async fn list(
 ) -> Result<()> {
     let cfg = Config::load().await?;
 
-    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg)?;
+    let address = AddressOrAlias::resolve_or_use_current_user(account, &cfg, &network)?;
     debug!("resolved `account` -> {:?}", address);
 
     let value = cfg
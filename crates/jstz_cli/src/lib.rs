@@ -3,21 +3,27 @@ use clap_complete::Shell;
 use std::path::PathBuf;
 
 mod account;
+mod agent;
 pub mod bridge;
+mod build;
 mod completions;
 pub mod config;
+#[cfg(feature = "v2_runtime")]
+mod debug;
 mod deploy;
 mod docs;
 pub mod error;
+mod init;
 mod jstz;
 mod kv;
 mod logs;
 mod network;
-#[cfg(not(feature = "v2_runtime"))]
 mod repl;
 mod run;
 mod sandbox;
+pub mod signer;
 pub mod term;
+mod test_runner;
 mod utils;
 
 use config::{Config, NetworkName};
@@ -28,6 +34,34 @@ use utils::{AddressOrAlias, Tez};
 #[derive(Debug, Parser)]
 #[command(name = "jstz", author = "TriliTech <contact@trili.tech>", version)]
 pub enum Command {
+    /// 🌱 Creates a new TypeScript smart function project
+    Init {
+        /// Directory of the project, created if it does not exist.
+        #[arg(value_name = "PATH", default_value = ".", value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+        /// Package name of the project, defaulting to the name of the directory.
+        #[arg(long, default_value = None)]
+        name: Option<String>,
+        /// Overwrites existing project files.
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// 🛠️  Bundles a smart function project into a single deployable module
+    Build {
+        /// Directory of the project.
+        #[arg(value_name = "PATH", default_value = ".", value_hint = clap::ValueHint::DirPath)]
+        path: PathBuf,
+        /// Entry point of the smart function, relative to the project directory.
+        #[arg(short, long, default_value = build::DEFAULT_ENTRY, value_hint = clap::ValueHint::FilePath)]
+        entry: PathBuf,
+        /// Output file of the bundle, relative to the project directory.
+        #[arg(short, long, default_value = build::DEFAULT_OUTFILE, value_hint = clap::ValueHint::FilePath)]
+        outfile: PathBuf,
+        /// Specifies the network from the config file whose reveal limit the smart function is
+        /// checked against, defaulting to the configured default network.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
     /// 🚀 Deploys a smart function to jstz
     Deploy {
         /// Function code.
@@ -39,6 +73,10 @@ pub enum Command {
         /// Name (or alias) of the function.
         #[arg(long, default_value = None)]
         name: Option<String>,
+        /// Salt of the function address. The address is then derived from the deployer, the
+        /// salt and the code, and can be predicted with `jstz account address --predict`.
+        #[arg(long, default_value = None)]
+        salt: Option<String>,
         /// Specifies the network from the config file, defaulting to the configured default network.
         /// Use `dev` for the local sandbox.
         #[arg(short, long, default_value = None)]
@@ -50,6 +88,20 @@ pub enum Command {
         #[arg(long, value_name = "PATH", default_value = None, value_hint = clap::ValueHint::FilePath)]
         config_path: Option<PathBuf>,
     },
+    /// 📦 Deploys a library module that smart functions can import
+    #[cfg(feature = "v2_runtime")]
+    DeployLibrary {
+        /// Library code.
+        #[arg(value_name = "CODE|PATH", default_value = None, value_hint = clap::ValueHint::FilePath)]
+        code: Option<String>,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+        /// overrides the path to the config file.
+        #[arg(long, value_name = "PATH", default_value = None, value_hint = clap::ValueHint::FilePath)]
+        config_path: Option<PathBuf>,
+    },
     /// 💵 Send a request to a transfer XTZ
     Transfer {
         /// The amount in XTZ to transfer.
@@ -101,6 +153,18 @@ pub enum Command {
         #[arg(name = "include", short, long)]
         include_response_headers: bool,
     },
+    /// 🧪 Runs smart function tests locally, without a sandbox
+    Test {
+        /// Test files, or directories to search for `*.test.json` files.
+        #[arg(value_name = "PATH", default_value = ".", value_hint = clap::ValueHint::AnyPath)]
+        paths: Vec<PathBuf>,
+        /// Format of the test report.
+        #[arg(short, long, value_enum, default_value = "tap")]
+        format: test_runner::Format,
+        /// Writes the test report to a file instead of stdout.
+        #[arg(short, long, value_name = "PATH", default_value = None, value_hint = clap::ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
     /// 🌉 Move XTZ between L1 and jstz with the jstz bridge {n}
     #[command(subcommand)]
     Bridge(bridge::Command),
@@ -114,11 +178,34 @@ pub enum Command {
         command: sandbox::Command,
     },
     /// ⚡️ Start a REPL session with jstz's JavaScript runtime {n}
-    #[cfg(not(feature = "v2_runtime"))]
     Repl {
         /// Sets the address of the REPL environment.
         #[arg(value_name = "ADDRESS|ALIAS", short, long)]
         account: Option<AddressOrAlias>,
+        /// Loads the code, balance and key-value store of the smart function at the REPL address from the network.
+        #[arg(long, requires = "account")]
+        load: bool,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
+    /// 🐞 Replays an operation with a debugger for Chrome DevTools {n}
+    #[cfg(feature = "v2_runtime")]
+    Debug {
+        /// Hash of a `RunFunction` operation injected through the node.
+        #[arg(value_name = "OPERATION_HASH")]
+        hash: String,
+        /// Loads another smart function called by the operation from the network. Can be repeated.
+        #[arg(value_name = "ADDRESS|ALIAS", long)]
+        load: Vec<AddressOrAlias>,
+        /// Port of the DevTools websocket server.
+        #[arg(long, default_value_t = debug::DEFAULT_PORT)]
+        port: u16,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
 
     /// 🪵  Explore logs from deployed smart functions
@@ -136,12 +223,29 @@ pub enum Command {
         /// User alias
         #[arg(value_name = "ALIAS")]
         alias: String,
+        /// Stores the secret key of a newly created account unencrypted.
+        #[arg(long)]
+        no_encrypt: bool,
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
     },
     /// 🚪 Logout from the current jstz account
-    Logout {},
+    Logout {
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
     /// 🤔 Display your account info {n}
     #[command(name = "whoami")]
-    WhoAmI {},
+    WhoAmI {
+        /// Specifies the network from the config file, defaulting to the configured default network.
+        /// Use `dev` for the local sandbox.
+        #[arg(short, long, default_value = None)]
+        network: Option<NetworkName>,
+    },
 
     /// Manage recognised networks
     #[command(subcommand)]
@@ -155,6 +259,9 @@ pub enum Command {
         #[arg(long, short)]
         shell: Shell,
     },
+    /// Runs the agent keeping unlocked secret keys, started when a key is unlocked
+    #[command(hide = true)]
+    KeyAgent,
 }
 
 pub async fn exec(command: Command) -> Result<()> {
@@ -166,14 +273,28 @@ pub async fn exec(command: Command) -> Result<()> {
         }
         Command::Bridge(bridge_command) => bridge::exec(bridge_command).await,
         Command::Account(account_command) => account::exec(account_command).await,
+        Command::Init { path, name, force } => init::exec(&path, name, force),
+        Command::Build {
+            path,
+            entry,
+            outfile,
+            network,
+        } => build::exec(&path, entry, outfile, network).await,
         Command::Deploy {
             code,
             balance,
             name,
+            salt,
             network,
             force,
             config_path,
-        } => deploy::exec(code, balance, name, network, force, config_path).await,
+        } => deploy::exec(code, balance, name, salt, network, force, config_path).await,
+        #[cfg(feature = "v2_runtime")]
+        Command::DeployLibrary {
+            code,
+            network,
+            config_path,
+        } => deploy::exec_library(code, network, config_path).await,
         Command::Transfer {
             amount,
             to,
@@ -205,12 +326,33 @@ pub async fn exec(command: Command) -> Result<()> {
             .await
         }
         #[cfg(not(feature = "v2_runtime"))]
-        Command::Repl { account } => repl::exec(account).await,
+        Command::Test {
+            paths,
+            format,
+            output,
+        } => test_runner::exec(paths, format, output).await,
+        Command::Repl {
+            account,
+            load,
+            network,
+        } => repl::exec(account, load, network).await,
+        #[cfg(feature = "v2_runtime")]
+        Command::Debug {
+            hash,
+            load,
+            port,
+            network,
+        } => debug::exec(hash, load, port, network).await,
         Command::Logs(logs) => logs::exec(logs).await,
-        Command::Login { alias } => account::login(alias).await,
-        Command::Logout {} => account::logout().await,
-        Command::WhoAmI {} => account::whoami().await,
+        Command::Login {
+            alias,
+            no_encrypt,
+            network,
+        } => account::login(alias, !no_encrypt, network).await,
+        Command::Logout { network } => account::logout(network).await,
+        Command::WhoAmI { network } => account::whoami(network).await,
         Command::Kv(kv_command) => kv::exec(kv_command).await,
         Command::Network(command) => network::exec(command).await,
+        Command::KeyAgent => agent::run(),
     }
 }
@@ -14,7 +14,7 @@ pub async fn exec(
 ) -> Result<()> {
     let cfg = Config::load().await?;
 
-    let address = address_or_alias.resolve(&cfg)?;
+    let address = address_or_alias.resolve(&cfg, network)?;
     debug!("resolved `address_or_alias` -> {:?}", address);
 
     let event_source = cfg.jstz_client(network)?.logs_stream(&address);
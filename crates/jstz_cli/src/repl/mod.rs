@@ -1,18 +1,13 @@
 use std::{borrow::Cow, fmt::Write};
 
-use boa_engine::{js_string, Context, JsResult, JsValue, Source};
-use jstz_api::{js_log::set_js_logger, stream::StreamApi};
-use jstz_core::{
-    host::HostRuntime,
-    kv::Transaction,
-    runtime::{self, Runtime},
+use jstz_client::JstzClient;
+use jstz_core::{host::HostRuntime, kv::Transaction};
+use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+use jstz_proto::{
+    context::account::{Account, Address},
+    runtime::{Kv, RuntimeVersion},
 };
-use jstz_crypto::{
-    hash::{Blake2b, Hash},
-    smart_function_hash::SmartFunctionHash,
-};
-use jstz_proto::runtime::v1::{ProtocolApi, WebApi};
-use log::{debug, error, info, warn};
+use log::{debug, info};
 use rustyline::{
     completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
     validate::Validator, Editor, Helper,
@@ -25,16 +20,19 @@ use syntect::{
 use tezos_smart_rollup_mock::MockHost;
 
 use crate::{
-    config::Config,
-    error::{anyhow, user_error, Result},
+    config::{Config, NetworkName},
+    error::{user_error, Result},
     utils::AddressOrAlias,
 };
 
-mod debug_api;
-mod js_logger;
-
-use debug_api::DebugApi;
-use js_logger::PrettyLogger;
+#[cfg(not(feature = "v2_runtime"))]
+mod v1;
+#[cfg(not(feature = "v2_runtime"))]
+use v1::Session;
+#[cfg(feature = "v2_runtime")]
+mod v2;
+#[cfg(feature = "v2_runtime")]
+use v2::Session;
 
 struct JsHighlighter {
     ss: SyntaxSet,
@@ -104,51 +102,52 @@ impl Completer for JsHighlighter {
     type Candidate = String;
 }
 
-const DEFAULT_SMART_FUNCTION_ADDRESS: &str = "KT1KRj5VMNmhxobTJBPq7u2kacqbxu9Cntx6";
-const DEFAULT_GAS_LIMIT: usize = usize::MAX;
+pub(crate) const DEFAULT_SMART_FUNCTION_ADDRESS: &str =
+    "KT1KRj5VMNmhxobTJBPq7u2kacqbxu9Cntx6";
 
-pub async fn exec(account: Option<AddressOrAlias>) -> Result<()> {
+pub async fn exec(
+    account: Option<AddressOrAlias>,
+    load: bool,
+    network: Option<NetworkName>,
+) -> Result<()> {
     let cfg = Config::load().await?;
 
     let address = match account {
         Some(account) => account
-            .resolve(&cfg)?
+            .resolve(&cfg, &network)?
             .as_smart_function()
-            .expect("expected smart function address")
-            .clone(),
+            .cloned()
+            .ok_or(user_error!(
+                "The REPL address must be a smart function address."
+            ))?,
         None => SmartFunctionHash::from_base58(DEFAULT_SMART_FUNCTION_ADDRESS)
             .expect("`DEFAULT_SMART_FUNCTION_ADDRESS` is an invalid address.")
             .clone(),
     };
     debug!("resolved `account` -> {:?}", address);
 
+    // `fetch` calls are sent from the current user, if any
+    let source = cfg
+        .accounts(&network)
+        .current_user()
+        .map(|(_, user)| user.address.clone())
+        .unwrap_or_else(jstz_mock::account1);
+
     // 1. Setup editor
     let mut rl = Editor::<JsHighlighter, _>::new()?;
     rl.set_helper(Some(JsHighlighter::new()));
 
-    // 2. Setup runtime
-    let mut rt = Runtime::new(DEFAULT_GAS_LIMIT)
-        .map_err(|_| anyhow!("Failed to initialize jstz's JavaScript runtime."))?;
+    // 2. Setup storage
+    let host = MockHost::default();
     let mut tx = Transaction::default();
     tx.begin();
+    if load {
+        load_smart_function(&cfg.jstz_client(&network)?, &address, &host, &mut tx)
+            .await?;
+    }
 
-    set_js_logger(&PrettyLogger);
-
-    let mut mock_hrt = MockHost::default();
-    let realm = rt.realm().clone();
-
-    realm.register_api(WebApi, &mut rt);
-    realm.register_api(
-        ProtocolApi {
-            address: address.clone(),
-            operation_hash: Blake2b::from(b"fake_op_hash".as_ref()),
-        },
-        &mut rt,
-    );
-
-    // realm.register_api(ConsoleApi, rt.context());
-    realm.register_api(StreamApi, rt.context());
-    realm.register_api(DebugApi, rt.context());
+    // 3. Setup runtime
+    let mut session = Session::new(host, tx, source, address)?;
 
     loop {
         let readline = rl.readline(">> ");
@@ -164,8 +163,7 @@ pub async fn exec(account: Option<AddressOrAlias>) -> Result<()> {
                 // Add the line to history so you can use arrow keys to recall it
                 rl.add_history_entry(line.as_str())?;
 
-                let result = evaluate(input, &mut rt, &mut mock_hrt, &mut tx);
-                print_rt_result(result, &mut rt);
+                session.eval(input).await;
             }
             Err(ReadlineError::Interrupted) => {
                 info!("CTRL-C");
@@ -182,47 +180,153 @@ pub async fn exec(account: Option<AddressOrAlias>) -> Result<()> {
     }
 }
 
-fn print_rt_result(result: JsResult<JsValue>, context: &mut Context) {
-    match result {
-        Ok(res) => {
-            if !res.is_undefined() {
-                info!(
-                    "{}",
-                    if res.is_callable() {
-                        res.to_string(context)
-                            .expect("Expected [[toString]] to be defined.")
-                            .to_std_string_escaped()
-                    } else {
-                        res.display().to_string()
-                    },
-                );
-            }
-
-            if context
-                .global_object()
-                .set(js_string!("_"), res, false, context)
-                .is_err()
-            {
-                warn!("Couldn't set '_' to REPL result.");
+/// Copies the code, balance and key-value store of the smart function at `address`
+/// from the node of `client` into the REPL storage
+pub(crate) async fn load_smart_function(
+    client: &JstzClient,
+    address: &SmartFunctionHash,
+    host: &impl HostRuntime,
+    tx: &mut Transaction,
+) -> Result<()> {
+    let account = Address::SmartFunction(address.clone());
+    let code = client
+        .get_code(address)
+        .await?
+        .ok_or(user_error!("No smart function found at '{}'.", address))?;
+    let version = client.get_runtime_version(address).await?;
+    set_code(host, tx, address, code, version)?;
+    let balance = client.get_balance(&account).await?;
+    Account::set_balance(host, tx, address, balance)?;
+
+    // Walk the key-value store one level of subkeys at a time
+    let kv = Kv::new(address.to_base58());
+    let mut entries = 0;
+    let mut prefixes = vec![String::new()];
+    while let Some(prefix) = prefixes.pop() {
+        let subkeys = client
+            .get_subkey_list(&account, &Some(prefix.clone()))
+            .await?
+            .unwrap_or_default();
+        for subkey in subkeys {
+            let key = if prefix.is_empty() {
+                subkey
+            } else {
+                format!("{prefix}/{subkey}")
+            };
+            if let Some(value) = client.get_value(&account, &key).await? {
+                kv.set(tx, &key, value)?;
+                entries += 1;
             }
-        }
-        Err(e) => {
-            error!("Uncaught {e}")
+            prefixes.push(key);
         }
     }
+
+    info!("Loaded '{address}' with {entries} key-value entries.");
+    Ok(())
 }
 
-fn evaluate(
-    input: &str,
-    rt: &mut Runtime,
-    hrt: &mut (impl HostRuntime + 'static),
+/// Sets the code of the smart function at `address`, creating it if it does not exist,
+/// and makes it run on the runtime `version`
+pub(crate) fn set_code(
+    host: &impl HostRuntime,
     tx: &mut Transaction,
-) -> JsResult<JsValue> {
-    runtime::enter_js_host_context(hrt, tx, || {
-        let result = rt.eval(Source::from_bytes(input))?;
-        jstz_core::future::block_on(async {
-            rt.run_event_loop().await;
-            rt.resolve_value(&result).await
-        })
-    })
+    address: &SmartFunctionHash,
+    code: String,
+    version: RuntimeVersion,
+) -> jstz_proto::Result<()> {
+    Account::set_function_code(host, tx, address, code)?;
+    Account::set_runtime_version(host, tx, address, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use jstz_client::JstzClient;
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+    use jstz_proto::{
+        context::account::Account,
+        runtime::{Kv, KvValue, RuntimeVersion},
+    };
+    use mockito::Matcher;
+    use serde_json::json;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{load_smart_function, DEFAULT_SMART_FUNCTION_ADDRESS};
+
+    #[tokio::test]
+    async fn load_smart_function_copies_state() {
+        let address =
+            SmartFunctionHash::from_base58(DEFAULT_SMART_FUNCTION_ADDRESS).unwrap();
+        let code = "export default () => new Response()";
+        let mut server = mockito::Server::new_async().await;
+        let account_path = format!("/accounts/{address}");
+        server
+            .mock("GET", format!("{account_path}/code").as_str())
+            .with_body(json!(code).to_string())
+            .create();
+        server
+            .mock("GET", format!("{account_path}/runtime").as_str())
+            .with_body(json!(RuntimeVersion::V1).to_string())
+            .create();
+        server
+            .mock("GET", format!("{account_path}/balance").as_str())
+            .with_body("1000")
+            .create();
+        let subkeys = [
+            ("", json!(["a", "b"])),
+            ("a", json!(["c"])),
+            ("b", json!([])),
+            ("a/c", json!([])),
+        ];
+        for (key, keys) in subkeys {
+            let path = format!("{account_path}/kv/subkeys");
+            let mock = server.mock("GET", path.as_str());
+            let mock = if key.is_empty() {
+                mock.match_query(Matcher::Missing)
+            } else {
+                mock.match_query(Matcher::UrlEncoded("key".into(), key.into()))
+            };
+            mock.with_body(keys.to_string()).create();
+        }
+        let values = [("b", json!(1)), ("a/c", json!("x"))];
+        for (key, value) in values {
+            server
+                .mock("GET", format!("{account_path}/kv").as_str())
+                .match_query(Matcher::UrlEncoded("key".into(), key.into()))
+                .with_body(value.to_string())
+                .create();
+        }
+        server
+            .mock("GET", format!("{account_path}/kv").as_str())
+            .match_query(Matcher::UrlEncoded("key".into(), "a".into()))
+            .with_status(404)
+            .create();
+
+        let host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        load_smart_function(&JstzClient::new(server.url()), &address, &host, &mut tx)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            &*Account::function_code(&host, &mut tx, &address).unwrap(),
+            code
+        );
+        assert_eq!(
+            Account::runtime_version(&host, &tx, &address).unwrap(),
+            RuntimeVersion::V1
+        );
+        assert_eq!(Account::balance(&host, &mut tx, &address).unwrap(), 1000);
+        let kv = Kv::new(address.to_base58());
+        assert!(kv.get(&host, &mut tx, "a").unwrap().is_none());
+        assert_eq!(
+            kv.get(&host, &mut tx, "b").unwrap().as_deref(),
+            Some(&KvValue::Json(json!(1)))
+        );
+        assert_eq!(
+            kv.get(&host, &mut tx, "a/c").unwrap().as_deref(),
+            Some(&KvValue::Json(json!("x")))
+        );
+    }
 }
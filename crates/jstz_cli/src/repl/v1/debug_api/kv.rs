@@ -1,8 +1,12 @@
 use std::ops::Deref;
 
 use boa_engine::{
-    js_string, object::ObjectInitializer, Context, JsArgs, JsObject, JsResult, JsValue,
-    NativeFunction,
+    js_string,
+    object::{
+        builtins::{JsArrayBuffer, JsUint8Array},
+        ObjectInitializer,
+    },
+    Context, JsArgs, JsObject, JsResult, JsValue, NativeFunction,
 };
 use jstz_core::runtime;
 use jstz_proto::runtime::{Kv, KvValue};
@@ -21,8 +25,13 @@ impl KvApi {
         let kv = Kv::new(account);
 
         runtime::with_js_hrt_and_tx(|hrt, tx| -> JsResult<JsValue> {
-            match kv.get(hrt.deref(), tx, &key)? {
-                Some(value) => JsValue::from_json(&value.0, context),
+            match kv.get(hrt.deref(), tx, &key)?.as_deref() {
+                Some(KvValue::Json(value)) => JsValue::from_json(value, context),
+                Some(KvValue::Bytes(bytes)) => Ok(JsUint8Array::from_array_buffer(
+                    JsArrayBuffer::from_byte_block(bytes.clone(), context)?,
+                    context,
+                )?
+                .into()),
                 None => Ok(JsValue::null()),
             }
         })
@@ -36,7 +45,7 @@ impl KvApi {
         let account: String = args.get_or_undefined(0).try_js_into(context)?;
         let key: String = args.get_or_undefined(1).try_js_into(context)?;
 
-        let value = KvValue(args.get_or_undefined(2).to_json(context)?);
+        let value = KvValue::Json(args.get_or_undefined(2).to_json(context)?);
 
         let kv = Kv::new(account);
 
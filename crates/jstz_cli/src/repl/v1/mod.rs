@@ -0,0 +1,114 @@
+use boa_engine::{js_string, Context, JsResult, JsValue, Source};
+use jstz_api::{js_log::set_js_logger, stream::StreamApi};
+use jstz_core::{
+    host::HostRuntime,
+    kv::Transaction,
+    runtime::{self, Runtime},
+};
+use jstz_crypto::{
+    hash::{Blake2b, Hash},
+    public_key_hash::PublicKeyHash,
+    smart_function_hash::SmartFunctionHash,
+};
+use jstz_proto::runtime::v1::{ProtocolApi, WebApi};
+use log::{error, info, warn};
+use tezos_smart_rollup_mock::MockHost;
+
+use crate::error::{anyhow, Result};
+
+mod debug_api;
+mod js_logger;
+
+use debug_api::DebugApi;
+use js_logger::PrettyLogger;
+
+const DEFAULT_GAS_LIMIT: usize = usize::MAX;
+
+/// REPL session on the v1 runtime
+pub struct Session {
+    rt: Runtime,
+    host: MockHost,
+    tx: Transaction,
+}
+
+impl Session {
+    pub fn new(
+        host: MockHost,
+        tx: Transaction,
+        _source: PublicKeyHash,
+        address: SmartFunctionHash,
+    ) -> Result<Self> {
+        let mut rt = Runtime::new(DEFAULT_GAS_LIMIT)
+            .map_err(|_| anyhow!("Failed to initialize jstz's JavaScript runtime."))?;
+
+        set_js_logger(&PrettyLogger);
+
+        let realm = rt.realm().clone();
+
+        realm.register_api(WebApi, &mut rt);
+        realm.register_api(
+            ProtocolApi {
+                address,
+                operation_hash: Blake2b::from(b"fake_op_hash".as_ref()),
+            },
+            &mut rt,
+        );
+
+        // realm.register_api(ConsoleApi, rt.context());
+        realm.register_api(StreamApi, rt.context());
+        realm.register_api(DebugApi, rt.context());
+
+        Ok(Self { rt, host, tx })
+    }
+
+    /// Evaluates `input` and prints its result
+    pub async fn eval(&mut self, input: &str) {
+        let result = evaluate(input, &mut self.rt, &mut self.host, &mut self.tx);
+        print_rt_result(result, &mut self.rt);
+    }
+}
+
+fn print_rt_result(result: JsResult<JsValue>, context: &mut Context) {
+    match result {
+        Ok(res) => {
+            if !res.is_undefined() {
+                info!(
+                    "{}",
+                    if res.is_callable() {
+                        res.to_string(context)
+                            .expect("Expected [[toString]] to be defined.")
+                            .to_std_string_escaped()
+                    } else {
+                        res.display().to_string()
+                    },
+                );
+            }
+
+            if context
+                .global_object()
+                .set(js_string!("_"), res, false, context)
+                .is_err()
+            {
+                warn!("Couldn't set '_' to REPL result.");
+            }
+        }
+        Err(e) => {
+            error!("Uncaught {e}")
+        }
+    }
+}
+
+fn evaluate(
+    input: &str,
+    rt: &mut Runtime,
+    hrt: &mut (impl HostRuntime + 'static),
+    tx: &mut Transaction,
+) -> JsResult<JsValue> {
+    runtime::enter_js_host_context(hrt, tx, || {
+        let result = rt.eval(Source::from_bytes(input))?;
+        jstz_core::future::block_on(async {
+            rt.run_event_loop().await;
+            rt.resolve_value(&result).await
+        })
+    })
+}
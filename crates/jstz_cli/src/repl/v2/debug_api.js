@@ -0,0 +1,42 @@
+import { inspect } from "ext:deno_console/01_console.js";
+
+const Kv = {
+  get: (account, key) => globalThis.Deno.core.ops.op_debug_kv_get(account, key),
+  set: (account, key, value) =>
+    globalThis.Deno.core.ops.op_debug_kv_set(account, key, value),
+  delete: (account, key) =>
+    globalThis.Deno.core.ops.op_debug_kv_delete(account, key),
+  has: (account, key) => globalThis.Deno.core.ops.op_debug_kv_has(account, key),
+};
+
+const Account = {
+  balance: (account) => globalThis.Deno.core.ops.op_debug_balance(account),
+  setBalance: (account, balance) =>
+    globalThis.Deno.core.ops.op_debug_set_balance(account, balance),
+  code: (account) => globalThis.Deno.core.ops.op_debug_code(account),
+  setCode: (account, code) =>
+    globalThis.Deno.core.ops.op_debug_set_code(account, code),
+};
+
+// Formats the REPL result `value`, or returns undefined if there is nothing to print
+function inspectResult(value) {
+  if (value === undefined) {
+    return undefined;
+  }
+  return inspect(value, { colors: false });
+}
+
+Object.defineProperties(globalThis, {
+  jstz: {
+    value: Object.freeze({ Kv, Account }),
+    enumerable: false,
+    configurable: false,
+    writable: false,
+  },
+  [Symbol.for("jstz.repl.inspect")]: {
+    value: inspectResult,
+    enumerable: false,
+    configurable: false,
+    writable: false,
+  },
+});
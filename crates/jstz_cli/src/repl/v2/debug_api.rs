@@ -0,0 +1,152 @@
+use deno_core::{extension, op2, OpState, ToJsBuffer};
+use deno_error::JsErrorBox;
+use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+use jstz_proto::{
+    context::account::{Account, Address},
+    runtime::{Kv, KvValue, RuntimeVersion},
+};
+use jstz_runtime::RuntimeContext;
+
+use crate::repl;
+
+/// Script printing the last REPL result `_`, evaluating to `undefined` if there is
+/// nothing to print
+pub const INSPECT_LAST_RESULT: &str = "globalThis[Symbol.for('jstz.repl.inspect')](_)";
+
+type Result<T> = std::result::Result<T, JsErrorBox>;
+
+fn debug_error(err: impl std::fmt::Display) -> JsErrorBox {
+    JsErrorBox::generic(err.to_string())
+}
+
+fn try_parse_address(account: &str) -> Result<Address> {
+    Address::from_base58(account)
+        .map_err(|_| JsErrorBox::type_error("Could not parse the address."))
+}
+
+fn try_parse_smart_function_address(account: &str) -> Result<SmartFunctionHash> {
+    SmartFunctionHash::from_base58(account)
+        .map_err(|_| JsErrorBox::type_error("Could not parse the address."))
+}
+
+/// A [`KvValue`] as returned to the REPL. Byte values are returned as `Uint8Array`s.
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum JsKvValue {
+    Json(serde_json::Value),
+    Bytes(ToJsBuffer),
+}
+
+#[op2]
+#[serde]
+fn op_debug_kv_get(
+    state: &mut OpState,
+    #[string] account: String,
+    #[string] key: String,
+) -> Result<Option<JsKvValue>> {
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    let value = Kv::new(account)
+        .get(host, tx, &key)
+        .map_err(debug_error)?
+        .map(|value| match value.clone() {
+            KvValue::Json(value) => JsKvValue::Json(value),
+            KvValue::Bytes(bytes) => JsKvValue::Bytes(bytes.into()),
+        });
+    Ok(value)
+}
+
+#[op2]
+fn op_debug_kv_set(
+    state: &mut OpState,
+    #[string] account: String,
+    #[string] key: String,
+    #[serde] value: serde_json::Value,
+) -> Result<()> {
+    let RuntimeContext { tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Kv::new(account)
+        .set(tx, &key, KvValue::Json(value))
+        .map_err(debug_error)
+}
+
+#[op2(fast)]
+fn op_debug_kv_delete(
+    state: &mut OpState,
+    #[string] account: String,
+    #[string] key: String,
+) -> Result<()> {
+    let RuntimeContext { tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Kv::new(account).delete(tx, &key).map_err(debug_error)
+}
+
+#[op2(fast)]
+fn op_debug_kv_has(
+    state: &mut OpState,
+    #[string] account: String,
+    #[string] key: String,
+) -> Result<bool> {
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Kv::new(account).has(host, tx, &key).map_err(debug_error)
+}
+
+#[op2(fast)]
+#[number]
+fn op_debug_balance(state: &mut OpState, #[string] account: String) -> Result<u64> {
+    let address = try_parse_address(&account)?;
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Account::balance(host, tx, &address).map_err(debug_error)
+}
+
+#[op2(fast)]
+fn op_debug_set_balance(
+    state: &mut OpState,
+    #[string] account: String,
+    #[number] balance: u64,
+) -> Result<()> {
+    let address = try_parse_address(&account)?;
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Account::set_balance(host, tx, &address, balance).map_err(debug_error)
+}
+
+#[op2]
+#[string]
+fn op_debug_code(
+    state: &mut OpState,
+    #[string] account: String,
+) -> Result<Option<String>> {
+    let address = try_parse_smart_function_address(&account)?;
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    let code = Account::function_code(host, tx, &address).map_err(debug_error)?;
+    Ok(match &*code {
+        "" => None,
+        code => Some(code.to_string()),
+    })
+}
+
+#[op2(fast)]
+fn op_debug_set_code(
+    state: &mut OpState,
+    #[string] account: String,
+    #[string] code: String,
+) -> Result<()> {
+    let address = try_parse_smart_function_address(&account)?;
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    // Code set from the REPL is written for the runtime of the REPL
+    repl::set_code(host, tx, &address, code, RuntimeVersion::CURRENT).map_err(debug_error)
+}
+
+extension!(
+    jstz_debug,
+    deps = [deno_console],
+    ops = [
+        op_debug_kv_get,
+        op_debug_kv_set,
+        op_debug_kv_delete,
+        op_debug_kv_has,
+        op_debug_balance,
+        op_debug_set_balance,
+        op_debug_code,
+        op_debug_set_code
+    ],
+    esm_entry_point = "ext:jstz_debug/debug_api.js",
+    esm = [dir "src/repl/v2", "debug_api.js"]
+);
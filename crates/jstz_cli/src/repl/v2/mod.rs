@@ -0,0 +1,225 @@
+use deno_core::v8;
+use jstz_core::kv::Transaction;
+use jstz_crypto::{
+    public_key_hash::PublicKeyHash, smart_function_hash::SmartFunctionHash,
+};
+use jstz_proto::runtime::v2::new_repl_runtime;
+use jstz_runtime::JstzRuntime;
+use log::{error, info};
+use regex::Regex;
+use tezos_smart_rollup_mock::MockHost;
+
+use crate::error::Result;
+
+mod debug_api;
+
+/// REPL session on the v2 runtime
+pub struct Session {
+    // Dropped before the host and transaction it refers to
+    runtime: JstzRuntime,
+    _host: Box<MockHost>,
+    _tx: Transaction,
+}
+
+impl Session {
+    pub fn new(
+        host: MockHost,
+        mut tx: Transaction,
+        source: PublicKeyHash,
+        address: SmartFunctionHash,
+    ) -> Result<Self> {
+        // The runtime keeps a pointer to the host, so it must not move
+        let mut host = Box::new(host);
+        let runtime = new_repl_runtime(
+            host.as_mut(),
+            &mut tx,
+            source,
+            address,
+            vec![debug_api::jstz_debug::init_ops_and_esm()],
+        );
+        Ok(Self {
+            runtime,
+            _host: host,
+            _tx: tx,
+        })
+    }
+
+    /// Evaluates `input`, awaiting its result if it is a promise, and prints it
+    pub async fn eval(&mut self, input: &str) {
+        let code = wrap_top_level_await(&mut self.runtime, input);
+        match self.runtime.execute_and_resolve(&code).await {
+            Ok(value) => {
+                let scope = &mut self.runtime.handle_scope();
+                let value = v8::Local::new(scope, value);
+                let global = scope.get_current_context().global(scope);
+                let key = v8::String::new(scope, "_").unwrap();
+                global.set(scope, key.into(), value);
+            }
+            Err(err) => {
+                error!("Uncaught {err}");
+                return;
+            }
+        }
+        match self
+            .runtime
+            .execute_with_result::<Option<String>>(debug_api::INSPECT_LAST_RESULT)
+        {
+            Ok(Some(output)) => info!("{output}"),
+            Ok(None) => {}
+            Err(err) => error!("Uncaught {err}"),
+        }
+    }
+}
+
+/// Wraps `input` in an async function if it uses `await`, since scripts cannot await
+/// at the top level. Expressions keep their value, while top level declarations in
+/// statements are turned into assignments to globals so that they outlive the function.
+fn wrap_top_level_await(runtime: &mut JstzRuntime, input: &str) -> String {
+    if !input.contains("await") {
+        return input.to_string();
+    }
+    let expression = format!("(async () => (\n{}\n))()", input.trim_end_matches(';'));
+    if compiles(runtime, &expression) {
+        expression
+    } else {
+        format!("(async () => {{\n{}\n}})()", hoist_declarations(input))
+    }
+}
+
+/// Rewrites the declarations of `input` that are not nested in a block, such as
+/// `const x = 1` or `function f() {}`, into assignments to properties of `globalThis`
+fn hoist_declarations(input: &str) -> String {
+    let variable = Regex::new(r"^(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*=").unwrap();
+    let function = Regex::new(
+        r"^(?:async\s+)?(?:function(?:\s*\*\s*|\s+)|class\s+)([A-Za-z_$][\w$]*)",
+    )
+    .unwrap();
+
+    let mut output = String::with_capacity(input.len());
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut statement_start = true;
+    let mut skip_to = 0;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if i < skip_to {
+            continue;
+        }
+        if let Some(q) = quote {
+            output.push(c);
+            if c == '\\' {
+                output.extend(chars.next().map(|(_, c)| c));
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if statement_start && depth == 0 && !c.is_whitespace() {
+            statement_start = false;
+            let rest = &input[i..];
+            if let Some(captures) = variable.captures(rest) {
+                output.push_str(&format!("globalThis.{} =", &captures[1]));
+                skip_to = i + captures[0].len();
+                continue;
+            }
+            if let Some(captures) = function.captures(rest) {
+                output.push_str(&format!("globalThis.{} = ", &captures[1]));
+            }
+        }
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ';' | '\n' if depth == 0 => statement_start = true,
+            _ => {}
+        }
+        output.push(c);
+    }
+    output
+}
+
+fn compiles(runtime: &mut JstzRuntime, code: &str) -> bool {
+    let scope = &mut runtime.handle_scope();
+    let scope = &mut v8::TryCatch::new(scope);
+    let Some(source) = v8::String::new(scope, code) else {
+        return false;
+    };
+    v8::Script::compile(scope, source, None).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{wrap_top_level_await, Session};
+    use crate::repl::DEFAULT_SMART_FUNCTION_ADDRESS;
+
+    fn session() -> Session {
+        let mut tx = Transaction::default();
+        tx.begin();
+        Session::new(
+            MockHost::default(),
+            tx,
+            jstz_mock::account1(),
+            SmartFunctionHash::from_base58(DEFAULT_SMART_FUNCTION_ADDRESS).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn wraps_top_level_await() {
+        let mut session = session();
+        assert_eq!(wrap_top_level_await(&mut session.runtime, "1 + 1"), "1 + 1");
+        assert_eq!(
+            wrap_top_level_await(&mut session.runtime, "await f();"),
+            "(async () => (\nawait f()\n))()"
+        );
+        assert_eq!(
+            wrap_top_level_await(&mut session.runtime, "const x = await f();"),
+            "(async () => {\nglobalThis.x = await f();\n})()"
+        );
+        assert_eq!(
+            wrap_top_level_await(
+                &mut session.runtime,
+                "async function g() { const y = 1; }; let s = 'const z = 2'; await g();"
+            ),
+            "(async () => {\nglobalThis.g = async function g() { const y = 1; }; \
+             globalThis.s = 'const z = 2'; await g();\n})()"
+        );
+    }
+
+    #[tokio::test]
+    async fn eval_sets_awaited_result() {
+        let mut session = session();
+        session.eval("await Promise.resolve(41)").await;
+        session.eval("_ + 1").await;
+        let result = session.runtime.execute_with_result::<u32>("_").unwrap();
+        assert_eq!(result, 42);
+    }
+
+    #[tokio::test]
+    async fn eval_keeps_declarations() {
+        let mut session = session();
+        session.eval("const x = 20").await;
+        session
+            .eval("jstz.Account.setBalance(Ledger.selfAddress, x)")
+            .await;
+        let result = session
+            .runtime
+            .execute_with_result::<u64>("jstz.Account.balance(Ledger.selfAddress) + x")
+            .unwrap();
+        assert_eq!(result, 40);
+    }
+
+    #[tokio::test]
+    async fn eval_keeps_awaited_declarations() {
+        let mut session = session();
+        session
+            .eval("const x = await Promise.resolve(20); let y = x + 1")
+            .await;
+        let result = session.runtime.execute_with_result::<u32>("x + y").unwrap();
+        assert_eq!(result, 41);
+    }
+}
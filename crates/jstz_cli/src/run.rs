@@ -6,7 +6,7 @@ use jstz_proto::context::account::{Address, Addressable};
 use jstz_proto::executor::smart_function::{JSTZ_HOST, NOOP_PATH, X_JSTZ_TRANSFER};
 use jstz_proto::{
     operation::{Content as OperationContent, Operation, RunFunction, SignedOperation},
-    receipt::{ReceiptContent, ReceiptResult},
+    receipt::{ReceiptContent, ReceiptResult, RunFunctionReceipt},
 };
 use log::{debug, info};
 use serde_json::Value;
@@ -34,10 +34,14 @@ pub enum Host {
 }
 
 impl Host {
-    pub fn resolve(&self, config: &Config) -> Result<String> {
+    pub fn resolve(
+        &self,
+        config: &Config,
+        network: &Option<NetworkName>,
+    ) -> Result<String> {
         match self {
             Host::AddressOrAlias(address_or_alias) => {
-                Ok(address_or_alias.resolve(config)?.to_base58())
+                Ok(address_or_alias.resolve(config, network)?.to_base58())
             }
             Host::Jstz => Ok(JSTZ_HOST.to_string()),
         }
@@ -124,7 +128,7 @@ pub async fn exec_transfer(
     network: Option<NetworkName>,
 ) -> Result<()> {
     let cfg = Config::load().await?;
-    let to = AddressOrAlias::resolve_or_use_current_user(Some(to), &cfg)?;
+    let to = AddressOrAlias::resolve_or_use_current_user(Some(to), &cfg, &network)?;
     let url = match &to {
         Address::User(_) => format!("jstz://{to}"),
         // for sf address, ignore the function execution and just transfer the amount
@@ -145,12 +149,18 @@ pub async fn exec_transfer(
 }
 
 pub async fn exec(args: RunArgs) -> Result<()> {
+    exec_with_receipt(args).await?;
+    Ok(())
+}
+
+/// Same as [exec] but returns the receipt of the run operation
+pub async fn exec_with_receipt(args: RunArgs) -> Result<RunFunctionReceipt> {
     // 1. Get the current user (checking if we are logged in)
     let mut cfg = Config::load().await?;
-    account::login_quick(&mut cfg).await?;
+    account::login_quick(&mut cfg, &args.network).await?;
     cfg.reload().await?;
 
-    let (_, user) = cfg.accounts.current_user().ok_or(anyhow!(
+    let (_, user) = cfg.accounts(&args.network).current_user().ok_or(anyhow!(
         "Failed to setup the account. Please run `{}`.",
         styles::command("jstz login")
     ))?;
@@ -167,7 +177,7 @@ pub async fn exec(args: RunArgs) -> Result<()> {
     ))?;
 
     let parsed_host = Host::try_from(host)?;
-    let resolved_host = parsed_host.resolve(&cfg)?;
+    let resolved_host = parsed_host.resolve(&cfg, &args.network)?;
 
     if host != resolved_host.as_str() {
         debug!("Resolved host '{}' to '{}'.", host, resolved_host);
@@ -232,7 +242,7 @@ pub async fn exec(args: RunArgs) -> Result<()> {
 
     debug!("Operation hash: {}", hash.to_string());
 
-    let signed_op = SignedOperation::new(user.secret_key.sign(&hash)?, op);
+    let signed_op = SignedOperation::new(user.sign(&hash).await?, op);
 
     debug!("Signed operation: {:?}", signed_op);
 
@@ -244,7 +254,7 @@ pub async fn exec(args: RunArgs) -> Result<()> {
 
     if args.trace {
         if let Host::AddressOrAlias(address_or_alias) = parsed_host {
-            let address = address_or_alias.resolve(&cfg)?;
+            let address = address_or_alias.resolve(&cfg, &args.network)?;
             spawn_trace(&address, &jstz_client).await?;
         }
     }
@@ -253,12 +263,8 @@ pub async fn exec(args: RunArgs) -> Result<()> {
     let receipt = jstz_client.wait_for_operation_receipt(&hash).await?;
 
     debug!("Receipt: {:?}", receipt);
-    let (status_code, headers, body) = match receipt.result {
-        ReceiptResult::Success(ReceiptContent::RunFunction(run_function)) => (
-            run_function.status_code,
-            run_function.headers,
-            run_function.body,
-        ),
+    let run_function = match receipt.result {
+        ReceiptResult::Success(ReceiptContent::RunFunction(run_function)) => run_function,
         ReceiptResult::Success(_) => {
             bail!("Expected a `RunFunction` receipt, but got something else.")
         }
@@ -267,8 +273,8 @@ pub async fn exec(args: RunArgs) -> Result<()> {
     };
 
     if args.include_response_headers {
-        info!("{}", status_code);
-        for (key, value) in headers.iter() {
+        info!("{}", run_function.status_code);
+        for (key, value) in run_function.headers.iter() {
             let header_value = value.to_str();
             if let Ok(hval) = header_value {
                 info!("{}: {}", key, hval);
@@ -282,13 +288,13 @@ pub async fn exec(args: RunArgs) -> Result<()> {
         info!("\n")
     }
 
-    if let Some(body) = body.0 {
-        let json = serde_json::from_slice::<Value>(&body)
+    if let Some(body) = &run_function.body.0 {
+        let json = serde_json::from_slice::<Value>(body)
             .and_then(|s| serde_json::to_string_pretty(&s));
         if json.is_ok() {
             info!("{}", json.unwrap());
         } else {
-            let body = String::from_utf8(body);
+            let body = String::from_utf8(body.clone());
             if body.is_ok() {
                 info!("{}", body.unwrap());
             } else {
@@ -299,7 +305,7 @@ pub async fn exec(args: RunArgs) -> Result<()> {
 
     cfg.save()?;
 
-    Ok(())
+    Ok(run_function)
 }
 
 fn validate_scheme(url: &Uri) -> Result<()> {
@@ -1,6 +1,7 @@
 mod consts;
 mod container;
 mod jstzd;
+mod snapshot;
 
 use crate::config::Config;
 use crate::error::{bail, bail_user_error};
@@ -43,6 +44,9 @@ pub enum Command {
         #[clap(long, short, default_value = "false")]
         detach: bool,
     },
+    /// 📸 Saves or loads snapshots of the sandbox.
+    #[command(subcommand)]
+    Snapshot(snapshot::Command),
 }
 
 pub async fn start(detach: bool, use_container: bool) -> Result<()> {
@@ -84,6 +88,7 @@ pub async fn exec(use_container: bool, command: Command) -> Result<()> {
             Ok(())
         }
         Command::Restart { detach } => restart(detach, use_container).await,
+        Command::Snapshot(command) => snapshot::exec(use_container, command).await,
     }
 }
 
@@ -0,0 +1,245 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::Subcommand;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    config::{jstz_home_dir, AccountConfig, Config, NetworkName},
+    error::{bail, bail_user_error, Result},
+    sandbox::{assert_sandbox_running, consts::JSTZD_SERVER_BASE_URL},
+    term::styles,
+};
+
+/// Copy of the data directories of the sandbox processes.
+const SANDBOX_DIR: &str = "sandbox";
+/// Copy of the accounts of the `dev` network in the CLI config.
+const ACCOUNTS_FILE: &str = "accounts.json";
+/// Copy of the accounts shared by all networks in the CLI config, which hold the
+/// user accounts used on the `dev` network too.
+const SHARED_ACCOUNTS_FILE: &str = "shared_accounts.json";
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 📸 Saves the state of the running sandbox and the CLI accounts used on the `dev`
+    /// network in a snapshot.
+    Save {
+        /// Name of the snapshot. An existing snapshot with the same name is replaced.
+        name: String,
+    },
+    /// ⏪ Resets the running sandbox and the CLI accounts used on the `dev` network to
+    /// a snapshot.
+    Load {
+        /// Name of the snapshot.
+        name: String,
+    },
+}
+
+fn snapshots_dir() -> PathBuf {
+    jstz_home_dir().join("snapshots")
+}
+
+fn snapshot_dir(snapshots_dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(std::path::is_separator)
+    {
+        bail_user_error!("Invalid snapshot name '{}'.", name);
+    }
+    Ok(snapshots_dir.join(name))
+}
+
+async fn request(jstzd_server_base_url: &str, action: &str, path: &Path) -> Result<()> {
+    let res = reqwest::Client::new()
+        .post(format!("{jstzd_server_base_url}/snapshot/{action}"))
+        .json(&json!({ "path": path }))
+        .send()
+        .await
+        .context("failed to reach the sandbox")?;
+    if !res.status().is_success() {
+        bail!(
+            "Failed to {} the snapshot: {}",
+            action,
+            res.text().await.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+async fn save(
+    jstzd_server_base_url: &str,
+    snapshots_dir: &Path,
+    name: &str,
+    cfg: &Config,
+) -> Result<()> {
+    let dir = snapshot_dir(snapshots_dir, name)?;
+    assert_sandbox_running(jstzd_server_base_url).await?;
+
+    fs::create_dir_all(dir.join(SANDBOX_DIR))?;
+    // jstzd may run in another working directory
+    let dir = fs::canonicalize(dir)?;
+    info!("Saving snapshot '{}'...", name);
+    request(jstzd_server_base_url, "save", &dir.join(SANDBOX_DIR)).await?;
+    fs::write(
+        dir.join(ACCOUNTS_FILE),
+        serde_json::to_string_pretty(
+            &cfg.network_accounts
+                .get(&NetworkName::Dev)
+                .cloned()
+                .unwrap_or_default(),
+        )?,
+    )?;
+    fs::write(
+        dir.join(SHARED_ACCOUNTS_FILE),
+        serde_json::to_string_pretty(&cfg.shared_accounts)?,
+    )?;
+
+    info!(
+        "Snapshot '{}' saved. Run {} to reset the sandbox to it.",
+        name,
+        styles::command(format!("jstz sandbox snapshot load {name}"))
+    );
+    Ok(())
+}
+
+async fn load(
+    jstzd_server_base_url: &str,
+    snapshots_dir: &Path,
+    name: &str,
+    cfg: &mut Config,
+) -> Result<()> {
+    let dir = snapshot_dir(snapshots_dir, name)?;
+    if !dir.join(SANDBOX_DIR).is_dir() {
+        bail_user_error!("Snapshot '{}' does not exist.", name);
+    }
+    let accounts: AccountConfig =
+        serde_json::from_str(&fs::read_to_string(dir.join(ACCOUNTS_FILE))?)
+            .context("failed to read the accounts of the snapshot")?;
+    // Snapshots saved before shared accounts were saved leave them unchanged
+    let shared_accounts: Option<AccountConfig> =
+        match fs::read_to_string(dir.join(SHARED_ACCOUNTS_FILE)) {
+            Ok(json) => Some(
+                serde_json::from_str(&json)
+                    .context("failed to read the shared accounts of the snapshot")?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => Err(e)?,
+        };
+    assert_sandbox_running(jstzd_server_base_url).await?;
+
+    let dir = fs::canonicalize(dir)?;
+    info!("Loading snapshot '{}'...", name);
+    request(jstzd_server_base_url, "load", &dir.join(SANDBOX_DIR)).await?;
+    cfg.network_accounts.insert(NetworkName::Dev, accounts);
+    if let Some(shared_accounts) = shared_accounts {
+        cfg.shared_accounts = shared_accounts;
+    }
+    cfg.save()?;
+
+    info!("Sandbox reset to snapshot '{}'.", name);
+    Ok(())
+}
+
+pub async fn exec(use_container: bool, command: Command) -> Result<()> {
+    if use_container {
+        bail_user_error!("Snapshots are not supported for sandboxes in a container.");
+    }
+    let mut cfg = Config::load().await?;
+    match command {
+        Command::Save { name } => {
+            save(JSTZD_SERVER_BASE_URL, &snapshots_dir(), &name, &cfg).await
+        }
+        Command::Load { name } => {
+            load(JSTZD_SERVER_BASE_URL, &snapshots_dir(), &name, &mut cfg).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_names() {
+        let dir = Path::new("/snapshots");
+        assert_eq!(snapshot_dir(dir, "foo").unwrap(), dir.join("foo"));
+        for name in ["", "..", ".hidden", "foo/bar"] {
+            assert!(snapshot_dir(dir, name).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn save_snapshot() {
+        let snapshots = tempfile::tempdir().unwrap();
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/health").create();
+        let mock = server
+            .mock("POST", "/snapshot/save")
+            .match_body(mockito::Matcher::Json(json!({
+                "path": fs::canonicalize(snapshots.path())
+                    .unwrap()
+                    .join("foo/sandbox")
+            })))
+            .with_status(204)
+            .create();
+
+        let mut cfg = Config::default();
+        cfg.accounts_mut(&Some(NetworkName::Dev)).current_alias =
+            Some("alice".to_string());
+        cfg.shared_accounts.current_alias = Some("bob".to_string());
+        save(&server.url(), snapshots.path(), "foo", &cfg)
+            .await
+            .unwrap();
+
+        mock.assert();
+        let accounts: AccountConfig = serde_json::from_str(
+            &fs::read_to_string(snapshots.path().join("foo/accounts.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(accounts.current_alias(), Some("alice"));
+        let shared_accounts: AccountConfig = serde_json::from_str(
+            &fs::read_to_string(snapshots.path().join("foo/shared_accounts.json"))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(shared_accounts.current_alias(), Some("bob"));
+    }
+
+    #[tokio::test]
+    async fn save_snapshot_fails() {
+        let snapshots = tempfile::tempdir().unwrap();
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/health").create();
+        server
+            .mock("POST", "/snapshot/save")
+            .with_status(500)
+            .with_body("failed to copy 'octez-node'")
+            .create();
+
+        let err = save(&server.url(), snapshots.path(), "foo", &Config::default())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to save the snapshot: failed to copy 'octez-node'"
+        );
+        assert!(!snapshots.path().join("foo/accounts.json").exists());
+        assert!(!snapshots.path().join("foo/shared_accounts.json").exists());
+    }
+
+    #[tokio::test]
+    async fn load_missing_snapshot() {
+        let snapshots = tempfile::tempdir().unwrap();
+        let err = load(
+            "http://dummy",
+            snapshots.path(),
+            "foo",
+            &mut Config::default(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Snapshot 'foo' does not exist.");
+    }
+}
@@ -0,0 +1,576 @@
+use std::{fmt, process::Command as ProcessCommand, time::Duration};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use dialoguer::Password;
+use jstz_crypto::{
+    public_key::PublicKey, public_key_hash::PublicKeyHash, secret_key::SecretKey,
+    signature::Signature,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as};
+use url::Url;
+
+use crate::{
+    agent,
+    error::{anyhow, bail_user_error, user_error, Result},
+};
+
+/// Environment variable read for the keystore password instead of prompting for it.
+pub const KEYSTORE_PASSWORD_ENV: &str = "JSTZ_KEYSTORE_PASSWORD";
+
+/// How long an unlocked secret key stays cached before the password is asked again.
+pub const UNLOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Where the secret key of a user account lives.
+///
+/// Untagged so that configuration files written before encrypted keys and
+/// external signers existed (with a plain `secret_key` field) still load.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum KeySource {
+    /// Secret key stored in plain text in the config file.
+    Plain { secret_key: SecretKey },
+    /// Secret key encrypted with a password-derived key.
+    Encrypted {
+        encrypted_secret_key: EncryptedSecretKey,
+    },
+    /// Signing is delegated to an external signer.
+    External { signer: ExternalSigner },
+}
+
+impl KeySource {
+    /// Signs `message` on behalf of the account `address`.
+    ///
+    /// Signatures produced by external signers are checked against `public_key`
+    /// so that a misconfigured signer fails here rather than in the rollup.
+    pub async fn sign(
+        &self,
+        address: &PublicKeyHash,
+        public_key: &PublicKey,
+        message: &[u8],
+    ) -> Result<Signature> {
+        match self {
+            KeySource::Plain { secret_key } => Ok(secret_key.sign(message)?),
+            KeySource::Encrypted {
+                encrypted_secret_key,
+            } => encrypted_secret_key.sign(address, message),
+            KeySource::External { signer } => {
+                let signature = signer.sign(address, message).await?;
+                signature.verify(public_key, message).map_err(|_| {
+                    user_error!(
+                        "The signer '{}' returned a signature that does not match the public key {}.",
+                        signer,
+                        public_key
+                    )
+                })?;
+                Ok(signature)
+            }
+        }
+    }
+}
+
+/// Key derivation function used to turn a password into an encryption key.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum Kdf {
+    Argon2id {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::Argon2id {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Kdf {
+    fn derive_key(&self, password: &str, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+        match self {
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                let params = Params::new(*m_cost, *t_cost, *p_cost, Some(KEY_LEN))
+                    .map_err(|e| anyhow!("Invalid key derivation parameters: {}", e))?;
+                let mut key = [0u8; KEY_LEN];
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), salt, &mut key)
+                    .map_err(|e| anyhow!("Failed to derive the encryption key: {}", e))?;
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// Authenticated cipher used to encrypt the secret key.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cipher {
+    #[default]
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305,
+}
+
+/// A secret key encrypted with a password.
+///
+/// The address of the account is used as associated data, so an encrypted key
+/// cannot be moved to another account entry without failing to decrypt.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedSecretKey {
+    pub kdf: Kdf,
+    pub cipher: Cipher,
+    #[serde_as(as = "Base64")]
+    pub salt: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    pub nonce: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedSecretKey {
+    pub fn encrypt(
+        secret_key: &SecretKey,
+        address: &PublicKeyHash,
+        password: &str,
+    ) -> Result<Self> {
+        Self::encrypt_with(Kdf::default(), secret_key, address, password)
+    }
+
+    fn encrypt_with(
+        kdf: Kdf,
+        secret_key: &SecretKey,
+        address: &PublicKeyHash,
+        password: &str,
+    ) -> Result<Self> {
+        let mut salt = vec![0u8; SALT_LEN];
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = kdf.derive_key(password, &salt)?;
+        let aad = address.to_string();
+        let ciphertext = XChaCha20Poly1305::new_from_slice(&key)
+            .map_err(|_| anyhow!("Invalid encryption key length"))?
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: secret_key.to_base58().as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt the secret key"))?;
+
+        Ok(Self {
+            kdf,
+            cipher: Cipher::XChaCha20Poly1305,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    pub fn decrypt(&self, address: &PublicKeyHash, password: &str) -> Result<SecretKey> {
+        if self.nonce.len() != NONCE_LEN {
+            bail_user_error!("The encrypted secret key of {} is corrupted.", address);
+        }
+
+        let key = self.kdf.derive_key(password, &self.salt)?;
+        let aad = address.to_string();
+        let plaintext = match self.cipher {
+            Cipher::XChaCha20Poly1305 => XChaCha20Poly1305::new_from_slice(&key)
+                .map_err(|_| anyhow!("Invalid encryption key length"))?
+                .decrypt(
+                    XNonce::from_slice(&self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: aad.as_bytes(),
+                    },
+                )
+                .map_err(|_| {
+                    user_error!("Wrong password for the secret key of {}.", address)
+                })?,
+        };
+
+        let secret_key = std::str::from_utf8(&plaintext)
+            .ok()
+            .and_then(|sk| SecretKey::from_base58(sk).ok())
+            .ok_or_else(|| {
+                user_error!("The encrypted secret key of {} is corrupted.", address)
+            })?;
+
+        Ok(secret_key)
+    }
+
+    /// Signs `message`, prompting for the password unless the key was unlocked
+    /// less than [`UNLOCK_TIMEOUT`] ago. Unlocked keys are kept by the key agent,
+    /// which signs with them so that consecutive commands do not ask for the
+    /// password again.
+    pub fn sign(&self, address: &PublicKeyHash, message: &[u8]) -> Result<Signature> {
+        if let Some(signature) = agent::sign(address, message) {
+            return Ok(signature);
+        }
+
+        let password = match std::env::var(KEYSTORE_PASSWORD_ENV) {
+            Ok(password) => password,
+            Err(_) => Password::new()
+                .with_prompt(format!("Enter the password for {address}"))
+                .interact()?,
+        };
+        let secret_key = self.decrypt(address, &password)?;
+        agent::add(address, &secret_key);
+
+        Ok(secret_key.sign(message)?)
+    }
+}
+
+/// Asks for the password of a new keystore entry, with confirmation.
+pub fn new_password() -> Result<String> {
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
+    }
+
+    let password = Password::new()
+        .with_prompt("Enter a password to encrypt the secret key")
+        .with_confirmation("Confirm the password", "The passwords do not match.")
+        .interact()?;
+
+    Ok(password)
+}
+
+/// A signer running outside of the `jstz` process.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExternalSigner {
+    /// An HTTP signer compatible with `octez-signer`, e.g. `http://localhost:6732`.
+    Remote { url: Url },
+    /// A program invoked as `<command> <args> public-key`, printing the public key,
+    /// and `<command> <args> sign <hex bytes>`, printing the signature.
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    public_key: PublicKey,
+}
+
+#[derive(Deserialize)]
+struct SignatureResponse {
+    signature: Signature,
+}
+
+impl ExternalSigner {
+    /// Parses an `octez-client` style remote signer URI, e.g.
+    /// `http://localhost:6732/tz1...`, into the signer and the key it holds.
+    pub fn from_remote_uri(uri: &Url) -> Result<(Self, PublicKeyHash)> {
+        let mut url = uri.clone();
+        let address = uri
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|segment| PublicKeyHash::from_base58(segment).ok())
+            .ok_or_else(|| {
+                user_error!(
+                    "The remote signer URI '{}' must end with the address of the key, e.g. http://localhost:6732/tz1...",
+                    uri
+                )
+            })?;
+        url.path_segments_mut()
+            .map_err(|_| user_error!("Invalid remote signer URI '{}'.", uri))?
+            .pop();
+
+        Ok((ExternalSigner::Remote { url }, address))
+    }
+
+    /// Parses a whitespace-separated command line into a command signer.
+    pub fn from_command_line(command_line: &str) -> Result<Self> {
+        let mut words = command_line.split_whitespace().map(str::to_string);
+        let command = words
+            .next()
+            .ok_or_else(|| user_error!("The signer command must not be empty."))?;
+
+        Ok(ExternalSigner::Command {
+            command,
+            args: words.collect(),
+        })
+    }
+
+    /// Fetches the public key of the account. `address` selects the key on
+    /// remote signers and is ignored by command signers, which hold one key.
+    pub async fn public_key(&self, address: Option<&PublicKeyHash>) -> Result<PublicKey> {
+        match self {
+            ExternalSigner::Remote { url } => {
+                let address = address.ok_or_else(|| {
+                    anyhow!("An address is required to query a remote signer")
+                })?;
+                let response = reqwest::get(keys_endpoint(url, address)?).await?;
+                if !response.status().is_success() {
+                    bail_user_error!(
+                        "The remote signer '{}' does not know the key {}: {}",
+                        url,
+                        address,
+                        response.text().await?
+                    );
+                }
+                Ok(response.json::<PublicKeyResponse>().await?.public_key)
+            }
+            ExternalSigner::Command { .. } => {
+                let output = self.run_command(&["public-key"])?;
+                PublicKey::from_base58(&output).map_err(|_| {
+                    user_error!(
+                        "The signer '{}' returned an invalid public key: {}",
+                        self,
+                        output
+                    )
+                })
+            }
+        }
+    }
+
+    pub async fn sign(
+        &self,
+        address: &PublicKeyHash,
+        message: &[u8],
+    ) -> Result<Signature> {
+        match self {
+            ExternalSigner::Remote { url } => {
+                // `octez-signer` expects the hex-encoded bytes as a JSON string
+                let response = reqwest::Client::new()
+                    .post(keys_endpoint(url, address)?)
+                    .json(&hex::encode(message))
+                    .send()
+                    .await?;
+                if !response.status().is_success() {
+                    bail_user_error!(
+                        "The remote signer '{}' refused to sign: {}",
+                        url,
+                        response.text().await?
+                    );
+                }
+                Ok(response.json::<SignatureResponse>().await?.signature)
+            }
+            ExternalSigner::Command { .. } => {
+                let output = self.run_command(&["sign", &hex::encode(message)])?;
+                serde_json::from_value(serde_json::Value::String(output.clone())).map_err(
+                    |_| {
+                        user_error!(
+                            "The signer '{}' returned an invalid signature: {}",
+                            self,
+                            output
+                        )
+                    },
+                )
+            }
+        }
+    }
+
+    fn run_command(&self, extra_args: &[&str]) -> Result<String> {
+        let ExternalSigner::Command { command, args } = self else {
+            return Err(anyhow!("Not a command signer"));
+        };
+
+        let output = ProcessCommand::new(command)
+            .args(args)
+            .args(extra_args)
+            .output()
+            .map_err(|e| user_error!("Failed to run the signer '{}': {}", self, e))?;
+
+        if !output.status.success() {
+            bail_user_error!(
+                "The signer '{}' failed: {}",
+                self,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+fn keys_endpoint(url: &Url, address: &PublicKeyHash) -> Result<Url> {
+    let endpoint = format!("{}/keys/{}", url.as_str().trim_end_matches('/'), address);
+    Ok(Url::parse(&endpoint)?)
+}
+
+impl fmt::Display for ExternalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalSigner::Remote { url } => write!(f, "{url}"),
+            ExternalSigner::Command { command, args } => {
+                write!(f, "{command}")?;
+                for arg in args {
+                    write!(f, " {arg}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jstz_crypto::{
+        public_key::PublicKey, public_key_hash::PublicKeyHash, secret_key::SecretKey,
+    };
+    use url::Url;
+
+    use super::{EncryptedSecretKey, ExternalSigner, Kdf, KeySource};
+
+    const SECRET_KEY: &str = "edsk3AbxMYLgdY71xPEjWjXi5JCx6tSS8jhQ2mc1KczZ1JfPrTqSgM";
+    const PUBLIC_KEY: &str = "edpkukK9ecWxib28zi52nvbXTdsYt8rYcvmt5bdH8KjipWXm8sH3Qi";
+
+    // Cheap parameters keep the tests fast; the default ones are used in practice
+    const TEST_KDF: Kdf = Kdf::Argon2id {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn address() -> PublicKeyHash {
+        PublicKeyHash::from_base58("tz1ficxJFv7MUtsCimF8bmT9SYPDok52ySg6").unwrap()
+    }
+
+    #[test]
+    fn encrypted_secret_key_round_trip() {
+        let sk = SecretKey::from_base58(SECRET_KEY).unwrap();
+        let encrypted =
+            EncryptedSecretKey::encrypt_with(TEST_KDF, &sk, &address(), "password")
+                .unwrap();
+
+        assert!(!encrypted.ciphertext.is_empty());
+        assert_eq!(encrypted.decrypt(&address(), "password").unwrap(), sk);
+        assert_eq!(
+            encrypted
+                .decrypt(&address(), "wrong")
+                .unwrap_err()
+                .to_string(),
+            "Wrong password for the secret key of tz1ficxJFv7MUtsCimF8bmT9SYPDok52ySg6."
+        );
+
+        // the key is bound to the address it was encrypted for
+        let other =
+            PublicKeyHash::from_base58("tz1WrJFFhUrHeozPin2KY29WJPZ9GzkmpX3Y").unwrap();
+        assert!(encrypted.decrypt(&other, "password").is_err());
+    }
+
+    #[test]
+    fn key_source_json() {
+        let plain: KeySource =
+            serde_json::from_value(serde_json::json!({ "secret_key": SECRET_KEY }))
+                .unwrap();
+        assert!(matches!(plain, KeySource::Plain { .. }));
+
+        let sk = SecretKey::from_base58(SECRET_KEY).unwrap();
+        let encrypted = KeySource::Encrypted {
+            encrypted_secret_key: EncryptedSecretKey::encrypt_with(
+                TEST_KDF,
+                &sk,
+                &address(),
+                "password",
+            )
+            .unwrap(),
+        };
+        let json = serde_json::to_value(&encrypted).unwrap();
+        assert_eq!(json["encrypted_secret_key"]["kdf"]["name"], "argon2id");
+        assert_eq!(json["encrypted_secret_key"]["cipher"], "xchacha20-poly1305");
+        assert!(matches!(
+            serde_json::from_value(json).unwrap(),
+            KeySource::Encrypted { .. }
+        ));
+
+        let external: KeySource = serde_json::from_value(serde_json::json!({
+            "signer": { "kind": "remote", "url": "http://localhost:6732/" }
+        }))
+        .unwrap();
+        assert!(matches!(
+            external,
+            KeySource::External {
+                signer: ExternalSigner::Remote { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn from_remote_uri() {
+        let uri =
+            Url::parse("http://localhost:6732/tz1ficxJFv7MUtsCimF8bmT9SYPDok52ySg6")
+                .unwrap();
+        let (signer, key_address) = ExternalSigner::from_remote_uri(&uri).unwrap();
+        assert_eq!(key_address, address());
+        assert_eq!(signer.to_string(), "http://localhost:6732/");
+
+        let uri = Url::parse("http://localhost:6732").unwrap();
+        assert!(ExternalSigner::from_remote_uri(&uri).is_err());
+    }
+
+    #[test]
+    fn from_command_line() {
+        let signer = ExternalSigner::from_command_line("my-signer --key foo").unwrap();
+        assert_eq!(
+            signer,
+            ExternalSigner::Command {
+                command: "my-signer".to_string(),
+                args: vec!["--key".to_string(), "foo".to_string()],
+            }
+        );
+        assert_eq!(signer.to_string(), "my-signer --key foo");
+        assert!(ExternalSigner::from_command_line("  ").is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_signer() {
+        let sk = SecretKey::from_base58(SECRET_KEY).unwrap();
+        let pk = PublicKey::from_base58(PUBLIC_KEY).unwrap();
+        let message = b"operation hash";
+        let signature = sk.sign(message).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let path = format!("/keys/{}", address());
+        server
+            .mock("GET", path.as_str())
+            .with_body(format!(r#"{{"public_key":"{PUBLIC_KEY}"}}"#))
+            .create();
+        server
+            .mock("POST", path.as_str())
+            .match_body(format!(r#""{}""#, hex::encode(message)).as_str())
+            .with_body(format!(r#"{{"signature":"{signature}"}}"#))
+            .create();
+
+        let signer = ExternalSigner::Remote {
+            url: Url::parse(&server.url()).unwrap(),
+        };
+        assert_eq!(signer.public_key(Some(&address())).await.unwrap(), pk);
+
+        let source = KeySource::External { signer };
+        assert_eq!(
+            source.sign(&address(), &pk, message).await.unwrap(),
+            signature
+        );
+
+        // signatures that do not match the account public key are rejected
+        let other_pk = PublicKey::from_base58(
+            "edpkuXDAprNEQejWbm4wrTdeQAKABByijQd3xC8hZEttgrNVA9B6gi",
+        )
+        .unwrap();
+        assert!(source.sign(&address(), &other_pk, message).await.is_err());
+    }
+}
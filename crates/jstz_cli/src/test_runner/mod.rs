@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::ValueEnum;
+use log::info;
+
+use crate::error::{bail_user_error, user_error, Result};
+
+mod report;
+mod runner;
+mod spec;
+
+use report::TestResult;
+use runner::Runner;
+use spec::TestFile;
+
+const TEST_FILE_SUFFIX: &str = ".test.json";
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    /// Test Anything Protocol
+    #[default]
+    Tap,
+    /// JUnit XML
+    Junit,
+}
+
+/// Collects the test files at `path`, searching directories recursively and
+/// skipping `node_modules` and hidden directories.
+fn collect_test_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if entry.is_dir() {
+            if name != "node_modules" && !name.starts_with('.') {
+                collect_test_files(&entry, files)?;
+            }
+        } else if name.ends_with(TEST_FILE_SUFFIX) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+async fn run_file(path: &Path, results: &mut Vec<TestResult>) -> Result<()> {
+    let file: TestFile = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| user_error!("Invalid test file '{}': {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let runner = Runner::new(&file, dir)
+        .map_err(|e| user_error!("Invalid test file '{}': {}", path.display(), e))?;
+
+    for test in &file.tests {
+        let start = Instant::now();
+        let error = runner.run(&test.steps).await.err().map(|e| e.to_string());
+        results.push(TestResult {
+            file: path.display().to_string(),
+            name: test.name.clone(),
+            duration: start.elapsed(),
+            error,
+        });
+    }
+    Ok(())
+}
+
+pub async fn exec(
+    paths: Vec<PathBuf>,
+    format: Format,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut files = vec![];
+    for path in &paths {
+        if !path.exists() {
+            bail_user_error!("'{}' does not exist.", path.display());
+        }
+        collect_test_files(path, &mut files)?;
+    }
+    if files.is_empty() {
+        bail_user_error!("No test files (*{}) found.", TEST_FILE_SUFFIX);
+    }
+
+    let mut results = vec![];
+    for file in &files {
+        run_file(file, &mut results).await?;
+    }
+
+    let report = match format {
+        Format::Tap => report::tap(&results),
+        Format::Junit => report::junit(&results),
+    };
+    match output {
+        Some(output) => {
+            fs::write(&output, report)?;
+            info!("Test report written to {}", output.display());
+        }
+        None => print!("{report}"),
+    }
+
+    let failures = results.iter().filter(|r| r.error.is_some()).count();
+    if failures > 0 {
+        bail_user_error!("{} of {} tests failed.", failures, results.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_test_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "a.test.json",
+            "b.json",
+            "nested/c.test.json",
+            "node_modules/pkg/d.test.json",
+            ".git/e.test.json",
+        ] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+
+        let mut files = vec![];
+        collect_test_files(dir.path(), &mut files).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("a.test.json"),
+                dir.path().join("nested/c.test.json")
+            ]
+        );
+
+        let mut files = vec![];
+        collect_test_files(&dir.path().join("b.json"), &mut files).unwrap();
+        assert_eq!(files, vec![dir.path().join("b.json")]);
+    }
+}
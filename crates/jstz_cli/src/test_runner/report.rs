@@ -0,0 +1,151 @@
+use std::{fmt::Write, time::Duration};
+
+/// Outcome of a single test.
+pub struct TestResult {
+    /// Test file the test belongs to.
+    pub file: String,
+    pub name: String,
+    pub duration: Duration,
+    pub error: Option<String>,
+}
+
+/// Formats results in the Test Anything Protocol, version 13.
+pub fn tap(results: &[TestResult]) -> String {
+    let mut out = format!("TAP version 13\n1..{}\n", results.len());
+    for (i, result) in results.iter().enumerate() {
+        let status = if result.error.is_some() {
+            "not ok"
+        } else {
+            "ok"
+        };
+        let _ = writeln!(
+            out,
+            "{} {} - {}: {}",
+            status,
+            i + 1,
+            result.file,
+            result.name.replace('#', "\\#")
+        );
+        if let Some(error) = &result.error {
+            let _ = writeln!(out, "  ---\n  message: {:?}\n  ...", error);
+        }
+    }
+    out
+}
+
+/// Formats results as a JUnit XML report, with one test suite per test file.
+pub fn junit(results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|r| r.error.is_some()).count();
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"jstz\" tests=\"{}\" failures=\"{}\">\n",
+        results.len(),
+        failures
+    );
+
+    let mut files: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+    files.dedup();
+    for file in files {
+        let suite: Vec<_> = results.iter().filter(|r| r.file == file).collect();
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            escape(file),
+            suite.len(),
+            suite.iter().filter(|r| r.error.is_some()).count(),
+            suite.iter().map(|r| r.duration.as_secs_f64()).sum::<f64>()
+        );
+        for result in suite {
+            let _ = write!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&result.name),
+                escape(file),
+                result.duration.as_secs_f64()
+            );
+            match &result.error {
+                Some(error) => {
+                    let _ = writeln!(
+                        out,
+                        ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                        escape(error)
+                    );
+                }
+                None => out.push_str("/>\n"),
+            }
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+        .replace('\n', "&#10;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<TestResult> {
+        vec![
+            TestResult {
+                file: "counter.test.json".to_string(),
+                name: "increments".to_string(),
+                duration: Duration::from_millis(12),
+                error: None,
+            },
+            TestResult {
+                file: "counter.test.json".to_string(),
+                name: "decrements #2".to_string(),
+                duration: Duration::from_millis(8),
+                error: Some("Step 1: Expected status 200, found 500.".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn formats_tap() {
+        assert_eq!(
+            tap(&results()),
+            r#"TAP version 13
+1..2
+ok 1 - counter.test.json: increments
+not ok 2 - counter.test.json: decrements \#2
+  ---
+  message: "Step 1: Expected status 200, found 500."
+  ...
+"#
+        );
+    }
+
+    #[test]
+    fn formats_junit() {
+        assert_eq!(
+            junit(&results()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="jstz" tests="2" failures="1">
+  <testsuite name="counter.test.json" tests="2" failures="1" time="0.020">
+    <testcase name="increments" classname="counter.test.json" time="0.012"/>
+    <testcase name="decrements #2" classname="counter.test.json" time="0.008">
+      <failure message="Step 1: Expected status 200, found 500."/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn escapes_xml() {
+        assert_eq!(
+            escape(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+}
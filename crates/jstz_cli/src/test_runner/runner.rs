@@ -0,0 +1,449 @@
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Uri};
+use jstz_core::kv::Transaction;
+use jstz_crypto::{
+    keypair_from_mnemonic, public_key::PublicKey, public_key_hash::PublicKeyHash,
+    secret_key::SecretKey,
+};
+use jstz_mock::host::{JstzMockHost, INJECTOR, NATIVE_TICKETER};
+use jstz_proto::{
+    context::account::{Account, Address},
+    executor::{execute_operation, smart_function::X_JSTZ_TRANSFER},
+    operation::{Content, DeployFunction, Operation, RunFunction, SignedOperation},
+    receipt::{ReceiptContent, ReceiptResult, RunFunctionReceipt},
+    runtime::{Kv, KvValue},
+    HttpBody,
+};
+use serde_json::Value;
+use tezos_crypto_rs::hash::ContractKt1Hash;
+
+use super::spec::{
+    BalanceAssertion, KvAssertion, ResponseAssertion, RunStep, Step, TestFile,
+};
+use crate::error::{anyhow, bail, Result};
+
+/// Mnemonic the keys of test accounts are derived from, with the account name as
+/// the passphrase.
+const TEST_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// Runs the tests of a file against an in-process executor backed by a mock host.
+pub struct Runner<'a> {
+    file: &'a TestFile,
+    /// Code of the smart functions of the file, by name.
+    code: BTreeMap<&'a str, String>,
+    keys: BTreeMap<&'a str, (PublicKey, SecretKey)>,
+}
+
+/// State of a single test.
+struct Session<'a> {
+    host: JstzMockHost,
+    ticketer: ContractKt1Hash,
+    injector: PublicKey,
+    keys: &'a BTreeMap<&'a str, (PublicKey, SecretKey)>,
+    addresses: BTreeMap<String, Address>,
+}
+
+impl<'a> Runner<'a> {
+    /// Checks the accounts and functions of the file and loads the function code,
+    /// resolving paths relative to `dir`.
+    pub fn new(file: &'a TestFile, dir: &Path) -> Result<Self> {
+        let mut keys = BTreeMap::new();
+        for name in file.accounts.keys() {
+            let (pk, sk) = keypair_from_mnemonic(TEST_MNEMONIC, name)?;
+            keys.insert(name.as_str(), (pk, sk));
+        }
+
+        let mut code = BTreeMap::new();
+        for (name, function) in &file.functions {
+            if file.accounts.contains_key(name) {
+                bail!("'{}' is both an account and a function.", name);
+            }
+            if !file.accounts.contains_key(&function.deployer) {
+                bail!(
+                    "Deployer '{}' of function '{}' is not an account.",
+                    function.deployer,
+                    name
+                );
+            }
+            let path = dir.join(&function.code);
+            let function_code = if path.is_file() {
+                fs::read_to_string(&path)?
+            } else {
+                function.code.clone()
+            };
+            code.insert(name.as_str(), function_code);
+        }
+
+        Ok(Self { file, code, keys })
+    }
+
+    /// Runs the steps of a test from a fresh state, returning the first failure.
+    pub async fn run(&self, steps: &[Step]) -> Result<()> {
+        let mut session = self.setup().await?;
+        for (i, step) in steps.iter().enumerate() {
+            session
+                .step(step)
+                .await
+                .map_err(|e| anyhow!("Step {}: {}", i + 1, e))?;
+        }
+        Ok(())
+    }
+
+    async fn setup(&self) -> Result<Session<'_>> {
+        let mut host = JstzMockHost::default();
+        #[cfg(feature = "v2_runtime")]
+        jstz_proto::runtime::ProtocolContext::init_global(host.rt(), 0)?;
+
+        let mut session = Session {
+            ticketer: ContractKt1Hash::from_base58_check(NATIVE_TICKETER)?,
+            host,
+            injector: PublicKey::from_base58(INJECTOR)?,
+            keys: &self.keys,
+            addresses: BTreeMap::new(),
+        };
+
+        let mut tx = Transaction::default();
+        tx.begin();
+        for (name, account) in &self.file.accounts {
+            let address = Address::User(PublicKeyHash::from(&self.keys[name.as_str()].0));
+            Account::add_balance(&*session.host, &mut tx, &address, account.balance)?;
+            session.addresses.insert(name.clone(), address);
+        }
+        tx.commit(session.host.rt())?;
+
+        for (name, function) in &self.file.functions {
+            let content = Content::DeployFunction(DeployFunction {
+                function_code: self.code[name.as_str()].clone(),
+                account_credit: 0,
+            });
+            let address = match session.execute(&function.deployer, content).await? {
+                Ok(ReceiptContent::DeployFunction(receipt)) => {
+                    Address::SmartFunction(receipt.address)
+                }
+                Ok(_) => bail!("Unexpected receipt deploying '{}'.", name),
+                Err(e) => bail!("Failed to deploy '{}': {}", name, e),
+            };
+
+            let mut tx = Transaction::default();
+            tx.begin();
+            Account::add_balance(&*session.host, &mut tx, &address, function.balance)?;
+            tx.commit(session.host.rt())?;
+            session.addresses.insert(name.clone(), address);
+        }
+
+        Ok(session)
+    }
+}
+
+impl Session<'_> {
+    async fn step(&mut self, step: &Step) -> Result<()> {
+        match step {
+            Step::Run(run) => self.run(run).await,
+            Step::Kv(kv) => self.check_kv(kv),
+            Step::Balance(balance) => self.check_balance(balance),
+        }
+    }
+
+    fn address(&self, name: &str) -> Result<&Address> {
+        self.addresses
+            .get(name)
+            .ok_or(anyhow!("'{}' is not an account or function.", name))
+    }
+
+    /// Replaces `{{name}}` with the address of the account or function `name`.
+    fn substitute(&self, text: &str) -> String {
+        self.addresses
+            .iter()
+            .fold(text.to_string(), |text, (name, address)| {
+                text.replace(&format!("{{{{{name}}}}}"), &address.to_string())
+            })
+    }
+
+    /// Signs and executes an operation from the account `sender`, returning the
+    /// receipt content or the error of the failed operation.
+    async fn execute(
+        &mut self,
+        sender: &str,
+        content: Content,
+    ) -> Result<std::result::Result<ReceiptContent, String>> {
+        let (public_key, secret_key) = self
+            .keys
+            .get(sender)
+            .ok_or(anyhow!("Sender '{}' is not an account.", sender))?;
+        let nonce =
+            Account::storage_get_nonce(&*self.host, &PublicKeyHash::from(public_key))?;
+        let op = Operation {
+            public_key: public_key.clone(),
+            nonce,
+            content,
+        };
+        let signed_op = SignedOperation::new(secret_key.sign(op.hash())?, op);
+
+        let mut tx = Transaction::default();
+        tx.begin();
+        let receipt = execute_operation(
+            self.host.rt(),
+            &mut tx,
+            signed_op,
+            &self.ticketer,
+            &self.injector,
+        )
+        .await;
+        tx.commit(self.host.rt())?;
+
+        Ok(match receipt.result {
+            ReceiptResult::Success(content) => Ok(content),
+            ReceiptResult::Failed(e) => Err(e),
+        })
+    }
+
+    async fn run(&mut self, run: &RunStep) -> Result<()> {
+        let path = self.substitute(&run.path);
+        let uri: Uri = format!(
+            "jstz://{}{}{}",
+            self.address(&run.function)?,
+            if path.starts_with('/') { "" } else { "/" },
+            path
+        )
+        .parse()
+        .map_err(|_| anyhow!("Invalid path '{}'.", run.path))?;
+        let method = Method::from_str(&run.method)
+            .map_err(|_| anyhow!("Invalid HTTP method: {}", run.method))?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &run.headers {
+            headers.insert(
+                HeaderName::from_str(name)?,
+                HeaderValue::from_str(&self.substitute(value))?,
+            );
+        }
+        if run.amount > 0 {
+            headers.insert(X_JSTZ_TRANSFER, HeaderValue::from(run.amount));
+        }
+
+        let body = match &run.body {
+            None => HttpBody::empty(),
+            Some(Value::String(body)) => {
+                HttpBody::from_bytes(self.substitute(body).into_bytes())
+            }
+            Some(body) => HttpBody::from_bytes(
+                self.substitute(&serde_json::to_string(body)?).into_bytes(),
+            ),
+        };
+
+        let content = Content::RunFunction(RunFunction {
+            uri,
+            method,
+            headers,
+            body,
+            gas_limit: run.gas_limit as usize,
+        });
+        let result = self.execute(&run.sender, content).await?;
+        check_response(&run.expect, result)
+    }
+
+    fn check_kv(&mut self, assertion: &KvAssertion) -> Result<()> {
+        let kv = Kv::new(self.address(&assertion.function)?.to_string());
+        let mut tx = Transaction::default();
+        tx.begin();
+        let actual = kv
+            .get(&*self.host, &mut tx, &assertion.key)?
+            .map(|value| KvValue::clone(&value));
+
+        match (actual, &assertion.value) {
+            (None, None) => Ok(()),
+            (Some(KvValue::Json(actual)), Some(expected)) if actual == *expected => {
+                Ok(())
+            }
+            (actual, expected) => bail!(
+                "Expected KV key '{}' of '{}' to be {}, found {}.",
+                assertion.key,
+                assertion.function,
+                expected
+                    .as_ref()
+                    .map_or("unset".to_string(), |value| value.to_string()),
+                match actual {
+                    None => "unset".to_string(),
+                    Some(KvValue::Json(value)) => value.to_string(),
+                    Some(KvValue::Bytes(bytes)) => format!("{} bytes", bytes.len()),
+                }
+            ),
+        }
+    }
+
+    fn check_balance(&mut self, assertion: &BalanceAssertion) -> Result<()> {
+        let address = self.address(&assertion.account)?.clone();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let balance = Account::balance(&*self.host, &mut tx, &address)?;
+        if balance != assertion.balance {
+            bail!(
+                "Expected the balance of '{}' to be {} mutez, found {} mutez.",
+                assertion.account,
+                assertion.balance,
+                balance
+            );
+        }
+        Ok(())
+    }
+}
+
+fn check_response(
+    expect: &ResponseAssertion,
+    result: std::result::Result<ReceiptContent, String>,
+) -> Result<()> {
+    let receipt = match (result, &expect.error) {
+        (Err(e), Some(expected)) if e.contains(expected.as_str()) => return Ok(()),
+        (Err(e), Some(expected)) => {
+            bail!(
+                "Expected an error containing '{}', found '{}'.",
+                expected,
+                e
+            )
+        }
+        (Err(e), None) => bail!("Operation failed: {}", e),
+        (Ok(_), Some(expected)) => {
+            bail!(
+                "Expected an error containing '{}', but the operation succeeded.",
+                expected
+            )
+        }
+        (Ok(ReceiptContent::RunFunction(receipt)), None) => receipt,
+        (Ok(_), None) => bail!("Unexpected receipt."),
+    };
+    let RunFunctionReceipt {
+        body,
+        status_code,
+        headers,
+        ..
+    } = receipt;
+
+    if let Some(status) = expect.status {
+        if status_code.as_u16() != status {
+            bail!(
+                "Expected status {}, found {}.",
+                status,
+                status_code.as_u16()
+            );
+        }
+    }
+    for (name, expected) in &expect.headers {
+        let actual = headers.get(name.as_str()).and_then(|v| v.to_str().ok());
+        if actual != Some(expected.as_str()) {
+            bail!(
+                "Expected header '{}' to be '{}', found {}.",
+                name,
+                expected,
+                actual.map_or("none".to_string(), |v| format!("'{v}'"))
+            );
+        }
+    }
+
+    let text = String::from_utf8_lossy(body.0.as_deref().unwrap_or_default());
+    if let Some(expected) = &expect.body {
+        if text != *expected {
+            bail!("Expected body '{}', found '{}'.", expected, text);
+        }
+    }
+    if let Some(expected) = &expect.json {
+        let actual: Value = serde_json::from_str(&text)
+            .map_err(|_| anyhow!("Expected a JSON body, found '{}'.", text))?;
+        if actual != *expected {
+            bail!("Expected JSON body {}, found {}.", expected, actual);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER: &str = r#"
+        export default async (request) => {
+            const url = new URL(request.url);
+            const count = (Kv.get("count") ?? 0) + 1;
+            if (url.pathname === "/increment") {
+                Kv.set("count", count);
+            }
+            return new Response(JSON.stringify({ count }), {
+                headers: { "X-Count": `${count}` },
+            });
+        }
+    "#;
+
+    fn test_file(tests: Value) -> TestFile {
+        serde_json::from_value(serde_json::json!({
+            "accounts": { "alice": { "balance": 1000 }, "bob": {} },
+            "functions": { "counter": { "code": COUNTER, "deployer": "alice", "balance": 5 } },
+            "tests": tests,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn runs_passing_steps() {
+        let file = test_file(serde_json::json!([{
+            "name": "increments",
+            "steps": [
+                { "run": { "function": "counter", "path": "/increment", "sender": "bob",
+                           "expect": { "status": 200, "json": { "count": 1 }, "headers": { "x-count": "1" } } } },
+                { "kv": { "function": "counter", "key": "count", "value": 1 } },
+                { "kv": { "function": "counter", "key": "missing" } },
+                { "run": { "function": "bob", "sender": "alice", "amount": 300, "method": "POST" } },
+                { "balance": { "account": "alice", "balance": 700 } },
+                { "balance": { "account": "bob", "balance": 300 } },
+                { "balance": { "account": "counter", "balance": 5 } }
+            ]
+        }]));
+        let runner = Runner::new(&file, Path::new(".")).unwrap();
+        runner.run(&file.tests[0].steps).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn tests_start_from_a_fresh_state() {
+        let step = serde_json::json!({ "run": { "function": "counter", "path": "/increment",
+            "sender": "alice", "expect": { "json": { "count": 1 } } } });
+        let file = test_file(serde_json::json!([
+            { "name": "first", "steps": [step] },
+            { "name": "second", "steps": [step] }
+        ]));
+        let runner = Runner::new(&file, Path::new(".")).unwrap();
+        for test in &file.tests {
+            runner.run(&test.steps).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_failing_steps() {
+        let file = test_file(serde_json::json!([
+            { "name": "kv", "steps": [{ "kv": { "function": "counter", "key": "count", "value": 1 } }] },
+            { "name": "balance", "steps": [{ "balance": { "account": "bob", "balance": 1 } }] },
+            { "name": "status", "steps": [{ "run": { "function": "counter", "sender": "alice",
+                "expect": { "status": 404 } } }] },
+            { "name": "error", "steps": [{ "run": { "function": "alice", "sender": "bob",
+                "amount": 1, "expect": { "error": "Insufficient" } } }] }
+        ]));
+        let runner = Runner::new(&file, Path::new(".")).unwrap();
+
+        let err = runner.run(&file.tests[0].steps).await.unwrap_err();
+        assert!(err.to_string().contains("Expected KV key 'count'"));
+        let err = runner.run(&file.tests[1].steps).await.unwrap_err();
+        assert!(err.to_string().contains("balance of 'bob'"));
+        let err = runner.run(&file.tests[2].steps).await.unwrap_err();
+        assert!(err.to_string().contains("Expected status 404, found 200"));
+        runner.run(&file.tests[3].steps).await.unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_deployer() {
+        let file: TestFile = serde_json::from_value(serde_json::json!({
+            "functions": { "counter": { "code": COUNTER, "deployer": "carol" } },
+            "tests": []
+        }))
+        .unwrap();
+        let err = Runner::new(&file, Path::new(".")).err().unwrap();
+        assert!(err.to_string().contains("not an account"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Format of jstz test files (`*.test.json`).
+//!
+//! ```json
+//! {
+//!   "accounts": { "alice": { "balance": 1000000 } },
+//!   "functions": { "counter": { "code": "dist/index.js", "deployer": "alice" } },
+//!   "tests": [
+//!     {
+//!       "name": "increments the counter",
+//!       "steps": [
+//!         { "run": { "function": "counter", "path": "/increment", "sender": "alice",
+//!                    "expect": { "status": 200, "json": "Incremented. Current value is 1" } } },
+//!         { "kv": { "function": "counter", "key": "myNumber", "value": 1 } },
+//!         { "balance": { "account": "alice", "balance": 1000000 } }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::run::DEFAULT_GAS_LIMIT;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestFile {
+    /// User accounts, by name. Their keys are derived from their names, so
+    /// their addresses are the same in every run.
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountSpec>,
+    /// Smart functions deployed before each test, by name.
+    #[serde(default)]
+    pub functions: BTreeMap<String, FunctionSpec>,
+    pub tests: Vec<TestCase>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    /// Initial balance in mutez.
+    #[serde(default)]
+    pub balance: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionSpec {
+    /// Path to the function code, relative to the test file, or the code itself.
+    pub code: String,
+    /// Name of the account that deploys the function.
+    pub deployer: String,
+    /// Initial balance in mutez, credited on top of the deployment.
+    #[serde(default)]
+    pub balance: u64,
+}
+
+/// A test starts from the state described by the accounts and functions of its
+/// file and runs its steps in order, stopping at the first failing step.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCase {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    /// Sends a `RunFunction` operation and checks its response.
+    Run(RunStep),
+    /// Checks a value in the key-value store of a smart function.
+    Kv(KvAssertion),
+    /// Checks the balance of an account or smart function.
+    Balance(BalanceAssertion),
+}
+
+/// Strings in `path`, `headers` and `body` may refer to the address of an account or
+/// smart function with `{{name}}`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunStep {
+    /// Name of the smart function, or of the account for plain transfers.
+    pub function: String,
+    #[serde(default = "default_path")]
+    pub path: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Name of the account that signs the operation.
+    pub sender: String,
+    /// Amount in mutez transferred with the request.
+    #[serde(default)]
+    pub amount: u64,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Request body. Strings are sent as is and other values as JSON.
+    #[serde(default)]
+    pub body: Option<Value>,
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u32,
+    #[serde(default)]
+    pub expect: ResponseAssertion,
+}
+
+/// Checks on the outcome of a [`RunStep`]. Unless `error` is set, the operation
+/// must succeed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseAssertion {
+    pub status: Option<u16>,
+    /// Exact response body as text.
+    pub body: Option<String>,
+    /// Response body parsed as JSON.
+    pub json: Option<Value>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Expects the operation to fail with an error containing this text.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KvAssertion {
+    pub function: String,
+    pub key: String,
+    /// Expected JSON value. Omit it or use `null` to check that the key is not set.
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalanceAssertion {
+    pub account: String,
+    /// Expected balance in mutez.
+    pub balance: u64,
+}
+
+fn default_path() -> String {
+    "/".to_string()
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_gas_limit() -> u32 {
+    DEFAULT_GAS_LIMIT
+}
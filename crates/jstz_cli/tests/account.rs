@@ -2,7 +2,13 @@
 mod utils;
 
 use regex::Regex;
-use utils::jstz_cmd;
+use utils::{jstz_cmd, ProcessSession};
+
+fn enter_new_password(process: &mut ProcessSession) {
+    process.send_line("password").unwrap();
+    process.exp_string("Confirm the password").unwrap();
+    process.send_line("password").unwrap();
+}
 
 #[test]
 fn create_account() {
@@ -12,20 +18,28 @@ fn create_account() {
     // empty passphrase
     process.send_line("").unwrap();
 
-    let output = process.exp_eof().unwrap();
+    let output = process
+        .exp_string("Enter a password to encrypt the secret key")
+        .unwrap();
     assert!(output.contains("Enter the passphrase for the new account or leave empty"));
     assert!(output.contains("Generated mnemonic:"));
     assert!(output.contains("Please keep the mnemonic and the passphrase safe"));
     assert!(output.contains("User created with address: tz1"));
     let address1 = address_pattern.captures(&output).unwrap();
+    enter_new_password(&mut process);
+
+    let output = process.exp_eof().unwrap();
+    assert!(!output.contains("stored unencrypted"));
 
     let mut process = jstz_cmd(["account", "create", "foo"], Some(process.tmp));
 
     let output = process.exp_eof().unwrap();
     assert!(output.contains("The account 'foo' already exists."));
 
-    let mut process =
-        jstz_cmd(["account", "create", "foo", "--force"], Some(process.tmp));
+    let mut process = jstz_cmd(
+        ["account", "create", "foo", "--force", "--no-encrypt"],
+        Some(process.tmp),
+    );
 
     // empty passphrase
     process.send_line("").unwrap();
@@ -35,6 +49,7 @@ fn create_account() {
     assert!(output.contains("Generated mnemonic:"));
     assert!(output.contains("Please keep the mnemonic and the passphrase safe"));
     assert!(output.contains("User created with address: tz1"));
+    assert!(output.contains("The secret key of 'foo' is stored unencrypted."));
     let address2 = address_pattern.captures(&output).unwrap();
 
     assert_ne!(&address1[0], &address2[0]);
@@ -48,7 +63,9 @@ fn login_new_account() {
     // empty passphrase
     process.send_line("").unwrap();
 
-    let output = process.exp_eof().unwrap();
+    let output = process
+        .exp_string("Enter a password to encrypt the secret key")
+        .unwrap();
     // prompt
     assert!(output.contains("Account not found. Do you want to create it? [y/n]"));
     // after accepting 'y'
@@ -56,6 +73,9 @@ fn login_new_account() {
     assert!(output.contains("Enter the passphrase for the new account or leave empty"));
     assert!(output.contains("Generated mnemonic:"));
     assert!(output.contains("User created with address: tz1"));
+    enter_new_password(&mut process);
+
+    let output = process.exp_eof().unwrap();
     assert!(output.contains("Logged in to account foo with address tz1"));
 }
 
@@ -67,8 +87,13 @@ fn import_account() {
         .send_line("edsk4YBTjLtZgLNWKUN95unbAZ6cfq2eXhRveVt4J5oFPYHMzadpc8")
         .unwrap();
 
-    let output = process.exp_eof().unwrap();
+    let output = process
+        .exp_string("Enter a password to encrypt the secret key")
+        .unwrap();
     assert!(output.contains("Enter the secret key of your account"));
+    enter_new_password(&mut process);
+
+    let output = process.exp_eof().unwrap();
     assert!(output.contains(
         "User foo imported with address: tz1WrJFFhUrHeozPin2KY29WJPZ9GzkmpX3Y"
     ));
@@ -80,8 +105,10 @@ fn import_account() {
     assert!(output.contains("The account 'foo' already exists."));
 
     // import to the same alias with --force should work
-    let mut process =
-        jstz_cmd(["account", "import", "foo", "--force"], Some(process.tmp));
+    let mut process = jstz_cmd(
+        ["account", "import", "foo", "--force", "--no-encrypt"],
+        Some(process.tmp),
+    );
 
     process
         .send_line("edsk3a3gq6ocr51rGDqqSb8sxxV46v77GZYmhyKyjqWjckhVTJXYCf")
@@ -2,6 +2,7 @@ use assert_cmd::cargo::CommandCargoExt;
 use jstz_cli::{
     bridge::deploy::DeployBridge,
     config::{self, Account, AccountConfig, Config, Network, NetworkConfig, NetworkName},
+    signer::KeySource,
 };
 use serde_json::Value;
 
@@ -173,14 +174,16 @@ fn build_config_from_jstzd(jstzd_config: &JstzdConfig) -> Config {
                     "tz1dbGzJfjYFSjX8umiRZ2fmsAQsk8XMH1E9",
                 )
                 .unwrap(),
-                secret_key: SecretKey::from_base58(
-                    "edsk3zmEDXpWukDYviGXHRoBt2UMggJwwZsKvyAgaDDLLcQ6FMLDqS",
-                )
-                .unwrap(),
                 public_key: PublicKey::from_base58(
                     "edpkuXDAprNEQejWbm4wrTdeQAKABByijQd3xC8hZEttgrNVA9B6gi",
                 )
                 .unwrap(),
+                key: KeySource::Plain {
+                    secret_key: SecretKey::from_base58(
+                        "edsk3zmEDXpWukDYviGXHRoBt2UMggJwwZsKvyAgaDDLLcQ6FMLDqS",
+                    )
+                    .unwrap(),
+                },
             }),
         )]
         .into_iter()
@@ -1,15 +1,20 @@
 use std::time::Duration;
 
 use anyhow::{bail, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use jstz_core::kv::outbox::{OutboxProof, OutboxQueueStatus};
 use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use jstz_proto::{
-    context::account::{Address, Addressable, Nonce},
+    context::{
+        account::{Address, Addressable, Nonce},
+        protocol_params::ProtocolParams,
+    },
     operation::{OperationHash, SignedOperation},
     receipt::Receipt,
-    runtime::KvValue,
+    runtime::{KvValue, RuntimeVersion},
 };
 use log::debug;
-use reqwest::StatusCode;
+use reqwest::{header::CONTENT_TYPE, StatusCode};
 use reqwest_eventsource::EventSource;
 use tokio::time::sleep;
 
@@ -48,6 +53,22 @@ impl JstzClient {
         }
     }
 
+    /// Get an operation injected through the node
+    pub async fn get_operation(
+        &self,
+        hash: &OperationHash,
+    ) -> Result<Option<SignedOperation>> {
+        let response = self
+            .get(&format!("{}/operations/{}", self.endpoint, hash))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(Some(response.json::<SignedOperation>().await?)),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => bail!("Failed to get the operation. Status: {}", status),
+        }
+    }
+
     pub async fn get_nonce(&self, address: &Address) -> Result<Nonce> {
         let response = self
             .get(&format!("{}/accounts/{}/nonce", self.endpoint, address))
@@ -83,6 +104,24 @@ impl JstzClient {
         }
     }
 
+    /// Get the runtime the smart function at `address` runs on
+    pub async fn get_runtime_version(
+        &self,
+        address: &SmartFunctionHash,
+    ) -> Result<RuntimeVersion> {
+        let response = self
+            .get(&format!("{}/accounts/{}/runtime", self.endpoint, address))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<RuntimeVersion>().await?),
+            StatusCode::NOT_FOUND => {
+                bail!("Account '{}' not found", address)
+            }
+            status => bail!("Failed to get the runtime version. Status: {}", status),
+        }
+    }
+
     pub async fn get_balance(&self, address: &Address) -> Result<u64> {
         let response = self
             .get(&format!("{}/accounts/{}/balance", self.endpoint, address))
@@ -114,7 +153,18 @@ impl JstzClient {
 
         match response.status() {
             StatusCode::OK => {
-                let kv = response.json::<KvValue>().await?;
+                let is_bytes = response
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|content_type| content_type.to_str().ok())
+                    .is_some_and(|content_type| {
+                        content_type.starts_with("application/octet-stream")
+                    });
+                let kv = if is_bytes {
+                    KvValue::Bytes(BASE64_STANDARD.decode(response.text().await?)?)
+                } else {
+                    response.json::<KvValue>().await?
+                };
                 Ok(Some(kv))
             }
             StatusCode::NOT_FOUND => Ok(None),
@@ -148,6 +198,49 @@ impl JstzClient {
         }
     }
 
+    /// Get the proof of the outbox message at `index` in the outbox of `level`.
+    /// Returns `None` until the commitment of `level` has been cemented.
+    pub async fn get_outbox_proof(
+        &self,
+        level: u32,
+        index: u32,
+    ) -> Result<Option<OutboxProof>> {
+        let response = self
+            .get(&format!("{}/outbox/{}/{}", self.endpoint, level, index))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let proof = response.json::<OutboxProof>().await?;
+                Ok(Some(proof))
+            }
+            StatusCode::NOT_FOUND => Ok(None),
+            status => bail!("Failed to get outbox proof. Status: {}", status),
+        }
+    }
+
+    /// Get the backlog of outbox messages waiting for space in the rollup outbox
+    pub async fn get_outbox_queue_status(&self) -> Result<OutboxQueueStatus> {
+        let response = self.get(&format!("{}/outbox/queue", self.endpoint)).await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<OutboxQueueStatus>().await?),
+            status => bail!("Failed to get outbox queue status. Status: {}", status),
+        }
+    }
+
+    /// Get the protocol parameters, such as the maximum size of a revealed operation
+    pub async fn get_protocol_params(&self) -> Result<ProtocolParams> {
+        let response = self
+            .get(&format!("{}/protocol/params", self.endpoint))
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<ProtocolParams>().await?),
+            status => bail!("Failed to get protocol parameters. Status: {}", status),
+        }
+    }
+
     pub async fn wait_for_operation_receipt(
         &self,
         hash: &OperationHash,
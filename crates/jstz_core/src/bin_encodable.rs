@@ -8,15 +8,36 @@ use crate::{
     reveal_data::MAX_REVEAL_SIZE,
 };
 
+/// Maximum number of bytes decoded from a single value (10MB)
+pub const MAX_DECODE_SIZE: usize = 10 * 1024 * 1024;
+
 // FixintEncoding is used for predictable, fixed-width integer encoding, which makes decoding
 // more strict and less ambiguous compared to VarintEncoding.
-// The decode limit (MAX_REVEAL_SIZE) is critical for safety — it prevents unbounded memory
+// The decode limit (MAX_DECODE_SIZE) is critical for safety — it prevents unbounded memory
 // allocation on malformed input, mitigating potential denial-of-service (DoS) risks.
-const BINCODE_CONFIGURATION: Configuration<LittleEndian, Fixint, Limit<MAX_REVEAL_SIZE>> =
+const BINCODE_CONFIGURATION: Configuration<LittleEndian, Fixint, Limit<MAX_DECODE_SIZE>> =
+    bincode::config::standard()
+        .with_fixed_int_encoding()
+        .with_limit();
+
+// Revealed data is decoded with the same encoding, but may be as large as the reveal
+// size limit. Its length is checked against the protocol limit while it is revealed.
+const REVEAL_CONFIGURATION: Configuration<LittleEndian, Fixint, Limit<MAX_REVEAL_SIZE>> =
     bincode::config::standard()
         .with_fixed_int_encoding()
         .with_limit();
 
+/// Decodes a value revealed from the reveal data channel
+pub(crate) fn decode_revealed<T: Decode>(bytes: &[u8]) -> Result<T> {
+    let (value, _) =
+        bincode::decode_from_slice(bytes, REVEAL_CONFIGURATION).map_err(|err| {
+            Error::SerializationError {
+                description: format!("{err}"),
+            }
+        })?;
+    Ok(value)
+}
+
 /// Trait for types that can be encoded to and decoded from binary format
 pub trait BinEncodable {
     fn encode(&self) -> Result<Vec<u8>>;
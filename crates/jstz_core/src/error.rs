@@ -4,6 +4,7 @@ use derive_more::{Display, Error, From};
 use crate::event;
 use crate::host;
 use crate::kv;
+use crate::micheline;
 use crate::reveal_data;
 
 #[derive(Display, Debug, Error, From)]
@@ -40,6 +41,9 @@ pub enum Error {
     EventError {
         source: event::EventError,
     },
+    MichelineError {
+        source: micheline::MichelineError,
+    },
 }
 
 impl From<Error> for JsError {
@@ -70,6 +74,9 @@ impl From<Error> for JsError {
             Error::EventError { source } => JsNativeError::eval()
                 .with_message(format!("EventError: {source}"))
                 .into(),
+            Error::MichelineError { source } => JsNativeError::eval()
+                .with_message(format!("MichelineError: {source}"))
+                .into(),
         }
     }
 }
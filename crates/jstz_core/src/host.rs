@@ -458,6 +458,17 @@ impl<'a> JsHostRuntime<'a> {
 
         unsafe { std::mem::transmute(jhr) }
     }
+
+    /// Returns a host runtime backed by the same runtime as `self`
+    pub fn reborrow(&mut self) -> JsHostRuntime<'static> {
+        let jhr: JsHostRuntime<'_> = JsHostRuntime {
+            inner: &mut *self.inner,
+        };
+
+        // SAFETY
+        // See `JsHostRuntime::new`
+        unsafe { std::mem::transmute(jhr) }
+    }
 }
 
 impl<'a: 'static> HostRuntime for JsHostRuntime<'a> {
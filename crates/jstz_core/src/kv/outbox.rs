@@ -1,4 +1,4 @@
-use crate::error::Result;
+use crate::{error::Result, micheline::Micheline};
 use bincode::{Decode, Encode};
 use derive_more::{Display, Error, From};
 use serde::{Deserialize, Serialize};
@@ -15,20 +15,43 @@ use tezos_smart_rollup::{
 
 use tezos_data_encoding::{enc::BinWriter, encoding::HasEncoding, nom::NomReader};
 use tezos_smart_rollup_host::{path::RefPath, runtime::Runtime};
+use utoipa::ToSchema;
 
 use super::Storage;
 
 const PERSISTENT_OUTBOX_QUEUE_ROOT: RefPath<'static> =
     RefPath::assert_from(b"/outbox/persistent");
 
-const JSTZ_OUTBOX_QUEUE_META: RefPath<'static> = RefPath::assert_from(b"/outbox/meta");
+pub const OUTBOX_QUEUE_META_PATH: &str = "/outbox/meta";
+const JSTZ_OUTBOX_QUEUE_META: RefPath<'static> =
+    RefPath::assert_from(OUTBOX_QUEUE_META_PATH.as_bytes());
+
+pub const OUTBOX_QUEUE_LEVELS_PATH: &str = "/outbox/levels";
+const JSTZ_OUTBOX_QUEUE_LEVELS: RefPath<'static> =
+    RefPath::assert_from(OUTBOX_QUEUE_LEVELS_PATH.as_bytes());
+
+pub const OUTBOX_CURSOR_PATH: &str = "/outbox/cursor";
+const JSTZ_OUTBOX_CURSOR: RefPath<'static> =
+    RefPath::assert_from(OUTBOX_CURSOR_PATH.as_bytes());
+
+const JSTZ_OUTBOX_SUSPENDED_CURSOR: RefPath<'static> =
+    RefPath::assert_from(b"/outbox/suspended_cursor");
+
+/// Default capacity of the persistent outbox queue
+const DEFAULT_OUTBOX_QUEUE_MAX: u32 = u16::MAX as u32;
+
+/// Maximum number of messages the rollup outbox accepts per level
+pub const MAX_OUTBOX_MESSAGES_PER_LEVEL: u32 = 100;
 
 type WithdrawalParameters = MichelsonPair<MichelsonContract, FA2_1Ticket>;
 type Withdrawal = OutboxMessageTransactionBatch<WithdrawalParameters>;
+type ContractCall = OutboxMessageTransactionBatch<Micheline>;
 
 #[derive(Debug, HasEncoding, PartialEq)]
 pub enum OutboxMessage {
     Withdrawal(Withdrawal),
+    /// Call to an arbitrary L1 contract entrypoint
+    ContractCall(ContractCall),
 }
 
 impl OutboxMessage {
@@ -51,6 +74,37 @@ impl OutboxMessage {
         );
         Ok(message)
     }
+
+    /// Fails if `parameters` have the shape of withdrawal parameters, so that
+    /// contract calls and withdrawals cannot be mistaken for one another
+    pub fn new_contract_call(
+        destination: &Contract,
+        entrypoint: &str,
+        parameters: Micheline,
+    ) -> Result<OutboxMessage> {
+        let entrypoint = Entrypoint::try_from(entrypoint.to_string())
+            .map_err(|_| OutboxError::InvalidEntrypoint)?;
+        if is_withdrawal_parameters(&parameters) {
+            return Err(OutboxError::WithdrawalParameters.into());
+        }
+        let message = OutboxMessage::ContractCall(
+            vec![OutboxMessageTransaction {
+                entrypoint,
+                parameters,
+                destination: destination.clone(),
+            }]
+            .into(),
+        );
+        Ok(message)
+    }
+}
+
+/// Returns true if `parameters` decode entirely as [WithdrawalParameters]
+fn is_withdrawal_parameters(parameters: &Micheline) -> bool {
+    matches!(
+        WithdrawalParameters::nom_read(parameters.as_bytes()),
+        Ok((rest, _)) if rest.is_empty()
+    )
 }
 
 impl AtomicBatch for OutboxMessage {}
@@ -59,15 +113,21 @@ impl BinWriter for OutboxMessage {
     fn bin_write(&self, output: &mut Vec<u8>) -> tezos_data_encoding::enc::BinResult {
         match self {
             OutboxMessage::Withdrawal(withdrawal) => withdrawal.bin_write(output),
+            OutboxMessage::ContractCall(call) => call.bin_write(output),
         }
     }
 }
 
 impl<'a> NomReader<'a> for OutboxMessage {
     fn nom_read(input: &'a [u8]) -> tezos_data_encoding::nom::NomResult<'a, Self> {
-        nom::branch::alt((nom::combinator::map(Withdrawal::nom_read, |withdrawal| {
-            OutboxMessage::Withdrawal(withdrawal)
-        }),))(input)
+        // Both variants share the binary encoding of the rollup outbox, which
+        // has no room for a tag. They are disjoint instead: contract calls are
+        // never built with withdrawal parameters (see [Self::new_contract_call]),
+        // so a batch that decodes as a withdrawal is one
+        nom::branch::alt((
+            nom::combinator::map(Withdrawal::nom_read, OutboxMessage::Withdrawal),
+            nom::combinator::map(ContractCall::nom_read, OutboxMessage::ContractCall),
+        ))(input)
     }
 }
 
@@ -83,6 +143,10 @@ impl From<OutboxMessage> for OutboxMessageFull<OutboxMessage> {
 pub(crate) struct SnapshotOutboxQueue(Vec<OutboxMessage>);
 
 impl SnapshotOutboxQueue {
+    pub fn len(&self) -> u32 {
+        self.0.len() as u32
+    }
+
     pub fn extend(&mut self, queue: SnapshotOutboxQueue) {
         self.0.extend(queue.0)
     }
@@ -141,9 +205,9 @@ pub(crate) struct PersistentOutboxQueue {
 impl PersistentOutboxQueue {
     fn init_inner(&mut self, rt: &mut impl Runtime) -> Result<()> {
         if self.inner.is_none() {
-            let max = u16::MAX as u32;
-            let inner = PersistentOutboxQueueInner::load(rt)
-                .or_else(|_| PersistentOutboxQueueInner::try_new(rt, max))?;
+            let inner = PersistentOutboxQueueInner::load(rt).or_else(|_| {
+                PersistentOutboxQueueInner::try_new(rt, DEFAULT_OUTBOX_QUEUE_MAX)
+            })?;
             self.inner = Some(inner);
         }
         Ok(())
@@ -167,6 +231,11 @@ impl PersistentOutboxQueue {
     fn flush(&mut self, rt: &mut impl Runtime) -> Result<u32> {
         let inner = self.get_or_init_inner_mut(rt)?;
         let flushed_count = inner.rollup_outbox_queue.flush_queue(rt) as u32;
+        if flushed_count > 0 {
+            let mut levels = OutboxQueueLevels::load(rt)?;
+            levels.pop(inner.meta.len, flushed_count);
+            levels.save(rt)?;
+        }
         inner.meta.len -= flushed_count;
         Ok(flushed_count)
     }
@@ -182,7 +251,7 @@ impl PersistentOutboxQueue {
         inner.rollup_outbox_queue.queue_message(rt, message)?;
         inner.meta.len += 1;
         inner.meta.save(rt)?;
-        Ok(())
+        record_enqueued(rt, 1)
     }
 
     /// Pushes outbox messages to the [OutboxQueue] and saves the final queue length
@@ -192,14 +261,19 @@ impl PersistentOutboxQueue {
         outbox_messages: std::vec::IntoIter<OutboxMessage>,
     ) -> Result<()> {
         let inner = self.get_or_init_inner_mut(rt)?;
+        let mut count = 0;
         for message in outbox_messages {
             inner
                 .rollup_outbox_queue
                 .queue_message(rt, message)
                 .expect("Unexpected error while queueing message"); // Fatal error
-            inner.meta.len += 1;
+            count += 1;
         }
+        inner.meta.len += count;
         inner.meta.save(rt)?;
+        if count > 0 {
+            record_enqueued(rt, count)?;
+        }
         Ok(())
     }
 
@@ -224,7 +298,7 @@ impl PersistentOutboxQueue {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
-pub(crate) struct OutboxQueueMeta {
+pub struct OutboxQueueMeta {
     /// Combined queue length of the rollup and snapshots'
     /// outbox queues
     pub len: u32,
@@ -244,6 +318,221 @@ impl OutboxQueueMeta {
     }
 }
 
+/// Identifies a message in the rollup outbox by the level it was written at
+/// and its index within that level. Together they locate the message for
+/// the rollup node's outbox proof RPC.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode, ToSchema,
+)]
+pub struct OutboxMessageId {
+    pub level: u32,
+    pub index: u32,
+}
+
+/// Id that will be assigned to the next message written to the rollup outbox.
+/// The cursor is only available once the kernel has recorded the current level
+/// with [set_outbox_level].
+fn load_cursor(rt: &impl Runtime) -> Result<Option<OutboxMessageId>> {
+    Storage::get::<OutboxMessageId>(rt, &JSTZ_OUTBOX_CURSOR)
+}
+
+/// Records the level of the inbox message being processed so that messages
+/// written to the outbox are assigned ids at that level. The index is reset
+/// whenever the level changes.
+pub fn set_outbox_level(rt: &mut impl Runtime, level: u32) -> Result<()> {
+    if let Some(cursor) = load_cursor(rt)? {
+        if cursor.level == level {
+            return Ok(());
+        }
+    } else if let Some(cursor) =
+        Storage::get::<OutboxMessageId>(rt, &JSTZ_OUTBOX_SUSPENDED_CURSOR)?
+    {
+        Storage::remove(rt, &JSTZ_OUTBOX_SUSPENDED_CURSOR)?;
+        if cursor.level == level {
+            return Storage::insert(rt, &JSTZ_OUTBOX_CURSOR, &cursor);
+        }
+    }
+    Storage::insert(
+        rt,
+        &JSTZ_OUTBOX_CURSOR,
+        &OutboxMessageId { level, index: 0 },
+    )
+}
+
+/// Forgets the current level until the next call to [set_outbox_level], for
+/// operations whose inbox level is not known yet. Messages written in the
+/// meantime are not assigned ids and do not advance the cursor, which resumes
+/// where it stopped if the level is recorded again.
+pub fn suspend_outbox_level(rt: &mut impl Runtime) -> Result<()> {
+    if let Some(cursor) = load_cursor(rt)? {
+        Storage::insert(rt, &JSTZ_OUTBOX_SUSPENDED_CURSOR, &cursor)?;
+        Storage::remove(rt, &JSTZ_OUTBOX_CURSOR)?;
+    }
+    Ok(())
+}
+
+/// Advances the cursor by the number of messages written to the outbox
+fn advance_cursor(rt: &mut impl Runtime, written: u32) -> Result<()> {
+    if let Some(mut cursor) = load_cursor(rt)? {
+        cursor.index += written;
+        Storage::insert(rt, &JSTZ_OUTBOX_CURSOR, &cursor)?;
+    }
+    Ok(())
+}
+
+/// Predicts the id of a message that is flushed after `pending` other messages.
+/// Returns `None` if the level is unknown or if the message will not fit in the
+/// current level's outbox, in which case it is deferred to the persistent queue
+/// and its position is only known once it is flushed.
+pub(crate) fn predict_message_id(
+    rt: &impl Runtime,
+    pending: u32,
+) -> Result<Option<OutboxMessageId>> {
+    Ok(load_cursor(rt)?.and_then(|cursor| {
+        let index = cursor.index + pending;
+        (index < MAX_OUTBOX_MESSAGES_PER_LEVEL).then_some(OutboxMessageId {
+            level: cursor.level,
+            index,
+        })
+    }))
+}
+
+/// Levels at which the messages in the persistent outbox queue were enqueued,
+/// oldest first. Consecutive messages enqueued at the same level share a single
+/// `(level, count)` entry. Messages enqueued before the level was known are not
+/// tracked and are always the oldest in the queue.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode,
+)]
+pub struct OutboxQueueLevels(Vec<(u32, u32)>);
+
+impl OutboxQueueLevels {
+    fn load(rt: &impl Runtime) -> Result<Self> {
+        Ok(
+            Storage::get::<OutboxQueueLevels>(rt, &JSTZ_OUTBOX_QUEUE_LEVELS)?
+                .unwrap_or_default(),
+        )
+    }
+
+    fn save(&self, rt: &mut impl Runtime) -> Result<()> {
+        Storage::insert(rt, &JSTZ_OUTBOX_QUEUE_LEVELS, self)
+    }
+
+    /// Number of tracked messages
+    fn len(&self) -> u32 {
+        self.0.iter().map(|(_, count)| count).sum()
+    }
+
+    fn push(&mut self, level: u32, count: u32) {
+        match self.0.last_mut() {
+            Some((last_level, last_count)) if *last_level == level => {
+                *last_count += count
+            }
+            _ => self.0.push((level, count)),
+        }
+    }
+
+    /// Removes the `count` oldest messages from a queue of `queue_len` messages.
+    /// Untracked messages are removed first.
+    fn pop(&mut self, queue_len: u32, count: u32) {
+        let untracked = queue_len.saturating_sub(self.len());
+        let mut remaining = count.saturating_sub(untracked);
+        while remaining > 0 {
+            let Some((_, front_count)) = self.0.first_mut() else {
+                break;
+            };
+            if *front_count > remaining {
+                *front_count -= remaining;
+                break;
+            }
+            remaining -= *front_count;
+            self.0.remove(0);
+        }
+    }
+
+    /// Level at which the oldest of `queue_len` messages was enqueued, if known
+    fn oldest(&self, queue_len: u32) -> Option<u32> {
+        if queue_len > self.len() {
+            return None;
+        }
+        self.0.first().map(|(level, _)| *level)
+    }
+}
+
+/// Records that `count` messages were pushed to the persistent queue at the
+/// current level
+fn record_enqueued(rt: &mut impl Runtime, count: u32) -> Result<()> {
+    if let Some(cursor) = load_cursor(rt)? {
+        let mut levels = OutboxQueueLevels::load(rt)?;
+        levels.push(cursor.level, count);
+        levels.save(rt)?;
+    }
+    Ok(())
+}
+
+/// Data required to execute an outbox message on L1
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxProof {
+    /// Address of the smart rollup the message was written by
+    pub rollup_address: String,
+    /// Hash of the cemented commitment that includes the message
+    pub commitment: String,
+    /// Hex-encoded output proof
+    pub proof: String,
+}
+
+/// Backlog of the persistent outbox queue, which holds the messages that did
+/// not fit in the rollup outbox of the level they were produced at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxQueueStatus {
+    /// Number of messages waiting to be written to the rollup outbox
+    pub len: u32,
+    /// Maximum number of messages the queue can hold
+    pub max: u32,
+    /// Level at which the oldest pending message was produced, if known
+    pub oldest_pending_level: Option<u32>,
+    /// Estimated level at which the last pending message will be written to
+    /// the rollup outbox, assuming the outbox is not filled by new messages
+    pub estimated_flush_level: Option<u32>,
+}
+
+impl OutboxQueueStatus {
+    pub fn new(
+        meta: Option<OutboxQueueMeta>,
+        levels: OutboxQueueLevels,
+        cursor: Option<OutboxMessageId>,
+    ) -> Self {
+        let (len, max) = meta
+            .map(|meta| (meta.len, meta.max))
+            .unwrap_or((0, DEFAULT_OUTBOX_QUEUE_MAX));
+        let (oldest_pending_level, estimated_flush_level) = if len == 0 {
+            (None, None)
+        } else {
+            // The queue is flushed first at every level, up to the outbox capacity
+            let flush_level = cursor
+                .map(|cursor| cursor.level + len.div_ceil(MAX_OUTBOX_MESSAGES_PER_LEVEL));
+            (levels.oldest(len), flush_level)
+        };
+        OutboxQueueStatus {
+            len,
+            max,
+            oldest_pending_level,
+            estimated_flush_level,
+        }
+    }
+}
+
+/// Reads the status of the persistent outbox queue from durable storage
+pub fn outbox_queue_status(rt: &impl Runtime) -> Result<OutboxQueueStatus> {
+    Ok(OutboxQueueStatus::new(
+        OutboxQueueMeta::load(rt)?,
+        OutboxQueueLevels::load(rt)?,
+        load_cursor(rt)?,
+    ))
+}
+
 /// Writes the outbox message directly to the Runtime outbox
 fn write_outbox_message(
     rt: &mut impl Runtime,
@@ -309,6 +598,7 @@ pub(crate) fn flush(
     //  3. Enqueue the remaining messages into the outbox queue
     persistent_queue.batch_queue_message(rt, outbox_messages)?;
     if flushed_count > 0 {
+        advance_cursor(rt, flushed_count)?;
         debug_msg!(
             rt,
             "Flush outbox queue (flushed_count: {})\n",
@@ -330,12 +620,14 @@ pub enum OutboxError {
     OutboxQueueMetaAlreadyExists,
     InvalidTicketType,
     InvalidEntrypoint,
+    /// Contract call parameters have the shape of withdrawal parameters
+    WithdrawalParameters,
 }
 
 #[cfg(test)]
 mod test {
     use jstz_crypto::{hash::Hash, public_key_hash::PublicKeyHash};
-    use tezos_data_encoding::nom::NomReader;
+    use tezos_data_encoding::{enc::BinWriter, nom::NomReader};
     use tezos_smart_rollup::{
         michelson::{
             ticket::FA2_1Ticket, MichelsonContract, MichelsonNat, MichelsonOption,
@@ -347,25 +639,38 @@ mod test {
 
     use tezos_smart_rollup_mock::MockHost;
 
-    use crate::kv::outbox::{flush, write_outbox_message, PersistentOutboxQueue};
+    use crate::{
+        kv::outbox::{flush, write_outbox_message, PersistentOutboxQueue},
+        micheline::Micheline,
+    };
 
-    use super::{OutboxMessage, SnapshotOutboxQueue};
+    use super::{
+        outbox_queue_status, predict_message_id, set_outbox_level, suspend_outbox_level,
+        OutboxError, OutboxMessage, OutboxMessageId, OutboxQueueLevels,
+        OutboxQueueStatus, SnapshotOutboxQueue, WithdrawalParameters,
+        MAX_OUTBOX_MESSAGES_PER_LEVEL,
+    };
 
-    fn make_withdrawal(account: &PublicKeyHash) -> OutboxMessage {
-        let creator =
-            Contract::from_b58check("KT1NgXQ6Mwu3XKFDcKdYFS6dkkY3iNKdBKEc").unwrap();
-        let parameters = MichelsonPair(
+    fn creator() -> Contract {
+        Contract::from_b58check("KT1NgXQ6Mwu3XKFDcKdYFS6dkkY3iNKdBKEc").unwrap()
+    }
+
+    fn withdrawal_parameters(account: &PublicKeyHash) -> WithdrawalParameters {
+        MichelsonPair(
             MichelsonContract(Contract::try_from(account.to_base58()).unwrap()),
             FA2_1Ticket::new(
-                creator.clone(),
+                creator(),
                 MichelsonPair(MichelsonNat::from(0), MichelsonOption(None)),
                 10,
             )
             .unwrap(),
-        );
+        )
+    }
+
+    fn make_withdrawal(account: &PublicKeyHash) -> OutboxMessage {
         let outbox_tx = OutboxMessageTransaction {
-            parameters,
-            destination: creator,
+            parameters: withdrawal_parameters(account),
+            destination: creator(),
             entrypoint: Entrypoint::try_from("burn".to_string()).unwrap(),
         };
         OutboxMessage::Withdrawal(vec![outbox_tx].into())
@@ -484,6 +789,143 @@ mod test {
         assert_eq!(100, outbox.len());
     }
 
+    #[test]
+    fn flush_advances_outbox_cursor() {
+        let mut host = MockHost::default();
+        let account = PublicKeyHash::digest(b"account1").unwrap();
+        assert_eq!(None, predict_message_id(&host, 0).unwrap());
+
+        set_outbox_level(&mut host, 5).unwrap();
+        let mut persistent_queue = PersistentOutboxQueue::default();
+        let outbox_queue_snapshot = SnapshotOutboxQueue(vec![
+            make_withdrawal(&account),
+            make_withdrawal(&account),
+        ]);
+        flush(&mut host, &mut persistent_queue, outbox_queue_snapshot).unwrap();
+        assert_eq!(
+            Some(OutboxMessageId { level: 5, index: 3 }),
+            predict_message_id(&host, 1).unwrap()
+        );
+
+        // Recording the same level keeps the cursor
+        set_outbox_level(&mut host, 5).unwrap();
+        assert_eq!(
+            Some(OutboxMessageId { level: 5, index: 2 }),
+            predict_message_id(&host, 0).unwrap()
+        );
+
+        set_outbox_level(&mut host, 6).unwrap();
+        assert_eq!(
+            Some(OutboxMessageId { level: 6, index: 0 }),
+            predict_message_id(&host, 0).unwrap()
+        );
+        assert_eq!(
+            None,
+            predict_message_id(&host, MAX_OUTBOX_MESSAGES_PER_LEVEL).unwrap()
+        );
+    }
+
+    #[test]
+    fn suspended_outbox_level_resumes_cursor() {
+        let mut host = MockHost::default();
+        let account = PublicKeyHash::digest(b"account1").unwrap();
+        let mut persistent_queue = PersistentOutboxQueue::default();
+        set_outbox_level(&mut host, 5).unwrap();
+        flush(
+            &mut host,
+            &mut persistent_queue,
+            SnapshotOutboxQueue(vec![make_withdrawal(&account)]),
+        )
+        .unwrap();
+
+        // Messages written while the level is suspended are not assigned ids
+        suspend_outbox_level(&mut host).unwrap();
+        assert_eq!(None, predict_message_id(&host, 0).unwrap());
+        flush(
+            &mut host,
+            &mut persistent_queue,
+            SnapshotOutboxQueue(vec![make_withdrawal(&account)]),
+        )
+        .unwrap();
+
+        set_outbox_level(&mut host, 5).unwrap();
+        assert_eq!(
+            Some(OutboxMessageId { level: 5, index: 1 }),
+            predict_message_id(&host, 0).unwrap()
+        );
+
+        suspend_outbox_level(&mut host).unwrap();
+        set_outbox_level(&mut host, 6).unwrap();
+        assert_eq!(
+            Some(OutboxMessageId { level: 6, index: 0 }),
+            predict_message_id(&host, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn flush_tracks_outbox_queue_status() {
+        let mut host = MockHost::default();
+        let mut persistent_queue = PersistentOutboxQueue::default();
+        set_outbox_level(&mut host, 5).unwrap();
+        let messages = (0..120)
+            .map(|i| {
+                make_withdrawal(
+                    &PublicKeyHash::digest(format!("account{i}").as_bytes()).unwrap(),
+                )
+            })
+            .collect();
+
+        flush(
+            &mut host,
+            &mut persistent_queue,
+            SnapshotOutboxQueue(messages),
+        )
+        .unwrap();
+        assert_eq!(
+            OutboxQueueStatus {
+                len: 20,
+                max: u16::MAX as u32,
+                oldest_pending_level: Some(5),
+                estimated_flush_level: Some(6),
+            },
+            outbox_queue_status(&host).unwrap()
+        );
+
+        host.run_level(|_| {});
+        set_outbox_level(&mut host, 6).unwrap();
+        flush(
+            &mut host,
+            &mut persistent_queue,
+            SnapshotOutboxQueue(vec![]),
+        )
+        .unwrap();
+        assert_eq!(
+            OutboxQueueStatus {
+                len: 0,
+                max: u16::MAX as u32,
+                oldest_pending_level: None,
+                estimated_flush_level: None,
+            },
+            outbox_queue_status(&host).unwrap()
+        );
+    }
+
+    #[test]
+    fn outbox_queue_levels_pops_untracked_messages_first() {
+        let mut levels = OutboxQueueLevels::default();
+        levels.push(3, 2);
+        levels.push(3, 1);
+        levels.push(4, 5);
+
+        // 2 untracked messages precede the tracked ones
+        assert_eq!(None, levels.oldest(10));
+        levels.pop(10, 4);
+        assert_eq!(Some(3), levels.oldest(6));
+        levels.pop(6, 2);
+        assert_eq!(Some(4), levels.oldest(4));
+        assert_eq!(OutboxQueueLevels(vec![(4, 4)]), levels);
+    }
+
     #[test]
     fn write_outbox_message_test() {
         let mut host = MockHost::default();
@@ -541,4 +983,37 @@ mod test {
             outbox_queue_snapshot1.0
         );
     }
+
+    #[test]
+    fn contract_calls_are_disjoint_from_withdrawals() {
+        let account = PublicKeyHash::digest(b"account1").unwrap();
+        let mut bytes = vec![];
+        withdrawal_parameters(&account)
+            .bin_write(&mut bytes)
+            .unwrap();
+        let (_, parameters) = Micheline::nom_read(&bytes).unwrap();
+
+        let error = OutboxMessage::new_contract_call(&creator(), "burn", parameters)
+            .expect_err("Expected withdrawal parameters to be rejected");
+        assert!(matches!(
+            error,
+            crate::Error::OutboxError {
+                source: OutboxError::WithdrawalParameters
+            }
+        ));
+
+        let call = OutboxMessage::new_contract_call(
+            &creator(),
+            "default",
+            Micheline::try_from(&serde_json::json!({ "int": "1" })).unwrap(),
+        )
+        .unwrap();
+        let mut bytes = vec![];
+        OutboxMessageFull::from(call).bin_write(&mut bytes).unwrap();
+        let (_, decoded) = OutboxMessageFull::<OutboxMessage>::nom_read(&bytes).unwrap();
+        assert!(matches!(
+            decoded,
+            OutboxMessageFull::AtomicTransactionBatch(OutboxMessage::ContractCall(_))
+        ));
+    }
 }
@@ -23,6 +23,8 @@ pub enum StorageUpdate {
     },
     /// Remove the value at the given key.
     Remove { key: String },
+    /// Remove the value at the given key and all values under it.
+    RemovePrefix { key: String },
 }
 
 /// Storage update event.
@@ -65,6 +67,12 @@ impl BatchStorageUpdate {
         });
     }
 
+    pub fn push_remove_prefix<K: Path>(&mut self, key: &K) {
+        self.0.push(StorageUpdate::RemovePrefix {
+            key: key.to_string(),
+        });
+    }
+
     fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
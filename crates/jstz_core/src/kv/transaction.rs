@@ -10,11 +10,15 @@ use parking_lot::{ArcMutexGuard, Mutex, RawMutex};
 
 use derive_more::{Deref, DerefMut};
 use tezos_smart_rollup::prelude::debug_msg;
-use tezos_smart_rollup_host::{path::OwnedPath, runtime::Runtime};
+use tezos_smart_rollup_host::{
+    path::{OwnedPath, Path},
+    runtime::Runtime,
+};
 
 use super::{
     outbox::{
-        flush, OutboxError, OutboxMessage, PersistentOutboxQueue, SnapshotOutboxQueue,
+        flush, predict_message_id, OutboxError, OutboxMessage, OutboxMessageId,
+        PersistentOutboxQueue, SnapshotOutboxQueue,
     },
     value::{BoxedValue, Value},
     Storage,
@@ -121,6 +125,9 @@ struct Snapshot {
     insert_edits: BTreeMap<Key, SnapshotValue>,
     // A set of 'remove' edits to be applied
     remove_edits: BTreeSet<Key>,
+    // A set of prefixes whose subtrees are removed from the persistent store. Edits
+    // made after the removal are recorded in the edits above.
+    remove_prefixes: BTreeSet<Key>,
     outbox_queue: SnapshotOutboxQueue,
 }
 
@@ -155,11 +162,24 @@ impl Snapshot {
         self.insert_edits.contains_key(key) && !self.remove_edits.contains(key)
     }
 
+    /// Returns `true` if `key` is under a prefix removed in this snapshot
+    pub fn is_prefix_removed(&self, key: &Key) -> bool {
+        self.remove_prefixes
+            .iter()
+            .any(|prefix| is_under_prefix(prefix, key))
+    }
+
     pub fn outbox_queue_mut(&mut self) -> &mut SnapshotOutboxQueue {
         &mut self.outbox_queue
     }
 }
 
+/// Returns `true` if `key` is `prefix` or a path under `prefix`
+fn is_under_prefix(prefix: &Key, key: &Key) -> bool {
+    let (prefix, key) = (prefix.as_bytes(), key.as_bytes());
+    key.starts_with(prefix) && (key.len() == prefix.len() || key[prefix.len()] == b'/')
+}
+
 impl LookupMap {
     fn update(&mut self, key: Key, idx: usize) {
         let key_history = self.entry(key).or_default();
@@ -238,6 +258,32 @@ impl InnerTransaction {
         Ok(())
     }
 
+    /// Remove `prefix` and all keys under it (as 'remove' edits and a removed prefix
+    /// of the current snapshot)
+    fn current_snapshot_remove_prefix(&mut self, prefix: Key) -> Result<()> {
+        let keys: Vec<Key> = self
+            .lookup_map
+            .range(prefix.clone()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.as_bytes().starts_with(prefix.as_bytes()))
+            .filter(|key| is_under_prefix(&prefix, key))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.current_snapshot_remove(key)?;
+        }
+        self.current_snapshot()?.remove_prefixes.insert(prefix);
+        Ok(())
+    }
+
+    /// Returns `true` if the persistent value of `key` was removed by a prefix
+    /// removal in any snapshot
+    fn is_prefix_removed(&self, key: &Key) -> bool {
+        self.stack
+            .iter()
+            .any(|snapshot| snapshot.is_prefix_removed(key))
+    }
+
     fn lookup<V: Value>(
         &mut self,
         rt: &impl Runtime,
@@ -251,6 +297,10 @@ impl InnerTransaction {
             return Ok(snapshot.lookup(&key));
         }
 
+        if self.is_prefix_removed(&key) {
+            return Ok(None);
+        }
+
         if let Some(value) = Storage::get::<V>(rt, &key)? {
             // TODO: This clone is probably not necessary
             self.current_snapshot_insert(key.clone(), SnapshotValue::new(value))?;
@@ -277,6 +327,8 @@ impl InnerTransaction {
             } else {
                 Ok(None)
             }
+        } else if self.is_prefix_removed(&key) {
+            Ok(None)
         } else if let Some(value) = Storage::get::<V>(rt, &key)? {
             self.current_snapshot_insert(key.clone(), SnapshotValue::new(value))?;
             self.current_snapshot_lookup_mut(&key)
@@ -296,6 +348,10 @@ impl InnerTransaction {
             return Ok(context.contains_key(key));
         }
 
+        if self.is_prefix_removed(key) {
+            return Ok(false);
+        }
+
         Storage::contains_key(rt, key)
     }
 
@@ -303,16 +359,48 @@ impl InnerTransaction {
         &mut self,
         rt: &mut impl Runtime,
         message: OutboxMessage,
-    ) -> Result<()> {
-        if self.persistent_outbox.len(rt)? + self.snapshot_outbox_len + 1
+    ) -> Result<Option<OutboxMessageId>> {
+        let persistent_len = self.persistent_outbox.len(rt)?;
+        if persistent_len + self.snapshot_outbox_len + 1
             > self.persistent_outbox.max(rt)?
         {
             Err(OutboxError::OutboxQueueFull)?;
         }
+        // Messages are flushed after the persistent queue, in snapshot order
+        let pending_snapshot_len: u32 =
+            self.stack.iter().map(|s| s.outbox_queue.len()).sum();
+        let message_id = predict_message_id(rt, persistent_len + pending_snapshot_len)?;
         let current_outbox_queue = self.current_snapshot()?.outbox_queue_mut();
         current_outbox_queue.queue_message(message);
         self.snapshot_outbox_len += 1;
-        Ok(())
+        Ok(message_id)
+    }
+
+    fn outbox_capacity(&mut self, rt: &mut impl Runtime) -> Result<u32> {
+        let queued = self.persistent_outbox.len(rt)? + self.snapshot_outbox_len;
+        Ok(self.persistent_outbox.max(rt)?.saturating_sub(queued))
+    }
+
+    /// Storage updates buffered in the current snapshot.
+    fn storage_updates(&self) -> Result<BatchStorageUpdate> {
+        let Some(curr_ctxt) = self.stack.last() else {
+            return Ok(BatchStorageUpdate::new(0));
+        };
+        let mut storage_updates = BatchStorageUpdate::new(
+            curr_ctxt.remove_prefixes.len()
+                + curr_ctxt.remove_edits.len()
+                + curr_ctxt.insert_edits.len(),
+        );
+        for prefix in &curr_ctxt.remove_prefixes {
+            storage_updates.push_remove_prefix(prefix);
+        }
+        for key in &curr_ctxt.remove_edits {
+            storage_updates.push_remove(key);
+        }
+        for (key, value) in &curr_ctxt.insert_edits {
+            storage_updates.push_insert(key, value.0.as_ref())?;
+        }
+        Ok(storage_updates)
     }
 
     /// Begin a transaction.
@@ -344,14 +432,26 @@ impl InnerTransaction {
                 prev_ctxt.insert(key, value);
             }
 
+            prev_ctxt.remove_prefixes.extend(curr_ctxt.remove_prefixes);
             prev_ctxt.outbox_queue.extend(curr_ctxt.outbox_queue);
         } else {
             let mut storage_updates = BatchStorageUpdate::new(
-                curr_ctxt.remove_edits.len() + curr_ctxt.insert_edits.len(),
+                curr_ctxt.remove_prefixes.len()
+                    + curr_ctxt.remove_edits.len()
+                    + curr_ctxt.insert_edits.len(),
             );
 
             // TODO: Ensure atomicity
             // https://github.com/jstz-dev/jstz/pull/1319#discussion_r2339917375
+            // Prefixes are removed first, since later edits under a removed prefix
+            // are recorded as edits
+            for prefix in &curr_ctxt.remove_prefixes {
+                if rt.store_has(prefix)?.is_some() {
+                    rt.store_delete(prefix)?;
+                }
+                storage_updates.push_remove_prefix(prefix);
+            }
+
             for key in &curr_ctxt.remove_edits {
                 Storage::remove(rt, key)?;
                 storage_updates.push_remove(key);
@@ -484,6 +584,15 @@ impl Transaction {
         inner.current_snapshot_remove(key)
     }
 
+    /// Removes `prefix` and all keys under it, including keys that were never read
+    /// by the transaction.
+    pub fn remove_prefix(&self, prefix: Key) -> Result<()> {
+        let rc = self.acquire_guard()?;
+        let mut inner = rc.borrow_mut();
+        inner.set_dirty(true);
+        inner.current_snapshot_remove_prefix(prefix)
+    }
+
     /// Returns the given key's corresponding entry in the transactional
     /// snapshot for in-place manipulation.
     pub fn entry<'a, 'b, V>(
@@ -522,17 +631,35 @@ impl Transaction {
         }
     }
 
+    /// Queues an outbox message to be flushed when the transaction is committed.
+    /// Returns the [OutboxMessageId] the message is expected to be written at, if known.
     pub fn queue_outbox_message(
         &self,
         rt: &mut impl Runtime,
         message: OutboxMessage,
-    ) -> Result<()> {
+    ) -> Result<Option<OutboxMessageId>> {
         let rc = self.acquire_guard()?;
         let mut inner = rc.borrow_mut();
         inner.set_dirty(true);
         inner.queue_outbox_message(rt, message)
     }
 
+    /// Returns the number of outbox messages that can still be queued before
+    /// [Self::queue_outbox_message] fails with [OutboxError::OutboxQueueFull].
+    pub fn outbox_capacity(&self, rt: &mut impl Runtime) -> Result<u32> {
+        let rc = self.acquire_guard()?;
+        let mut inner = rc.borrow_mut();
+        inner.outbox_capacity(rt)
+    }
+
+    /// Returns the storage updates buffered in the current snapshot, i.e. the
+    /// updates that a commit of the current snapshot would apply.
+    pub fn storage_updates(&self) -> Result<BatchStorageUpdate> {
+        let rc = self.acquire_guard()?;
+        let inner = rc.borrow();
+        inner.storage_updates()
+    }
+
     pub fn get_dirty(&self) -> bool {
         let rc = self.acquire_guard().unwrap();
         let inner = rc.borrow();
@@ -800,7 +927,9 @@ mod test {
     use tezos_smart_rollup_mock::MockHost;
 
     use crate::kv::{
-        outbox::{OutboxMessage, PersistentOutboxQueue},
+        outbox::{
+            set_outbox_level, OutboxMessage, OutboxMessageId, PersistentOutboxQueue,
+        },
         Storage,
     };
 
@@ -916,6 +1045,7 @@ mod test {
         let tx = Transaction::default();
         tx.acquire_guard().unwrap().borrow_mut().persistent_outbox =
             PersistentOutboxQueue::try_new(&mut host, 120).unwrap();
+        assert_eq!(120, tx.outbox_capacity(&mut host).unwrap());
 
         for i in 0..120 {
             if i % 10 == 0 {
@@ -930,6 +1060,7 @@ mod test {
             120,
             tx.acquire_guard().unwrap().borrow().snapshot_outbox_len
         );
+        assert_eq!(0, tx.outbox_capacity(&mut host).unwrap());
 
         // Adding an additional message to a full outbox queue without
         // flushing should fail
@@ -980,6 +1111,52 @@ mod test {
         assert_eq!(0, outbox.len());
     }
 
+    #[test]
+    fn queue_outbox_message_returns_expected_message_id() {
+        let mut host = MockHost::default();
+        let tx = Transaction::default();
+        let acc = PublicKeyHash::digest(b"account").unwrap();
+
+        // The level is unknown until the kernel records it
+        tx.begin();
+        let id = tx
+            .queue_outbox_message(&mut host, make_withdrawal(&acc))
+            .unwrap();
+        assert_eq!(id, None);
+        tx.rollback().unwrap();
+
+        set_outbox_level(&mut host, 7).unwrap();
+        tx.begin();
+        let id = tx
+            .queue_outbox_message(&mut host, make_withdrawal(&acc))
+            .unwrap();
+        assert_eq!(id, Some(OutboxMessageId { level: 7, index: 0 }));
+
+        // Messages of rolled back snapshots are never written
+        tx.begin();
+        tx.queue_outbox_message(&mut host, make_withdrawal(&acc))
+            .unwrap();
+        tx.rollback().unwrap();
+
+        tx.begin();
+        let id = tx
+            .queue_outbox_message(&mut host, make_withdrawal(&acc))
+            .unwrap();
+        assert_eq!(id, Some(OutboxMessageId { level: 7, index: 1 }));
+        tx.commit(&mut host).unwrap();
+        tx.commit(&mut host).unwrap();
+
+        tx.begin();
+        let id = tx
+            .queue_outbox_message(&mut host, make_withdrawal(&acc))
+            .unwrap();
+        assert_eq!(id, Some(OutboxMessageId { level: 7, index: 2 }));
+        tx.commit(&mut host).unwrap();
+
+        let level = host.run_level(|_| {});
+        assert_eq!(3, host.outbox_at(level).len());
+    }
+
     #[test]
     #[ignore]
     fn final_commit_resets_snapshot_queue_len() {
@@ -1144,6 +1321,7 @@ mod test {
 #[cfg(test)]
 mod tests {
     use crate::event::test::Sink;
+    use crate::kv::storage_update::StorageUpdate;
     use bincode::{Decode, Encode};
     use serde::{Deserialize, Serialize};
     use tezos_smart_rollup_mock::MockHost;
@@ -1253,6 +1431,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn storage_updates_of_current_snapshot() {
+        let tx = Transaction::default();
+        assert_eq!(tx.storage_updates().unwrap().into_iter().count(), 0);
+
+        tx.begin();
+        tx.insert(OwnedPath::try_from("/key1".to_string()).unwrap(), 42)
+            .unwrap();
+        tx.begin();
+        tx.remove(OwnedPath::try_from("/key2".to_string()).unwrap())
+            .unwrap();
+        assert_eq!(
+            tx.storage_updates()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![StorageUpdate::Remove {
+                key: "/key2".to_string()
+            }]
+        );
+
+        tx.rollback().unwrap();
+        assert_eq!(
+            tx.storage_updates()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![StorageUpdate::Insert {
+                key: "/key1".to_string(),
+                value: vec![42, 0, 0, 0]
+            }]
+        );
+    }
+
     #[test]
     fn storage_update_event_is_not_published_when_there_are_no_kv_changes() {
         let mut sink = Sink(Vec::new());
@@ -1269,4 +1481,94 @@ mod tests {
         tx.commit(&mut hrt).unwrap();
         assert!(sink.lines().first().unwrap().is_empty());
     }
+
+    fn path(path: &str) -> OwnedPath {
+        OwnedPath::try_from(path.to_string()).unwrap()
+    }
+
+    #[test]
+    fn remove_prefix_hides_persistent_and_buffered_keys() {
+        let mut hrt = MockHost::default();
+        let tx = Transaction::default();
+        tx.begin();
+        tx.insert(path("/kv/a"), TestValue(1)).unwrap();
+        tx.insert(path("/kv/a/b"), TestValue(2)).unwrap();
+        tx.insert(path("/kv/ab"), TestValue(3)).unwrap();
+        tx.commit(&mut hrt).unwrap();
+
+        tx.begin();
+        tx.insert(path("/kv/a/c"), TestValue(4)).unwrap();
+        tx.begin();
+        tx.remove_prefix(path("/kv/a")).unwrap();
+        for key in ["/kv/a", "/kv/a/b", "/kv/a/c"] {
+            assert!(!tx.contains_key(&hrt, &path(key)).unwrap(), "{key}");
+            assert!(tx.get::<TestValue>(&hrt, path(key)).unwrap().is_none());
+        }
+        assert!(tx.contains_key(&hrt, &path("/kv/ab")).unwrap());
+
+        // Keys can be written again after the removal
+        tx.insert(path("/kv/a/d"), TestValue(5)).unwrap();
+        tx.commit(&mut hrt).unwrap();
+        assert!(!tx.contains_key(&hrt, &path("/kv/a/b")).unwrap());
+        assert!(tx.contains_key(&hrt, &path("/kv/a/d")).unwrap());
+        tx.commit(&mut hrt).unwrap();
+
+        assert!(!Storage::contains_key(&hrt, &path("/kv/a")).unwrap());
+        assert!(!Storage::contains_key(&hrt, &path("/kv/a/b")).unwrap());
+        assert!(!Storage::contains_key(&hrt, &path("/kv/a/c")).unwrap());
+        assert_eq!(
+            Storage::get::<TestValue>(&hrt, &path("/kv/a/d"))
+                .unwrap()
+                .unwrap()
+                .0,
+            5
+        );
+        assert!(Storage::contains_key(&hrt, &path("/kv/ab")).unwrap());
+    }
+
+    #[test]
+    fn rollback_restores_removed_prefix() {
+        let mut hrt = MockHost::default();
+        let tx = Transaction::default();
+        tx.begin();
+        tx.insert(path("/kv/a/b"), TestValue(1)).unwrap();
+        tx.commit(&mut hrt).unwrap();
+
+        tx.begin();
+        tx.begin();
+        tx.remove_prefix(path("/kv/a")).unwrap();
+        assert!(!tx.contains_key(&hrt, &path("/kv/a/b")).unwrap());
+        tx.rollback().unwrap();
+        assert!(tx.contains_key(&hrt, &path("/kv/a/b")).unwrap());
+        tx.commit(&mut hrt).unwrap();
+        assert!(Storage::contains_key(&hrt, &path("/kv/a/b")).unwrap());
+    }
+
+    #[test]
+    fn remove_prefix_storage_updates() {
+        let mut sink = Sink(Vec::new());
+        let mut hrt = MockHost::default();
+        hrt.set_debug_handler(unsafe {
+            std::mem::transmute::<&mut std::vec::Vec<u8>, &'static mut Vec<u8>>(
+                &mut sink.0,
+            )
+        });
+        let tx = Transaction::default();
+        tx.begin();
+        tx.remove_prefix(path("/kv/a")).unwrap();
+        assert_eq!(
+            tx.storage_updates()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![StorageUpdate::RemovePrefix {
+                key: "/kv/a".to_string()
+            }]
+        );
+        tx.commit(&mut hrt).unwrap();
+        assert_eq!(
+            sink.lines().first().unwrap(),
+            r#"[BATCH_STORAGE_UPDATE][{"RemovePrefix":{"key":"/kv/a"}}]"#
+        );
+    }
 }
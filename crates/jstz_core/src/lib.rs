@@ -7,6 +7,7 @@ pub mod iterators;
 pub mod js_fn;
 pub mod kv;
 pub mod log_record;
+pub mod micheline;
 pub mod native;
 pub mod realm;
 pub mod reveal_data;
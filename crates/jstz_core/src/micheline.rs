@@ -0,0 +1,680 @@
+//! Binary encoding of Micheline, the syntax of Michelson values. Used to build
+//! the parameters of L1 contract calls from their JSON representation, for
+//! example `{ "prim": "Pair", "args": [{ "int": "1" }, { "string": "foo" }] }`.
+
+use derive_more::{Display, Error};
+use nom::{
+    bytes::complete::take_till,
+    error::{ErrorKind, ParseError},
+    multi::length_data,
+    number::complete::{be_u32, u8 as nom_u8},
+    IResult,
+};
+use num_bigint::{BigInt, Sign};
+use serde_json::{json, Map, Value as JsonValue};
+use tezos_data_encoding::{
+    enc::{BinResult, BinWriter},
+    encoding::{Encoding, HasEncoding},
+    nom::{NomError, NomReader, NomResult},
+};
+use tezos_smart_rollup::michelson::Michelson;
+
+const INT_TAG: u8 = 0x00;
+const STRING_TAG: u8 = 0x01;
+const SEQ_TAG: u8 = 0x02;
+const PRIM_0_ARGS_TAG: u8 = 0x03;
+const PRIM_0_ARGS_ANNOTS_TAG: u8 = 0x04;
+const PRIM_1_ARG_TAG: u8 = 0x05;
+const PRIM_1_ARG_ANNOTS_TAG: u8 = 0x06;
+const PRIM_2_ARGS_TAG: u8 = 0x07;
+const PRIM_2_ARGS_ANNOTS_TAG: u8 = 0x08;
+const PRIM_N_ARGS_TAG: u8 = 0x09;
+const BYTES_TAG: u8 = 0x0a;
+
+/// Prefix of packed Michelson values
+const PACK_TAG: u8 = 0x05;
+
+/// Michelson primitives, indexed by their binary code
+const PRIMITIVES: [&str; 157] = [
+    "parameter",
+    "storage",
+    "code",
+    "False",
+    "Elt",
+    "Left",
+    "None",
+    "Pair",
+    "Right",
+    "Some",
+    "True",
+    "Unit",
+    "PACK",
+    "UNPACK",
+    "BLAKE2B",
+    "SHA256",
+    "SHA512",
+    "ABS",
+    "ADD",
+    "AMOUNT",
+    "AND",
+    "BALANCE",
+    "CAR",
+    "CDR",
+    "CHECK_SIGNATURE",
+    "COMPARE",
+    "CONCAT",
+    "CONS",
+    "CREATE_ACCOUNT",
+    "CREATE_CONTRACT",
+    "IMPLICIT_ACCOUNT",
+    "DIP",
+    "DROP",
+    "DUP",
+    "EDIV",
+    "EMPTY_MAP",
+    "EMPTY_SET",
+    "EQ",
+    "EXEC",
+    "FAILWITH",
+    "GE",
+    "GET",
+    "GT",
+    "HASH_KEY",
+    "IF",
+    "IF_CONS",
+    "IF_LEFT",
+    "IF_NONE",
+    "INT",
+    "LAMBDA",
+    "LE",
+    "LEFT",
+    "LOOP",
+    "LSL",
+    "LSR",
+    "LT",
+    "MAP",
+    "MEM",
+    "MUL",
+    "NEG",
+    "NEQ",
+    "NIL",
+    "NONE",
+    "NOT",
+    "NOW",
+    "OR",
+    "PAIR",
+    "PUSH",
+    "RIGHT",
+    "SIZE",
+    "SOME",
+    "SOURCE",
+    "SENDER",
+    "SELF",
+    "STEPS_TO_QUOTA",
+    "SUB",
+    "SWAP",
+    "TRANSFER_TOKENS",
+    "SET_DELEGATE",
+    "UNIT",
+    "UPDATE",
+    "XOR",
+    "ITER",
+    "LOOP_LEFT",
+    "ADDRESS",
+    "CONTRACT",
+    "ISNAT",
+    "CAST",
+    "RENAME",
+    "bool",
+    "contract",
+    "int",
+    "key",
+    "key_hash",
+    "lambda",
+    "list",
+    "map",
+    "big_map",
+    "nat",
+    "option",
+    "or",
+    "pair",
+    "set",
+    "signature",
+    "string",
+    "bytes",
+    "mutez",
+    "timestamp",
+    "unit",
+    "operation",
+    "address",
+    "SLICE",
+    "DIG",
+    "DUG",
+    "EMPTY_BIG_MAP",
+    "APPLY",
+    "chain_id",
+    "CHAIN_ID",
+    "LEVEL",
+    "SELF_ADDRESS",
+    "never",
+    "NEVER",
+    "UNPAIR",
+    "VOTING_POWER",
+    "TOTAL_VOTING_POWER",
+    "KECCAK",
+    "SHA3",
+    "PAIRING_CHECK",
+    "bls12_381_g1",
+    "bls12_381_g2",
+    "bls12_381_fr",
+    "sapling_state",
+    "sapling_transaction_deprecated",
+    "SAPLING_EMPTY_STATE",
+    "SAPLING_VERIFY_UPDATE",
+    "ticket",
+    "TICKET_DEPRECATED",
+    "READ_TICKET",
+    "SPLIT_TICKET",
+    "JOIN_TICKETS",
+    "GET_AND_UPDATE",
+    "chest",
+    "chest_key",
+    "OPEN_CHEST",
+    "VIEW",
+    "view",
+    "constant",
+    "SUB_MUTEZ",
+    "tx_rollup_l2_address",
+    "MIN_BLOCK_TIME",
+    "sapling_transaction",
+    "EMIT",
+    "Lambda_rec",
+    "LAMBDA_REC",
+    "TICKET",
+    "BYTES",
+    "NAT",
+];
+
+#[derive(Display, Debug, Error)]
+pub enum MichelineError {
+    #[display(fmt = "invalid Micheline JSON node: {node}")]
+    InvalidNode {
+        #[error(not(source))]
+        node: String,
+    },
+    #[display(fmt = "invalid Micheline integer: {value}")]
+    InvalidInt {
+        #[error(not(source))]
+        value: String,
+    },
+    #[display(fmt = "invalid Micheline bytes: {value}")]
+    InvalidBytes {
+        #[error(not(source))]
+        value: String,
+    },
+    #[display(fmt = "unknown Michelson primitive: {prim}")]
+    UnknownPrimitive {
+        #[error(not(source))]
+        prim: String,
+    },
+    #[display(fmt = "invalid Micheline binary encoding")]
+    InvalidEncoding,
+}
+
+pub type Result<T> = std::result::Result<T, MichelineError>;
+
+/// A Micheline expression in its binary encoding, as accepted in the
+/// parameters of L1 contract calls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Micheline(Vec<u8>);
+
+impl Micheline {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Decodes the result of the Michelson `PACK` instruction, that is, a
+    /// Micheline expression prefixed with the `0x05` tag
+    pub fn unpack(bytes: &[u8]) -> Result<Self> {
+        let expr = bytes
+            .strip_prefix(&[PACK_TAG])
+            .ok_or(MichelineError::InvalidEncoding)?;
+        match Self::nom_read(expr) {
+            Ok((rest, micheline)) if rest.is_empty() => Ok(micheline),
+            _ => Err(MichelineError::InvalidEncoding),
+        }
+    }
+
+    /// Returns the JSON representation of the expression
+    pub fn to_json(&self) -> Result<JsonValue> {
+        read_node::<()>(&self.0)
+            .map(|(_, value)| value)
+            .map_err(|_| MichelineError::InvalidEncoding)
+    }
+}
+
+impl TryFrom<&JsonValue> for Micheline {
+    type Error = MichelineError;
+
+    fn try_from(value: &JsonValue) -> Result<Self> {
+        let mut output = vec![];
+        encode_node(value, &mut output)?;
+        Ok(Micheline(output))
+    }
+}
+
+fn invalid_node(node: &JsonValue) -> MichelineError {
+    MichelineError::InvalidNode {
+        node: node.to_string(),
+    }
+}
+
+fn write_dynamic(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    output.extend_from_slice(bytes);
+}
+
+/// Writes `value` in the zarith encoding: the sign and the 6 least significant
+/// bits in the first byte then 7 bits per byte, the most significant bit of
+/// each byte indicating whether another byte follows
+fn write_zarith(value: &BigInt, output: &mut Vec<u8>) {
+    let (sign, bytes) = value.to_bytes_le();
+    let bits: Vec<bool> = bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    let len = bits.iter().rposition(|bit| *bit).map_or(0, |i| i + 1);
+    let take_bits = |from: usize, count: usize| -> u8 {
+        (0..count)
+            .filter(|i| from + i < len && bits[from + i])
+            .fold(0, |acc, i| acc | (1 << i))
+    };
+
+    let mut byte = take_bits(0, 6);
+    if sign == Sign::Minus {
+        byte |= 0x40;
+    }
+    let mut position = 6;
+    while position < len {
+        output.push(byte | 0x80);
+        byte = take_bits(position, 7);
+        position += 7;
+    }
+    output.push(byte);
+}
+
+fn encode_prim(node: &Map<String, JsonValue>, output: &mut Vec<u8>) -> Result<()> {
+    let prim = node
+        .get("prim")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid_node(&JsonValue::Object(node.clone())))?;
+    let code = PRIMITIVES.iter().position(|p| *p == prim).ok_or_else(|| {
+        MichelineError::UnknownPrimitive {
+            prim: prim.to_string(),
+        }
+    })? as u8;
+    let args = match node.get("args") {
+        Some(JsonValue::Array(args)) => args.as_slice(),
+        None => &[],
+        Some(args) => return Err(invalid_node(args)),
+    };
+    let annots = match node.get("annots") {
+        Some(JsonValue::Array(annots)) => annots
+            .iter()
+            .map(|annot| annot.as_str().ok_or_else(|| invalid_node(annot)))
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![],
+        Some(annots) => return Err(invalid_node(annots)),
+    };
+
+    let tag = match (args.len(), annots.is_empty()) {
+        (0, true) => PRIM_0_ARGS_TAG,
+        (0, false) => PRIM_0_ARGS_ANNOTS_TAG,
+        (1, true) => PRIM_1_ARG_TAG,
+        (1, false) => PRIM_1_ARG_ANNOTS_TAG,
+        (2, true) => PRIM_2_ARGS_TAG,
+        (2, false) => PRIM_2_ARGS_ANNOTS_TAG,
+        _ => PRIM_N_ARGS_TAG,
+    };
+    output.push(tag);
+    output.push(code);
+    if tag == PRIM_N_ARGS_TAG {
+        let mut encoded_args = vec![];
+        for arg in args {
+            encode_node(arg, &mut encoded_args)?;
+        }
+        write_dynamic(output, &encoded_args);
+        write_dynamic(output, annots.join(" ").as_bytes());
+    } else {
+        for arg in args {
+            encode_node(arg, output)?;
+        }
+        if !annots.is_empty() {
+            write_dynamic(output, annots.join(" ").as_bytes());
+        }
+    }
+    Ok(())
+}
+
+fn encode_node(node: &JsonValue, output: &mut Vec<u8>) -> Result<()> {
+    match node {
+        JsonValue::Array(nodes) => {
+            let mut encoded_nodes = vec![];
+            for node in nodes {
+                encode_node(node, &mut encoded_nodes)?;
+            }
+            output.push(SEQ_TAG);
+            write_dynamic(output, &encoded_nodes);
+        }
+        JsonValue::Object(object) => {
+            if let Some(value) = object.get("int") {
+                let int = value
+                    .as_str()
+                    .and_then(|value| value.parse::<BigInt>().ok())
+                    .ok_or_else(|| MichelineError::InvalidInt {
+                        value: value.to_string(),
+                    })?;
+                output.push(INT_TAG);
+                write_zarith(&int, output);
+            } else if let Some(value) = object.get("string") {
+                let string = value.as_str().ok_or_else(|| invalid_node(node))?;
+                output.push(STRING_TAG);
+                write_dynamic(output, string.as_bytes());
+            } else if let Some(value) = object.get("bytes") {
+                let bytes = value
+                    .as_str()
+                    .and_then(|value| hex::decode(value).ok())
+                    .ok_or_else(|| MichelineError::InvalidBytes {
+                        value: value.to_string(),
+                    })?;
+                output.push(BYTES_TAG);
+                write_dynamic(output, &bytes);
+            } else {
+                encode_prim(object, output)?;
+            }
+        }
+        _ => return Err(invalid_node(node)),
+    }
+    Ok(())
+}
+
+/// Reads a zarith encoded integer. See [write_zarith]
+fn read_zarith<'a, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], BigInt, E> {
+    let (input, head) = take_till(|byte| byte & 0x80 == 0)(input)?;
+    let (input, last) = nom_u8(input)?;
+    let mut value = BigInt::default();
+    let mut shift = 0;
+    for (i, byte) in head.iter().chain([last].iter()).enumerate() {
+        let bits = if i == 0 { byte & 0x3f } else { byte & 0x7f };
+        value += BigInt::from(bits) << shift;
+        shift += if i == 0 { 6 } else { 7 };
+    }
+    let is_negative = head.first().unwrap_or(&last) & 0x40 != 0;
+    Ok((input, if is_negative { -value } else { value }))
+}
+
+fn read_annots<'a, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], Vec<JsonValue>, E> {
+    let (input, annots) = length_data(be_u32)(input)?;
+    let annots = std::str::from_utf8(annots)
+        .map_err(|_| nom::Err::Error(E::from_error_kind(annots, ErrorKind::Verify)))?;
+    let annots = annots
+        .split(' ')
+        .filter(|annot| !annot.is_empty())
+        .map(|annot| JsonValue::String(annot.to_string()))
+        .collect();
+    Ok((input, annots))
+}
+
+fn read_nodes<'a, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], Vec<JsonValue>, E> {
+    let (input, mut nodes) = length_data(be_u32)(input)?;
+    let mut values = vec![];
+    while !nodes.is_empty() {
+        let (rest, value) = read_node::<E>(nodes)?;
+        values.push(value);
+        nodes = rest;
+    }
+    Ok((input, values))
+}
+
+/// Reads a single Micheline node in its binary encoding into its JSON
+/// representation
+fn read_node<'a, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], JsonValue, E> {
+    let (input, tag) = nom_u8(input)?;
+    match tag {
+        INT_TAG => {
+            let (input, int) = read_zarith(input)?;
+            Ok((input, json!({ "int": int.to_string() })))
+        }
+        STRING_TAG => {
+            let (input, string) = length_data(be_u32)(input)?;
+            let string = std::str::from_utf8(string).map_err(|_| {
+                nom::Err::Error(E::from_error_kind(string, ErrorKind::Verify))
+            })?;
+            Ok((input, json!({ "string": string })))
+        }
+        BYTES_TAG => {
+            let (input, bytes) = length_data(be_u32)(input)?;
+            Ok((input, json!({ "bytes": hex::encode(bytes) })))
+        }
+        SEQ_TAG => {
+            let (input, nodes) = read_nodes(input)?;
+            Ok((input, JsonValue::Array(nodes)))
+        }
+        PRIM_0_ARGS_TAG..=PRIM_N_ARGS_TAG => {
+            let (mut input, code) = nom_u8(input)?;
+            let prim = PRIMITIVES.get(code as usize).ok_or_else(|| {
+                nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag))
+            })?;
+            let mut args = vec![];
+            if tag == PRIM_N_ARGS_TAG {
+                (input, args) = read_nodes(input)?;
+            } else {
+                for _ in 0..(tag - PRIM_0_ARGS_TAG) / 2 {
+                    let (rest, arg) = read_node::<E>(input)?;
+                    args.push(arg);
+                    input = rest;
+                }
+            }
+            let mut annots = vec![];
+            if tag == PRIM_N_ARGS_TAG || (tag - PRIM_0_ARGS_TAG) % 2 == 1 {
+                (input, annots) = read_annots(input)?;
+            }
+
+            let mut node = Map::new();
+            node.insert("prim".to_string(), JsonValue::String(prim.to_string()));
+            if !args.is_empty() {
+                node.insert("args".to_string(), JsonValue::Array(args));
+            }
+            if !annots.is_empty() {
+                node.insert("annots".to_string(), JsonValue::Array(annots));
+            }
+            Ok((input, JsonValue::Object(node)))
+        }
+        _ => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Tag))),
+    }
+}
+
+impl HasEncoding for Micheline {
+    fn encoding() -> Encoding {
+        Encoding::Custom
+    }
+}
+
+impl BinWriter for Micheline {
+    fn bin_write(&self, output: &mut Vec<u8>) -> BinResult {
+        output.extend_from_slice(&self.0);
+        Ok(())
+    }
+}
+
+impl<'a> NomReader<'a> for Micheline {
+    fn nom_read(input: &'a [u8]) -> NomResult<'a, Self> {
+        let (rest, _) = read_node::<NomError>(input)?;
+        let consumed = input.len() - rest.len();
+        Ok((rest, Micheline(input[..consumed].to_vec())))
+    }
+}
+
+impl Michelson for Micheline {}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tezos_data_encoding::nom::NomReader;
+    use tezos_smart_rollup::michelson::{
+        MichelsonBytes, MichelsonInt, MichelsonOr, MichelsonPair, MichelsonString,
+        MichelsonUnit,
+    };
+
+    use super::{Micheline, MichelineError};
+
+    fn encode(value: serde_json::Value) -> Vec<u8> {
+        Micheline::try_from(&value).unwrap().as_bytes().to_vec()
+    }
+
+    fn bin_write(value: impl tezos_data_encoding::enc::BinWriter) -> Vec<u8> {
+        let mut output = vec![];
+        value.bin_write(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn encodes_like_typed_michelson_values() {
+        assert_eq!(
+            encode(
+                json!({ "prim": "Pair", "args": [{ "int": "-65" }, { "string": "foo" }] })
+            ),
+            bin_write(MichelsonPair(
+                MichelsonInt::from(-65),
+                MichelsonString("foo".to_string())
+            ))
+        );
+        assert_eq!(
+            encode(json!({ "prim": "Left", "args": [{ "prim": "Unit" }] })),
+            bin_write(MichelsonOr::<MichelsonUnit, MichelsonUnit>::Left(
+                MichelsonUnit
+            ))
+        );
+        assert_eq!(
+            encode(json!({ "bytes": "cafe" })),
+            bin_write(MichelsonBytes(vec![0xca, 0xfe]))
+        );
+    }
+
+    #[test]
+    fn encodes_ints() {
+        assert_eq!(encode(json!({ "int": "0" })), vec![0x00, 0x00]);
+        assert_eq!(encode(json!({ "int": "-1" })), vec![0x00, 0x41]);
+        assert_eq!(encode(json!({ "int": "64" })), vec![0x00, 0x80, 0x01]);
+        assert_eq!(
+            encode(json!({ "int": "1000000000000000000000" })),
+            vec![0x00, 0x80, 0x80, 0x80, 0xea, 0xbb, 0xf1, 0xd6, 0xc9, 0xeb, 0xd8, 0x01]
+        );
+    }
+
+    #[test]
+    fn encodes_sequences_annotations_and_n_ary_prims() {
+        assert_eq!(
+            encode(json!([{ "int": "1" }, { "prim": "Unit" }])),
+            vec![0x02, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x03, 0x0b]
+        );
+        assert_eq!(
+            encode(json!({ "prim": "nat", "annots": [":amount"] })),
+            vec![
+                0x04, 0x62, 0x00, 0x00, 0x00, 0x07, b':', b'a', b'm', b'o', b'u', b'n',
+                b't'
+            ]
+        );
+        assert_eq!(
+            encode(
+                json!({ "prim": "Pair", "args": [{ "int": "1" }, { "int": "2" }, { "int": "3" }] })
+            ),
+            vec![
+                0x09, 0x07, 0x00, 0x00, 0x00, 0x06, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03,
+                0x00, 0x00, 0x00, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn nom_read_round_trips() {
+        let value = json!({
+            "prim": "Pair",
+            "args": [
+                [{ "prim": "Elt", "args": [{ "string": "a" }, { "bytes": "00" }] }],
+                { "prim": "Some", "args": [{ "int": "-12345" }], "annots": ["%foo"] },
+                { "prim": "Unit" }
+            ]
+        });
+        let micheline = Micheline::try_from(&value).unwrap();
+        let mut input = micheline.as_bytes().to_vec();
+        input.extend_from_slice(b"rest");
+
+        let (rest, decoded) = Micheline::nom_read(&input).unwrap();
+        assert_eq!(rest, b"rest");
+        assert_eq!(decoded, micheline);
+        assert_eq!(decoded.to_json().unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_decodes_packed_values() {
+        let packed = [0x05, 0x01, 0x00, 0x00, 0x00, 0x03, b'f', b'o', b'o'];
+        let micheline = Micheline::unpack(&packed).unwrap();
+        assert_eq!(micheline.to_json().unwrap(), json!({ "string": "foo" }));
+
+        let packed = [
+            0x05, 0x00, 0x80, 0x80, 0x80, 0xea, 0xbb, 0xf1, 0xd6, 0xc9, 0xeb, 0xd8, 0x01,
+        ];
+        let micheline = Micheline::unpack(&packed).unwrap();
+        assert_eq!(
+            micheline.to_json().unwrap(),
+            json!({ "int": "1000000000000000000000" })
+        );
+
+        let packed = [0x05, 0x00, 0xc1, 0x01];
+        let micheline = Micheline::unpack(&packed).unwrap();
+        assert_eq!(micheline.to_json().unwrap(), json!({ "int": "-65" }));
+
+        // Missing pack prefix, trailing bytes and unknown primitive
+        assert!(matches!(
+            Micheline::unpack(&packed[1..]),
+            Err(MichelineError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            Micheline::unpack(&[0x05, 0x03, 0x0b, 0x00]),
+            Err(MichelineError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            Micheline::unpack(&[0x05, 0x03, 0xff]),
+            Err(MichelineError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(matches!(
+            Micheline::try_from(&json!({ "prim": "Foo" })),
+            Err(MichelineError::UnknownPrimitive { .. })
+        ));
+        assert!(matches!(
+            Micheline::try_from(&json!({ "int": 1 })),
+            Err(MichelineError::InvalidInt { .. })
+        ));
+        assert!(matches!(
+            Micheline::try_from(&json!({ "bytes": "xyz" })),
+            Err(MichelineError::InvalidBytes { .. })
+        ));
+        assert!(matches!(
+            Micheline::try_from(&json!("foo")),
+            Err(MichelineError::InvalidNode { .. })
+        ));
+    }
+}
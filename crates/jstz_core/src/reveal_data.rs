@@ -1,5 +1,6 @@
 use crate::error::Result;
-use crate::{host::HostRuntime, BinEncodable};
+use crate::{bin_encodable::decode_revealed, host::HostRuntime, BinEncodable};
+use bincode::Decode;
 use derive_more::From;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
@@ -7,18 +8,22 @@ use std::fmt;
 use tezos_smart_rollup::{
     core_unsafe::PREIMAGE_HASH_SIZE,
     dac::{
-        self, PreimageHash as DacPreimageHash, PreimageHashError, V0SliceContentPage,
-        MAX_PAGE_SIZE,
+        self, PreimageHash as DacPreimageHash, PreimageHashError, SlicePage,
+        V0SliceContentPage, MAX_PAGE_SIZE,
     },
 };
+use tezos_smart_rollup_host::runtime::Runtime;
 use thiserror::Error;
 
-// Maximum number of DAC levels to support, can reveal up to 59MB of data.
-const MAX_DAC_LEVELS: usize = 3;
-// Support `MAX_DAC_LEVELS` levels of hashes pages, + the bottom layer of content.
-const MAX_REVEAL_BUFFER_SIZE: usize = MAX_PAGE_SIZE * (MAX_DAC_LEVELS + 1);
-/// maximum size of the reveal data in bytes (10MB)
-pub const MAX_REVEAL_SIZE: usize = 10 * 1024 * 1024;
+/// Upper bound on the size of the reveal data in bytes (128MB). Protocol limits can
+/// be configured up to this size.
+pub const MAX_REVEAL_SIZE: usize = 128 * 1024 * 1024;
+/// Default protocol limit on the size of the reveal data in bytes (10MB)
+pub const DEFAULT_MAX_REVEAL_SIZE: usize = 10 * 1024 * 1024;
+
+/// Content bytes of a full DAC content page, which starts with a 1-byte tag and a
+/// 4-byte length
+const MAX_PAGE_CONTENT_SIZE: usize = MAX_PAGE_SIZE - 5;
 
 /// A 33-byte hash corresponding to a preimage
 type RawPreimageHash = [u8; PREIMAGE_HASH_SIZE];
@@ -77,32 +82,77 @@ pub enum Error {
 pub struct RevealData;
 
 impl RevealData {
+    /// Upper bound on the number of pages, hash pages included, of the page tree of
+    /// `max_size` bytes of data. Trees built by [`dac::prepare_preimages`] have fewer
+    /// hash pages than content pages, but pages can be shared in a crafted tree, so
+    /// the number of pages must be bounded on its own.
+    fn max_pages(max_size: usize) -> usize {
+        2 * max_size.div_ceil(MAX_PAGE_CONTENT_SIZE).max(1)
+    }
+
+    /// Walks the DAC page tree rooted at `root_hash` depth-first, passing the content
+    /// pages to `save_content` in order, and fails once more than `max_pages` pages
+    /// were revealed.
+    ///
+    /// Pending hashes are kept on the heap rather than in a buffer per level, so the
+    /// tree can be of any depth.
     fn reveal<H, F>(
         hrt: &mut H,
         root_hash: &PreimageHash,
+        max_pages: usize,
         save_content: &mut F,
     ) -> Result<()>
     where
         H: HostRuntime,
-        F: FnMut(&mut H, V0SliceContentPage) -> std::result::Result<(), &'static str>,
+        F: FnMut(&mut H, V0SliceContentPage) -> Result<()>,
     {
-        let mut reveal_buffer = [0; MAX_REVEAL_BUFFER_SIZE];
-        dac::reveal_loop(
-            hrt,
-            0,
-            root_hash.as_ref(),
-            &mut reveal_buffer,
-            MAX_DAC_LEVELS,
-            save_content,
-        )
-        .map_err(|e| e.to_owned().into())
+        let mut page_buffer = [0; MAX_PAGE_SIZE];
+        let mut pending: Vec<RawPreimageHash> = vec![*root_hash.as_ref()];
+        let mut revealed_pages = 0;
+        while let Some(hash) = pending.pop() {
+            revealed_pages += 1;
+            if revealed_pages > max_pages {
+                return Err(Error::RevealDataSizeExceedsMaximumLimit.into());
+            }
+            let size = hrt.reveal_preimage(&hash, &mut page_buffer).map_err(|e| {
+                Error::RevealDataError {
+                    description: format!("Failed to reveal preimage: {e:?}"),
+                }
+            })?;
+            let page = SlicePage::try_from(&page_buffer[..size]).map_err(|_| {
+                Error::RevealDataError {
+                    description: "Unable to decode DAC page".to_string(),
+                }
+            })?;
+            match page {
+                // Reversed so that the first child is popped first
+                SlicePage::V0HashPage(page) => pending.extend(page.hashes().iter().rev()),
+                SlicePage::V0ContentPage(page) => save_content(hrt, page)?,
+            }
+        }
+        Ok(())
     }
 
     /// Reveal the data and decode it into the given type.
     pub fn reveal_and_decode<H, T>(hrt: &mut H, root_hash: &PreimageHash) -> Result<T>
     where
         H: HostRuntime,
-        T: BinEncodable,
+        T: Decode,
+    {
+        Self::reveal_and_decode_with_limit(hrt, root_hash, MAX_REVEAL_SIZE)
+    }
+
+    /// Reveal the data and decode it into the given type, failing as soon as the
+    /// revealed data exceeds `max_size` bytes or its page tree has more pages than
+    /// data of that size needs.
+    pub fn reveal_and_decode_with_limit<H, T>(
+        hrt: &mut H,
+        root_hash: &PreimageHash,
+        max_size: usize,
+    ) -> Result<T>
+    where
+        H: HostRuntime,
+        T: Decode,
     {
         // TODO: include the size of the data in the operation to avoid the allocation of a large buffer
         // https://linear.app/tezos/issue/JSTZ-359/optimize-reveal-data
@@ -110,12 +160,16 @@ impl RevealData {
         Self::reveal(
             hrt,
             root_hash,
+            Self::max_pages(max_size),
             &mut |_: &mut H, page: V0SliceContentPage| {
+                if content.len() + page.as_ref().len() > max_size {
+                    return Err(Error::RevealDataSizeExceedsMaximumLimit.into());
+                }
                 content.extend_from_slice(page.as_ref());
                 Ok(())
             },
         )?;
-        T::decode(&content[..])
+        decode_revealed(&content[..])
     }
 
     /// Encode the data, prepare the preimages and return the root preimage hash.
@@ -145,8 +199,7 @@ impl RevealData {
 mod tests {
     use super::*;
     use crate::error::Error;
-    use crate::BinEncodable;
-    use bincode::{Decode, Encode};
+    use bincode::Encode;
     use tezos_smart_rollup_mock::MockHost;
 
     #[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
@@ -160,6 +213,55 @@ mod tests {
         assert!(matches!(err, Error::RevealDataError { .. }));
     }
 
+    #[test]
+    fn reveal_and_decode_fails_if_size_exceeds_limit() {
+        let mut host = MockHost::default();
+        let data = TestData(vec![1; 3 * MAX_PAGE_SIZE]);
+        let preimage_hash = RevealData::encode_and_prepare_preimages(&data, |_, page| {
+            host.set_preimage(page);
+        })
+        .expect("should prepare preimages");
+
+        let err = RevealData::reveal_and_decode_with_limit::<_, TestData>(
+            &mut host,
+            &preimage_hash,
+            2 * MAX_PAGE_SIZE,
+        )
+        .expect_err("should fail");
+        assert!(matches!(err, Error::RevealDataError { .. }));
+
+        let decoded = RevealData::reveal_and_decode_with_limit::<_, TestData>(
+            &mut host,
+            &preimage_hash,
+            4 * MAX_PAGE_SIZE,
+        )
+        .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn reveal_and_decode_fails_if_pages_exceed_limit() {
+        let mut host = MockHost::default();
+        // A content page of one byte shared by every leaf of two levels of hash
+        // pages: 123 * 123 content pages that only hold as many bytes
+        let content_hash = host.set_preimage([&[0, 0, 0, 0, 1][..], &[1]].concat());
+        let hash_page = |host: &mut MockHost, hash: [u8; PREIMAGE_HASH_SIZE]| {
+            let hashes = [hash; 123].concat();
+            let size = (hashes.len() as u32).to_be_bytes();
+            host.set_preimage([&[1][..], &size, &hashes].concat())
+        };
+        let inner_hash = hash_page(&mut host, content_hash);
+        let root_hash = PreimageHash::from(hash_page(&mut host, inner_hash));
+
+        let err = RevealData::reveal_and_decode_with_limit::<_, TestData>(
+            &mut host,
+            &root_hash,
+            5 * MAX_PAGE_SIZE,
+        )
+        .expect_err("should fail");
+        assert!(matches!(err, Error::RevealDataError { .. }));
+    }
+
     #[test]
     fn test_encode_and_decode_with_rdc() {
         let data = TestData(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
@@ -169,7 +271,7 @@ mod tests {
     #[test]
     fn test_encode_and_decode_with_rdc_large_data() {
         let sample: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
-        let large_data = vec![sample.clone(); MAX_REVEAL_SIZE / sample.len()]
+        let large_data = vec![sample.clone(); DEFAULT_MAX_REVEAL_SIZE / sample.len()]
             .into_iter()
             .flatten()
             .collect::<Vec<u8>>();
@@ -177,9 +279,21 @@ mod tests {
         encode_and_decode_data_with_rdc(large_data);
     }
 
+    #[test]
+    #[ignore = "reveals 64MB of data"]
+    fn test_encode_and_decode_with_rdc_deep_tree() {
+        // More than 123 * 123 content pages need four levels of hash pages
+        let sample: Vec<u8> = (0..=250).collect();
+        let deep_data = vec![sample.clone(); 64 * 1024 * 1024 / sample.len()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<u8>>();
+        encode_and_decode_data_with_rdc(TestData(deep_data));
+    }
+
     fn encode_and_decode_data_with_rdc<T>(data: T)
     where
-        T: BinEncodable + Clone + PartialEq + Eq + std::fmt::Debug,
+        T: Encode + Decode + Clone + PartialEq + Eq + std::fmt::Debug,
     {
         let mut host = MockHost::default();
         let preimage_hash = RevealData::encode_and_prepare_preimages(&data, |_, page| {
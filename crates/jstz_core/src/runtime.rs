@@ -114,7 +114,8 @@ thread_local! {
     static JS_TRANSACTION: RefCell<Option<JsTransaction>> = const { RefCell::new(None) };
 }
 
-/// Enters a new host context, running the closure `f` with the new context
+/// Enters a new host context, running the closure `f` with the new context. The
+/// enclosing context, if any, is restored once `f` returns.
 pub fn enter_js_host_context<F, R>(
     hrt: &mut impl HostRuntime,
     tx: &mut Transaction,
@@ -123,18 +124,21 @@ pub fn enter_js_host_context<F, R>(
 where
     F: FnOnce() -> R,
 {
-    JS_HOST_RUNTIME.with(|js_hrt| *js_hrt.borrow_mut() = Some(JsHostRuntime::new(hrt)));
+    let previous_hrt =
+        JS_HOST_RUNTIME.with(|js_hrt| js_hrt.replace(Some(JsHostRuntime::new(hrt))));
 
-    JS_TRANSACTION.with(|js_tx| *js_tx.borrow_mut() = Some(JsTransaction::new(tx)));
+    let previous_tx =
+        JS_TRANSACTION.with(|js_tx| js_tx.replace(Some(JsTransaction::new(tx))));
 
     let result = f();
 
+    // Restore the enclosing context, if any
     JS_HOST_RUNTIME.with(|hrt| {
-        *hrt.borrow_mut() = None;
+        *hrt.borrow_mut() = previous_hrt;
     });
 
     JS_TRANSACTION.with(|tx| {
-        *tx.borrow_mut() = None;
+        *tx.borrow_mut() = previous_tx;
     });
 
     result
@@ -149,18 +153,21 @@ pub async fn async_enter_js_host_context<F, R>(
 where
     F: Future<Output = R>,
 {
-    JS_HOST_RUNTIME.with(|js_hrt| *js_hrt.borrow_mut() = Some(JsHostRuntime::new(hrt)));
+    let previous_hrt =
+        JS_HOST_RUNTIME.with(|js_hrt| js_hrt.replace(Some(JsHostRuntime::new(hrt))));
 
-    JS_TRANSACTION.with(|js_tx| *js_tx.borrow_mut() = Some(JsTransaction::new(tx)));
+    let previous_tx =
+        JS_TRANSACTION.with(|js_tx| js_tx.replace(Some(JsTransaction::new(tx))));
 
     let result = fut.await;
 
+    // Restore the enclosing context, if any
     JS_HOST_RUNTIME.with(|hrt| {
-        *hrt.borrow_mut() = None;
+        *hrt.borrow_mut() = previous_hrt;
     });
 
     JS_TRANSACTION.with(|tx| {
-        *tx.borrow_mut() = None;
+        *tx.borrow_mut() = previous_tx;
     });
 
     result
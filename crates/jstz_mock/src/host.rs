@@ -9,8 +9,8 @@ use tezos_crypto_rs::hash::ContractKt1Hash;
 use tezos_smart_rollup::{
     inbox::ExternalMessageFrame,
     michelson::{
-        ticket::FA2_1Ticket, MichelsonContract, MichelsonOption, MichelsonOr,
-        MichelsonPair,
+        ticket::FA2_1Ticket, MichelsonBytes, MichelsonContract, MichelsonOption,
+        MichelsonOr, MichelsonPair,
     },
     storage::path::RefPath,
     types::SmartRollupAddress,
@@ -45,11 +45,14 @@ pub const MOCK_TICKETER: &str = "KT1H28iie4mW9LmmJeYLjH6zkC8wwSmfHf5P";
 pub const TICKETER_PATH: RefPath = RefPath::assert_from(b"/ticketer");
 pub const INJECTOR_PATH: RefPath = RefPath::assert_from(b"/injector");
 pub type RollupType = MichelsonOr<
-    MichelsonPair<MichelsonContract, FA2_1Ticket>,
-    MichelsonPair<
-        MichelsonContract,
-        MichelsonPair<MichelsonOption<MichelsonContract>, FA2_1Ticket>,
+    MichelsonOr<
+        MichelsonPair<MichelsonContract, FA2_1Ticket>,
+        MichelsonPair<
+            MichelsonContract,
+            MichelsonPair<MichelsonOption<MichelsonContract>, FA2_1Ticket>,
+        >,
     >,
+    MichelsonPair<MichelsonContract, MichelsonBytes>,
 >;
 
 // Wrapper over Mockhost to simplify setup of mock scenarios
@@ -87,3 +87,13 @@ pub fn ticket_hash1() -> TicketHash {
     .unwrap();
     ticket.hash().unwrap()
 }
+
+pub fn ticket_hash2() -> TicketHash {
+    let ticket = UnitTicket::new(
+        Contract::from_b58check("tz1QcqnzZ8pa6VuE4MSeMjsJkiW94wNrPbgX").unwrap(),
+        MichelsonUnit,
+        10,
+    )
+    .unwrap();
+    ticket.hash().unwrap()
+}
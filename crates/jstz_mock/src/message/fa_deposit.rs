@@ -79,10 +79,10 @@ impl From<&MockFaDeposit> for RollupType {
             .clone()
             .map(|p| MichelsonContract(Contract::from_b58check(&p.to_base58()).unwrap()));
 
-        MichelsonOr::Right(MichelsonPair(
+        MichelsonOr::Left(MichelsonOr::Right(MichelsonPair(
             MichelsonContract(val.receiver.clone()),
             MichelsonPair(MichelsonOption(proxy), ticket),
-        ))
+        )))
     }
 }
 
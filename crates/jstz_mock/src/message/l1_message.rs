@@ -0,0 +1,57 @@
+use tezos_crypto_rs::hash::ContractKt1Hash;
+use tezos_smart_rollup::{
+    michelson::{MichelsonBytes, MichelsonContract, MichelsonOr, MichelsonPair},
+    types::{Contract, PublicKeyHash, SmartRollupAddress},
+};
+
+use crate::host::{RollupType, MOCK_PROXY, MOCK_SENDER, MOCK_SOURCE};
+
+use super::MockInternalMessage;
+
+pub struct MockL1Message {
+    pub sender: ContractKt1Hash,
+    pub source: PublicKeyHash,
+    pub receiver: Contract,
+    // Packed Michelson value
+    pub payload: Vec<u8>,
+    pub smart_rollup: Option<SmartRollupAddress>,
+}
+
+impl Default for MockL1Message {
+    fn default() -> Self {
+        Self {
+            sender: ContractKt1Hash::from_base58_check(MOCK_SENDER).unwrap(),
+            source: PublicKeyHash::from_b58check(MOCK_SOURCE).unwrap(),
+            receiver: Contract::from_b58check(MOCK_PROXY).unwrap(),
+            // PACK (Pair 1 "foo")
+            payload: vec![
+                0x05, 0x07, 0x07, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x03, b'f', b'o',
+                b'o',
+            ],
+            smart_rollup: None,
+        }
+    }
+}
+
+impl From<&MockL1Message> for RollupType {
+    fn from(val: &MockL1Message) -> Self {
+        MichelsonOr::Right(MichelsonPair(
+            MichelsonContract(val.receiver.clone()),
+            MichelsonBytes(val.payload.clone()),
+        ))
+    }
+}
+
+impl MockInternalMessage for &MockL1Message {
+    fn source(&self) -> PublicKeyHash {
+        self.source.clone()
+    }
+
+    fn sender(&self) -> ContractKt1Hash {
+        self.sender.clone()
+    }
+
+    fn smart_rollup(&self) -> Option<SmartRollupAddress> {
+        self.smart_rollup.clone()
+    }
+}
@@ -61,10 +61,10 @@ impl From<&MockNativeDeposit> for RollupType {
             val.ticket_content.clone(),
         );
 
-        MichelsonOr::Left(MichelsonPair(
+        MichelsonOr::Left(MichelsonOr::Left(MichelsonPair(
             MichelsonContract(val.receiver.clone()),
             ticket,
-        ))
+        )))
     }
 }
 
@@ -162,6 +162,9 @@ pub struct JstzNodeConfig {
     pub mode: RunMode,
     /// When enabled, the node will sync storage updates to the database from the kernel_log_file.
     pub storage_sync: bool,
+    /// Directory of the node databases. Temporary files are used if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
 }
 
 impl JstzNodeConfig {
@@ -187,6 +190,7 @@ impl JstzNodeConfig {
             injector,
             mode,
             storage_sync,
+            data_dir: None,
         }
     }
 }
@@ -232,6 +236,7 @@ mod tests {
         assert_eq!(json["debug_log_path"], serde_json::Value::Null);
         assert_eq!(json["runtime_env"], serde_json::Value::Null);
         assert_eq!(json["storage_sync"], true);
+        assert_eq!(json["data_dir"], serde_json::Value::Null);
 
         config.mode = RunMode::Sequencer {
             capacity: 123,
@@ -12,10 +12,12 @@ use services::{
     accounts::AccountsService,
     logs::{broadcaster::Broadcaster, db::Db, LogsService},
     operations::OperationsService,
+    outbox::OutboxService,
+    protocol::ProtocolService,
     utils,
 };
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic::AtomicU64, Arc, RwLock},
     time::SystemTime,
 };
@@ -75,6 +77,9 @@ pub struct RunOptions {
     pub injector: KeyPair,
     pub mode: RunMode,
     pub storage_sync: bool,
+    /// Directory of the node databases and the inbox checkpoint. Temporary files are
+    /// used when it is not set.
+    pub data_dir: Option<PathBuf>,
 }
 
 pub async fn run_with_config(config: JstzNodeConfig) -> Result<()> {
@@ -90,6 +95,7 @@ pub async fn run_with_config(config: JstzNodeConfig) -> Result<()> {
         injector: config.injector,
         mode: config.mode,
         storage_sync: config.storage_sync,
+        data_dir: config.data_dir,
     })
     .await
 }
@@ -104,6 +110,7 @@ pub async fn run(
         injector,
         mode,
         storage_sync,
+        data_dir,
     }: RunOptions,
 ) -> Result<()> {
     let rollup_client = OctezRollupClient::new(rollup_endpoint.to_string());
@@ -112,8 +119,7 @@ pub async fn run(
         _ => 0,
     })));
 
-    // will make db_path configurable later
-    let (runtime_db, _runtime_db_file) = temp_db()?;
+    let (runtime_db, _runtime_db_file) = open_db(data_dir.as_deref(), "runtime.db")?;
     let worker = match mode {
         #[cfg(not(test))]
         RunMode::Sequencer {
@@ -156,13 +162,14 @@ pub async fn run(
         RunMode::Default => None,
     };
 
-    // TODO: make checkpoint path configurable
-    // https://linear.app/tezos/issue/JSTZ-912/make-inbox-checkpoint-file-configurable
     let _checkpoint = NamedTempFile::new()?;
     let _monitor: Option<Monitor> = match mode {
         #[cfg(not(test))]
         RunMode::Sequencer { .. } => {
-            let path = _checkpoint.path().to_path_buf();
+            let path = match &data_dir {
+                Some(dir) => dir.join("checkpoint.json"),
+                None => _checkpoint.path().to_path_buf(),
+            };
             Some(inbox::spawn_monitor(rollup_endpoint, queue.clone(), path).await?)
         }
         #[cfg(test)]
@@ -181,7 +188,8 @@ pub async fn run(
 
     let (broadcaster, db, log_service_handle) = LogsService::init(&log_file_path).await?;
 
-    let (storage_sync_db, _storage_sync_db_file) = temp_db()?;
+    let (storage_sync_db, _storage_sync_db_file) =
+        open_db(data_dir.as_deref(), "storage_sync.db")?;
     let mut storage_sync_handles = JoinSet::new();
     if storage_sync {
         storage_sync_handles.spawn(storage_sync::spawn(
@@ -263,11 +271,34 @@ fn temp_db() -> Result<(sequencer::db::Db, NamedTempFile)> {
     Ok((sequencer::db::Db::init(Some(db_path))?, db_file))
 }
 
+/// Opens the database `name` in `data_dir`, or a temporary database if `data_dir`
+/// is not set.
+fn open_db(
+    data_dir: Option<&Path>,
+    name: &str,
+) -> Result<(sequencer::db::Db, Option<NamedTempFile>)> {
+    match data_dir {
+        Some(dir) => {
+            let db_path = dir.join(name);
+            let db_path = db_path
+                .to_str()
+                .ok_or(anyhow::anyhow!("failed to convert db path to str"))?;
+            Ok((sequencer::db::Db::init(Some(db_path))?, None))
+        }
+        None => temp_db().map(|(db, file)| (db, Some(file))),
+    }
+}
+
 fn router() -> OpenApiRouter<AppState> {
-    OpenApiRouter::with_openapi(ApiDoc::openapi())
+    let router = OpenApiRouter::with_openapi(ApiDoc::openapi())
         .merge(OperationsService::router_with_openapi())
         .merge(AccountsService::router_with_openapi())
         .merge(LogsService::router_with_openapi())
+        .merge(OutboxService::router_with_openapi())
+        .merge(ProtocolService::router_with_openapi());
+    #[cfg(feature = "compat")]
+    let router = router.merge(services::compat::CompatService::router_with_openapi());
+    router
         .route("/mode", get(utils::get_mode))
         .route("/health", get(http::StatusCode::OK))
         .route("/worker/health", get(utils::worker_health))
@@ -362,6 +393,7 @@ mod test {
                 injector: default_injector(),
                 mode: mode.clone(),
                 storage_sync: false,
+                data_dir: None,
             }));
 
             let res = jstz_utils::poll(10, 500, || async {
@@ -416,6 +448,7 @@ mod test {
                 injector: default_injector(),
                 mode,
                 storage_sync: false,
+                data_dir: None,
             }));
 
             sleep(Duration::from_secs(1)).await;
@@ -507,6 +540,7 @@ mod test {
             injector: default_injector(),
             mode,
             storage_sync: true,
+            data_dir: None,
         }))
     }
 
@@ -75,6 +75,10 @@ struct Args {
 
     #[arg(long, action = ArgAction::SetTrue)]
     storage_sync: bool,
+
+    /// Directory of the node databases. Temporary files are used if not set.
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -109,6 +113,7 @@ async fn main() -> anyhow::Result<()> {
                     .context("failed to parse injector key file")?,
                 mode: run_mode_builder.build()?,
                 storage_sync: args.storage_sync,
+                data_dir: args.data_dir,
             })
             .await
         }
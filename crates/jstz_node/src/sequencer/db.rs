@@ -10,6 +10,9 @@ use rusqlite::OptionalExtension;
 
 pub type SqliteConnectionPool = Pool<SqliteConnectionManager>;
 
+/// Number of most recent injected operations kept by [`Db::write_operation`].
+pub const MAX_RECORDED_OPERATIONS: u32 = 10_000;
+
 /// Database wrapper that manipulates the sequencer database.
 #[derive(Clone)]
 pub struct Db {
@@ -57,6 +60,7 @@ impl Db {
     fn setup(pool: Pool<SqliteConnectionManager>) -> Result<()> {
         let conn = pool.get().context("failed to get connection from pool")?;
         conn.execute("CREATE TABLE IF NOT EXISTS jstz_kv (jstz_key TEXT NOT NULL PRIMARY KEY, jstz_value, UNIQUE(jstz_key))", []).context("failed to create table")?;
+        conn.execute("CREATE TABLE IF NOT EXISTS jstz_operation (hash TEXT NOT NULL PRIMARY KEY, operation TEXT NOT NULL)", []).context("failed to create operation table")?;
         // Allows reads while writes are taking place. This works when there is only one writer
         // and is fine in our use case.
         conn.pragma_update(None, "journal_mode", "WAL")
@@ -170,6 +174,45 @@ impl Db {
         let conn = self.connection()?;
         exec_write(&conn, key, value)
     }
+
+    /// Records an operation injected through the node, keyed by its hash. Only the
+    /// [`MAX_RECORDED_OPERATIONS`] most recent operations are kept.
+    pub fn write_operation(&self, hash: &str, operation: &str) -> Result<()> {
+        self.write_operation_bounded(hash, operation, MAX_RECORDED_OPERATIONS)
+    }
+
+    fn write_operation_bounded(
+        &self,
+        hash: &str,
+        operation: &str,
+        limit: u32,
+    ) -> Result<()> {
+        let conn = self.connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO jstz_operation (hash, operation) VALUES (?1, ?2)",
+            params![hash, operation],
+        )?;
+        // Rows get increasing rowids as they are inserted, so the rows below the
+        // latest `limit` ones are the oldest
+        conn.execute(
+            "DELETE FROM jstz_operation WHERE rowid <= (SELECT MAX(rowid) FROM jstz_operation) - ?1",
+            params![limit],
+        )?;
+        Ok(())
+    }
+
+    /// Reads an operation recorded with [`Db::write_operation`].
+    pub fn read_operation(&self, hash: &str) -> Result<Option<String>> {
+        let conn = self.connection()?;
+        let result = conn
+            .query_row(
+                "SELECT operation FROM jstz_operation WHERE hash = ?",
+                [hash],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(result)
+    }
 }
 
 /// Reads a row using an existing database connection.
@@ -254,6 +297,37 @@ mod tests {
         assert_eq!(result, Ok("jstz_kv".to_string()));
     }
 
+    #[test]
+    fn write_and_read_operation() {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = Db::init(Some(db_file.path().to_str().unwrap())).unwrap();
+
+        assert!(db.read_operation("abc").unwrap().is_none());
+
+        db.write_operation("abc", "{}").unwrap();
+        assert_eq!(db.read_operation("abc").unwrap().unwrap(), "{}");
+    }
+
+    #[test]
+    fn write_operation_keeps_latest() {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = Db::init(Some(db_file.path().to_str().unwrap())).unwrap();
+
+        for hash in ["a", "b", "c"] {
+            db.write_operation_bounded(hash, hash, 2).unwrap();
+        }
+        assert!(db.read_operation("a").unwrap().is_none());
+        assert_eq!(db.read_operation("b").unwrap().unwrap(), "b");
+        assert_eq!(db.read_operation("c").unwrap().unwrap(), "c");
+
+        // Recording an operation again makes it the latest
+        db.write_operation_bounded("b", "b", 2).unwrap();
+        db.write_operation_bounded("d", "d", 2).unwrap();
+        assert!(db.read_operation("c").unwrap().is_none());
+        assert_eq!(db.read_operation("b").unwrap().unwrap(), "b");
+        assert_eq!(db.read_operation("d").unwrap().unwrap(), "d");
+    }
+
     #[test]
     fn key_exists() {
         let db_file = NamedTempFile::new().unwrap();
@@ -1,5 +1,5 @@
 pub mod db;
-mod host;
+pub(crate) mod host;
 pub mod inbox;
 pub mod queue;
 mod riscv_pvm;
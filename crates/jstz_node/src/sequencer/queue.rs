@@ -15,6 +15,16 @@ pub enum WrappedOperation {
 }
 
 impl WrappedOperation {
+    /// Level of the L1 inbox the operation comes from, if any
+    pub fn l1_level(&self) -> Option<u32> {
+        match self {
+            WrappedOperation::FromInbox { message, .. } => {
+                Some(message.inbox_id.l1_level)
+            }
+            WrappedOperation::FromNode(_) => None,
+        }
+    }
+
     pub fn to_message(self) -> ParsedInboxMessage {
         match self {
             WrappedOperation::FromInbox { message, .. } => message.content,
@@ -141,6 +151,7 @@ mod tests {
             },
             original_inbox_message: "0002".to_string(),
         };
+        assert_eq!(op.l1_level(), Some(0));
         assert_eq!(
             op.to_message(),
             jstz_kernel::inbox::ParsedInboxMessage::LevelInfo(
@@ -150,6 +161,7 @@ mod tests {
 
         let inner = dummy_signed_op();
         let op = WrappedOperation::FromNode(inner.clone());
+        assert_eq!(op.l1_level(), None);
         assert_eq!(
             op.to_message(),
             jstz_kernel::inbox::ParsedInboxMessage::JstzMessage(
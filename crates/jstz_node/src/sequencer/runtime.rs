@@ -235,7 +235,8 @@ mod tests {
             ReceiptResult::Success(ReceiptContent::RunFunction(RunFunctionReceipt {
                 body: _,
                 status_code: StatusCode::OK,
-                headers: _
+                headers: _,
+                ..
             }))
         ));
 
@@ -493,7 +494,8 @@ mod tests {
             ReceiptResult::Success(ReceiptContent::RunFunction(RunFunctionReceipt {
                 body,
                 status_code: StatusCode::OK,
-                headers: _
+                headers: _,
+                ..
             })) if String::from_utf8(body.clone().unwrap()).unwrap() == "this is a big function"));
     }
 }
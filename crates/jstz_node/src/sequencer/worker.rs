@@ -18,11 +18,12 @@ use std::{
 };
 
 use anyhow::Context;
+use jstz_core::kv::outbox::{set_outbox_level, suspend_outbox_level};
 use jstz_proto::operation::internal::InboxId;
 use jstz_utils::KeyPair;
 use log::{error, info, warn};
 use tezos_crypto_rs::hash::SmartRollupHash;
-use tezos_smart_rollup::types::SmartRollupAddress;
+use tezos_smart_rollup::{prelude::Runtime, types::SmartRollupAddress};
 
 use super::{db::Db, queue::OperationQueue};
 use jstz_kernel::inbox::{encode_signed_operation, ParsedInboxMessage};
@@ -136,6 +137,7 @@ fn spawn_native_worker(
 
                     match v {
                         Some(op) => {
+                            record_outbox_level(&mut host_rt, &op);
                             if let ParsedInboxMessage::JstzMessage(message) =
                                 op.to_message()
                             {
@@ -189,6 +191,9 @@ fn run_event_loop(
                 }
             };
 
+            if let Some(wrapper) = &v {
+                record_outbox_level(&mut host, wrapper);
+            }
             match v {
                 Some(wrapper) => match wrapper.to_message() {
                     ParsedInboxMessage::JstzMessage(op) => {
@@ -229,6 +234,20 @@ fn run_event_loop(
     })
 }
 
+// Outbox messages written while processing an operation are assigned ids at the
+// level of the last inbox message, as in the kernel. Operations injected through
+// the node reach the rollup inbox at a level that is not known yet, so their
+// outbox messages are left without ids.
+fn record_outbox_level(rt: &mut impl Runtime, op: &WrappedOperation) {
+    let result = match op.l1_level() {
+        Some(level) => set_outbox_level(rt, level),
+        None => suspend_outbox_level(rt),
+    };
+    if let Err(e) = result {
+        warn!("worker failed to set outbox level: {e:?}");
+    }
+}
+
 pub(crate) fn write_heartbeat(heartbeat: &Arc<AtomicU64>) {
     let current_sec = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -338,8 +357,15 @@ mod tests {
         time::Duration,
     };
 
-    use crate::sequencer::{db::Db, queue::OperationQueue, tests::dummy_op};
+    use crate::sequencer::{
+        db::Db,
+        queue::{OperationQueue, WrappedOperation},
+        tests::dummy_op,
+    };
     use crate::{sequencer::inbox::test_utils::hash_of, test::default_injector};
+    use jstz_core::kv::outbox::OUTBOX_CURSOR_PATH;
+    use jstz_kernel::inbox::{LevelInfo, ParsedInboxMessage, ParsedInboxMessageWrapper};
+    use jstz_proto::operation::internal::InboxId;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -414,4 +440,73 @@ mod tests {
             buf.contains("Smart function deployed: KT1H4GfcBgx11M8ri6wwyDtbMUbqYfDQ7WmU")
         );
     }
+
+    #[test]
+    fn worker_sets_outbox_level() {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = Db::init(Some(db_file.path().to_str().unwrap())).unwrap();
+        let mut q = OperationQueue::new(1);
+        q.insert(WrappedOperation::FromInbox {
+            message: ParsedInboxMessageWrapper {
+                content: ParsedInboxMessage::LevelInfo(LevelInfo::Start),
+                inbox_id: InboxId {
+                    l1_level: 7,
+                    l1_message_id: 0,
+                },
+            },
+            original_inbox_message: "0001".to_string(),
+        })
+        .unwrap();
+
+        let _worker = super::spawn(
+            Arc::new(RwLock::new(q)),
+            db.clone(),
+            &default_injector(),
+            PathBuf::new(),
+            None,
+            &crate::config::RuntimeEnv::Native,
+            move || {},
+        );
+
+        // to ensure that the worker has enough time to consume the queue
+        thread::sleep(Duration::from_millis(1000));
+
+        // withdrawals of the following operations are assigned outbox ids at level 7
+        assert!(db.key_exists(OUTBOX_CURSOR_PATH).unwrap());
+    }
+
+    #[test]
+    fn worker_suspends_outbox_level_for_node_operations() {
+        let db_file = NamedTempFile::new().unwrap();
+        let db = Db::init(Some(db_file.path().to_str().unwrap())).unwrap();
+        let mut q = OperationQueue::new(2);
+        q.insert(WrappedOperation::FromInbox {
+            message: ParsedInboxMessageWrapper {
+                content: ParsedInboxMessage::LevelInfo(LevelInfo::Start),
+                inbox_id: InboxId {
+                    l1_level: 7,
+                    l1_message_id: 0,
+                },
+            },
+            original_inbox_message: "0001".to_string(),
+        })
+        .unwrap();
+        q.insert(dummy_op()).unwrap();
+
+        let _worker = super::spawn(
+            Arc::new(RwLock::new(q)),
+            db.clone(),
+            &default_injector(),
+            PathBuf::new(),
+            None,
+            &crate::config::RuntimeEnv::Native,
+            move || {},
+        );
+
+        // to ensure that the worker has enough time to consume the queue
+        thread::sleep(Duration::from_millis(1000));
+
+        // withdrawals of the node operation are not assigned outbox ids
+        assert!(!db.key_exists(OUTBOX_CURSOR_PATH).unwrap());
+    }
 }
@@ -1,17 +1,21 @@
 use anyhow::{anyhow, Context};
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
     Json,
 };
+use base64::{prelude::BASE64_STANDARD, Engine};
 use jstz_core::BinEncodable;
 use jstz_proto::{
     context::account::{
-        Account, Nonce, SmartFunctionAccount, UserAccount, ACCOUNTS_PATH_PREFIX,
+        Account, Nonce, SmartFunctionAccount, StorageUsage, UserAccount,
+        ACCOUNTS_PATH_PREFIX, RUNTIME_VERSIONS_PATH_PREFIX, STORAGE_USAGE_PATH_PREFIX,
     },
-    runtime::{KvValue, ParsedCode},
+    runtime::{KvValue, ParsedCode, RuntimeVersion},
 };
 use serde::Deserialize;
-use utoipa::IntoParams;
+use utoipa::{IntoParams, ToSchema};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use super::{
@@ -42,6 +46,38 @@ struct KvQuery {
     key: Option<String>,
 }
 
+/// Encoding of Kv byte values
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum BytesEncoding {
+    #[default]
+    Base64,
+    Hex,
+}
+
+impl BytesEncoding {
+    fn content_type(&self) -> &'static str {
+        match self {
+            BytesEncoding::Base64 => "application/octet-stream; encoding=base64",
+            BytesEncoding::Hex => "application/octet-stream; encoding=hex",
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            BytesEncoding::Base64 => BASE64_STANDARD.encode(bytes),
+            BytesEncoding::Hex => hex::encode(bytes),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+struct KvValueQuery {
+    key: Option<String>,
+    /// Encoding of byte values, `base64` by default
+    encoding: Option<BytesEncoding>,
+}
+
 pub struct AccountsService;
 
 /// Get account
@@ -224,17 +260,123 @@ async fn get_balance(
     Ok(Json(account_balance))
 }
 
+/// Get storage usage
+///
+/// Get the Kv storage used by an account and the deposit locked for it
+#[utoipa::path(
+    get,
+    path = "/{address}/storage",
+    tag = ACCOUNTS_TAG,
+    responses(
+        (status = 200, body = StorageUsage),
+        (status = 404),
+        (status = 500)
+    )
+)]
+async fn get_storage(
+    State(AppState {
+        mode,
+        rollup_client,
+        runtime_db,
+        storage_sync,
+        storage_sync_db,
+        ..
+    }): State<AppState>,
+    Path(address): Path<String>,
+) -> ServiceResult<Json<StorageUsage>> {
+    let store = StoreWrapper::new(
+        mode,
+        storage_sync,
+        rollup_client,
+        runtime_db,
+        storage_sync_db,
+    );
+    if store
+        .get_value(construct_accounts_key(&address))
+        .await?
+        .is_none()
+    {
+        Err(ServiceError::NotFound)?
+    }
+    let key = format!("{STORAGE_USAGE_PATH_PREFIX}/{address}");
+    let storage_usage = match store.get_value(key).await? {
+        Some(value) => StorageUsage::decode(value.as_slice())
+            .map_err(|_| anyhow!("Failed to deserialize storage usage"))?,
+        None => StorageUsage::default(),
+    };
+    Ok(Json(storage_usage))
+}
+
+/// Get runtime version of a smart function
+///
+/// Get the runtime the smart function runs on, i.e. the runtime it was deployed for
+#[utoipa::path(
+    get,
+    path = "/{address}/runtime",
+    tag = ACCOUNTS_TAG,
+    responses(
+        (status = 200, body = RuntimeVersion),
+        (status = 400),
+        (status = 404),
+        (status = 500)
+    )
+)]
+async fn get_runtime_version(
+    State(AppState {
+        mode,
+        rollup_client,
+        runtime_db,
+        storage_sync,
+        storage_sync_db,
+        ..
+    }): State<AppState>,
+    Path(address): Path<String>,
+) -> ServiceResult<Json<RuntimeVersion>> {
+    let store = StoreWrapper::new(
+        mode,
+        storage_sync,
+        rollup_client,
+        runtime_db,
+        storage_sync_db,
+    );
+    match store.get_value(construct_accounts_key(&address)).await? {
+        Some(value) => {
+            if let Account::User(_) = deserialize_account(value.as_slice())? {
+                Err(ServiceError::BadRequest(
+                    "Account is not a smart function".to_string(),
+                ))?
+            }
+        }
+        None => Err(ServiceError::NotFound)?,
+    }
+    // Smart functions deployed before runtime versions were recorded were deployed
+    // for the v1 runtime
+    let key = format!("{RUNTIME_VERSIONS_PATH_PREFIX}/{address}");
+    let version = match store.get_value(key).await? {
+        Some(value) => RuntimeVersion::decode(value.as_slice())
+            .map_err(|_| anyhow!("Failed to deserialize runtime version"))?,
+        None => RuntimeVersion::V1,
+    };
+    Ok(Json(version))
+}
+
 /// Get KV value under a given key path
 ///
 /// Get KV value under a given key path for an account. If `key` is not provided,
-/// the empty key path will be used.
+/// the empty key path will be used. JSON values are returned as JSON. Byte values are
+/// returned as base64 or, if `encoding` is `hex`, as hex, with the content type
+/// `application/octet-stream; encoding=<encoding>`.
 #[utoipa::path(
     get,
-    params(KvQuery),
+    params(KvValueQuery),
     path = "/{address}/kv",
     tag = ACCOUNTS_TAG,
     responses(
-        (status = 200, body = KvValue),
+        (status = 200, content(
+            (KvValue = "application/json"),
+            (String = "application/octet-stream; encoding=base64"),
+            (String = "application/octet-stream; encoding=hex")
+        )),
         (status = 404),
         (status = 500)
     )
@@ -249,8 +391,8 @@ async fn get_kv_value(
         ..
     }): State<AppState>,
     Path(address): Path<String>,
-    Query(KvQuery { key }): Query<KvQuery>,
-) -> ServiceResult<Json<KvValue>> {
+    Query(KvValueQuery { key, encoding }): Query<KvValueQuery>,
+) -> ServiceResult<Response> {
     let key = construct_storage_key(&address, &key);
     let store = StoreWrapper::new(
         mode,
@@ -265,7 +407,17 @@ async fn get_kv_value(
             .map_err(|_| anyhow!("Failed to deserialize kv value"))?,
         None => Err(ServiceError::NotFound)?,
     };
-    Ok(Json(kv_value))
+    Ok(match kv_value {
+        KvValue::Json(value) => Json(value).into_response(),
+        KvValue::Bytes(bytes) => {
+            let encoding = encoding.unwrap_or_default();
+            (
+                [(header::CONTENT_TYPE, encoding.content_type())],
+                encoding.encode(&bytes),
+            )
+                .into_response()
+        }
+    })
 }
 
 /// Get array of KV subkeys under a given key path
@@ -317,6 +469,8 @@ impl Service for AccountsService {
             .routes(routes!(get_nonce))
             .routes(routes!(get_code))
             .routes(routes!(get_balance))
+            .routes(routes!(get_storage))
+            .routes(routes!(get_runtime_version))
             .routes(routes!(get_kv_value))
             .routes(routes!(get_kv_subkeys));
 
@@ -331,8 +485,10 @@ mod tests {
     use axum::{body::Body, extract::Request, response::Response, Router};
     use jstz_core::BinEncodable;
     use jstz_proto::{
-        context::account::{Account, Nonce, SmartFunctionAccount, UserAccount},
-        runtime::{KvValue, ParsedCode},
+        context::account::{
+            Account, Nonce, SmartFunctionAccount, StorageUsage, UserAccount,
+        },
+        runtime::{KvValue, ParsedCode, RuntimeVersion},
     };
     use mockito::Matcher;
     use octez::OctezRollupClient;
@@ -698,6 +854,177 @@ mod tests {
         assert_eq!(res.status(), 404);
     }
 
+    #[tokio::test]
+    async fn get_storage_sequencer() {
+        let smart_function_account = Account::SmartFunction(SmartFunctionAccount {
+            amount: 888,
+            nonce: Nonce(50),
+            function_code: ParsedCode::default(),
+        });
+        let smart_function_hash = "KT19GXucGUitURBXXeEMMfqqhSQ5byt4P1zX";
+        let other_smart_function_hash = "KT1TxqZ8QtKvLu3V3JH7Gx58n7Co8pgtpQU5";
+        let storage_usage = StorageUsage {
+            bytes: 42,
+            deposit: 84,
+        };
+        let db_file = NamedTempFile::new().unwrap();
+        let state = mock_app_state(
+            "",
+            PathBuf::default(),
+            db_file.path().to_str().unwrap(),
+            RunMode::Sequencer {
+                capacity: 0,
+                debug_log_path: PathBuf::new(),
+                runtime_env: RuntimeEnv::Native,
+            },
+        )
+        .await;
+        for address in [smart_function_hash, other_smart_function_hash] {
+            state
+                .runtime_db
+                .write(
+                    &format!("/jstz_account/{address}"),
+                    &hex::encode(smart_function_account.encode().unwrap()),
+                )
+                .unwrap();
+        }
+        state
+            .runtime_db
+            .write(
+                &format!("/jstz_storage_usage/{smart_function_hash}"),
+                &hex::encode(storage_usage.encode().unwrap()),
+            )
+            .unwrap();
+
+        let (mut router, _) = AccountsService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{smart_function_hash}/storage"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        let usage = serde_json::from_slice::<StorageUsage>(&bytes).unwrap();
+        assert_eq!(usage, storage_usage);
+
+        // account without Kv storage
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{other_smart_function_hash}/storage"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        let usage = serde_json::from_slice::<StorageUsage>(&bytes).unwrap();
+        assert_eq!(usage, StorageUsage::default());
+
+        // non-existent address
+        let res =
+            send_simple_get_request(router.borrow_mut(), "/accounts/bad_addr/storage")
+                .await
+                .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn get_runtime_version_sequencer() {
+        let smart_function_account = Account::SmartFunction(SmartFunctionAccount {
+            amount: 888,
+            nonce: Nonce(50),
+            function_code: ParsedCode::default(),
+        });
+        let user_account = Account::User(UserAccount {
+            amount: 300,
+            nonce: Nonce(1),
+        });
+        let smart_function_hash = "KT19GXucGUitURBXXeEMMfqqhSQ5byt4P1zX";
+        let other_smart_function_hash = "KT1TxqZ8QtKvLu3V3JH7Gx58n7Co8pgtpQU5";
+        let user_hash = "tz1TGu6TN5GSez2ndXXeDX6LgUDvLzPLqgYV";
+        let db_file = NamedTempFile::new().unwrap();
+        let state = mock_app_state(
+            "",
+            PathBuf::default(),
+            db_file.path().to_str().unwrap(),
+            RunMode::Sequencer {
+                capacity: 0,
+                debug_log_path: PathBuf::new(),
+                runtime_env: RuntimeEnv::Native,
+            },
+        )
+        .await;
+        for address in [smart_function_hash, other_smart_function_hash] {
+            state
+                .runtime_db
+                .write(
+                    &format!("/jstz_account/{address}"),
+                    &hex::encode(smart_function_account.encode().unwrap()),
+                )
+                .unwrap();
+        }
+        state
+            .runtime_db
+            .write(
+                &format!("/jstz_account/{user_hash}"),
+                &hex::encode(user_account.encode().unwrap()),
+            )
+            .unwrap();
+        state
+            .runtime_db
+            .write(
+                &format!("/jstz_runtime_version/{smart_function_hash}"),
+                &hex::encode(RuntimeVersion::V2.encode().unwrap()),
+            )
+            .unwrap();
+
+        let (mut router, _) = AccountsService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{smart_function_hash}/runtime"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        let version = serde_json::from_slice::<RuntimeVersion>(&bytes).unwrap();
+        assert_eq!(version, RuntimeVersion::V2);
+
+        // smart function without a recorded runtime version
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{other_smart_function_hash}/runtime"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        let version = serde_json::from_slice::<RuntimeVersion>(&bytes).unwrap();
+        assert_eq!(version, RuntimeVersion::V1);
+
+        // user account
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{user_hash}/runtime"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 400);
+
+        // non-existent address
+        let res =
+            send_simple_get_request(router.borrow_mut(), "/accounts/bad_addr/runtime")
+                .await
+                .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+
     #[tokio::test]
     async fn get_kv_value_sequencer() {
         let address = "tz1TGu6TN5GSez2ndXXeDX6LgUDvLzPLqgYV";
@@ -717,7 +1044,7 @@ mod tests {
             .runtime_db
             .write(
                 &format!("/jstz_kv/{address}/foo"),
-                &hex::encode(KvValue(serde_json::json!("foo!")).encode().unwrap()),
+                &hex::encode(KvValue::Json(serde_json::json!("foo!")).encode().unwrap()),
             )
             .unwrap();
         state
@@ -725,12 +1052,19 @@ mod tests {
             .write(
                 &format!("/jstz_kv/{address}/foo/bar"),
                 &hex::encode(
-                    KvValue(serde_json::json!({"bar": "bar!"}))
+                    KvValue::Json(serde_json::json!({"bar": "bar!"}))
                         .encode()
                         .unwrap(),
                 ),
             )
             .unwrap();
+        state
+            .runtime_db
+            .write(
+                &format!("/jstz_kv/{address}/bytes"),
+                &hex::encode(KvValue::Bytes(vec![1, 2, 255]).encode().unwrap()),
+            )
+            .unwrap();
         state
             .runtime_db
             .write(
@@ -742,7 +1076,7 @@ mod tests {
             .runtime_db
             .write(
                 &format!("/jstz_kv/{address}"),
-                &hex::encode(KvValue(serde_json::json!("root!")).encode().unwrap()),
+                &hex::encode(KvValue::Json(serde_json::json!("root!")).encode().unwrap()),
             )
             .unwrap();
 
@@ -760,7 +1094,7 @@ mod tests {
         assert_eq!(res.status(), 200);
         let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
         let value = serde_json::from_slice::<KvValue>(&bytes).unwrap();
-        assert_eq!(value.0, serde_json::json!("root!"));
+        assert_eq!(value, KvValue::Json(serde_json::json!("root!")));
 
         // base level key
         let res = send_simple_get_request(
@@ -772,7 +1106,7 @@ mod tests {
         assert_eq!(res.status(), 200);
         let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
         let value = serde_json::from_slice::<KvValue>(&bytes).unwrap();
-        assert_eq!(value.0, serde_json::json!("foo!"));
+        assert_eq!(value, KvValue::Json(serde_json::json!("foo!")));
 
         // nested key
         let res = send_simple_get_request(
@@ -784,7 +1118,36 @@ mod tests {
         assert_eq!(res.status(), 200);
         let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
         let value = serde_json::from_slice::<KvValue>(&bytes).unwrap();
-        assert_eq!(value.0, serde_json::json!({"bar": "bar!"}));
+        assert_eq!(value, KvValue::Json(serde_json::json!({"bar": "bar!"})));
+
+        // byte value
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{address}/kv?key=bytes"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers()["content-type"],
+            "application/octet-stream; encoding=base64"
+        );
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        assert_eq!(bytes, "AQL/");
+
+        let res = send_simple_get_request(
+            router.borrow_mut(),
+            format!("/accounts/{address}/kv?key=bytes&encoding=hex"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.headers()["content-type"],
+            "application/octet-stream; encoding=hex"
+        );
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        assert_eq!(bytes, "0102ff");
 
         // bad non-json value
         let res = send_simple_get_request(
@@ -831,7 +1194,7 @@ mod tests {
                 .runtime_db
                 .write(
                     &format!("/jstz_kv/{address}/{key}"),
-                    &hex::encode(KvValue(serde_json::json!("!")).encode().unwrap()),
+                    &hex::encode(KvValue::Json(serde_json::json!("!")).encode().unwrap()),
                 )
                 .unwrap();
         }
@@ -0,0 +1,84 @@
+use anyhow::{anyhow, Context};
+use axum::{extract::State, routing::post, Json};
+use jstz_proto::runtime::compat::{self, Mismatch, RecordedRequest};
+use utoipa_axum::router::OpenApiRouter;
+
+use super::{
+    error::{ServiceError, ServiceResult},
+    Service,
+};
+use crate::{sequencer::host::Host, AppState, RunMode};
+
+pub struct CompatService;
+
+/// Check runtime compatibility
+///
+/// Replays the requests on the v1 and v2 runtimes against the current state and
+/// returns the requests whose outcomes differ. Nothing is written to the state.
+/// Only available in sequencer mode, where the node holds the rollup state.
+async fn check(
+    State(AppState {
+        mode,
+        runtime_db,
+        rollup_preimages_dir,
+        ..
+    }): State<AppState>,
+    Json(requests): Json<Vec<RecordedRequest>>,
+) -> ServiceResult<Json<Vec<Mismatch>>> {
+    if !matches!(mode, RunMode::Sequencer { .. }) {
+        return Err(ServiceError::BadRequest(
+            "Compatibility checks are only available in sequencer mode".to_string(),
+        ));
+    }
+    // Smart functions do not run on multi-threaded runtimes
+    let mismatches = tokio::task::spawn_blocking(move || {
+        let mut host = Host::new(runtime_db, rollup_preimages_dir);
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("failed to build tokio runtime")?;
+        rt.block_on(compat::check(&mut host, requests))
+            .map_err(|e| anyhow!("failed to check compatibility: {e}"))
+    })
+    .await
+    .context("failed to wait for compatibility check")??;
+    Ok(Json(mismatches))
+}
+
+impl Service for CompatService {
+    fn router_with_openapi() -> OpenApiRouter<AppState> {
+        OpenApiRouter::new().route("/compat/check", post(check))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use crate::{
+        services::{compat::CompatService, Service},
+        utils::tests::mock_app_state,
+        RunMode,
+    };
+
+    #[tokio::test]
+    async fn check_requires_sequencer_mode() {
+        let state = mock_app_state("", PathBuf::default(), "", RunMode::Default).await;
+        let (router, _) = CompatService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let res = router
+            .oneshot(
+                Request::post("/compat/check")
+                    .header("content-type", "application/json")
+                    .body(Body::from("[]"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+}
@@ -3,9 +3,13 @@ use crate::AppState;
 use utoipa_axum::router::OpenApiRouter;
 
 pub mod accounts;
+#[cfg(feature = "compat")]
+pub mod compat;
 pub mod error;
 pub mod logs;
 pub mod operations;
+pub mod outbox;
+pub mod protocol;
 pub mod utils;
 
 pub trait Service {
@@ -10,6 +10,7 @@ use crate::services::accounts::get_account_nonce;
 use crate::RunMode;
 
 use super::error::{ServiceError, ServiceResult};
+use super::protocol::load_params;
 use super::utils::StoreWrapper;
 use super::{AppState, Service};
 use anyhow::anyhow;
@@ -21,11 +22,12 @@ use axum::{
     Json,
 };
 
-use jstz_core::reveal_data::{PreimageHash, RevealData, MAX_REVEAL_SIZE};
+use jstz_core::reveal_data::{PreimageHash, RevealData, DEFAULT_MAX_REVEAL_SIZE};
 use jstz_core::BinEncodable;
 use jstz_proto::operation::{Content, Operation, SignedOperation};
 use jstz_proto::receipt::Receipt;
 use jstz_utils::KeyPair;
+use log::warn;
 use octez::OctezRollupClient;
 #[cfg(feature = "inject_inbox")]
 use tezos_crypto_rs::hash::{ContractKt1Hash, SmartRollupHash};
@@ -97,6 +99,18 @@ async fn prepare_rlp_operation(
     Ok(SignedOperation::new(signature, rlp_operation))
 }
 
+// Reads the protocol limit on revealed operations from the rollup storage. The kernel
+// enforces the limit regardless, so the default is used if it cannot be read.
+async fn get_max_reveal_size(store: &StoreWrapper) -> usize {
+    match load_params(store).await {
+        Ok(params) => params.max_reveal_size(),
+        Err(e) => {
+            warn!("Failed to read the maximum reveal size, using the default: {e}");
+            DEFAULT_MAX_REVEAL_SIZE
+        }
+    }
+}
+
 // Encode an operation. if the operation is too large, encode it into a reveal large payload operation
 async fn encode_operation(
     operation: SignedOperation,
@@ -110,7 +124,15 @@ async fn encode_operation(
 
     let (op, contents) = match encoded_op.len() {
         size if size <= MAX_DIRECT_OPERATION_SIZE => (operation, encoded_op),
-        size if size <= MAX_REVEAL_SIZE => {
+        size => {
+            let max_size = get_max_reveal_size(store).await;
+            if size > max_size {
+                Err(anyhow!(
+                    "Operation size exceeds maximum allowed size ({} bytes > {} bytes)",
+                    size,
+                    max_size
+                ))?
+            }
             let op =
                 prepare_rlp_operation(&operation, injector, store, rollup_preimages_dir)
                     .await?;
@@ -119,11 +141,6 @@ async fn encode_operation(
                 .map_err(|e| anyhow!("Failed to encode rlp operation: {e}"))?;
             (op, encoded_op)
         }
-        size => Err(anyhow!(
-            "Operation size exceeds maximum allowed size ({} bytes > {} MB)",
-            size,
-            MAX_REVEAL_SIZE / 1024 / 1024
-        ))?,
     };
 
     Ok((op, contents))
@@ -158,9 +175,10 @@ async fn inject(
         mode.clone(),
         storage_sync,
         rollup_client.clone(),
-        runtime_db,
+        runtime_db.clone(),
         storage_sync_db,
     );
+    let injected_operation = operation.clone();
     let (operation, encoded_operation) =
         encode_operation(operation, &injector, &store, &rollup_preimages_dir).await?;
     match mode {
@@ -171,6 +189,15 @@ async fn inject(
             insert_operation_queue(&queue, WrappedOperation::FromNode(operation)).await?;
         }
     }
+    // Recorded for debugging only, so failures do not fail the injection
+    if let Err(e) = serde_json::to_string(&injected_operation)
+        .map_err(anyhow::Error::from)
+        .and_then(|value| {
+            runtime_db.write_operation(&injected_operation.hash().to_string(), &value)
+        })
+    {
+        warn!("Failed to record the injected operation: {e}");
+    }
     Ok(())
 }
 
@@ -359,6 +386,33 @@ async fn receipt(
     Ok(Json(receipt))
 }
 
+/// Get an operation injected through this node
+#[utoipa::path(
+        get,
+        path = "/{operation_hash}",
+        tag = OPERATIONS_TAG,
+        params(
+            ("operation_hash" = String, description = "Operation hash")
+        ),
+        responses(
+            (status = 200, body = SignedOperation),
+            (status = 404),
+            (status = 500)
+        )
+    )]
+async fn operation(
+    State(AppState { runtime_db, .. }): State<AppState>,
+    Path(hash): Path<String>,
+) -> ServiceResult<Json<SignedOperation>> {
+    let operation = match runtime_db.read_operation(&hash)? {
+        Some(value) => serde_json::from_str(&value)
+            .map_err(|_| anyhow!("Failed to deserialize operation"))?,
+        None => Err(ServiceError::NotFound)?,
+    };
+
+    Ok(Json(operation))
+}
+
 /// Returns the hex encoded hash of an Operation
 #[utoipa::path(
         post,
@@ -381,6 +435,7 @@ impl Service for OperationsService {
         let routes = OpenApiRouter::new()
             .routes(routes!(inject))
             .routes(routes!(receipt))
+            .routes(routes!(operation))
             .routes(routes!(hash_operation));
 
         #[cfg(feature = "inject_inbox")]
@@ -401,7 +456,7 @@ mod tests {
         body::Body,
         http::{HeaderMap, Method, Request, Uri},
     };
-    use jstz_core::reveal_data::MAX_REVEAL_SIZE;
+    use jstz_core::reveal_data::DEFAULT_MAX_REVEAL_SIZE;
     use jstz_core::BinEncodable;
     use jstz_crypto::{
         hash::Hash,
@@ -544,11 +599,53 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn encodes_large_run_function_operation_into_reveal() {
+        let (pkh, pk, sk) = bootstrap1();
+        let mut server = mockito::Server::new_async().await;
+        let url = format!(
+            "/global/block/head/durable/wasm_2_0_0/value?key=/jstz_account/{pkh}"
+        );
+        server
+            .mock("GET", url.as_str())
+            .with_status(200)
+            .with_body(r#""01000000000000000000000000000000000000000901000000000000636f6e7374204b4559203d2022636f756e746572223b0a0a636f6e73742068616e646c6572203d202829203d3e207b0a20206c657420636f756e746572203d204b762e676574284b4559293b0a2020636f6e736f6c652e6c6f672860436f756e7465723a20247b636f756e7465727d60293b0a202069662028636f756e746572203d3d3d206e756c6c29207b0a20202020636f756e746572203d20303b0a20207d20656c7365207b0a20202020636f756e7465722b2b3b0a20207d0a20204b762e736574284b45592c20636f756e746572293b0a202072657475726e206e657720526573706f6e736528293b0a7d3b0a0a6578706f72742064656661756c742068616e646c65723b0a""#)
+            .create();
+        let client = OctezRollupClient::new(server.url());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let operation = make_signed_op(Content::RunFunction(RunFunction {
+            uri: format!("jstz://{pkh}/").try_into().unwrap(),
+            method: Method::POST,
+            headers: HeaderMap::new(),
+            body: HttpBody::from_bytes(vec![1; MAX_DIRECT_OPERATION_SIZE]),
+            gas_limit: 10000,
+        }));
+        let original_op_hash = operation.hash();
+        let key_pair = KeyPair(pk, sk);
+        let store = StoreWrapper::Rollup(client);
+        let (op, _) = encode_operation(operation, &key_pair, &store, temp_dir.path())
+            .await
+            .unwrap();
+        match op.content() {
+            Content::RevealLargePayload(RevealLargePayload {
+                reveal_type,
+                original_op_hash: hash,
+                ..
+            }) => {
+                assert_eq!(reveal_type, &RevealType::RunFunction);
+                assert_eq!(hash, &original_op_hash);
+            }
+            _ => panic!("Expected a RevealLargePayload operation"),
+        }
+        assert!(get_dir_size(temp_dir.path()) > MAX_DIRECT_OPERATION_SIZE as u64);
+    }
+
     #[tokio::test]
     async fn encodes_operation_throws_if_operation_is_too_large() {
         let (_, pk, sk) = bootstrap1();
         let client = OctezRollupClient::new("http://localhost:8732".to_string());
-        let code = mock_code(MAX_REVEAL_SIZE + 1);
+        let code = mock_code(DEFAULT_MAX_REVEAL_SIZE + 1);
         let operation = make_signed_op(Content::DeployFunction(DeployFunction {
             account_credit: Amount::default(),
             function_code: code,
@@ -676,6 +773,63 @@ mod tests {
         assert_eq!(res.status(), 503);
     }
 
+    #[tokio::test]
+    async fn get_injected_operation() {
+        let db_file = NamedTempFile::new().unwrap();
+        let state = mock_app_state(
+            "",
+            PathBuf::default(),
+            db_file.path().to_str().unwrap(),
+            RunMode::Sequencer {
+                capacity: 0,
+                debug_log_path: NamedTempFile::new().unwrap().path().to_path_buf(),
+                runtime_env: RuntimeEnv::Native,
+            },
+        )
+        .await;
+        let (mut router, _) = OperationsService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let dummy_op = make_signed_op(Content::RunFunction(RunFunction {
+            uri: Uri::from_static("http://http://"),
+            method: Method::HEAD,
+            headers: HeaderMap::new(),
+            body: HttpBody::empty(),
+            gas_limit: 0,
+        }));
+        let get_operation = |hash: String| {
+            Request::builder()
+                .uri(format!("/operations/{hash}"))
+                .method("GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = router
+            .borrow_mut()
+            .oneshot(get_operation(dummy_op.hash().to_string()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+
+        let res = router
+            .borrow_mut()
+            .oneshot(inject_operation_request(dummy_op.clone()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+
+        let res = router
+            .borrow_mut()
+            .oneshot(get_operation(dummy_op.hash().to_string()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 10000).await.unwrap();
+        let operation = serde_json::from_slice::<SignedOperation>(&bytes).unwrap();
+        assert_eq!(operation, dummy_op);
+    }
+
     #[tokio::test]
     async fn inject_large_operation_sequencer() {
         let db_file = NamedTempFile::new().unwrap();
@@ -0,0 +1,256 @@
+use anyhow::anyhow;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jstz_core::{
+    kv::outbox::{
+        OutboxMessageId, OutboxProof, OutboxQueueLevels, OutboxQueueMeta,
+        OutboxQueueStatus, OUTBOX_CURSOR_PATH, OUTBOX_QUEUE_LEVELS_PATH,
+        OUTBOX_QUEUE_META_PATH,
+    },
+    BinEncodable,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::{
+    error::{ServiceError, ServiceResult},
+    utils::StoreWrapper,
+    Service,
+};
+use crate::AppState;
+
+const OUTBOX_TAG: &str = "Outbox";
+
+pub struct OutboxService;
+
+/// Get outbox message proof
+///
+/// Get the commitment and proof needed to execute the outbox message at `index`
+/// in the outbox of `level`. The proof is only available once the commitment
+/// of `level` has been cemented.
+#[utoipa::path(
+        get,
+        path = "/{level}/{index}",
+        tag = OUTBOX_TAG,
+        params(
+            ("level" = u32, description = "Level the message was written at"),
+            ("index" = u32, description = "Index of the message in the level's outbox")
+        ),
+        responses(
+            (status = 200, body = OutboxProof),
+            (status = 404),
+            (status = 500)
+        )
+    )]
+async fn get_outbox_proof(
+    State(AppState { rollup_client, .. }): State<AppState>,
+    Path((level, index)): Path<(u32, u32)>,
+) -> ServiceResult<Json<OutboxProof>> {
+    let proof = rollup_client
+        .get_outbox_proof(level, index)
+        .await?
+        .ok_or(ServiceError::NotFound)?;
+    let rollup_address = rollup_client.get_rollup_address().await?;
+    Ok(Json(OutboxProof {
+        rollup_address: rollup_address.to_b58check(),
+        commitment: proof.commitment,
+        proof: proof.proof,
+    }))
+}
+
+async fn read_value<T: BinEncodable>(
+    store: &StoreWrapper,
+    key: &str,
+) -> ServiceResult<Option<T>> {
+    match store.get_value(key.to_string()).await? {
+        Some(value) => {
+            Ok(Some(T::decode(value.as_slice()).map_err(|_| {
+                anyhow!("Failed to deserialize value at {key}")
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Get outbox queue status
+///
+/// Get the backlog of outbox messages, such as withdrawals, that did not fit in
+/// the rollup outbox of the level they were produced at and are waiting to be
+/// written at a later level.
+#[utoipa::path(
+        get,
+        path = "/queue",
+        tag = OUTBOX_TAG,
+        responses(
+            (status = 200, body = OutboxQueueStatus),
+            (status = 500)
+        )
+    )]
+async fn get_outbox_queue(
+    State(AppState {
+        mode,
+        rollup_client,
+        runtime_db,
+        storage_sync,
+        storage_sync_db,
+        ..
+    }): State<AppState>,
+) -> ServiceResult<Json<OutboxQueueStatus>> {
+    let store = StoreWrapper::new(
+        mode,
+        storage_sync,
+        rollup_client,
+        runtime_db,
+        storage_sync_db,
+    );
+    let meta = read_value::<OutboxQueueMeta>(&store, OUTBOX_QUEUE_META_PATH).await?;
+    let levels = read_value::<OutboxQueueLevels>(&store, OUTBOX_QUEUE_LEVELS_PATH)
+        .await?
+        .unwrap_or_default();
+    let cursor = read_value::<OutboxMessageId>(&store, OUTBOX_CURSOR_PATH).await?;
+    Ok(Json(OutboxQueueStatus::new(meta, levels, cursor)))
+}
+
+impl Service for OutboxService {
+    fn router_with_openapi() -> OpenApiRouter<AppState> {
+        let routes = OpenApiRouter::new()
+            .routes(routes!(get_outbox_proof))
+            .routes(routes!(get_outbox_queue));
+
+        OpenApiRouter::new().nest("/outbox", routes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use axum::{body::Body, http::Request};
+    use jstz_core::{
+        kv::outbox::{OutboxMessageId, OutboxProof, OutboxQueueMeta, OutboxQueueStatus},
+        BinEncodable,
+    };
+    use mockito::Matcher;
+    use tower::ServiceExt;
+
+    use crate::{
+        services::{outbox::OutboxService, Service},
+        utils::tests::mock_app_state,
+        RunMode,
+    };
+
+    const PROOF_PATH: &str = "/global/block/cemented/helpers/proofs/outbox/12/messages";
+
+    #[tokio::test]
+    async fn get_outbox_proof() {
+        let mut server = mockito::Server::new_async().await;
+        let mock_proof = server
+            .mock("GET", PROOF_PATH)
+            .match_query(Matcher::UrlEncoded("index".into(), "3".into()))
+            .with_body(r#"{"commitment":"src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu","proof":"0300"}"#)
+            .create();
+        let mock_rollup_addr = server
+            .mock("GET", "/global/smart_rollup_address")
+            .with_body("sr1PuFMgaRUN12rKQ3J2ae5psNtwCxPNmGNK")
+            .create();
+
+        let state =
+            mock_app_state(&server.url(), PathBuf::default(), "", RunMode::Default).await;
+        let (router, _) = OutboxService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let res = router
+            .oneshot(Request::get("/outbox/12/3").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<OutboxProof>(&body).unwrap(),
+            OutboxProof {
+                rollup_address: "sr1PuFMgaRUN12rKQ3J2ae5psNtwCxPNmGNK".to_string(),
+                commitment: "src13aUmJ5fEVJJM1qbjeNhqCH5Y6KsMJSDP3yuDBDCRvYvQUVphuu"
+                    .to_string(),
+                proof: "0300".to_string(),
+            }
+        );
+        mock_proof.assert();
+        mock_rollup_addr.assert();
+    }
+
+    #[tokio::test]
+    async fn get_outbox_proof_not_cemented() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", PROOF_PATH)
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .create();
+
+        let state =
+            mock_app_state(&server.url(), PathBuf::default(), "", RunMode::Default).await;
+        let (router, _) = OutboxService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let res = router
+            .oneshot(Request::get("/outbox/12/3").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn get_outbox_queue() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mock_value = |key: &str, body: String| {
+            server
+                .mock("GET", "/global/block/head/durable/wasm_2_0_0/value")
+                .match_query(Matcher::UrlEncoded("key".to_string(), key.to_string()))
+                .with_body(body)
+                .create()
+        };
+        let meta = OutboxQueueMeta { len: 120, max: 500 };
+        let cursor = OutboxMessageId {
+            level: 5,
+            index: 100,
+        };
+        let mock_meta = mock_value(
+            "/outbox/meta",
+            format!("\"{}\"", hex::encode(meta.encode().unwrap())),
+        );
+        let mock_levels = mock_value("/outbox/levels", "null".to_string());
+        let mock_cursor = mock_value(
+            "/outbox/cursor",
+            format!("\"{}\"", hex::encode(cursor.encode().unwrap())),
+        );
+
+        let state =
+            mock_app_state(&server.url(), PathBuf::default(), "", RunMode::Default).await;
+        let (router, _) = OutboxService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let res = router
+            .oneshot(Request::get("/outbox/queue").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<OutboxQueueStatus>(&body).unwrap(),
+            OutboxQueueStatus {
+                len: 120,
+                max: 500,
+                oldest_pending_level: None,
+                estimated_flush_level: Some(7),
+            }
+        );
+        mock_meta.assert();
+        mock_levels.assert();
+        mock_cursor.assert();
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::anyhow;
+use axum::{extract::State, Json};
+use jstz_core::BinEncodable;
+use jstz_proto::{
+    context::protocol_params::{ProtocolParams, VersionedProtocolParams},
+    storage::PROTOCOL_PARAMS_PATH,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::{error::ServiceResult, utils::StoreWrapper, Service};
+use crate::AppState;
+
+const PROTOCOL_TAG: &str = "Protocol";
+
+pub struct ProtocolService;
+
+/// Reads the protocol parameters from the rollup storage, or the default
+/// parameters if none are set
+pub(crate) async fn load_params(store: &StoreWrapper) -> anyhow::Result<ProtocolParams> {
+    match store.get_value(PROTOCOL_PARAMS_PATH.to_string()).await? {
+        Some(value) => Ok(VersionedProtocolParams::decode(&value)
+            .map_err(|_| anyhow!("Failed to deserialize protocol parameters"))?
+            .into()),
+        None => Ok(ProtocolParams::default()),
+    }
+}
+
+/// Get protocol parameters
+///
+/// Get the parameters limiting the resources used by operations and smart
+/// function calls, such as the maximum size of a revealed operation.
+#[utoipa::path(
+    get,
+    path = "/params",
+    tag = PROTOCOL_TAG,
+    responses(
+        (status = 200, body = ProtocolParams),
+        (status = 500)
+    )
+)]
+async fn get_params(
+    State(AppState {
+        mode,
+        rollup_client,
+        runtime_db,
+        storage_sync,
+        storage_sync_db,
+        ..
+    }): State<AppState>,
+) -> ServiceResult<Json<ProtocolParams>> {
+    let store = StoreWrapper::new(
+        mode,
+        storage_sync,
+        rollup_client,
+        runtime_db,
+        storage_sync_db,
+    );
+    Ok(Json(load_params(&store).await?))
+}
+
+impl Service for ProtocolService {
+    fn router_with_openapi() -> OpenApiRouter<AppState> {
+        let routes = OpenApiRouter::new().routes(routes!(get_params));
+
+        OpenApiRouter::new().nest("/protocol", routes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use axum::{body::Body, extract::Request};
+    use jstz_core::BinEncodable;
+    use jstz_proto::{
+        context::protocol_params::{ProtocolParams, VersionedProtocolParams},
+        storage::PROTOCOL_PARAMS_PATH,
+    };
+    use tempfile::NamedTempFile;
+    use tower::ServiceExt;
+
+    use crate::{
+        config::RuntimeEnv,
+        services::{protocol::ProtocolService, Service},
+        utils::tests::mock_app_state,
+        RunMode,
+    };
+
+    async fn get_params(
+        db_path: &str,
+        stored: Option<&ProtocolParams>,
+    ) -> ProtocolParams {
+        let state = mock_app_state(
+            "",
+            PathBuf::default(),
+            db_path,
+            RunMode::Sequencer {
+                capacity: 0,
+                debug_log_path: PathBuf::new(),
+                runtime_env: RuntimeEnv::Native,
+            },
+        )
+        .await;
+        if let Some(params) = stored {
+            let params = VersionedProtocolParams::from(params.clone());
+            state
+                .runtime_db
+                .write(
+                    &PROTOCOL_PARAMS_PATH.to_string(),
+                    &hex::encode(params.encode().unwrap()),
+                )
+                .unwrap();
+        }
+
+        let (router, _) = ProtocolService::router_with_openapi()
+            .with_state(state)
+            .split_for_parts();
+        let res = router
+            .oneshot(
+                Request::builder()
+                    .uri("/protocol/params")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let bytes = axum::body::to_bytes(res.into_body(), 1000).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_default_params() {
+        let db_file = NamedTempFile::new().unwrap();
+        assert_eq!(
+            get_params(db_file.path().to_str().unwrap(), None).await,
+            ProtocolParams::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_stored_params() {
+        let db_file = NamedTempFile::new().unwrap();
+        let params = ProtocolParams {
+            max_reveal_size: 1024,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_params(db_file.path().to_str().unwrap(), Some(&params)).await,
+            params
+        );
+    }
+}
@@ -156,6 +156,10 @@ fn apply_batch_tx(db: &Db, updates: BatchStorageUpdate) -> Result<()> {
                 }
                 sequencer::db::exec_delete(&tx, key).map(|_| ())
             }
+            StorageUpdate::RemovePrefix { ref key } => {
+                sequencer::db::exec_delete(&tx, key)
+                    .and_then(|_| sequencer::db::exec_delete_glob(&tx, key))
+            }
         };
         if let Err(e) = res {
             error!("error writing storage update {:?} {e}", update);
@@ -319,6 +323,24 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_apply_batch_tx_remove_prefix() -> Result<()> {
+        let (db, _db_file) = temp_db().unwrap();
+        let keys = ["/foo", "/foo/bar", "/foo/bar/baz", "/foobar"]
+            .map(|key| OwnedPath::try_from(key.to_string()).unwrap());
+        let mut batch = BatchStorageUpdate::new(5);
+        for key in &keys {
+            let _ = batch.push_insert(key, &DummyValue(1));
+        }
+        batch.push_remove_prefix(&keys[0]);
+        apply_batch_tx(&db, batch)?;
+        for key in &keys[..3] {
+            assert!(!db.key_exists(&key.to_string())?);
+        }
+        assert!(db.key_exists(&keys[3].to_string())?);
+        Ok(())
+    }
+
     #[test]
     fn test_apply_batch_tx_atomicity() -> Result<()> {
         let (db, _db_file) = temp_db().unwrap();
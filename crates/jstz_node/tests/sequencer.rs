@@ -287,7 +287,8 @@ async fn call_function(client: &Client, base_uri: &str) {
         ReceiptResult::Success(ReceiptContent::RunFunction(RunFunctionReceipt {
             body,
             status_code: StatusCode::OK,
-            headers: _
+            headers: _,
+            ..
         })) if &String::from_utf8(body.clone().unwrap()).unwrap() == "this is a big function"
     ));
 }
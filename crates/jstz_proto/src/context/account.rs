@@ -6,8 +6,9 @@ use std::{
 };
 
 use crate::{
+    context::ticket_table::TicketTable,
     error::{Error, Result},
-    runtime::ParsedCode,
+    runtime::{ParsedCode, RuntimeVersion},
 };
 use bincode::{Decode, Encode};
 use boa_gc::{empty_trace, Finalize, Trace};
@@ -21,7 +22,7 @@ use jstz_core::{
     host::HostRuntime,
     kv::{Entry, Transaction},
 };
-use jstz_crypto::hash::Hash;
+use jstz_crypto::hash::{Blake2b, Hash};
 use jstz_crypto::public_key_hash::PublicKeyHash;
 use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use serde::{Deserialize, Serialize};
@@ -201,6 +202,21 @@ impl Address {
 
 pub const ACCOUNTS_PATH_PREFIX: &str = "/jstz_account";
 const ACCOUNTS_PATH: RefPath = RefPath::assert_from(ACCOUNTS_PATH_PREFIX.as_bytes());
+pub const RUNTIME_VERSIONS_PATH_PREFIX: &str = "/jstz_runtime_version";
+const RUNTIME_VERSIONS_PATH: RefPath =
+    RefPath::assert_from(RUNTIME_VERSIONS_PATH_PREFIX.as_bytes());
+pub const STORAGE_USAGE_PATH_PREFIX: &str = "/jstz_storage_usage";
+const STORAGE_USAGE_PATH: RefPath =
+    RefPath::assert_from(STORAGE_USAGE_PATH_PREFIX.as_bytes());
+pub const ADMINS_PATH_PREFIX: &str = "/jstz_admin";
+const ADMINS_PATH: RefPath = RefPath::assert_from(ADMINS_PATH_PREFIX.as_bytes());
+pub const TOMBSTONES_PATH_PREFIX: &str = "/jstz_tombstone";
+const TOMBSTONES_PATH: RefPath = RefPath::assert_from(TOMBSTONES_PATH_PREFIX.as_bytes());
+// Kv store, published Kv prefixes and accounted Kv sizes of smart functions, written
+// by the Kv APIs of the runtimes
+const KV_PATH: RefPath = RefPath::assert_from(b"/jstz_kv");
+const KV_PUBLISHED_PATH: RefPath = RefPath::assert_from(b"/jstz_kv_published");
+const KV_SIZES_PATH: RefPath = RefPath::assert_from(b"/jstz_kv_size");
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Encode, Decode, ToSchema)]
 pub struct UserAccount {
@@ -216,6 +232,17 @@ pub struct SmartFunctionAccount {
     pub function_code: ParsedCode,
 }
 
+/// Durable storage used by the Kv store of a smart function
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode, ToSchema,
+)]
+pub struct StorageUsage {
+    /// Bytes used by the Kv store
+    pub bytes: u64,
+    /// Storage deposit held for the used bytes, in mutez
+    pub deposit: Amount,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, ToSchema)]
 pub enum Account {
     User(UserAccount),
@@ -228,6 +255,35 @@ impl Account {
         Ok(path::concat(&ACCOUNTS_PATH, &account_path)?)
     }
 
+    fn runtime_version_path(addr: &SmartFunctionHash) -> Result<OwnedPath> {
+        let account_path = OwnedPath::try_from(format!("/{}", addr.to_base58()))?;
+        Ok(path::concat(&RUNTIME_VERSIONS_PATH, &account_path)?)
+    }
+
+    fn storage_usage_path(addr: &SmartFunctionHash) -> Result<OwnedPath> {
+        let account_path = OwnedPath::try_from(format!("/{}", addr.to_base58()))?;
+        Ok(path::concat(&STORAGE_USAGE_PATH, &account_path)?)
+    }
+
+    fn admin_path(addr: &SmartFunctionHash) -> Result<OwnedPath> {
+        let account_path = OwnedPath::try_from(format!("/{}", addr.to_base58()))?;
+        Ok(path::concat(&ADMINS_PATH, &account_path)?)
+    }
+
+    fn tombstone_path(addr: &SmartFunctionHash) -> Result<OwnedPath> {
+        let account_path = OwnedPath::try_from(format!("/{}", addr.to_base58()))?;
+        Ok(path::concat(&TOMBSTONES_PATH, &account_path)?)
+    }
+
+    fn kv_paths(addr: &SmartFunctionHash) -> Result<(OwnedPath, OwnedPath, OwnedPath)> {
+        let account_path = OwnedPath::try_from(format!("/{}", addr.to_base58()))?;
+        Ok((
+            path::concat(&KV_PATH, &account_path)?,
+            path::concat(&KV_PUBLISHED_PATH, &account_path)?,
+            path::concat(&KV_SIZES_PATH, &account_path)?,
+        ))
+    }
+
     fn default_account(addr: &impl Addressable) -> Self {
         match addr.kind() {
             AddressKind::User => Self::User(UserAccount::default()),
@@ -286,6 +342,10 @@ impl Account {
         tx: &'a mut Transaction,
         addr: &impl Addressable,
     ) -> Result<GuardedMut<'a, Account>> {
+        // Deleted accounts must not be recreated with the default account
+        if Self::is_deleted(hrt, tx, addr)? {
+            return Err(Error::AccountDeleted);
+        }
         let account_entry = tx.entry::<Self>(hrt, Self::path(addr)?)?;
         Ok(account_entry.or_insert_with(|| Self::default_account(addr)))
     }
@@ -301,6 +361,23 @@ impl Account {
         result
     }
 
+    /// Returns true if `addr` is the address of a deleted smart function. Deleted
+    /// addresses cannot be deployed to, receive transfers or use storage.
+    pub fn is_deleted(
+        hrt: &impl HostRuntime,
+        tx: &Transaction,
+        addr: &impl Addressable,
+    ) -> Result<bool> {
+        let address: Address = addr.clone().into();
+        let Address::SmartFunction(address) = address else {
+            return Ok(false);
+        };
+        let is_dirty = tx.get_dirty();
+        let result = tx.contains_key(hrt, &Self::tombstone_path(&address)?);
+        tx.set_dirty(is_dirty);
+        Ok(result?)
+    }
+
     fn try_insert(
         self,
         hrt: &impl HostRuntime,
@@ -362,14 +439,167 @@ impl Account {
             format!("{}{}{}", creator.to_base58(), function_code, nonce.deref())
                 .as_bytes(),
         )?;
+        let result = Self::insert_smart_function(
+            hrt,
+            tx,
+            creator,
+            &address,
+            amount,
+            function_code,
+        );
+        tx.set_dirty(is_dirty);
+        result.map(|_| address)
+    }
+
+    /// Returns the address of the smart function deployed by `creator` with `salt`
+    /// and `function_code`. Unlike the address given by [`Self::create_smart_function`],
+    /// it does not depend on the nonce of `creator`, so it can be computed before the
+    /// smart function is deployed.
+    pub fn salted_address(
+        creator: &impl Addressable,
+        salt: &str,
+        function_code: &str,
+    ) -> Result<SmartFunctionHash> {
+        let code_hash = Blake2b::from(function_code.as_bytes());
+        Ok(SmartFunctionHash::digest(
+            format!("salted{}{}{}", creator.to_base58(), code_hash, salt).as_bytes(),
+        )?)
+    }
+
+    /// Creates a smart function at [`Self::salted_address`]
+    pub fn create_salted_smart_function(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        creator: &impl Addressable,
+        amount: Amount,
+        function_code: ParsedCode,
+        salt: &str,
+    ) -> Result<SmartFunctionHash> {
+        let is_dirty = tx.get_dirty();
+        let address = Self::salted_address(creator, salt, &function_code.0)?;
+        let result = Self::insert_smart_function(
+            hrt,
+            tx,
+            creator,
+            &address,
+            amount,
+            function_code,
+        );
+        tx.set_dirty(is_dirty);
+        result.map(|_| address)
+    }
+
+    fn insert_smart_function(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        creator: &impl Addressable,
+        address: &SmartFunctionHash,
+        amount: Amount,
+        function_code: ParsedCode,
+    ) -> Result<()> {
+        if Self::is_deleted(hrt, tx, address)? {
+            return Err(Error::AccountDeleted);
+        }
         let account = SmartFunctionAccount {
             amount,
             nonce: Nonce::default(),
             function_code,
         };
-        Self::SmartFunction(account).try_insert(hrt, tx, Self::path(&address)?)?;
+        Self::SmartFunction(account).try_insert(hrt, tx, Self::path(address)?)?;
+        tx.insert(
+            Self::runtime_version_path(address)?,
+            RuntimeVersion::CURRENT,
+        )?;
+        let admin: Address = creator.clone().into();
+        tx.insert(Self::admin_path(address)?, admin)?;
+        Ok(())
+    }
+
+    /// Returns the runtime the smart function at `addr` was deployed for. Smart
+    /// functions deployed before runtime versions were recorded were deployed for
+    /// the v1 runtime.
+    pub fn runtime_version(
+        hrt: &impl HostRuntime,
+        tx: &Transaction,
+        addr: &SmartFunctionHash,
+    ) -> Result<RuntimeVersion> {
+        let is_dirty = tx.get_dirty();
+        let result = tx
+            .get::<RuntimeVersion>(hrt, Self::runtime_version_path(addr)?)?
+            .map(|version| *version)
+            .unwrap_or(RuntimeVersion::V1);
         tx.set_dirty(is_dirty);
-        Ok(address)
+        Ok(result)
+    }
+
+    /// Sets the runtime the smart function at `addr` runs on. Used to migrate a v1
+    /// smart function to the v2 runtime.
+    pub fn set_runtime_version(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        addr: &SmartFunctionHash,
+        version: RuntimeVersion,
+    ) -> Result<()> {
+        if !Self::exists(hrt, tx, addr)? {
+            return Err(Error::AccountDoesNotExist);
+        }
+        tx.insert(Self::runtime_version_path(addr)?, version)?;
+        Ok(())
+    }
+
+    /// Returns the address allowed to delete the smart function at `addr`, i.e. the
+    /// address that deployed it. Smart functions deployed before admins were
+    /// recorded have no admin.
+    pub fn admin(
+        hrt: &impl HostRuntime,
+        tx: &Transaction,
+        addr: &SmartFunctionHash,
+    ) -> Result<Option<Address>> {
+        let is_dirty = tx.get_dirty();
+        let result = tx
+            .get::<Address>(hrt, Self::admin_path(addr)?)?
+            .map(|admin| admin.clone());
+        tx.set_dirty(is_dirty);
+        Ok(result)
+    }
+
+    /// Deletes the smart function at `addr`. Its balance and storage deposit are
+    /// transferred to `beneficiary`, its account and Kv store are removed and its
+    /// address is tombstoned. Returns the amount transferred to `beneficiary`.
+    /// Fails if the smart function still holds tickets.
+    pub fn delete_smart_function(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        addr: &SmartFunctionHash,
+        beneficiary: &impl Addressable,
+    ) -> Result<Amount> {
+        if !Self::exists(hrt, tx, addr)? {
+            return Err(Error::AccountDoesNotExist);
+        }
+        if beneficiary.to_base58() == addr.to_base58() {
+            return Err(Error::InvalidBeneficiary);
+        }
+        if Self::is_deleted(hrt, tx, beneficiary)? {
+            return Err(Error::AccountDeleted);
+        }
+        if TicketTable::holds_tickets(hrt, tx, addr)? {
+            return Err(Error::TicketsHeld);
+        }
+        let balance = Self::balance(hrt, tx, addr)?;
+        let deposit = Self::storage_usage(hrt, tx, addr)?.deposit;
+        let amount = balance.checked_add(deposit).ok_or(Error::BalanceOverflow)?;
+        Self::add_balance(hrt, tx, beneficiary, amount)?;
+
+        let (kv_path, kv_published_path, kv_sizes_path) = Self::kv_paths(addr)?;
+        tx.remove(Self::path(addr)?)?;
+        tx.remove(Self::runtime_version_path(addr)?)?;
+        tx.remove(Self::storage_usage_path(addr)?)?;
+        tx.remove(Self::admin_path(addr)?)?;
+        tx.remove(kv_published_path)?;
+        tx.remove_prefix(kv_path)?;
+        tx.remove_prefix(kv_sizes_path)?;
+        tx.insert(Self::tombstone_path(addr)?, true)?;
+        Ok(amount)
     }
 
     pub fn function_code<'a>(
@@ -448,6 +678,75 @@ impl Account {
         Ok(*balance)
     }
 
+    /// Returns the durable storage used by the Kv store of `addr`
+    pub fn storage_usage(
+        hrt: &impl HostRuntime,
+        tx: &Transaction,
+        addr: &SmartFunctionHash,
+    ) -> Result<StorageUsage> {
+        let is_dirty = tx.get_dirty();
+        let result = tx
+            .get::<StorageUsage>(hrt, Self::storage_usage_path(addr)?)?
+            .map(|usage| usage.clone())
+            .unwrap_or_default();
+        tx.set_dirty(is_dirty);
+        Ok(result)
+    }
+
+    /// Accounts for the Kv store of `addr` changing by `delta` bytes.
+    ///
+    /// Growing the store beyond `quota` bytes fails. Every new byte takes a deposit of
+    /// `deposit_per_byte` from the balance of `addr`, and every freed byte refunds its
+    /// share of the deposit held.
+    pub fn update_storage_usage(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        addr: &SmartFunctionHash,
+        delta: i64,
+        quota: u64,
+        deposit_per_byte: Amount,
+    ) -> Result<StorageUsage> {
+        if Self::is_deleted(hrt, tx, addr)? {
+            return Err(Error::AccountDeleted);
+        }
+        let mut usage = Self::storage_usage(hrt, tx, addr)?;
+        if delta >= 0 {
+            let added = delta as u64;
+            let bytes = usage
+                .bytes
+                .checked_add(added)
+                .ok_or(Error::StorageQuotaExceeded)?;
+            if bytes > quota {
+                return Err(Error::StorageQuotaExceeded);
+            }
+            let deposit = added
+                .checked_mul(deposit_per_byte)
+                .ok_or(Error::BalanceOverflow)?;
+            if deposit > 0 {
+                Self::sub_balance(hrt, tx, addr, deposit)?;
+            }
+            usage.bytes = bytes;
+            usage.deposit = usage
+                .deposit
+                .checked_add(deposit)
+                .ok_or(Error::BalanceOverflow)?;
+        } else {
+            let freed = delta.unsigned_abs().min(usage.bytes);
+            let refund = if usage.bytes == 0 {
+                0
+            } else {
+                (usage.deposit as u128 * freed as u128 / usage.bytes as u128) as Amount
+            };
+            if refund > 0 {
+                Self::add_balance(hrt, tx, addr, refund)?;
+            }
+            usage.bytes -= freed;
+            usage.deposit -= refund;
+        }
+        tx.insert(Self::storage_usage_path(addr)?, usage.clone())?;
+        Ok(usage)
+    }
+
     pub fn set_balance(
         hrt: &impl HostRuntime,
         tx: &mut Transaction,
@@ -983,5 +1282,217 @@ mod test {
                 _ => panic!("Expected SmartFunction account"),
             }
         }
+
+        #[test]
+        fn test_create_salted_smart_function() {
+            let (host, mut tx) = setup_test_env();
+            let (creator, _) = create_test_addresses();
+            let code = ParsedCode("function test() {}".to_string());
+
+            // The address is known before the deployment and does not depend on the
+            // nonce of the creator
+            let predicted = Account::salted_address(&creator, "salt", &code.0).unwrap();
+            Account::nonce(&host, &mut tx, &creator)
+                .unwrap()
+                .increment();
+            let sf_hash = Account::create_salted_smart_function(
+                &host,
+                &mut tx,
+                &creator,
+                0,
+                code.clone(),
+                "salt",
+            )
+            .unwrap();
+            assert_eq!(sf_hash, predicted);
+            assert!(Account::exists(&host, &tx, &sf_hash).unwrap());
+
+            // The same salt and code cannot be deployed twice
+            assert!(matches!(
+                Account::create_salted_smart_function(
+                    &host,
+                    &mut tx,
+                    &creator,
+                    0,
+                    code.clone(),
+                    "salt"
+                ),
+                Err(Error::AccountExists)
+            ));
+
+            // Another salt or code yields another address
+            assert_ne!(
+                Account::salted_address(&creator, "other salt", &code.0).unwrap(),
+                sf_hash
+            );
+            assert_ne!(
+                Account::salted_address(&creator, "salt", "function other() {}").unwrap(),
+                sf_hash
+            );
+        }
+
+        #[test]
+        fn test_storage_usage() {
+            let (host, mut tx) = setup_test_env();
+            let (_, sf_addr) = create_test_addresses();
+            let sf_hash = sf_addr.as_smart_function().unwrap();
+            Account::add_balance(&host, &mut tx, sf_hash, 1000).unwrap();
+
+            assert_eq!(
+                Account::storage_usage(&host, &tx, sf_hash).unwrap(),
+                StorageUsage::default()
+            );
+
+            // Growing the store takes a deposit
+            let usage =
+                Account::update_storage_usage(&host, &mut tx, sf_hash, 100, 200, 2)
+                    .unwrap();
+            assert_eq!(
+                usage,
+                StorageUsage {
+                    bytes: 100,
+                    deposit: 200
+                }
+            );
+            assert_eq!(Account::balance(&host, &mut tx, sf_hash).unwrap(), 800);
+
+            // The quota cannot be exceeded
+            assert!(matches!(
+                Account::update_storage_usage(&host, &mut tx, sf_hash, 101, 200, 2),
+                Err(Error::StorageQuotaExceeded)
+            ));
+
+            // The deposit is taken from the balance
+            assert!(matches!(
+                Account::update_storage_usage(&host, &mut tx, sf_hash, 100, 1000, 10),
+                Err(Error::InsufficientFunds)
+            ));
+
+            // Freeing bytes refunds their deposit
+            let usage =
+                Account::update_storage_usage(&host, &mut tx, sf_hash, -40, 200, 2)
+                    .unwrap();
+            assert_eq!(
+                usage,
+                StorageUsage {
+                    bytes: 60,
+                    deposit: 120
+                }
+            );
+            assert_eq!(Account::balance(&host, &mut tx, sf_hash).unwrap(), 880);
+            assert_eq!(Account::storage_usage(&host, &tx, sf_hash).unwrap(), usage);
+        }
+
+        #[test]
+        fn test_runtime_version() {
+            let (host, mut tx) = setup_test_env();
+            let (creator, sf_addr) = create_test_addresses();
+            let sf_hash = sf_addr.as_smart_function().unwrap();
+
+            // Smart functions without a recorded runtime version were deployed
+            // for the v1 runtime
+            assert_eq!(
+                Account::runtime_version(&host, &tx, sf_hash).unwrap(),
+                RuntimeVersion::V1
+            );
+
+            let code = ParsedCode("export default () => {}".to_string());
+            let sf_hash =
+                Account::create_smart_function(&host, &mut tx, &creator, 0, code)
+                    .unwrap();
+            assert_eq!(
+                Account::runtime_version(&host, &tx, &sf_hash).unwrap(),
+                RuntimeVersion::CURRENT
+            );
+
+            Account::set_runtime_version(&host, &mut tx, &sf_hash, RuntimeVersion::V1)
+                .unwrap();
+            assert_eq!(
+                Account::runtime_version(&host, &tx, &sf_hash).unwrap(),
+                RuntimeVersion::V1
+            );
+
+            // Unknown smart functions cannot be migrated
+            let unknown = SmartFunctionHash::from_base58(KT1).unwrap();
+            assert!(matches!(
+                Account::set_runtime_version(
+                    &host,
+                    &mut tx,
+                    &unknown,
+                    RuntimeVersion::V2
+                ),
+                Err(Error::AccountDoesNotExist)
+            ));
+        }
+
+        #[test]
+        fn test_delete_smart_function() {
+            let (mut host, mut tx) = setup_test_env();
+            let (creator, _) = create_test_addresses();
+            let code = ParsedCode("export default () => {}".to_string());
+            let sf_hash =
+                Account::create_smart_function(&host, &mut tx, &creator, 100, code)
+                    .unwrap();
+            assert_eq!(
+                Account::admin(&host, &tx, &sf_hash).unwrap(),
+                Some(creator.clone())
+            );
+            Account::update_storage_usage(&host, &mut tx, &sf_hash, 10, 100, 2).unwrap();
+            let kv_key = OwnedPath::try_from(format!("/jstz_kv/{sf_hash}/key")).unwrap();
+            tx.insert(kv_key.clone(), 1).unwrap();
+            tx.commit(&mut host).unwrap();
+
+            tx.begin();
+            // A smart function cannot be its own beneficiary
+            assert!(matches!(
+                Account::delete_smart_function(&host, &mut tx, &sf_hash, &sf_hash),
+                Err(Error::InvalidBeneficiary)
+            ));
+
+            // Nor be deleted while it holds tickets
+            let ticket_hash = jstz_mock::ticket_hash1();
+            TicketTable::add(&mut host, &mut tx, &sf_hash, &ticket_hash, 1).unwrap();
+            assert!(matches!(
+                Account::delete_smart_function(&host, &mut tx, &sf_hash, &creator),
+                Err(Error::TicketsHeld)
+            ));
+            TicketTable::sub(&mut host, &mut tx, &sf_hash, &ticket_hash, 1).unwrap();
+
+            // The balance and the storage deposit go to the beneficiary
+            assert_eq!(
+                Account::delete_smart_function(&host, &mut tx, &sf_hash, &creator)
+                    .unwrap(),
+                100
+            );
+            assert_eq!(Account::balance(&host, &mut tx, &creator).unwrap(), 100);
+            assert!(!Account::exists(&host, &tx, &sf_hash).unwrap());
+            assert!(Account::is_deleted(&host, &tx, &sf_hash).unwrap());
+            assert!(Account::admin(&host, &tx, &sf_hash).unwrap().is_none());
+            assert!(!tx.contains_key(&host, &kv_key).unwrap());
+            tx.commit(&mut host).unwrap();
+            assert!(!Storage::contains_key(&host, &kv_key).unwrap());
+
+            // Deleted addresses cannot receive transfers or be deleted again
+            tx.begin();
+            assert!(matches!(
+                Account::add_balance(&host, &mut tx, &sf_hash, 1),
+                Err(Error::AccountDeleted)
+            ));
+            assert!(matches!(
+                Account::transfer(&host, &mut tx, &creator, &sf_hash, 1),
+                Err(Error::AccountDeleted)
+            ));
+            assert!(matches!(
+                Account::delete_smart_function(&host, &mut tx, &sf_hash, &creator),
+                Err(Error::AccountDoesNotExist)
+            ));
+
+            // The address cannot be redeployed
+            let code = ParsedCode("export default () => {}".to_string());
+            assert!(matches!(
+                Account::create_smart_function(&host, &mut tx, &creator, 0, code),
+                Err(Error::AccountDeleted)
+            ));
+        }
     }
 }
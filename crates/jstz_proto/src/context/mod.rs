@@ -1,3 +1,4 @@
 pub mod account;
+pub mod protocol_params;
 pub mod receipt;
 pub mod ticket_table;
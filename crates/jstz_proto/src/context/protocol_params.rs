@@ -0,0 +1,265 @@
+use bincode::{Decode, Encode};
+use jstz_core::{
+    host::HostRuntime,
+    kv::Storage,
+    reveal_data::{DEFAULT_MAX_REVEAL_SIZE, MAX_REVEAL_SIZE},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{context::account::Amount, error::Result, storage::PROTOCOL_PARAMS_PATH};
+
+/// Default maximum depth of nested smart function calls
+pub const DEFAULT_MAX_CALL_DEPTH: u8 = 5;
+/// Default heap limit of each smart function call, in bytes
+pub const DEFAULT_HEAP_LIMIT: u64 = 64 * 1024 * 1024;
+/// Default maximum size of the Kv store of a smart function, in bytes
+pub const DEFAULT_STORAGE_QUOTA: u64 = 16 * 1024 * 1024;
+/// Default maximum size of the code of a library, in bytes
+pub const DEFAULT_MAX_LIBRARY_SIZE: u64 = 1024 * 1024;
+
+/// Protocol parameters limiting the resources used by operations and smart function calls
+#[derive(
+    Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, ToSchema,
+)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolParams {
+    /// Maximum depth of nested smart function calls
+    pub max_call_depth: u8,
+    /// Heap limit of each smart function call, in bytes
+    pub heap_limit: u64,
+    /// Maximum size of the Kv store of a smart function, in bytes
+    pub storage_quota: u64,
+    /// Deposit taken from a smart function for every byte it stores, and from the
+    /// deployer of a library for every byte of its code, in mutez
+    pub storage_deposit_per_byte: Amount,
+    /// Maximum size of an operation revealed from the reveal data channel, in bytes.
+    /// Capped at 128MB.
+    pub max_reveal_size: u64,
+    /// Maximum size of the code of a library, in bytes
+    pub max_library_size: u64,
+}
+
+impl Default for ProtocolParams {
+    fn default() -> Self {
+        Self {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            heap_limit: DEFAULT_HEAP_LIMIT,
+            storage_quota: DEFAULT_STORAGE_QUOTA,
+            storage_deposit_per_byte: 0,
+            max_reveal_size: DEFAULT_MAX_REVEAL_SIZE as u64,
+            max_library_size: DEFAULT_MAX_LIBRARY_SIZE,
+        }
+    }
+}
+
+/// Protocol parameters as stored at [`PROTOCOL_PARAMS_PATH`]. New parameters
+/// are added in a new version so that stored parameters always decode.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum VersionedProtocolParams {
+    V1 {
+        max_call_depth: u8,
+        heap_limit: u64,
+    },
+    V2 {
+        max_call_depth: u8,
+        heap_limit: u64,
+        storage_quota: u64,
+        storage_deposit_per_byte: Amount,
+    },
+    V3 {
+        max_call_depth: u8,
+        heap_limit: u64,
+        storage_quota: u64,
+        storage_deposit_per_byte: Amount,
+        max_reveal_size: u64,
+    },
+    V4(ProtocolParams),
+}
+
+impl From<VersionedProtocolParams> for ProtocolParams {
+    fn from(params: VersionedProtocolParams) -> Self {
+        match params {
+            VersionedProtocolParams::V1 {
+                max_call_depth,
+                heap_limit,
+            } => Self {
+                max_call_depth,
+                heap_limit,
+                ..Default::default()
+            },
+            VersionedProtocolParams::V2 {
+                max_call_depth,
+                heap_limit,
+                storage_quota,
+                storage_deposit_per_byte,
+            } => Self {
+                max_call_depth,
+                heap_limit,
+                storage_quota,
+                storage_deposit_per_byte,
+                ..Default::default()
+            },
+            VersionedProtocolParams::V3 {
+                max_call_depth,
+                heap_limit,
+                storage_quota,
+                storage_deposit_per_byte,
+                max_reveal_size,
+            } => Self {
+                max_call_depth,
+                heap_limit,
+                storage_quota,
+                storage_deposit_per_byte,
+                max_reveal_size,
+                ..Default::default()
+            },
+            VersionedProtocolParams::V4(params) => params,
+        }
+    }
+}
+
+impl From<ProtocolParams> for VersionedProtocolParams {
+    fn from(params: ProtocolParams) -> Self {
+        Self::V4(params)
+    }
+}
+
+impl ProtocolParams {
+    /// Loads the parameters from storage, or the default parameters if none are set
+    pub fn load(rt: &impl HostRuntime) -> Result<Self> {
+        Ok(
+            Storage::get::<VersionedProtocolParams>(rt, &PROTOCOL_PARAMS_PATH)?
+                .map(Self::from)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Maximum size of a revealed operation, capped at [`MAX_REVEAL_SIZE`]
+    pub fn max_reveal_size(&self) -> usize {
+        usize::try_from(self.max_reveal_size)
+            .map_or(MAX_REVEAL_SIZE, |size| size.min(MAX_REVEAL_SIZE))
+    }
+
+    /// Writes the parameters to storage
+    pub fn store(&self, rt: &mut impl HostRuntime) -> Result<()> {
+        let params = VersionedProtocolParams::from(self.clone());
+        Ok(Storage::insert(rt, &PROTOCOL_PARAMS_PATH, &params)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_core::{
+        kv::Storage,
+        reveal_data::{DEFAULT_MAX_REVEAL_SIZE, MAX_REVEAL_SIZE},
+        BinEncodable,
+    };
+    use tezos_smart_rollup::host::Runtime;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{ProtocolParams, VersionedProtocolParams};
+    use crate::storage::PROTOCOL_PARAMS_PATH;
+
+    #[test]
+    fn store_and_load_protocol_params() {
+        let mut host = MockHost::default();
+        assert_eq!(
+            ProtocolParams::load(&host).unwrap(),
+            ProtocolParams::default()
+        );
+
+        let params = ProtocolParams {
+            max_call_depth: 2,
+            heap_limit: 1024,
+            storage_quota: 4096,
+            storage_deposit_per_byte: 1,
+            max_reveal_size: 1024 * 1024,
+            max_library_size: 2048,
+        };
+        params.store(&mut host).unwrap();
+        assert_eq!(ProtocolParams::load(&host).unwrap(), params);
+    }
+
+    #[test]
+    fn load_previous_versions() {
+        let mut host = MockHost::default();
+        let v1 = VersionedProtocolParams::V1 {
+            max_call_depth: 2,
+            heap_limit: 1024,
+        };
+        Storage::insert(&mut host, &PROTOCOL_PARAMS_PATH, &v1).unwrap();
+        assert_eq!(
+            ProtocolParams::load(&host).unwrap(),
+            ProtocolParams {
+                max_call_depth: 2,
+                heap_limit: 1024,
+                ..Default::default()
+            }
+        );
+
+        let v2 = VersionedProtocolParams::V2 {
+            max_call_depth: 2,
+            heap_limit: 1024,
+            storage_quota: 4096,
+            storage_deposit_per_byte: 1,
+        };
+        Storage::insert(&mut host, &PROTOCOL_PARAMS_PATH, &v2).unwrap();
+        assert_eq!(
+            ProtocolParams::load(&host).unwrap(),
+            ProtocolParams {
+                max_call_depth: 2,
+                heap_limit: 1024,
+                storage_quota: 4096,
+                storage_deposit_per_byte: 1,
+                ..Default::default()
+            }
+        );
+
+        let v3 = VersionedProtocolParams::V3 {
+            max_call_depth: 2,
+            heap_limit: 1024,
+            storage_quota: 4096,
+            storage_deposit_per_byte: 1,
+            max_reveal_size: 1024 * 1024,
+        };
+        Storage::insert(&mut host, &PROTOCOL_PARAMS_PATH, &v3).unwrap();
+        assert_eq!(
+            ProtocolParams::load(&host).unwrap(),
+            ProtocolParams {
+                max_call_depth: 2,
+                heap_limit: 1024,
+                storage_quota: 4096,
+                storage_deposit_per_byte: 1,
+                max_reveal_size: 1024 * 1024,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn max_reveal_size_is_capped() {
+        let params = ProtocolParams {
+            max_reveal_size: u64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(params.max_reveal_size(), MAX_REVEAL_SIZE);
+        assert_eq!(
+            ProtocolParams::default().max_reveal_size(),
+            DEFAULT_MAX_REVEAL_SIZE
+        );
+    }
+
+    #[test]
+    fn load_invalid_protocol_params_fails() {
+        let mut host = MockHost::default();
+        host.store_write_all(&PROTOCOL_PARAMS_PATH, &[0xff; 3])
+            .unwrap();
+        assert!(ProtocolParams::load(&host).is_err());
+        // Unversioned parameters are rejected
+        let params = ProtocolParams::default().encode().unwrap();
+        host.store_write_all(&PROTOCOL_PARAMS_PATH, &params)
+            .unwrap();
+        assert!(ProtocolParams::load(&host).is_err());
+    }
+}
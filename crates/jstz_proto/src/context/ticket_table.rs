@@ -18,6 +18,8 @@ pub enum TicketTableError {
 }
 
 const TICKET_TABLE_PATH: RefPath = RefPath::assert_from(b"/ticket_table");
+// Number of tickets for which each owner has a non-zero balance
+const TICKET_COUNT_PATH: RefPath = RefPath::assert_from(b"/ticket_count");
 
 pub struct TicketTable;
 
@@ -32,6 +34,48 @@ impl TicketTable {
         )?)
     }
 
+    fn count_path(owner: &impl Addressable) -> Result<OwnedPath> {
+        let owner_path = OwnedPath::try_from(format!("/{}", owner.to_base58()))?;
+
+        Ok(path::concat(&TICKET_COUNT_PATH, &owner_path)?)
+    }
+
+    /// Returns true if `owner` has a non-zero balance of any ticket. Balances
+    /// credited before holdings were counted are not taken into account.
+    pub fn holds_tickets(
+        rt: &impl Runtime,
+        tx: &Transaction,
+        owner: &impl Addressable,
+    ) -> Result<bool> {
+        let count = tx.get::<u64>(rt, Self::count_path(owner)?)?;
+        Ok(count.is_some_and(|count| *count > 0))
+    }
+
+    fn update_count(
+        rt: &impl Runtime,
+        tx: &mut Transaction,
+        owner: &impl Addressable,
+        previous_balance: Amount,
+        new_balance: Amount,
+    ) -> Result<()> {
+        let path = Self::count_path(owner)?;
+        let count = tx
+            .get::<u64>(rt, path.clone())?
+            .map(|count| *count)
+            .unwrap_or(0);
+        let count = match (previous_balance, new_balance) {
+            (0, new) if new > 0 => count + 1,
+            (previous, 0) if previous > 0 => count.saturating_sub(1),
+            _ => return Ok(()),
+        };
+        if count == 0 {
+            tx.remove(path)?;
+        } else {
+            tx.insert(path, count)?;
+        }
+        Ok(())
+    }
+
     pub fn get_balance(
         rt: &mut impl Runtime,
         tx: &mut Transaction,
@@ -58,20 +102,23 @@ impl TicketTable {
         amount: Amount, // TODO: check if its the correct size
     ) -> Result<Amount> {
         let path = Self::path(ticket_hash, owner)?;
-        match tx.entry::<Amount>(rt, path)? {
+        let (previous_balance, new_balance) = match tx.entry::<Amount>(rt, path)? {
             Entry::Vacant(vacant_entry) => {
                 vacant_entry.insert(amount);
-                Ok(amount)
+                (0, amount)
             }
             Entry::Occupied(mut occupied) => {
                 let mut balance = occupied.get_mut();
+                let previous_balance = *balance;
                 let checked_balance = balance
                     .checked_add(amount)
                     .ok_or(crate::error::Error::BalanceOverflow)?;
                 *balance = checked_balance;
-                Ok(checked_balance)
+                (previous_balance, checked_balance)
             }
-        }
+        };
+        Self::update_count(rt, tx, owner, previous_balance, new_balance)?;
+        Ok(new_balance)
     }
 
     /// Subtracts the given `amount` from the ticket balance of `owner`
@@ -85,17 +132,20 @@ impl TicketTable {
         amount: u64,
     ) -> Result<Amount> {
         let path = Self::path(ticket_hash, owner)?;
-        match tx.entry::<Amount>(rt, path)? {
+        let (previous_balance, new_balance) = match tx.entry::<Amount>(rt, path)? {
             Entry::Vacant(_) => Err(TicketTableError::AccountNotFound)?,
             Entry::Occupied(mut occupied) => {
                 let mut balance = occupied.get_mut();
                 if *balance < amount {
                     return Err(TicketTableError::InsufficientFunds)?;
                 }
+                let previous_balance = *balance;
                 *balance -= amount;
-                Ok(*balance)
+                (previous_balance, *balance)
             }
-        }
+        };
+        Self::update_count(rt, tx, owner, previous_balance, new_balance)?;
+        Ok(new_balance)
     }
 }
 
@@ -265,4 +315,25 @@ mod test {
             TicketTable::get_balance(host.rt(), &mut tx, &owner, &ticket_hash).unwrap();
         assert_eq!(70, balance);
     }
+
+    #[test]
+    fn holds_tickets_counts_non_zero_balances() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+
+        let owner = smart_function_address();
+        let ticket_hash1 = jstz_mock::ticket_hash1();
+        let ticket_hash2 = jstz_mock::ticket_hash2();
+        assert!(!TicketTable::holds_tickets(host.rt(), &tx, &owner).unwrap());
+
+        TicketTable::add(host.rt(), &mut tx, &owner, &ticket_hash1, 10).unwrap();
+        TicketTable::add(host.rt(), &mut tx, &owner, &ticket_hash2, 10).unwrap();
+        TicketTable::sub(host.rt(), &mut tx, &owner, &ticket_hash1, 10).unwrap();
+        assert!(TicketTable::holds_tickets(host.rt(), &tx, &owner).unwrap());
+
+        TicketTable::sub(host.rt(), &mut tx, &owner, &ticket_hash2, 10).unwrap();
+        assert!(!TicketTable::holds_tickets(host.rt(), &tx, &owner).unwrap());
+        tx.commit(host.rt()).unwrap();
+    }
 }
@@ -4,7 +4,7 @@ use tezos_smart_rollup::michelson::ticket::TicketHashError;
 
 use crate::{
     context::ticket_table,
-    executor::{fa_deposit, fa_withdraw},
+    executor::{fa_deposit, fa_withdraw, l1_call},
 };
 
 #[derive(Display, Debug, Error, From)]
@@ -23,7 +23,10 @@ pub enum Error {
     InvalidAddress,
     InvalidScheme,
     RefererShouldNotBeSet,
-    GasLimitExceeded,
+    #[display(fmt = "GasLimitExceeded (gas used: {gas_used})")]
+    GasLimitExceeded {
+        gas_used: u64,
+    },
     UnsupportedPath,
     InvalidHost,
     InvalidHttpRequest,
@@ -41,6 +44,9 @@ pub enum Error {
     FaWithdrawError {
         source: fa_withdraw::FaWithdrawError,
     },
+    L1CallError {
+        source: l1_call::L1CallError,
+    },
     TicketHashError(TicketHashError),
     TicketAmountTooLarge,
     ZeroAmountNotAllowed,
@@ -50,6 +56,20 @@ pub enum Error {
     RevealNotSupported,
     InvalidInjector,
     InvalidOracleKey,
+    UnsupportedRuntimeVersion,
+    StorageQuotaExceeded,
+    AccountDeleted,
+    /// A smart function cannot be its own beneficiary
+    InvalidBeneficiary,
+    /// A smart function cannot be deleted while it holds tickets
+    TicketsHeld,
+    InvalidAdmin,
+    /// The native ticketer is missing from durable storage
+    TicketerNotSet,
+    /// The path is reserved for requests made by the protocol
+    ReservedPath,
+    /// The library code exceeds the `max_library_size` protocol parameter
+    LibraryTooLarge,
     #[cfg(feature = "v2_runtime")]
     V2Error(crate::runtime::v2::Error),
 }
@@ -77,8 +97,8 @@ impl From<Error> for JsError {
             Error::RefererShouldNotBeSet => JsNativeError::eval()
                 .with_message("RefererShouldNotBeSet")
                 .into(),
-            Error::GasLimitExceeded => JsNativeError::eval()
-                .with_message("GasLimitExceeded")
+            Error::GasLimitExceeded { gas_used } => JsNativeError::eval()
+                .with_message(format!("GasLimitExceeded (gas used: {gas_used})"))
                 .into(),
             Error::InvalidHttpRequest => JsNativeError::eval()
                 .with_message("InvalidHttpRequest")
@@ -104,6 +124,9 @@ impl From<Error> for JsError {
             Error::FaWithdrawError { source } => JsNativeError::eval()
                 .with_message(format!("FaWithdrawError: {source}"))
                 .into(),
+            Error::L1CallError { source } => JsNativeError::eval()
+                .with_message(format!("L1CallError: {source}"))
+                .into(),
             Error::TicketHashError(inner) => JsNativeError::eval()
                 .with_message(format!("{inner}"))
                 .into(),
@@ -144,6 +167,33 @@ impl From<Error> for JsError {
             Error::InvalidOracleKey => JsNativeError::eval()
                 .with_message("InvalidOracleKey")
                 .into(),
+            Error::UnsupportedRuntimeVersion => JsNativeError::eval()
+                .with_message("UnsupportedRuntimeVersion")
+                .into(),
+            Error::StorageQuotaExceeded => JsNativeError::eval()
+                .with_message("StorageQuotaExceeded")
+                .into(),
+            Error::AccountDeleted => {
+                JsNativeError::eval().with_message("AccountDeleted").into()
+            }
+            Error::InvalidBeneficiary => JsNativeError::eval()
+                .with_message("InvalidBeneficiary")
+                .into(),
+            Error::TicketsHeld => {
+                JsNativeError::eval().with_message("TicketsHeld").into()
+            }
+            Error::InvalidAdmin => {
+                JsNativeError::eval().with_message("InvalidAdmin").into()
+            }
+            Error::TicketerNotSet => {
+                JsNativeError::eval().with_message("TicketerNotSet").into()
+            }
+            Error::ReservedPath => {
+                JsNativeError::eval().with_message("ReservedPath").into()
+            }
+            Error::LibraryTooLarge => {
+                JsNativeError::eval().with_message("LibraryTooLarge").into()
+            }
             #[cfg(feature = "v2_runtime")]
             Error::V2Error(_) => {
                 unimplemented!("V2 runtime errors are not supported in boa")
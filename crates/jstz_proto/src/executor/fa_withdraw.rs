@@ -2,7 +2,10 @@ use bincode::{Decode, Encode};
 use derive_more::{Display, Error, From};
 use jstz_core::{
     host::HostRuntime,
-    kv::{outbox::OutboxMessage, Transaction},
+    kv::{
+        outbox::{OutboxMessage, OutboxMessageId},
+        Transaction,
+    },
 };
 use jstz_crypto::smart_function_hash::Kt1Hash;
 use serde::{Deserialize, Serialize};
@@ -88,6 +91,9 @@ pub struct FaWithdrawReceipt {
     pub source: Address,
     #[serde(flatten)]
     pub withdrawal: FaWithdraw,
+    /// Location of the withdrawal message in the rollup outbox. `None` if the
+    /// message was deferred to a later level because the outbox was full.
+    pub outbox_message_id: Option<OutboxMessageId>,
 }
 
 impl FaWithdrawReceipt {
@@ -127,13 +133,10 @@ fn withdraw_from_ticket_owner(
     routing_info: &RoutingInfo,
     amount: Amount,
     ticket: Ticket,
-) -> Result<()> {
+) -> Result<Option<OutboxMessageId>> {
     TicketTable::sub(rt, tx, ticket_owner, &ticket.hash, amount)?;
     let message = create_fa_withdrawal_message(routing_info, ticket.value)?;
-    tx.queue_outbox_message(rt, message)?;
-    // TODO: https://linear.app/tezos/issue/JSTZ-113/implement-outbox-message-id
-    // Implement outbox message id
-    Ok(())
+    tx.queue_outbox_message(rt, message)
 }
 
 impl FaWithdraw {
@@ -160,10 +163,12 @@ impl FaWithdraw {
             ticket_info,
         } = &self;
         let ticket = ticket_info.to_ticket(*amount)?;
-        withdraw_from_ticket_owner(rt, tx, source, routing_info, *amount, ticket)?;
+        let outbox_message_id =
+            withdraw_from_ticket_owner(rt, tx, source, routing_info, *amount, ticket)?;
         Ok(FaWithdrawReceipt {
             source: source.clone().into(),
             withdrawal: self,
+            outbox_message_id,
         })
     }
 
@@ -251,7 +256,8 @@ mod test {
         assert_eq!(
             FaWithdrawReceipt {
                 source,
-                withdrawal: fa_withdrawal
+                withdrawal: fa_withdrawal,
+                outbox_message_id: None
             },
             fa_withdrawal_receipt_content,
         );
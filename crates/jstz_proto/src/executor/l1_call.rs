@@ -0,0 +1,619 @@
+#![cfg_attr(feature = "v2_runtime", allow(unused))]
+use bincode::{Decode, Encode};
+use derive_more::{Display, Error, From};
+use jstz_core::{
+    host::HostRuntime,
+    kv::{
+        outbox::{OutboxMessage, OutboxMessageId},
+        Storage, Transaction,
+    },
+    micheline::Micheline,
+};
+use jstz_crypto::smart_function_hash::{Kt1Hash, SmartFunctionHash};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use tezos_smart_rollup::{storage::path::RefPath, types::Contract};
+use utoipa::ToSchema;
+
+use crate::{
+    context::account::{Address, Addressable},
+    Error, HttpBody, Result,
+};
+
+/// Smart functions allowed to call L1 contracts, see [L1Caller]. Set by the
+/// rollup operator through the `l1_callers` of the jstzd genesis.
+pub const L1_CALLERS_PATH: &str = "/l1_callers";
+const L1_CALLERS: RefPath = RefPath::assert_from(L1_CALLERS_PATH.as_bytes());
+
+/// A smart function allowed to call L1 contracts, and the entrypoints it may call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct L1Caller {
+    pub address: SmartFunctionHash,
+    pub destinations: Vec<L1Destination>,
+}
+
+/// An entrypoint of an L1 contract, with the type of its parameter. Calls are
+/// type checked against `parameter_type`, so it must match the type of the
+/// entrypoint on L1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct L1Destination {
+    pub address: Kt1Hash,
+    pub entrypoint: String,
+    pub parameter_type: ParameterType,
+}
+
+/// Michelson type of the parameter of an L1 entrypoint, in the Micheline JSON
+/// format, e.g. `{ "prim": "pair", "args": [{ "prim": "nat" }, { "prim": "string" }] }`.
+/// Tickets cannot be represented, so that the rollup never passes the tickets
+/// it holds for its users to L1 contracts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+#[serde(try_from = "JsonValue", into = "JsonValue")]
+pub enum ParameterType {
+    Unit,
+    Bool,
+    Int,
+    Nat,
+    Mutez,
+    Timestamp,
+    String,
+    Bytes,
+    Address,
+    KeyHash,
+    Key,
+    Signature,
+    ChainId,
+    Contract(Box<ParameterType>),
+    Option(Box<ParameterType>),
+    List(Box<ParameterType>),
+    Set(Box<ParameterType>),
+    Map(Box<ParameterType>, Box<ParameterType>),
+    Pair(Box<ParameterType>, Box<ParameterType>),
+    Or(Box<ParameterType>, Box<ParameterType>),
+}
+
+impl TryFrom<JsonValue> for ParameterType {
+    type Error = String;
+
+    fn try_from(value: JsonValue) -> std::result::Result<Self, Self::Error> {
+        let prim = value
+            .get("prim")
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| format!("invalid Michelson type: {value}"))?;
+        let args = match value.get("args") {
+            Some(JsonValue::Array(args)) => args
+                .iter()
+                .cloned()
+                .map(ParameterType::try_from)
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+            _ => vec![],
+        };
+        let arity_error = || format!("invalid number of arguments for type {prim}");
+        let ty = match (prim, args.as_slice()) {
+            ("unit", []) => Self::Unit,
+            ("bool", []) => Self::Bool,
+            ("int", []) => Self::Int,
+            ("nat", []) => Self::Nat,
+            ("mutez", []) => Self::Mutez,
+            ("timestamp", []) => Self::Timestamp,
+            ("string", []) => Self::String,
+            ("bytes", []) => Self::Bytes,
+            ("address", []) => Self::Address,
+            ("key_hash", []) => Self::KeyHash,
+            ("key", []) => Self::Key,
+            ("signature", []) => Self::Signature,
+            ("chain_id", []) => Self::ChainId,
+            ("contract", [ty]) => Self::Contract(Box::new(ty.clone())),
+            ("option", [ty]) => Self::Option(Box::new(ty.clone())),
+            ("list", [ty]) => Self::List(Box::new(ty.clone())),
+            ("set", [ty]) => Self::Set(Box::new(ty.clone())),
+            ("map", [key, value]) => {
+                Self::Map(Box::new(key.clone()), Box::new(value.clone()))
+            }
+            // Right combs of pairs are equivalent to n-ary pairs
+            ("pair", [_, _, ..]) => args
+                .iter()
+                .cloned()
+                .rev()
+                .reduce(|right, left| Self::Pair(Box::new(left), Box::new(right)))
+                .ok_or_else(arity_error)?,
+            ("or", [left, right]) => {
+                Self::Or(Box::new(left.clone()), Box::new(right.clone()))
+            }
+            (
+                "unit" | "bool" | "int" | "nat" | "mutez" | "timestamp" | "string"
+                | "bytes" | "address" | "key_hash" | "key" | "signature" | "chain_id"
+                | "contract" | "option" | "list" | "set" | "map" | "pair" | "or",
+                _,
+            ) => return Err(arity_error()),
+            _ => return Err(format!("unsupported Michelson type: {prim}")),
+        };
+        Ok(ty)
+    }
+}
+
+impl From<ParameterType> for JsonValue {
+    fn from(ty: ParameterType) -> Self {
+        let prim = |prim: &str, args: Vec<ParameterType>| {
+            if args.is_empty() {
+                json!({ "prim": prim })
+            } else {
+                let args: Vec<JsonValue> = args.into_iter().map(Into::into).collect();
+                json!({ "prim": prim, "args": args })
+            }
+        };
+        match ty {
+            ParameterType::Unit => prim("unit", vec![]),
+            ParameterType::Bool => prim("bool", vec![]),
+            ParameterType::Int => prim("int", vec![]),
+            ParameterType::Nat => prim("nat", vec![]),
+            ParameterType::Mutez => prim("mutez", vec![]),
+            ParameterType::Timestamp => prim("timestamp", vec![]),
+            ParameterType::String => prim("string", vec![]),
+            ParameterType::Bytes => prim("bytes", vec![]),
+            ParameterType::Address => prim("address", vec![]),
+            ParameterType::KeyHash => prim("key_hash", vec![]),
+            ParameterType::Key => prim("key", vec![]),
+            ParameterType::Signature => prim("signature", vec![]),
+            ParameterType::ChainId => prim("chain_id", vec![]),
+            ParameterType::Contract(ty) => prim("contract", vec![*ty]),
+            ParameterType::Option(ty) => prim("option", vec![*ty]),
+            ParameterType::List(ty) => prim("list", vec![*ty]),
+            ParameterType::Set(ty) => prim("set", vec![*ty]),
+            ParameterType::Map(key, value) => prim("map", vec![*key, *value]),
+            ParameterType::Pair(left, right) => prim("pair", vec![*left, *right]),
+            ParameterType::Or(left, right) => prim("or", vec![*left, *right]),
+        }
+    }
+}
+
+impl ParameterType {
+    /// Returns true if `value`, in the Micheline JSON format, is a value of this type
+    pub fn typecheck(&self, value: &JsonValue) -> bool {
+        let is_int = |value: &JsonValue| value.get("int").is_some();
+        let is_nat = |value: &JsonValue| matches!(value.get("int"), Some(JsonValue::String(n)) if !n.starts_with('-'));
+        let is_string = |value: &JsonValue| value.get("string").is_some();
+        let is_bytes = |value: &JsonValue| value.get("bytes").is_some();
+        let prim = value.get("prim").and_then(JsonValue::as_str);
+        let args = match value.get("args") {
+            Some(JsonValue::Array(args)) => args.as_slice(),
+            _ => &[],
+        };
+        match self {
+            Self::Unit => prim == Some("Unit"),
+            Self::Bool => matches!(prim, Some("True" | "False")),
+            Self::Int => is_int(value),
+            Self::Nat | Self::Mutez => is_nat(value),
+            Self::Timestamp => is_int(value) || is_string(value),
+            Self::String => is_string(value),
+            Self::Bytes => is_bytes(value),
+            Self::Address
+            | Self::KeyHash
+            | Self::Key
+            | Self::Signature
+            | Self::ChainId
+            | Self::Contract(_) => is_string(value) || is_bytes(value),
+            Self::Option(ty) => match (prim, args) {
+                (Some("None"), []) => true,
+                (Some("Some"), [value]) => ty.typecheck(value),
+                _ => false,
+            },
+            Self::List(ty) | Self::Set(ty) => match value {
+                JsonValue::Array(values) => values.iter().all(|v| ty.typecheck(v)),
+                _ => false,
+            },
+            Self::Map(key_ty, value_ty) => match value {
+                JsonValue::Array(entries) => entries.iter().all(|entry| {
+                    match (entry.get("prim"), entry.get("args")) {
+                        (Some(prim), Some(JsonValue::Array(kv))) if prim == "Elt" => {
+                            matches!(kv.as_slice(), [k, v] if key_ty.typecheck(k) && value_ty.typecheck(v))
+                        }
+                        _ => false,
+                    }
+                }),
+                _ => false,
+            },
+            Self::Pair(left_ty, right_ty) => match (prim, args) {
+                (Some("Pair"), [left, right]) => {
+                    left_ty.typecheck(left) && right_ty.typecheck(right)
+                }
+                // Right combs of pairs are equivalent to n-ary pairs
+                (Some("Pair"), [left, rest @ ..]) if rest.len() > 1 => {
+                    left_ty.typecheck(left)
+                        && right_ty.typecheck(&json!({ "prim": "Pair", "args": rest }))
+                }
+                _ => false,
+            },
+            Self::Or(left_ty, right_ty) => match (prim, args) {
+                (Some("Left"), [value]) => left_ty.typecheck(value),
+                (Some("Right"), [value]) => right_ty.typecheck(value),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// A call to the entrypoint of an L1 contract. `parameters` are given in the
+/// Micheline JSON format, e.g. `{ "prim": "Pair", "args": [{ "int": "1" }, { "string": "foo" }] }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct L1Call {
+    pub destination: Kt1Hash,
+    pub entrypoint: String,
+    #[schema(value_type = Object)]
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct L1CallReceipt {
+    pub source: Address,
+    #[serde(flatten)]
+    pub call: L1Call,
+    /// Location of the call message in the rollup outbox. `None` if the
+    /// message was deferred to a later level because the outbox was full.
+    pub outbox_message_id: Option<OutboxMessageId>,
+}
+
+impl L1CallReceipt {
+    pub fn to_http_body(&self) -> HttpBody {
+        HttpBody::from_json(json!(&self))
+    }
+}
+
+#[derive(Display, Debug, Error, From)]
+pub enum L1CallError {
+    UnauthorisedCaller,
+    UnauthorisedDestination,
+    /// Tickets held by the rollup cannot be passed to L1 contracts
+    TicketParameters,
+    /// The parameters do not have the type of the destination entrypoint
+    IllTypedParameters,
+}
+
+/// Returns the L1 callers configured by the rollup operator
+pub fn l1_callers(rt: &impl HostRuntime) -> Result<Vec<L1Caller>> {
+    Ok(Storage::get(rt, &L1_CALLERS)?.unwrap_or_default())
+}
+
+/// Returns the entrypoints `address` is allowed to call, or `None` if it is not
+/// allowed to call L1 contracts
+pub fn authorised_destinations(
+    rt: &impl HostRuntime,
+    address: &Address,
+) -> Result<Option<Vec<L1Destination>>> {
+    let Address::SmartFunction(address) = address else {
+        return Ok(None);
+    };
+    Ok(l1_callers(rt)?
+        .into_iter()
+        .find(|caller| &caller.address == address)
+        .map(|caller| caller.destinations))
+}
+
+/// Returns true if `value` contains a ticket literal. Ticket literals are
+/// rejected before type checking since they can be given for any ticket type.
+fn contains_ticket(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Array(nodes) => nodes.iter().any(contains_ticket),
+        JsonValue::Object(node) => {
+            node.get("prim") == Some(&json!("Ticket"))
+                || matches!(node.get("args"), Some(JsonValue::Array(args)) if args.iter().any(contains_ticket))
+        }
+        _ => false,
+    }
+}
+
+impl L1Call {
+    /// Queue the [L1Call] in the outbox on behalf of `source`.
+    ///
+    /// Fails if:
+    /// * `source` is not an authorised caller of the entrypoint. See [L1_CALLERS_PATH]
+    /// * `parameters` contain a ticket
+    /// * `parameters` do not have the type of the entrypoint
+    /// * `parameters` is not valid Micheline or `entrypoint` is invalid
+    /// * Outbox queue is full
+    pub fn execute(
+        self,
+        rt: &mut impl HostRuntime,
+        tx: &mut Transaction,
+        source: &impl Addressable,
+    ) -> Result<L1CallReceipt> {
+        let source: Address = source.clone().into();
+        let destination = authorised_destinations(rt, &source)?
+            .ok_or(L1CallError::UnauthorisedCaller)?
+            .into_iter()
+            .find(|destination| {
+                destination.address == self.destination
+                    && destination.entrypoint == self.entrypoint
+            })
+            .ok_or(L1CallError::UnauthorisedDestination)?;
+        if contains_ticket(&self.parameters) {
+            return Err(L1CallError::TicketParameters.into());
+        }
+        if !destination.parameter_type.typecheck(&self.parameters) {
+            return Err(L1CallError::IllTypedParameters.into());
+        }
+        let parameters =
+            Micheline::try_from(&self.parameters).map_err(jstz_core::Error::from)?;
+        let message = OutboxMessage::new_contract_call(
+            &Contract::Originated(self.destination.clone().into()),
+            &self.entrypoint,
+            parameters,
+        )?;
+        let outbox_message_id = tx.queue_outbox_message(rt, message)?;
+        Ok(L1CallReceipt {
+            source,
+            call: self,
+            outbox_message_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_core::{
+        kv::{outbox::OutboxMessage, Storage, Transaction},
+        micheline::Micheline,
+    };
+    use jstz_crypto::smart_function_hash::SmartFunctionHash;
+    use serde_json::{json, Value as JsonValue};
+    use tezos_data_encoding::nom::NomReader;
+    use tezos_smart_rollup::{outbox::OutboxMessageFull, types::Contract};
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{
+        L1Call, L1CallError, L1Caller, L1Destination, ParameterType, L1_CALLERS,
+    };
+    use crate::{context::account::Address, Error};
+
+    fn l1_call() -> L1Call {
+        L1Call {
+            destination: jstz_mock::kt1_account1().into(),
+            entrypoint: "default".to_string(),
+            parameters: json!({ "prim": "Pair", "args": [{ "int": "1" }, { "string": "foo" }] }),
+        }
+    }
+
+    fn source() -> SmartFunctionHash {
+        SmartFunctionHash::from_base58("KT1RycYvM4EVs6BAXWEsGXaAaRqiMP53KT4w").unwrap()
+    }
+
+    fn authorise_source_with_type(host: &mut MockHost, parameter_type: JsonValue) {
+        let callers = vec![L1Caller {
+            address: source(),
+            destinations: vec![L1Destination {
+                address: jstz_mock::kt1_account1().into(),
+                entrypoint: "default".to_string(),
+                parameter_type: serde_json::from_value(parameter_type).unwrap(),
+            }],
+        }];
+        Storage::insert(host, &L1_CALLERS, &callers).unwrap();
+    }
+
+    fn authorise_source(host: &mut MockHost) {
+        authorise_source_with_type(
+            host,
+            json!({ "prim": "pair", "args": [{ "prim": "int" }, { "prim": "string" }] }),
+        );
+    }
+
+    fn ticket_shaped_type() -> JsonValue {
+        json!({ "prim": "pair", "args": [
+            { "prim": "address" },
+            { "prim": "pair", "args": [{ "prim": "nat" }, { "prim": "option", "args": [{ "prim": "bytes" }] }] },
+            { "prim": "nat" }
+        ] })
+    }
+
+    #[test]
+    fn execute_fails_on_unauthorised_caller() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let result = l1_call().execute(&mut host, &mut tx, &source());
+        assert!(matches!(
+            result,
+            Err(Error::L1CallError {
+                source: L1CallError::UnauthorisedCaller
+            })
+        ));
+
+        let user = Address::User(jstz_mock::account1());
+        let result = l1_call().execute(&mut host, &mut tx, &user);
+        assert!(matches!(
+            result,
+            Err(Error::L1CallError {
+                source: L1CallError::UnauthorisedCaller
+            })
+        ));
+    }
+
+    #[test]
+    fn execute_fails_on_unauthorised_destination() {
+        let mut host = MockHost::default();
+        authorise_source(&mut host);
+        let mut tx = Transaction::default();
+        tx.begin();
+        let call = L1Call {
+            destination: SmartFunctionHash::from_base58(
+                "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV",
+            )
+            .unwrap()
+            .0
+            .into(),
+            ..l1_call()
+        };
+        let result = call.execute(&mut host, &mut tx, &source());
+        assert!(matches!(
+            result,
+            Err(Error::L1CallError {
+                source: L1CallError::UnauthorisedDestination
+            })
+        ));
+
+        let call = L1Call {
+            entrypoint: "other".to_string(),
+            ..l1_call()
+        };
+        let result = call.execute(&mut host, &mut tx, &source());
+        assert!(matches!(
+            result,
+            Err(Error::L1CallError {
+                source: L1CallError::UnauthorisedDestination
+            })
+        ));
+    }
+
+    #[test]
+    fn execute_fails_on_ticket_parameters() {
+        let mut host = MockHost::default();
+        authorise_source_with_type(&mut host, ticket_shaped_type());
+        let mut tx = Transaction::default();
+        tx.begin();
+        let ticket = json!({ "prim": "Ticket", "args": [
+            { "string": "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV" },
+            { "prim": "pair", "args": [{ "prim": "nat" }, { "prim": "option", "args": [{ "prim": "bytes" }] }] },
+            { "prim": "Pair", "args": [{ "int": "0" }, { "prim": "None" }] },
+            { "int": "10" }
+        ] });
+        for parameters in [ticket.clone(), json!({ "prim": "Some", "args": [ticket] })] {
+            let call = L1Call {
+                parameters,
+                ..l1_call()
+            };
+            let result = call.execute(&mut host, &mut tx, &source());
+            assert!(matches!(
+                result,
+                Err(Error::L1CallError {
+                    source: L1CallError::TicketParameters
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn execute_fails_on_ill_typed_parameters() {
+        let mut host = MockHost::default();
+        authorise_source(&mut host);
+        let mut tx = Transaction::default();
+        tx.begin();
+        let contents =
+            json!({ "prim": "Pair", "args": [{ "int": "0" }, { "prim": "None" }] });
+        for parameters in [
+            json!({ "prim": "Pair", "args": [{ "string": "foo" }, { "int": "1" }] }),
+            json!({ "prim": "Pair", "args": [{ "int": "1" }, { "string": "foo" }, { "int": "2" }] }),
+            // Values with the shape of a ticket are rejected unless the entrypoint
+            // declares a type they belong to
+            json!({ "prim": "Pair", "args": [
+                { "bytes": "01ecf2a1f0d1fe2b3f5ecc1b7e7c9f2b3b7a0c3e5d00" },
+                contents,
+                { "int": "10" }
+            ] }),
+        ] {
+            let call = L1Call {
+                parameters,
+                ..l1_call()
+            };
+            let result = call.execute(&mut host, &mut tx, &source());
+            assert!(matches!(
+                result,
+                Err(Error::L1CallError {
+                    source: L1CallError::IllTypedParameters
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn execute_accepts_ticket_shaped_parameters_of_declared_type() {
+        let mut host = MockHost::default();
+        authorise_source_with_type(&mut host, ticket_shaped_type());
+        let mut tx = Transaction::default();
+        tx.begin();
+        let contents =
+            json!({ "prim": "Pair", "args": [{ "int": "0" }, { "prim": "None" }] });
+        let ticketer = json!({ "string": "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV" });
+        for parameters in [
+            json!({ "prim": "Pair", "args": [ticketer, contents, { "int": "10" }] }),
+            json!({ "prim": "Pair", "args": [ticketer, { "prim": "Pair", "args": [contents, { "int": "10" }] }] }),
+        ] {
+            let call = L1Call {
+                parameters,
+                ..l1_call()
+            };
+            assert!(call.execute(&mut host, &mut tx, &source()).is_ok());
+        }
+    }
+
+    #[test]
+    fn parameter_type_from_json() {
+        let ty: ParameterType = serde_json::from_value(ticket_shaped_type()).unwrap();
+        assert_eq!(
+            ty,
+            ParameterType::Pair(
+                Box::new(ParameterType::Address),
+                Box::new(ParameterType::Pair(
+                    Box::new(ParameterType::Pair(
+                        Box::new(ParameterType::Nat),
+                        Box::new(ParameterType::Option(Box::new(ParameterType::Bytes)))
+                    )),
+                    Box::new(ParameterType::Nat)
+                ))
+            )
+        );
+        assert_eq!(
+            serde_json::from_value::<ParameterType>(serde_json::to_value(&ty).unwrap())
+                .unwrap(),
+            ty
+        );
+
+        for invalid in [
+            json!({ "prim": "ticket", "args": [{ "prim": "nat" }] }),
+            json!({ "prim": "option", "args": [{ "prim": "ticket", "args": [{ "prim": "nat" }] }] }),
+            json!({ "prim": "option" }),
+            json!({ "int": "1" }),
+        ] {
+            assert!(serde_json::from_value::<ParameterType>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn execute_fails_on_invalid_parameters() {
+        let mut host = MockHost::default();
+        authorise_source(&mut host);
+        let mut tx = Transaction::default();
+        tx.begin();
+        let call = L1Call {
+            parameters: json!({ "prim": "Foo" }),
+            ..l1_call()
+        };
+        let result = call.execute(&mut host, &mut tx, &source());
+        assert!(matches!(result, Err(Error::CoreError { .. })));
+    }
+
+    #[test]
+    fn execute_queues_contract_call() {
+        let mut host = MockHost::default();
+        authorise_source(&mut host);
+        let mut tx = Transaction::default();
+        tx.begin();
+        let receipt = l1_call().execute(&mut host, &mut tx, &source()).unwrap();
+        assert_eq!(receipt.source, Address::SmartFunction(source()));
+        assert_eq!(receipt.call, l1_call());
+        tx.commit(&mut host).unwrap();
+
+        let level = host.run_level(|_| {});
+        let outbox = host.outbox_at(level);
+        assert_eq!(1, outbox.len());
+
+        let (_, message) =
+            OutboxMessageFull::<OutboxMessage>::nom_read(&outbox[0]).unwrap();
+        let expected = OutboxMessage::new_contract_call(
+            &Contract::Originated(jstz_mock::kt1_account1()),
+            "default",
+            Micheline::try_from(&l1_call().parameters).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(message, expected.into());
+    }
+}
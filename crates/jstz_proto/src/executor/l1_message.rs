@@ -0,0 +1,219 @@
+use http::{header::CONTENT_TYPE, HeaderMap, Method, Uri};
+use jstz_core::{host::HostRuntime, kv::Transaction};
+use jstz_crypto::smart_function_hash::{Kt1Hash, SmartFunctionHash};
+
+use crate::{
+    executor::smart_function,
+    operation::{internal::L1Message, RunFunction},
+    receipt::{Receipt, ReceiptContent},
+    Error, Result,
+};
+
+/// Gas available to the receiver of an L1 message. The sender does not pay for
+/// the call, so it is bounded like the default gas limit of user calls
+const L1_MESSAGE_GAS_LIMIT: usize = 550_000;
+
+/// Path of the requests delivering L1 messages. Only the protocol can make
+/// requests to it, so that the `Referer` of these requests is always an L1
+/// contract. See [is_reserved_path]
+pub const L1_MESSAGE_URI: &str = "/-/l1-message";
+
+/// Returns true if requests to `path` can only be made by the protocol, and not
+/// by operations or smart functions
+pub fn is_reserved_path(path: &str) -> bool {
+    path == L1_MESSAGE_URI
+}
+
+fn new_run_function(message: &L1Message) -> Result<RunFunction> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        "application/json; charset=utf-8"
+            .parse()
+            .map_err(|_| Error::InvalidHeaderValue)?,
+    );
+    Ok(RunFunction {
+        uri: Uri::builder()
+            .scheme("jstz")
+            .authority(message.receiver.to_string())
+            .path_and_query(L1_MESSAGE_URI)
+            .build()
+            .map_err(|_| Error::InvalidUri)?,
+        method: Method::POST,
+        headers,
+        body: message.to_http_body(),
+        gas_limit: L1_MESSAGE_GAS_LIMIT,
+    })
+}
+
+/// Delivers the [L1Message] to its receiver as a `POST` request to
+/// [L1_MESSAGE_URI]. The request is made on behalf of the L1 contract that
+/// sent the message, which is therefore exposed in the `Referer` header.
+pub async fn execute(
+    rt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    message: L1Message,
+) -> Receipt {
+    let operation_hash = message.hash();
+    let sender = SmartFunctionHash(Kt1Hash(message.sender.clone()));
+    let result = match new_run_function(&message) {
+        Ok(run) => {
+            smart_function::run::execute(rt, tx, &sender, run, operation_hash.clone())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    Receipt::new(operation_hash, result.map(ReceiptContent::RunFunction))
+}
+
+#[cfg(test)]
+mod test {
+    use http::{HeaderMap, Method};
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{hash::Blake2b, smart_function_hash::SmartFunctionHash};
+    use serde_json::json;
+    use tezos_crypto_rs::hash::ContractKt1Hash;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use crate::{
+        context::account::Address,
+        executor::smart_function,
+        operation::{
+            internal::{InboxId, L1Message},
+            RunFunction,
+        },
+        receipt::{ReceiptContent, ReceiptResult},
+        HttpBody,
+    };
+
+    fn l1_message(receiver: SmartFunctionHash) -> L1Message {
+        L1Message {
+            inbox_id: InboxId {
+                l1_level: 1,
+                l1_message_id: 34,
+            },
+            sender: ContractKt1Hash::from_base58_check(jstz_mock::host::MOCK_SENDER)
+                .unwrap(),
+            source: jstz_mock::account1(),
+            receiver,
+            payload: json!({ "prim": "Pair", "args": [{ "int": "1" }, { "string": "foo" }] }),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_delivers_message_to_smart_function() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        let code = r#"
+        export default async (request) => {
+            const url = new URL(request.url);
+            if (request.method !== "POST" || url.pathname !== "/-/l1-message") {
+                return Response.error();
+            }
+            const payload = await request.json();
+            return new Response(`${request.headers.get("Referer")} ${payload.args[1].string}`);
+        }
+        "#;
+        tx.begin();
+        let receiver = smart_function::deploy(
+            &mut host,
+            &mut tx,
+            &Address::User(jstz_mock::account1()),
+            code.to_string(),
+            0,
+        )
+        .unwrap();
+        tx.commit(&mut host).unwrap();
+
+        tx.begin();
+        let message = l1_message(receiver);
+        let expected_hash = message.hash();
+        let receipt = super::execute(&mut host, &mut tx, message).await;
+        assert_eq!(expected_hash, *receipt.hash());
+        match receipt.result {
+            ReceiptResult::Success(ReceiptContent::RunFunction(run)) => {
+                assert!(run.status_code.is_success());
+                assert_eq!(
+                    String::from_utf8(run.body.0.unwrap()).unwrap(),
+                    format!("{} foo", jstz_mock::host::MOCK_SENDER)
+                );
+            }
+            _ => panic!("Expected success"),
+        }
+    }
+
+    #[tokio::test]
+    async fn smart_functions_cannot_send_l1_messages() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        let user = Address::User(jstz_mock::account1());
+        tx.begin();
+        let receiver = smart_function::deploy(
+            &mut host,
+            &mut tx,
+            &user,
+            "export default () => new Response();".to_string(),
+            0,
+        )
+        .unwrap();
+        let relay = smart_function::deploy(
+            &mut host,
+            &mut tx,
+            &user,
+            format!(
+                r#"
+                export default async () => {{
+                    try {{
+                        const response = await fetch("jstz://{receiver}/-/l1-message", {{
+                            method: "POST",
+                            body: JSON.stringify({{ int: "1" }}),
+                        }});
+                        return new Response(String(response.status));
+                    }} catch (e) {{
+                        return new Response("rejected");
+                    }}
+                }}
+                "#
+            ),
+            0,
+        )
+        .unwrap();
+        tx.commit(&mut host).unwrap();
+
+        tx.begin();
+        let run = RunFunction {
+            uri: format!("jstz://{relay}/").parse().unwrap(),
+            method: Method::GET,
+            headers: HeaderMap::new(),
+            body: HttpBody::empty(),
+            gas_limit: 100_000,
+        };
+        let receipt = smart_function::run::execute(
+            &mut host,
+            &mut tx,
+            &user,
+            run,
+            Blake2b::from(b"op_hash".as_ref()),
+        )
+        .await
+        .unwrap();
+        let body = String::from_utf8(receipt.body.0.unwrap()).unwrap();
+        assert!(body == "403" || body == "rejected", "{body}");
+    }
+
+    #[tokio::test]
+    async fn execute_fails_on_missing_smart_function() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let receiver =
+            SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton")
+                .unwrap();
+        let receipt = super::execute(&mut host, &mut tx, l1_message(receiver)).await;
+        assert!(!matches!(
+            receipt.result,
+            ReceiptResult::Success(ReceiptContent::RunFunction(ref run))
+                if run.status_code.is_success()
+        ));
+    }
+}
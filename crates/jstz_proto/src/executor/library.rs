@@ -0,0 +1,179 @@
+use jstz_core::{host::HostRuntime, kv::Transaction};
+use tezos_smart_rollup::prelude::debug_msg;
+
+use crate::{
+    context::{
+        account::{Account, Addressable},
+        protocol_params::ProtocolParams,
+    },
+    error::{Error, Result},
+    operation::DeployLibrary,
+    receipt::DeployLibraryReceipt,
+    runtime::v2::library::{Library, LibraryHash},
+};
+
+/// Deploys a library on behalf of `source`.
+///
+/// Fails if the code is larger than the `max_library_size` protocol parameter.
+/// Libraries cannot be deleted, so the first deployment of some code takes a
+/// deposit of `storage_deposit_per_byte` for every byte of the code from `source`,
+/// which is not refunded.
+pub fn execute(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &impl Addressable,
+    deployment: DeployLibrary,
+) -> Result<DeployLibraryReceipt> {
+    let DeployLibrary { library_code } = deployment;
+    let params = ProtocolParams::load(hrt)?;
+    if library_code.len() as u64 > params.max_library_size {
+        return Err(Error::LibraryTooLarge);
+    }
+
+    tx.begin();
+    match deploy(hrt, tx, source, library_code, &params) {
+        Ok(hash) => {
+            tx.commit(hrt)?;
+            debug_msg!(hrt, "[📚] Library deployed: {}\n", hash);
+            Ok(DeployLibraryReceipt { hash })
+        }
+        Err(err) => {
+            tx.rollback()?;
+            debug_msg!(hrt, "[📚] Library deployment failed. \n");
+            Err(err)
+        }
+    }
+}
+
+fn deploy(
+    hrt: &impl HostRuntime,
+    tx: &mut Transaction,
+    source: &impl Addressable,
+    library_code: String,
+    params: &ProtocolParams,
+) -> Result<LibraryHash> {
+    if !Library::is_deployed(hrt, tx, &LibraryHash::from(library_code.as_bytes()))? {
+        let deposit = (library_code.len() as u64)
+            .checked_mul(params.storage_deposit_per_byte)
+            .ok_or(Error::BalanceOverflow)?;
+        if deposit > 0 {
+            Account::sub_balance(hrt, tx, source, deposit)?;
+        }
+    }
+    Library::deploy(hrt, tx, library_code)
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_mock::host::JstzMockHost;
+
+    use super::*;
+
+    #[test]
+    fn execute_deploy_library() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        let hrt = host.rt();
+        let source = jstz_mock::account1();
+        tx.begin();
+
+        let library_code = "export const answer = 42;".to_string();
+        let receipt = execute(
+            hrt,
+            &mut tx,
+            &source,
+            DeployLibrary {
+                library_code: library_code.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Library::code(hrt, &tx, &receipt.hash).unwrap(),
+            Some(library_code)
+        );
+
+        let result = execute(
+            hrt,
+            &mut tx,
+            &source,
+            DeployLibrary {
+                library_code: "invalid js".to_string(),
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn execute_deploy_library_takes_deposit_once() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        let hrt = host.rt();
+        let source = jstz_mock::account1();
+        ProtocolParams {
+            storage_deposit_per_byte: 2,
+            ..Default::default()
+        }
+        .store(hrt)
+        .unwrap();
+        tx.begin();
+        Account::add_balance(hrt, &mut tx, &source, 100).unwrap();
+
+        let library_code = "export const answer = 42;".to_string();
+        let deployment = DeployLibrary {
+            library_code: library_code.clone(),
+        };
+        execute(hrt, &mut tx, &source, deployment.clone()).unwrap();
+        let deposit = 2 * library_code.len() as u64;
+        assert_eq!(
+            Account::balance(hrt, &mut tx, &source).unwrap(),
+            100 - deposit
+        );
+
+        // Deploying the same code again is free
+        execute(hrt, &mut tx, &source, deployment).unwrap();
+        assert_eq!(
+            Account::balance(hrt, &mut tx, &source).unwrap(),
+            100 - deposit
+        );
+
+        // Deployments fail if the source cannot pay the deposit
+        let result = execute(
+            hrt,
+            &mut tx,
+            &source,
+            DeployLibrary {
+                library_code: "export const question = 'unknown';".to_string(),
+            },
+        );
+        assert!(matches!(result, Err(Error::InsufficientFunds)));
+        assert_eq!(
+            Account::balance(hrt, &mut tx, &source).unwrap(),
+            100 - deposit
+        );
+    }
+
+    #[test]
+    fn execute_deploy_library_fails_on_large_code() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        let hrt = host.rt();
+        let source = jstz_mock::account1();
+        ProtocolParams {
+            max_library_size: 16,
+            ..Default::default()
+        }
+        .store(hrt)
+        .unwrap();
+        tx.begin();
+
+        let result = execute(
+            hrt,
+            &mut tx,
+            &source,
+            DeployLibrary {
+                library_code: "export const answer = 42;".to_string(),
+            },
+        );
+        assert!(matches!(result, Err(Error::LibraryTooLarge)));
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
 };
 
 use crate::{
+    context::protocol_params::ProtocolParams,
     operation::{
         self, Content, InternalOperation, Operation, OperationHash, SignedOperation,
     },
@@ -13,15 +14,30 @@ use crate::{
     Error, Result,
 };
 use futures::future::FutureExt;
-use jstz_core::{host::HostRuntime, kv::Transaction, reveal_data::RevealData};
+use jstz_core::{
+    host::HostRuntime,
+    kv::Transaction,
+    reveal_data::{RevealData, DEFAULT_MAX_REVEAL_SIZE},
+};
 use jstz_crypto::{hash::Blake2b, public_key::PublicKey};
 use tezos_crypto_rs::hash::ContractKt1Hash;
 pub mod deposit;
 pub mod fa_deposit;
 pub mod fa_withdraw;
+pub mod l1_call;
+pub mod l1_message;
+#[cfg(feature = "v2_runtime")]
+pub mod library;
 pub mod smart_function;
 pub mod withdraw;
 
+/// Returns the protocol limit on the size of revealed operations, set in the
+/// [`ProtocolParams`]
+pub fn max_reveal_size(rt: &impl HostRuntime) -> usize {
+    ProtocolParams::load(rt)
+        .map_or(DEFAULT_MAX_REVEAL_SIZE, |params| params.max_reveal_size())
+}
+
 async fn execute_operation_inner(
     hrt: &mut impl HostRuntime,
     tx: &mut Transaction,
@@ -38,6 +54,9 @@ async fn execute_operation_inner(
             Ok((op_hash, receipt::ReceiptContent::DeployFunction(result)))
         }
         operation::Content::RunFunction(run) => {
+            if l1_message::is_reserved_path(run.uri.path()) {
+                return Err(Error::ReservedPath);
+            }
             let result =
                 smart_function::run::execute(hrt, tx, &source, run, op_hash.clone())
                     .await?;
@@ -47,9 +66,11 @@ async fn execute_operation_inner(
             if op.public_key != *injector {
                 return Err(Error::InvalidInjector);
             }
-            let signed_op = RevealData::reveal_and_decode::<_, SignedOperation>(
+            let max_size = max_reveal_size(hrt);
+            let signed_op = RevealData::reveal_and_decode_with_limit::<_, SignedOperation>(
                 hrt,
                 &reveal.root_hash,
+                max_size,
             )?;
             signed_op.verify()?;
             signed_op.verify_and_increment_nonce(hrt)?;
@@ -91,6 +112,20 @@ async fn execute_operation_inner(
                 ReceiptContent::OracleResponse(OracleResponseReceipt { request_id }),
             ))
         }
+        #[cfg(feature = "v2_runtime")]
+        operation::Content::DeployLibrary(deployment) => {
+            let result = library::execute(hrt, tx, &source, deployment)?;
+            Ok((op_hash, ReceiptContent::DeployLibrary(result)))
+        }
+        operation::Content::DeleteFunction(deletion) => {
+            let result = smart_function::delete::execute(hrt, tx, &source, deletion)?;
+            Ok((op_hash, receipt::ReceiptContent::DeleteFunction(result)))
+        }
+        operation::Content::DeploySaltedFunction(deployment) => {
+            let result =
+                smart_function::deploy::execute_salted(hrt, tx, &source, deployment)?;
+            Ok((op_hash, receipt::ReceiptContent::DeployFunction(result)))
+        }
     }
 }
 
@@ -104,6 +139,9 @@ pub async fn execute_internal_operation(
         InternalOperation::FaDeposit(fa_deposit) => {
             fa_deposit::execute(hrt, tx, fa_deposit).await
         }
+        InternalOperation::L1Message(message) => {
+            l1_message::execute(hrt, tx, message).await
+        }
     }
 }
 
@@ -143,10 +181,14 @@ fn resolve_operation_hash(op: &Operation) -> Blake2b {
 #[cfg(test)]
 mod tests {
     use http::{HeaderMap, Method, Uri};
-    use jstz_core::{kv::transaction::Guarded, reveal_data::PreimageHash, BinEncodable};
+    use jstz_core::{
+        kv::transaction::Guarded,
+        reveal_data::{PreimageHash, MAX_REVEAL_SIZE},
+        BinEncodable,
+    };
     use jstz_crypto::{
         hash::Hash, public_key::PublicKey, public_key_hash::PublicKeyHash,
-        secret_key::SecretKey,
+        secret_key::SecretKey, smart_function_hash::SmartFunctionHash,
     };
     #[cfg(feature = "v2_runtime")]
     use jstz_utils::{test_util::alice_keys, KeyPair};
@@ -160,7 +202,9 @@ mod tests {
     use crate::runtime::v2::fetch::http::Request;
     use crate::{
         context::account::Nonce,
-        operation::{Content, DeployFunction, RevealLargePayload, RunFunction},
+        operation::{
+            Content, DeleteFunction, DeployFunction, RevealLargePayload, RunFunction,
+        },
         receipt::{ReceiptContent, ReceiptResult},
         HttpBody,
     };
@@ -295,10 +339,26 @@ mod tests {
         pk: PublicKey,
         sk: SecretKey,
         original_op_hash: OperationHash,
+    ) -> SignedOperation {
+        signed_rdc_op_of_type(
+            root_hash,
+            RevealType::DeployFunction,
+            pk,
+            sk,
+            original_op_hash,
+        )
+    }
+
+    fn signed_rdc_op_of_type(
+        root_hash: PreimageHash,
+        reveal_type: RevealType,
+        pk: PublicKey,
+        sk: SecretKey,
+        original_op_hash: OperationHash,
     ) -> SignedOperation {
         let rdc_op = RevealLargePayload {
             root_hash,
-            reveal_type: RevealType::DeployFunction,
+            reveal_type,
             original_op_hash,
         };
         let rdc_op_content = rdc_op;
@@ -338,7 +398,88 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn throws_error_if_reveal_type_not_supported() {
+    async fn throws_if_revealed_operation_exceeds_protocol_limit() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let (_, pk1, sk1) = bootstrap1();
+        let (_, pk2, sk2) = bootstrap2();
+        let deploy_op = make_signed_op(deploy_function_content(), pk2, sk2);
+        let root_hash = make_data_available(&mut host, deploy_op.clone());
+        let rdc_op = signed_rdc_op(root_hash, pk1.clone(), sk1, deploy_op.hash());
+        ProtocolParams {
+            max_reveal_size: 16,
+            ..Default::default()
+        }
+        .store(&mut host)
+        .unwrap();
+        assert_eq!(max_reveal_size(&host), 16);
+
+        let ticketer = ContractKt1Hash::try_from_bytes(&[0; 20]).unwrap();
+        let receipt =
+            execute_operation(&mut host, &mut tx, rdc_op, &ticketer, &pk1).await;
+        assert!(matches!(
+            receipt.result,
+            ReceiptResult::Failed(e) if e.contains("exceeds the maximum limit")
+        ));
+    }
+
+    #[test]
+    fn max_reveal_size_defaults_and_is_capped() {
+        let mut host = MockHost::default();
+        assert_eq!(max_reveal_size(&host), DEFAULT_MAX_REVEAL_SIZE);
+        ProtocolParams {
+            max_reveal_size: u64::MAX,
+            ..Default::default()
+        }
+        .store(&mut host)
+        .unwrap();
+        assert_eq!(max_reveal_size(&host), MAX_REVEAL_SIZE);
+    }
+
+    #[tokio::test]
+    async fn reveals_large_run_function_operation() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let (_, pk1, sk1) = bootstrap1();
+        let (pkh2, pk2, sk2) = bootstrap2();
+        let run_op = make_signed_op(
+            Content::RunFunction(RunFunction {
+                uri: Uri::try_from(format!("jstz://{pkh2}/")).unwrap(),
+                method: Method::POST,
+                headers: HeaderMap::new(),
+                body: HttpBody::from_bytes(vec![1; 8192]),
+                gas_limit: 10000,
+            }),
+            pk2,
+            sk2,
+        );
+        let root_hash = make_data_available(&mut host, run_op.clone());
+        let rdc_op = signed_rdc_op_of_type(
+            root_hash,
+            RevealType::RunFunction,
+            pk1.clone(),
+            sk1,
+            run_op.hash(),
+        );
+        let ticketer = ContractKt1Hash::try_from_bytes(&[0; 20]).unwrap();
+        let receipt =
+            execute_operation(&mut host, &mut tx, rdc_op, &ticketer, &pk1).await;
+        assert!(matches!(
+            receipt.result,
+            ReceiptResult::Success(ReceiptContent::RunFunction(_))
+        ));
+        assert_eq!(receipt.hash().to_string(), run_op.hash().to_string());
+        // The nonce of the original signer is consumed
+        assert_eq!(
+            crate::context::account::Account::storage_get_nonce(&host, &pkh2).unwrap(),
+            Nonce(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn throws_error_if_reveal_type_mismatch() {
         let mut host = MockHost::default();
         let mut tx = Transaction::default();
         tx.begin();
@@ -348,6 +489,32 @@ mod tests {
         let root_hash = make_data_available(&mut host, run_op.clone());
         let rdc_op = signed_rdc_op(root_hash, pk1.clone(), sk1.clone(), run_op.hash());
         let ticketer = ContractKt1Hash::try_from_bytes(&[0; 20]).unwrap();
+        let receipt =
+            execute_operation(&mut host, &mut tx, rdc_op, &ticketer, &pk1).await;
+        assert!(matches!(
+            receipt.result,
+            ReceiptResult::Failed(e) if e.contains("RevealTypeMismatch")
+        ));
+    }
+
+    #[tokio::test]
+    async fn throws_error_if_reveal_type_not_supported() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let (_, pk1, sk1) = bootstrap1();
+        let (_, pk2, sk2) = bootstrap2();
+        let delete_op = make_signed_op(
+            Content::DeleteFunction(DeleteFunction {
+                address: SmartFunctionHash::digest(b"function").unwrap(),
+                beneficiary: PublicKeyHash::from(&pk2).into(),
+            }),
+            pk2.clone(),
+            sk2.clone(),
+        );
+        let root_hash = make_data_available(&mut host, delete_op.clone());
+        let rdc_op = signed_rdc_op(root_hash, pk1.clone(), sk1.clone(), delete_op.hash());
+        let ticketer = ContractKt1Hash::try_from_bytes(&[0; 20]).unwrap();
         let receipt =
             execute_operation(&mut host, &mut tx, rdc_op, &ticketer, &pk1).await;
         assert!(matches!(
@@ -466,6 +633,35 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn run_function_to_reserved_path_fails() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let (_, pk1, sk1) = bootstrap1();
+        let run_op = make_signed_op(
+            Content::RunFunction(RunFunction {
+                uri: Uri::try_from(
+                    "jstz://KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton/-/l1-message",
+                )
+                .unwrap(),
+                method: Method::POST,
+                headers: HeaderMap::new(),
+                body: HttpBody::empty(),
+                gas_limit: 10000,
+            }),
+            pk1.clone(),
+            sk1,
+        );
+        let ticketer = ContractKt1Hash::try_from_bytes(&[0; 20]).unwrap();
+
+        let receipt =
+            execute_operation(&mut host, &mut tx, run_op, &ticketer, &pk1).await;
+        assert!(
+            matches!(receipt.result, ReceiptResult::Failed(e) if e.contains("ReservedPath"))
+        );
+    }
+
     #[cfg(feature = "v2_runtime")]
     #[tokio::test]
     async fn operation_response_successful() {
@@ -0,0 +1,88 @@
+use jstz_core::{host::HostRuntime, kv::Transaction};
+use tezos_smart_rollup::prelude::debug_msg;
+
+use crate::{
+    context::account::{Account, Address, Addressable},
+    error::Result,
+    operation::DeleteFunction,
+    receipt::DeleteFunctionReceipt,
+    Error,
+};
+
+pub fn execute(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &impl Addressable,
+    deletion: DeleteFunction,
+) -> Result<DeleteFunctionReceipt> {
+    let DeleteFunction {
+        address,
+        beneficiary,
+    } = deletion;
+
+    let source: Address = source.clone().into();
+    if Account::admin(hrt, tx, &address)? != Some(source) {
+        return Err(Error::InvalidAdmin);
+    }
+
+    // SAFETY: The balance transfer and the removal of the account must be atomic
+    tx.begin();
+    match Account::delete_smart_function(hrt, tx, &address, &beneficiary) {
+        Ok(amount) => {
+            tx.commit(hrt)?;
+            debug_msg!(hrt, "[📜] Smart function deleted: {}\n", address);
+            Ok(DeleteFunctionReceipt {
+                beneficiary,
+                amount,
+            })
+        }
+        Err(err) => {
+            tx.rollback()?;
+            debug_msg!(hrt, "[📜] Smart function deletion failed. \n");
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_mock::host::JstzMockHost;
+
+    use crate::executor::smart_function;
+
+    use super::*;
+
+    #[test]
+    fn execute_delete_deletes_smart_function() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        let source = Address::User(jstz_mock::account1());
+        let beneficiary = Address::User(jstz_mock::account2());
+        let hrt = host.rt();
+        tx.begin();
+        Account::set_balance(hrt, &mut tx, &source, 100).unwrap();
+        let address = smart_function::deploy(
+            hrt,
+            &mut tx,
+            &source,
+            "export default () => {}".to_string(),
+            100,
+        )
+        .unwrap();
+
+        // Only the deployer can delete the smart function
+        let deletion = DeleteFunction {
+            address: address.clone(),
+            beneficiary: beneficiary.clone(),
+        };
+        assert!(matches!(
+            execute(hrt, &mut tx, &beneficiary, deletion.clone()),
+            Err(Error::InvalidAdmin)
+        ));
+
+        let receipt = execute(hrt, &mut tx, &source, deletion).unwrap();
+        assert_eq!(receipt.amount, 100);
+        assert_eq!(Account::balance(hrt, &mut tx, &beneficiary).unwrap(), 100);
+        assert!(Account::is_deleted(hrt, &tx, &address).unwrap());
+    }
+}
@@ -5,7 +5,7 @@ use tezos_smart_rollup::prelude::debug_msg;
 use crate::{
     context::account::{Account, Addressable},
     error::Result,
-    operation::DeployFunction,
+    operation::{DeployFunction, DeploySaltedFunction},
     receipt::DeployFunctionReceipt,
     runtime::ParsedCode,
     Error,
@@ -25,6 +25,28 @@ pub fn deploy_smart_function(
     Ok(address)
 }
 
+/// Deploys a smart function at [`Account::salted_address`]
+pub fn deploy_salted_smart_function(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &impl Addressable,
+    function_code: String,
+    account_credit: u64,
+    salt: &str,
+) -> Result<SmartFunctionHash> {
+    let function_code = ParsedCode::try_from(function_code)?;
+    let address = Account::create_salted_smart_function(
+        hrt,
+        tx,
+        source,
+        account_credit,
+        function_code,
+        salt,
+    )?;
+    Account::sub_balance(hrt, tx, source, account_credit)?;
+    Ok(address)
+}
+
 pub fn execute(
     hrt: &mut impl HostRuntime,
     tx: &mut Transaction,
@@ -38,7 +60,41 @@ pub fn execute(
 
     // SAFETY: Smart function creation and sub_balance must be atomic
     tx.begin();
-    match deploy_smart_function(hrt, tx, source, function_code, account_credit) {
+    let result = deploy_smart_function(hrt, tx, source, function_code, account_credit);
+    commit_deployment(hrt, tx, result)
+}
+
+pub fn execute_salted(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &impl Addressable,
+    deployment: DeploySaltedFunction,
+) -> Result<DeployFunctionReceipt> {
+    let DeploySaltedFunction {
+        function_code,
+        account_credit,
+        salt,
+    } = deployment;
+
+    // SAFETY: Smart function creation and sub_balance must be atomic
+    tx.begin();
+    let result = deploy_salted_smart_function(
+        hrt,
+        tx,
+        source,
+        function_code,
+        account_credit,
+        &salt,
+    );
+    commit_deployment(hrt, tx, result)
+}
+
+fn commit_deployment(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    result: Result<SmartFunctionHash>,
+) -> Result<DeployFunctionReceipt> {
+    match result {
         Ok(address) => {
             tx.commit(hrt)?;
             debug_msg!(hrt, "[📜] Smart function deployed: {}\n", address);
@@ -99,4 +155,36 @@ mod test {
         let result = smart_function::deploy::execute(hrt, &mut tx, &source, deployment);
         assert!(result.is_err_and(|e| { e.to_string().contains("InsufficientFunds") }));
     }
+
+    #[test]
+    fn execute_deploy_deploys_smart_function_at_salted_address() {
+        let mut host = JstzMockHost::default();
+        let mut tx = Transaction::default();
+        let source = Address::User(jstz_mock::account1());
+        let hrt = host.rt();
+        tx.begin();
+
+        let function_code = "export default () => {}".to_string();
+        let deployment = DeploySaltedFunction {
+            function_code: function_code.clone(),
+            account_credit: 0,
+            salt: "salt".to_string(),
+        };
+        let receipt = smart_function::deploy::execute_salted(
+            hrt,
+            &mut tx,
+            &source,
+            deployment.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            receipt.address,
+            Account::salted_address(&source, "salt", &function_code).unwrap()
+        );
+
+        // The same salt and code cannot be deployed twice
+        let result =
+            smart_function::deploy::execute_salted(hrt, &mut tx, &source, deployment);
+        assert!(matches!(result, Err(Error::AccountExists)));
+    }
 }
@@ -1,18 +1,13 @@
 #![cfg_attr(feature = "v2_runtime", allow(unused))]
 use crate::{context::account::Addressable, operation::RunFunction, HttpBody};
 
-use jstz_core::{
-    host::HostRuntime,
-    kv::{Storage, Transaction},
-};
-use jstz_crypto::smart_function_hash::SmartFunctionHash;
+use jstz_core::{host::HostRuntime, kv::Transaction};
 use serde::Deserialize;
 use tezos_crypto_rs::hash::ContractKt1Hash;
-use tezos_smart_rollup::storage::path::{OwnedPath, RefPath};
 
 use crate::{
     error::Result,
-    executor::{fa_withdraw::FaWithdraw, withdraw::Withdrawal},
+    executor::{fa_withdraw::FaWithdraw, l1_call::L1Call, withdraw::Withdrawal},
     receipt::RunFunctionReceipt,
     Error,
 };
@@ -20,8 +15,9 @@ use crate::{
 pub const JSTZ_HOST: &str = "jstz";
 pub const WITHDRAW_PATH: &str = "/withdraw";
 pub const FA_WITHDRAW_PATH: &str = "/fa-withdraw";
+pub const L1_CALL_PATH: &str = "/l1-call";
 
-fn validate_withdraw_request<'de, T>(run: &'de RunFunction) -> Result<T>
+fn validate_post_request<'de, T>(run: &'de RunFunction) -> Result<T>
 where
     T: Deserialize<'de>,
 {
@@ -60,7 +56,7 @@ pub(crate) fn execute(
             // TODO: https://linear.app/tezos/issue/JSTZ-77/check-gas-limit-when-performing-native-withdraws
             // Check gas limit
 
-            let withdrawal = validate_withdraw_request::<Withdrawal>(&run)?;
+            let withdrawal = validate_post_request::<Withdrawal>(&run)?;
             crate::executor::withdraw::execute_withdraw(
                 hrt, tx, source, withdrawal, ticketer,
             )?;
@@ -68,11 +64,13 @@ pub(crate) fn execute(
                 body: HttpBody::empty(),
                 status_code: http::StatusCode::OK,
                 headers: http::HeaderMap::new(),
+                withdrawals: vec![],
+                gas_used: 0,
             };
             Ok(receipt)
         }
         FA_WITHDRAW_PATH => {
-            let fa_withdraw = validate_withdraw_request::<FaWithdraw>(&run)?;
+            let fa_withdraw = validate_post_request::<FaWithdraw>(&run)?;
             let fa_withdraw_receipt_content = fa_withdraw.execute(
                 hrt, tx, source, 1000, // fake gas limit
             )?;
@@ -80,6 +78,20 @@ pub(crate) fn execute(
                 body: fa_withdraw_receipt_content.to_http_body(),
                 status_code: http::StatusCode::OK,
                 headers: http::HeaderMap::new(),
+                withdrawals: vec![],
+                gas_used: 0,
+            };
+            Ok(receipt)
+        }
+        L1_CALL_PATH => {
+            let l1_call = validate_post_request::<L1Call>(&run)?;
+            let l1_call_receipt = l1_call.execute(hrt, tx, source)?;
+            let receipt = RunFunctionReceipt {
+                body: l1_call_receipt.to_http_body(),
+                status_code: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                withdrawals: vec![],
+                gas_used: 0,
             };
             Ok(receipt)
         }
@@ -93,9 +105,7 @@ pub fn execute_without_ticketer(
     source: &impl Addressable,
     run: RunFunction,
 ) -> Result<RunFunctionReceipt> {
-    let ticketer_path = OwnedPath::from(&RefPath::assert_from(b"/ticketer"));
-    let ticketer: SmartFunctionHash =
-        Storage::get(hrt, &ticketer_path)?.expect("ticketer should be set");
+    let ticketer = crate::executor::withdraw::read_ticketer(hrt)?;
     execute(hrt, tx, &ticketer, source, run)
 }
 
@@ -1,8 +1,9 @@
+pub(crate) mod delete;
 pub(crate) mod deploy;
 pub(crate) mod host;
 pub(crate) mod run;
 
-pub use host::{FA_WITHDRAW_PATH, JSTZ_HOST, WITHDRAW_PATH};
-pub use run::{NOOP_PATH, X_JSTZ_AMOUNT, X_JSTZ_TRANSFER};
+pub use host::{FA_WITHDRAW_PATH, JSTZ_HOST, L1_CALL_PATH, WITHDRAW_PATH};
+pub use run::{MAX_GAS_LIMIT, NOOP_PATH, X_JSTZ_AMOUNT, X_JSTZ_TRANSFER};
 
 pub use deploy::deploy_smart_function as deploy;
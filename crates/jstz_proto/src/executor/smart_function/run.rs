@@ -1,8 +1,17 @@
 use jstz_core::{host::HostRuntime, kv::Transaction};
 
+#[cfg(not(all(feature = "v1_runtime", feature = "v2_runtime")))]
+use crate::runtime::run_toplevel_fetch;
+#[cfg(all(feature = "v1_runtime", feature = "v2_runtime"))]
+use {
+    crate::{context::account::Account, runtime::RuntimeVersion},
+    jstz_crypto::smart_function_hash::SmartFunctionHash,
+};
+
 use crate::{
     context::account::Addressable,
     error::Result,
+    executor::withdraw::take_withdrawal_log,
     operation::{self, OperationHash},
     receipt::RunFunctionReceipt,
 };
@@ -10,23 +19,75 @@ use crate::{
 pub const NOOP_PATH: &str = "/-/noop";
 pub const X_JSTZ_TRANSFER: &str = "X-JSTZ-TRANSFER";
 pub const X_JSTZ_AMOUNT: &str = "X-JSTZ-AMOUNT";
+/// Protocol cap on the gas limit of a single operation
+pub const MAX_GAS_LIMIT: usize = 10_000_000;
 
 pub async fn execute(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &(impl Addressable + 'static),
+    mut run_operation: operation::RunFunction,
+    operation_hash: OperationHash,
+) -> Result<RunFunctionReceipt> {
+    run_operation.gas_limit = run_operation.gas_limit.min(MAX_GAS_LIMIT);
+    let result = run_toplevel_fetch(hrt, tx, source, run_operation, operation_hash).await;
+    // Always drain the log so that withdrawals never leak into the next operation
+    let withdrawals = take_withdrawal_log(hrt, tx)?;
+    result.map(|receipt| RunFunctionReceipt {
+        withdrawals,
+        ..receipt
+    })
+}
+
+// Runs the smart function on the runtime it was deployed for
+#[cfg(all(feature = "v1_runtime", feature = "v2_runtime"))]
+async fn run_toplevel_fetch(
     hrt: &mut impl HostRuntime,
     tx: &mut Transaction,
     source: &(impl Addressable + 'static),
     run_operation: operation::RunFunction,
     operation_hash: OperationHash,
 ) -> Result<RunFunctionReceipt> {
-    crate::runtime::run_toplevel_fetch(hrt, tx, source, run_operation, operation_hash)
-        .await
+    let target = run_operation
+        .uri
+        .host()
+        .and_then(|host| SmartFunctionHash::from_base58(host).ok());
+    let version = match target {
+        Some(address) => Some(Account::runtime_version(hrt, tx, &address)?),
+        None => None,
+    };
+    match version {
+        Some(RuntimeVersion::V1) => {
+            crate::runtime::v1::run_toplevel_fetch(
+                hrt,
+                tx,
+                source,
+                run_operation,
+                operation_hash,
+            )
+            .await
+        }
+        _ => {
+            crate::runtime::run_toplevel_fetch(
+                hrt,
+                tx,
+                source,
+                run_operation,
+                operation_hash,
+            )
+            .await
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use http::{HeaderMap, Method};
-    use jstz_core::kv::Transaction;
+    use jstz_core::kv::{
+        outbox::{set_outbox_level, OutboxMessageId},
+        Transaction,
+    };
     use jstz_crypto::{hash::Blake2b, smart_function_hash::SmartFunctionHash};
     use jstz_mock::host::JstzMockHost;
 
@@ -34,7 +95,7 @@ mod test {
 
     use crate::{
         context::account::{Account, Address},
-        executor::smart_function,
+        executor::{smart_function, withdraw::WithdrawalReceipt},
         operation::RunFunction,
         HttpBody,
     };
@@ -515,6 +576,63 @@ mod test {
         assert_eq!(balance_after, 0);
     }
 
+    #[tokio::test]
+    async fn ledger_withdraw_from_smart_function_is_reported_in_receipt() {
+        let source = Address::User(jstz_mock::account1());
+        let receiver = Address::User(jstz_mock::account2());
+        let mut jstz_mock_host = JstzMockHost::default();
+        let host = jstz_mock_host.rt();
+        let mut tx = Transaction::default();
+
+        // 1. Deploy a smart function that withdraws part of its balance
+        tx.begin();
+        Account::add_balance(host, &mut tx, &source, 100).unwrap();
+        let code = format!(
+            r#"
+        export default () => {{
+            Ledger.withdraw("{receiver}", 60);
+            return new Response();
+        }};
+        "#
+        );
+        let smart_function =
+            smart_function::deploy(host, &mut tx, &source, code, 100).unwrap();
+        tx.commit(host).unwrap();
+
+        // 2. Call the smart function
+        set_outbox_level(host, 3).unwrap();
+        tx.begin();
+        let run_function = RunFunction {
+            uri: format!("jstz://{}/", &smart_function).try_into().unwrap(),
+            method: Method::GET,
+            headers: HeaderMap::new(),
+            body: HttpBody::empty(),
+            gas_limit: 1000,
+        };
+        let fake_op_hash = Blake2b::from(b"fake_op_hash".as_ref());
+        let receipt = execute(host, &mut tx, &source, run_function, fake_op_hash)
+            .await
+            .expect("run function expected");
+        tx.commit(host).unwrap();
+
+        // 3. Assert the withdrawal is reported and queued in the outbox
+        assert_eq!(
+            receipt.withdrawals,
+            vec![WithdrawalReceipt {
+                amount: 60,
+                receiver,
+                outbox_message_id: Some(OutboxMessageId { level: 3, index: 0 })
+            }]
+        );
+        tx.begin();
+        assert_eq!(
+            40,
+            Account::balance(host, &mut tx, &smart_function).unwrap()
+        );
+        let level = host.run_level(|_| {});
+        assert_eq!(1, host.outbox_at(level).len());
+    }
+
     // TODO: https://linear.app/tezos/issue/JSTZ-655/support-hostscript-fa-withdraw-in-v2
     // v2 runtime does not support HostScript withdrawals yet
     #[cfg(not(feature = "v2_runtime"))]
@@ -1577,4 +1695,76 @@ mod test {
             http::StatusCode::INTERNAL_SERVER_ERROR
         );
     }
+
+    #[cfg(not(feature = "v2_runtime"))]
+    #[tokio::test]
+    async fn v1_kv_storage_is_accounted() {
+        use crate::context::{account::StorageUsage, protocol_params::ProtocolParams};
+
+        let source = Address::User(jstz_mock::account1());
+        let mut jstz_mock_host = JstzMockHost::default();
+        let host = jstz_mock_host.rt();
+        ProtocolParams {
+            storage_deposit_per_byte: 2,
+            ..Default::default()
+        }
+        .store(host)
+        .unwrap();
+
+        let code = r#"
+        const handler = async (request) => {
+            if (request.method === "DELETE") {
+                Kv.delete("key");
+            } else {
+                Kv.set("key", "xxxxxxxxxx");
+            }
+            return new Response();
+        };
+        export default handler;
+        "#;
+        let mut tx = Transaction::default();
+        tx.begin();
+        Account::add_balance(host, &mut tx, &source, 1000).unwrap();
+        let smart_function =
+            smart_function::deploy(host, &mut tx, &source, code.to_string(), 1000)
+                .unwrap();
+        tx.commit(host).unwrap();
+
+        for (method, usage, balance) in [
+            // `key` takes 3 bytes and `"xxxxxxxxxx"` 12 bytes
+            (
+                Method::POST,
+                StorageUsage {
+                    bytes: 15,
+                    deposit: 30,
+                },
+                970,
+            ),
+            (Method::DELETE, StorageUsage::default(), 1000),
+        ] {
+            tx.begin();
+            let run_function = RunFunction {
+                uri: format!("jstz://{}/", &smart_function).try_into().unwrap(),
+                method,
+                headers: HeaderMap::new(),
+                body: HttpBody::empty(),
+                gas_limit: 10000,
+            };
+            let fake_op_hash = Blake2b::from(b"fake_op_hash".as_ref());
+            let response = execute(host, &mut tx, &source, run_function, fake_op_hash)
+                .await
+                .unwrap();
+            assert!(response.status_code.is_success());
+            tx.commit(host).unwrap();
+
+            assert_eq!(
+                Account::storage_usage(host, &tx, &smart_function).unwrap(),
+                usage
+            );
+            assert_eq!(
+                Account::balance(host, &mut tx, &smart_function).unwrap(),
+                balance
+            );
+        }
+    }
 }
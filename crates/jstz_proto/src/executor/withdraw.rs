@@ -1,15 +1,22 @@
 #![cfg_attr(feature = "v2_runtime", allow(unused))]
+use bincode::{Decode, Encode};
 use jstz_core::{
     host::HostRuntime,
-    kv::{outbox::OutboxMessage, Transaction},
+    kv::{
+        outbox::{OutboxMessage, OutboxMessageId},
+        Storage, Transaction,
+    },
 };
+use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use serde::{Deserialize, Serialize};
 use tezos_smart_rollup::{
     michelson::{ticket::FA2_1Ticket, MichelsonOption, MichelsonPair},
+    storage::path::{OwnedPath, RefPath},
     types::Contract,
 };
 
 use tezos_crypto_rs::hash::ContractKt1Hash;
+use utoipa::ToSchema;
 
 use crate::{
     context::account::{Account, Address, Addressable, Amount},
@@ -18,12 +25,33 @@ use crate::{
 
 const BURN_ENTRYPOINT: &str = "burn";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+const TICKETER_PATH: RefPath = RefPath::assert_from(b"/ticketer");
+
+// Withdrawals performed during the current operation. The log lives in the
+// transaction so that it is rolled back together with the outbox messages it
+// describes and is drained when the operation's receipt is built.
+const WITHDRAWAL_LOG_PATH: RefPath = RefPath::assert_from(b"/jstz_withdrawals");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, Encode, Decode)]
 pub struct Withdrawal {
     pub amount: Amount,
     pub receiver: Address,
 }
 
+/// A native withdrawal as reported in the receipt of the operation that performed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, Encode, Decode)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawalReceipt {
+    pub amount: Amount,
+    pub receiver: Address,
+    /// Location of the withdrawal message in the rollup outbox. `None` if the
+    /// message was deferred to a later level because the outbox was full.
+    pub outbox_message_id: Option<OutboxMessageId>,
+}
+
+#[derive(Debug, Clone, Default, Encode, Decode)]
+struct WithdrawalLog(Vec<WithdrawalReceipt>);
+
 fn create_withdrawal(
     amount: Amount,
     receiver: &Address,
@@ -51,18 +79,25 @@ fn withdraw(
     source: &impl Addressable,
     withdrawal: Withdrawal,
     ticketer: &ContractKt1Hash,
-) -> Result<()> {
+) -> Result<WithdrawalReceipt> {
     let Withdrawal { amount, receiver } = withdrawal;
     Account::sub_balance(rt, tx, source, amount)?;
     let message = create_withdrawal(amount, &receiver, ticketer)?;
-    tx.queue_outbox_message(rt, message)?;
-    Ok(())
+    let outbox_message_id = tx.queue_outbox_message(rt, message)?;
+    Ok(WithdrawalReceipt {
+        amount,
+        receiver,
+        outbox_message_id,
+    })
 }
 
 /// Process the native withdrawal request by atomically deducting user balance
 /// then pushing a withdraw message to the outbox queue.`ticketer` is expected
 /// to be the XTZ Exchanger contract. See /jstz/contracts/exchanger.mligo.
 ///
+/// The withdrawal is recorded so that it can be reported in the operation's
+/// receipt. See [take_withdrawal_log].
+///
 /// Fails if the source account has insufficient funds or if the outbox
 /// queue is full.
 pub(crate) fn execute_withdraw(
@@ -71,31 +106,85 @@ pub(crate) fn execute_withdraw(
     source: &impl Addressable,
     withdrawal: Withdrawal,
     ticketer: &ContractKt1Hash,
-) -> Result<()> {
+) -> Result<WithdrawalReceipt> {
     tx.begin();
     let result = withdraw(rt, tx, source, withdrawal, ticketer);
-    if result.is_ok() {
-        tx.commit(rt)?;
-    } else {
-        tx.rollback()?;
+    match result {
+        Ok(receipt) => {
+            tx.commit(rt)?;
+            let mut log = tx
+                .entry::<WithdrawalLog>(rt, OwnedPath::from(&WITHDRAWAL_LOG_PATH))?
+                .or_insert_default();
+            log.0.push(receipt.clone());
+            Ok(receipt)
+        }
+        Err(e) => {
+            tx.rollback()?;
+            Err(e)
+        }
+    }
+}
+
+/// Reads the native ticketer (the XTZ Exchanger contract) from durable storage.
+pub(crate) fn read_ticketer(rt: &impl HostRuntime) -> Result<ContractKt1Hash> {
+    let ticketer: SmartFunctionHash =
+        Storage::get(rt, &TICKETER_PATH)?.ok_or(Error::TicketerNotSet)?;
+    Ok(ticketer.into())
+}
+
+/// Process a native withdrawal requested by the smart function at `source`
+/// through the runtime's `Ledger.withdraw` API. See [execute_withdraw].
+///
+/// Fails if the amount is zero, the smart function has insufficient funds
+/// or the outbox queue is full.
+pub(crate) fn execute_smart_function_withdraw(
+    rt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &SmartFunctionHash,
+    withdrawal: Withdrawal,
+) -> Result<()> {
+    if withdrawal.amount == 0 {
+        return Err(Error::ZeroAmountNotAllowed);
     }
-    result
+    let ticketer = read_ticketer(rt)?;
+    execute_withdraw(rt, tx, source, withdrawal, &ticketer)?;
+    Ok(())
+}
+
+/// Returns the withdrawals performed since the log was last drained and
+/// clears the log.
+pub(crate) fn take_withdrawal_log(
+    rt: &impl HostRuntime,
+    tx: &mut Transaction,
+) -> Result<Vec<WithdrawalReceipt>> {
+    let path = OwnedPath::from(&WITHDRAWAL_LOG_PATH);
+    let withdrawals = match tx.get::<WithdrawalLog>(rt, path.clone())? {
+        Some(log) => log.0.clone(),
+        None => return Ok(vec![]),
+    };
+    tx.remove(path)?;
+    Ok(withdrawals)
 }
 
 #[cfg(test)]
 mod test {
-    use jstz_core::kv::Transaction;
+    use jstz_core::kv::{
+        outbox::{set_outbox_level, OutboxMessageId},
+        Transaction,
+    };
     use jstz_mock::{self};
     use tezos_crypto_rs::hash::ContractKt1Hash;
     use tezos_smart_rollup_mock::MockHost;
 
     use crate::{
         context::{account::Account, account::Address},
-        executor::withdraw::execute_withdraw,
+        executor::withdraw::{
+            execute_smart_function_withdraw, execute_withdraw, take_withdrawal_log,
+        },
         Error,
     };
 
-    use super::Withdrawal;
+    use super::{Withdrawal, WithdrawalReceipt};
 
     #[test]
     fn execute_withdraw_fails_on_insufficient_funds() {
@@ -150,4 +239,58 @@ mod test {
         let balance = Account::balance(&host, &mut tx, &source).unwrap();
         assert_eq!(0, balance)
     }
+
+    #[test]
+    fn execute_withdraw_records_outbox_message_id() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        let source = Address::User(jstz_mock::account1());
+        let receiver = Address::User(jstz_mock::account2());
+        let ticketer =
+            ContractKt1Hash::from_base58_check(jstz_mock::host::NATIVE_TICKETER).unwrap();
+        set_outbox_level(&mut host, 42).unwrap();
+
+        tx.begin();
+        Account::add_balance(&host, &mut tx, &source, 10)
+            .expect("Add balance should succeed");
+        for _ in 0..2 {
+            let withdrawal = Withdrawal {
+                amount: 5,
+                receiver: receiver.clone(),
+            };
+            execute_withdraw(&mut host, &mut tx, &source, withdrawal, &ticketer).unwrap();
+        }
+
+        let expected = (0..2)
+            .map(|index| WithdrawalReceipt {
+                amount: 5,
+                receiver: receiver.clone(),
+                outbox_message_id: Some(OutboxMessageId { level: 42, index }),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, take_withdrawal_log(&host, &mut tx).unwrap());
+        assert!(take_withdrawal_log(&host, &mut tx).unwrap().is_empty());
+        tx.commit(&mut host).unwrap();
+
+        let level = host.run_level(|_| {});
+        assert_eq!(2, host.outbox_at(level).len());
+    }
+
+    #[test]
+    fn smart_function_withdraw_fails_without_ticketer() {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        let source = jstz_mock::sf_account1();
+        let withdrawal = Withdrawal {
+            amount: 10,
+            receiver: Address::User(jstz_mock::account2()),
+        };
+
+        tx.begin();
+        Account::add_balance(&host, &mut tx, &source, 10).unwrap();
+        let result =
+            execute_smart_function_withdraw(&mut host, &mut tx, &source, withdrawal);
+        assert!(matches!(result, Err(Error::TicketerNotSet)));
+        assert_eq!(10, Account::balance(&host, &mut tx, &source).unwrap());
+    }
 }
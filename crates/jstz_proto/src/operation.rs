@@ -15,7 +15,7 @@ use jstz_core::{host::HostRuntime, reveal_data::PreimageHash};
 use jstz_crypto::verifier::Verifier;
 use jstz_crypto::{
     hash::Blake2b, public_key::PublicKey, public_key_hash::PublicKeyHash,
-    signature::Signature,
+    signature::Signature, smart_function_hash::SmartFunctionHash,
 };
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -89,6 +89,12 @@ impl Operation {
             }) => Blake2b::from(
                 format!("{public_key}{nonce}{uri}{method}{headers:?}{body:?}").as_bytes(),
             ),
+            Content::DeleteFunction(DeleteFunction {
+                address,
+                beneficiary,
+            }) => Blake2b::from(
+                format!("{public_key}{nonce}{address}{beneficiary}").as_bytes(),
+            ),
             Content::RevealLargePayload(RevealLargePayload {
                 root_hash,
                 reveal_type,
@@ -104,6 +110,18 @@ impl Operation {
             }) => Blake2b::from(
                 format!("{}{}{}{:?}", public_key, nonce, request_id, response).as_bytes(),
             ),
+            #[cfg(feature = "v2_runtime")]
+            Content::DeployLibrary(DeployLibrary { library_code }) => {
+                Blake2b::from(format!("{public_key}{nonce}{library_code}").as_bytes())
+            }
+            Content::DeploySaltedFunction(DeploySaltedFunction {
+                function_code,
+                account_credit,
+                salt,
+            }) => Blake2b::from(
+                format!("{public_key}{nonce}{function_code}{account_credit}{salt:?}")
+                    .as_bytes(),
+            ),
         }
     }
 }
@@ -117,6 +135,48 @@ pub struct DeployFunction {
     pub account_credit: Amount,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema)]
+#[schema(
+    description = "Deploys a smart function at an address derived from the sender, the salt \
+    and the hash of the code instead of the nonce of the sender, so that the address can be \
+    computed before the deployment."
+)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploySaltedFunction {
+    /// Smart function code
+    pub function_code: String,
+    /// Amount of tez to credit to the smart function account, debited from the sender
+    pub account_credit: Amount,
+    /// Salt of the smart function address
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema)]
+#[schema(
+    description = "Deletes a smart function. Only the address that deployed the smart function \
+    can delete it. The balance of the smart function is transferred to the beneficiary, \
+    its Kv store is removed and its address can never be used again."
+)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFunction {
+    /// Address of the smart function to delete
+    pub address: SmartFunctionHash,
+    /// Account credited with the balance of the smart function
+    pub beneficiary: Address,
+}
+
+#[cfg(feature = "v2_runtime")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema)]
+#[schema(
+    description = "Deploys a library module that smart functions can import \
+    with `import lib from \"jstz-lib:<hash>\"`. Libraries are addressed by the hash of their code."
+)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployLibrary {
+    /// Library code
+    pub library_code: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema)]
 #[schema(description = "Request used to run a smart function. \
     The target smart function is given by the host part of the uri. \
@@ -143,13 +203,16 @@ pub struct RunFunction {
     #[schema(schema_with = openapi::request_headers)]
     pub headers: HeaderMap,
     pub body: HttpBody,
-    /// Maximum amount of gas that is allowed for the execution of this operation
+    /// Maximum amount of gas that is allowed for the execution of this operation,
+    /// capped by the protocol at `MAX_GAS_LIMIT`
     pub gas_limit: usize,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, ToSchema, Serialize, Deserialize, Display)]
 pub enum RevealType {
     DeployFunction,
+    RunFunction,
+    DeploySaltedFunction,
 }
 
 impl TryFrom<&Content> for RevealType {
@@ -157,6 +220,8 @@ impl TryFrom<&Content> for RevealType {
     fn try_from(value: &Content) -> Result<Self> {
         match *value {
             Content::DeployFunction(_) => Ok(RevealType::DeployFunction),
+            Content::RunFunction(_) => Ok(RevealType::RunFunction),
+            Content::DeploySaltedFunction(_) => Ok(RevealType::DeploySaltedFunction),
             _ => Err(Error::RevealNotSupported),
         }
     }
@@ -205,6 +270,13 @@ pub enum Content {
     #[cfg(feature = "v2_runtime")]
     #[schema(title = "OracleResponse")]
     OracleResponse(#[bincode(with_serde)] OracleResponse),
+    #[cfg(feature = "v2_runtime")]
+    #[schema(title = "DeployLibrary")]
+    DeployLibrary(#[bincode(with_serde)] DeployLibrary),
+    #[schema(title = "DeleteFunction")]
+    DeleteFunction(#[bincode(with_serde)] DeleteFunction),
+    #[schema(title = "DeploySaltedFunction")]
+    DeploySaltedFunction(#[bincode(with_serde)] DeploySaltedFunction),
 }
 
 impl Content {
@@ -273,6 +345,8 @@ impl From<SignedOperation> for Operation {
 }
 
 pub mod internal {
+    use jstz_crypto::smart_function_hash::SmartFunctionHash;
+    use tezos_crypto_rs::hash::ContractKt1Hash;
     use tezos_smart_rollup::michelson::ticket::TicketHash;
 
     use super::*;
@@ -351,12 +425,38 @@ pub mod internal {
             self.inbox_id.hash()
         }
     }
+
+    /// A message sent by an L1 contract to a smart function
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct L1Message {
+        // Inbox message id
+        pub inbox_id: InboxId,
+        /// L1 contract that sent the message
+        pub sender: ContractKt1Hash,
+        /// Source of the L1 operation that sent the message
+        pub source: PublicKeyHash,
+        // Smart function receiving the message
+        pub receiver: SmartFunctionHash,
+        /// Unpacked payload in the Micheline JSON format
+        pub payload: serde_json::Value,
+    }
+
+    impl L1Message {
+        pub fn to_http_body(&self) -> HttpBody {
+            self.payload.clone().into()
+        }
+
+        pub fn hash(&self) -> OperationHash {
+            self.inbox_id.hash()
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum InternalOperation {
     Deposit(internal::Deposit),
     FaDeposit(internal::FaDeposit),
+    L1Message(internal::L1Message),
 }
 
 pub mod openapi {
@@ -402,7 +502,10 @@ pub mod openapi {
 
 #[cfg(test)]
 mod test {
-    use super::{Content, DeployFunction, RevealLargePayload, RevealType, RunFunction};
+    use super::{
+        Content, DeployFunction, DeploySaltedFunction, RevealLargePayload, RevealType,
+        RunFunction,
+    };
     use super::{Operation, SignedOperation};
     use crate::context::account::{Account, Address, Nonce};
     use crate::operation::internal::{FaDeposit, InboxId};
@@ -448,6 +551,15 @@ mod test {
         })
     }
 
+    fn deploy_salted_function_content() -> Content {
+        Content::DeploySaltedFunction(DeploySaltedFunction {
+            function_code: r#"export default () => new Response("hello world!");"#
+                .to_string(),
+            account_credit: 100000,
+            salt: "salt".to_string(),
+        })
+    }
+
     fn dummy_content() -> Content {
         // Simply picks one the existing test content we have
         run_function_content()
@@ -504,6 +616,44 @@ mod test {
         assert_eq!(deploy_function, bin_decoded);
     }
 
+    #[test]
+    fn test_deploy_salted_function_json_round_trip() {
+        let deploy_salted_function = deploy_salted_function_content();
+        let json = serde_json::to_value(&deploy_salted_function).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "_type":"DeploySaltedFunction",
+                "accountCredit":100000,
+                "functionCode":"export default () => new Response(\"hello world!\");",
+                "salt":"salt"
+            })
+        );
+        let decoded = serde_json::from_value::<Content>(json).unwrap();
+        assert_eq!(deploy_salted_function, decoded);
+    }
+
+    #[test]
+    fn test_deploy_salted_function_bin_round_trip() {
+        let deploy_salted_function = deploy_salted_function_content();
+        let binary = deploy_salted_function.encode().unwrap();
+        let bin_decoded = Content::decode(binary.as_slice()).unwrap();
+        assert_eq!(deploy_salted_function, bin_decoded);
+    }
+
+    #[test]
+    fn deploy_salted_function_salt_is_signed() {
+        let operation = |content| Operation {
+            public_key: jstz_mock::pk1(),
+            nonce: Nonce(0),
+            content,
+        };
+        assert_ne!(
+            operation(deploy_function_content()).hash(),
+            operation(deploy_salted_function_content()).hash()
+        );
+    }
+
     fn mock_hrt_with_nonces<'a>(
         nonces: impl IntoIterator<Item = &'a (PublicKeyHash, Nonce)>,
     ) -> JstzMockHost {
@@ -647,6 +797,35 @@ mod test {
         assert_eq!(reveal_large_payload_operation, bin_decoded);
     }
 
+    #[test]
+    fn test_reveal_type_from_content() {
+        let run_function = Content::RunFunction(RunFunction {
+            uri: Uri::try_from("jstz://tz1cD5CuvAALcxgypqBXcBQEA8dkLJivoFjU/").unwrap(),
+            method: Method::POST,
+            headers: HeaderMap::new(),
+            body: HttpBody::from_bytes(vec![0; 8192]),
+            gas_limit: 10000,
+        });
+        assert_eq!(
+            RevealType::try_from(&run_function).unwrap(),
+            RevealType::RunFunction
+        );
+        assert_eq!(
+            RevealType::try_from(&deploy_salted_function_content()).unwrap(),
+            RevealType::DeploySaltedFunction
+        );
+
+        let reveal = Content::new_reveal_large_payload(
+            PreimageHash::default(),
+            RevealType::RunFunction,
+            OperationHash::default(),
+        );
+        assert!(matches!(
+            RevealType::try_from(&reveal),
+            Err(RevealNotSupported)
+        ));
+    }
+
     #[cfg(feature = "v2_runtime")]
     #[test]
     fn test_oracle_response_signed_operation_json_round_trip() {
@@ -686,6 +865,44 @@ mod test {
         assert_eq!(signed_op, decoded)
     }
 
+    // Variants are encoded by index, so new variants must not move existing ones
+    #[cfg(feature = "v2_runtime")]
+    #[test]
+    fn test_oracle_response_bin_encoding_is_stable() {
+        use crate::runtime::v2::fetch::http::Response;
+
+        use super::OracleResponse;
+        let oracle_response = Content::OracleResponse(OracleResponse {
+            request_id: 1,
+            response: Response {
+                status: 200,
+                status_text: "OK".into(),
+                headers: vec![],
+                body: vec![1, 2].into(),
+            },
+        });
+        let fixture: Vec<u8> = [
+            // variant index
+            &[3, 0, 0, 0][..],
+            // request id
+            &[1, 0, 0, 0, 0, 0, 0, 0],
+            // status
+            &[200, 0],
+            // status text
+            &[2, 0, 0, 0, 0, 0, 0, 0, b'O', b'K'],
+            // headers
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+            // body
+            &[2, 0, 0, 0, 0, 0, 0, 0, 1, 2],
+        ]
+        .concat();
+        assert_eq!(oracle_response.encode().unwrap(), fixture);
+        assert_eq!(
+            Content::decode(fixture.as_slice()).unwrap(),
+            oracle_response
+        );
+    }
+
     #[test]
     fn fa_deposit_json() {
         let d = FaDeposit {
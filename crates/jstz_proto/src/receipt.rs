@@ -1,8 +1,11 @@
 #[cfg(feature = "v2_runtime")]
-use crate::runtime::v2::oracle::RequestId;
+use crate::runtime::v2::{library::LibraryHash, oracle::RequestId};
 use crate::{
     context::account::Address,
-    executor::{fa_deposit::FaDepositReceipt, fa_withdraw::FaWithdrawReceipt},
+    executor::{
+        fa_deposit::FaDepositReceipt, fa_withdraw::FaWithdrawReceipt,
+        withdraw::WithdrawalReceipt,
+    },
     operation::OperationHash,
     HttpBody, Result,
 };
@@ -67,6 +70,21 @@ pub struct RunFunctionReceipt {
     #[serde(with = "http_serde::header_map")]
     #[schema(schema_with = crate::operation::openapi::response_headers)]
     pub headers: HeaderMap,
+    /// Native withdrawals performed during the operation
+    #[serde(default)]
+    pub withdrawals: Vec<WithdrawalReceipt>,
+    /// Gas consumed by the operation
+    #[serde(default)]
+    pub gas_used: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Encode, Decode)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFunctionReceipt {
+    pub beneficiary: Address,
+    /// Amount transferred to the beneficiary, including the storage deposit held by
+    /// the smart function
+    pub amount: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Encode, Decode)]
@@ -83,6 +101,14 @@ pub struct OracleResponseReceipt {
     pub request_id: RequestId,
 }
 
+#[cfg(feature = "v2_runtime")]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployLibraryReceipt {
+    /// Hash of the library, imported with `jstz-lib:<hash>`
+    pub hash: LibraryHash,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Encode, Decode)]
 #[serde(tag = "_type")]
 pub enum ReceiptContent {
@@ -99,4 +125,9 @@ pub enum ReceiptContent {
     #[cfg(feature = "v2_runtime")]
     #[schema(title = "OracleResponse")]
     OracleResponse(OracleResponseReceipt),
+    #[cfg(feature = "v2_runtime")]
+    #[schema(title = "DeployLibrary")]
+    DeployLibrary(#[bincode(with_serde)] DeployLibraryReceipt),
+    #[schema(title = "DeleteFunction")]
+    DeleteFunction(DeleteFunctionReceipt),
 }
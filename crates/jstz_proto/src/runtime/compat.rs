@@ -0,0 +1,255 @@
+//! Compatibility checker between the v1 and v2 runtimes.
+//!
+//! Smart functions deployed for the v1 runtime are not guaranteed to behave the same
+//! on the v2 runtime. [`check`] replays requests recorded for smart functions on both
+//! runtimes and reports the requests whose responses or storage updates differ. Once
+//! a smart function passes the check, it can be migrated with
+//! [`Account::set_runtime_version`].
+//!
+//! Each runtime replays the requests in order on its own transaction, so later
+//! requests observe the storage updates of earlier ones. Nothing is committed to
+//! durable storage.
+//!
+//! The check is served by `jstz_node` at `POST /compat/check` when it is built with
+//! the `compat` feature.
+
+use jstz_core::{host::HostRuntime, kv::storage_update::StorageUpdate, kv::Transaction};
+use jstz_crypto::{hash::Blake2b, smart_function_hash::SmartFunctionHash};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::account::{Account, Address, RUNTIME_VERSIONS_PATH_PREFIX},
+    error::Result,
+    operation::RunFunction,
+    receipt::RunFunctionReceipt,
+    runtime::{v1, v2, RuntimeVersion},
+};
+
+/// A request sent to a smart function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub source: Address,
+    pub request: RunFunction,
+}
+
+/// Response of a smart function. Headers are sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Response {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl From<RunFunctionReceipt> for Response {
+    fn from(receipt: RunFunctionReceipt) -> Self {
+        let mut headers: Vec<(String, String)> = receipt
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).to_string(),
+                )
+            })
+            .collect();
+        headers.sort();
+        Self {
+            status_code: receipt.status_code.as_u16(),
+            headers,
+            body: receipt.body.0,
+        }
+    }
+}
+
+/// Outcome of a request on one runtime
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Outcome {
+    /// The response, or the error message if the request failed
+    pub response: std::result::Result<Response, String>,
+    /// Storage updates made by the request
+    pub storage_updates: Vec<StorageUpdate>,
+}
+
+/// A request whose outcome differs between the runtimes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mismatch {
+    /// Index of the request in the replayed requests
+    pub index: usize,
+    pub v1: Outcome,
+    pub v2: Outcome,
+}
+
+impl Mismatch {
+    pub fn response_differs(&self) -> bool {
+        self.v1.response != self.v2.response
+    }
+
+    pub fn storage_differs(&self) -> bool {
+        self.v1.storage_updates != self.v2.storage_updates
+    }
+}
+
+/// Replays `requests` on the v1 and v2 runtimes and returns the requests whose
+/// outcomes differ. An empty result means that the runtimes agree on all requests.
+pub async fn check(
+    hrt: &mut impl HostRuntime,
+    requests: Vec<RecordedRequest>,
+) -> Result<Vec<Mismatch>> {
+    let v1 = replay(hrt, &requests, RuntimeVersion::V1).await?;
+    let v2 = replay(hrt, &requests, RuntimeVersion::V2).await?;
+    Ok(v1
+        .into_iter()
+        .zip(v2)
+        .enumerate()
+        .filter(|(_, (v1, v2))| v1 != v2)
+        .map(|(index, (v1, v2))| Mismatch { index, v1, v2 })
+        .collect())
+}
+
+async fn replay(
+    hrt: &mut impl HostRuntime,
+    requests: &[RecordedRequest],
+    version: RuntimeVersion,
+) -> Result<Vec<Outcome>> {
+    let mut tx = Transaction::default();
+    tx.begin();
+    let mut outcomes = Vec::with_capacity(requests.len());
+    for (index, RecordedRequest { source, request }) in requests.iter().enumerate() {
+        tx.begin();
+        // The v2 runtime only runs smart functions deployed for it
+        if let Some(address) = target(request) {
+            if Account::exists(hrt, &tx, &address)? {
+                Account::set_runtime_version(hrt, &mut tx, &address, version)?;
+            }
+        }
+        let operation_hash = Blake2b::from(format!("compat{index}").as_bytes());
+        let result = match version {
+            RuntimeVersion::V1 => {
+                v1::run_toplevel_fetch(
+                    hrt,
+                    &mut tx,
+                    source,
+                    request.clone(),
+                    operation_hash,
+                )
+                .await
+            }
+            RuntimeVersion::V2 => {
+                v2::run_toplevel_fetch(
+                    hrt,
+                    &mut tx,
+                    source,
+                    request.clone(),
+                    operation_hash,
+                )
+                .await
+            }
+        };
+        let storage_updates = tx
+            .storage_updates()?
+            .into_iter()
+            .filter(|update| !is_runtime_version_update(update))
+            .collect();
+        match result {
+            Ok(receipt) => {
+                tx.commit(hrt)?;
+                outcomes.push(Outcome {
+                    response: Ok(receipt.into()),
+                    storage_updates,
+                });
+            }
+            Err(err) => {
+                tx.rollback()?;
+                outcomes.push(Outcome {
+                    response: Err(err.to_string()),
+                    storage_updates,
+                });
+            }
+        }
+    }
+    tx.rollback()?;
+    Ok(outcomes)
+}
+
+fn target(request: &RunFunction) -> Option<SmartFunctionHash> {
+    SmartFunctionHash::from_base58(request.uri.host()?).ok()
+}
+
+fn is_runtime_version_update(update: &StorageUpdate) -> bool {
+    match update {
+        StorageUpdate::Insert { key, .. }
+        | StorageUpdate::Remove { key }
+        | StorageUpdate::RemovePrefix { key } => {
+            key.starts_with(RUNTIME_VERSIONS_PATH_PREFIX)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::{HeaderMap, Method};
+    use jstz_core::kv::Transaction;
+    use jstz_mock::host::JstzMockHost;
+
+    use super::{check, RecordedRequest};
+    use crate::{
+        context::account::{Account, Address},
+        operation::RunFunction,
+        runtime::ParsedCode,
+        HttpBody,
+    };
+
+    fn deploy(host: &mut JstzMockHost, code: &str) -> RecordedRequest {
+        let source = Address::User(jstz_mock::account1());
+        let rt = host.rt();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let address = Account::create_smart_function(rt, &mut tx, &source, 0, unsafe {
+            ParsedCode::new_unchecked(code.to_string())
+        })
+        .unwrap();
+        tx.commit(rt).unwrap();
+        RecordedRequest {
+            source,
+            request: RunFunction {
+                uri: format!("jstz://{address}/").try_into().unwrap(),
+                method: Method::GET,
+                headers: HeaderMap::new(),
+                body: HttpBody::empty(),
+                gas_limit: 100_000,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn compatible_smart_function() {
+        let mut host = JstzMockHost::default();
+        let request = deploy(
+            &mut host,
+            r#"export default () => new Response("hello", { status: 201 })"#,
+        );
+        let mismatches = check(host.rt(), vec![request.clone(), request])
+            .await
+            .unwrap();
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+    }
+
+    #[tokio::test]
+    async fn incompatible_smart_function() {
+        let mut host = JstzMockHost::default();
+        // `SmartFunction` is only a global in the v1 runtime
+        let request = deploy(
+            &mut host,
+            r#"export default () => {
+                Kv.set("smart_function", typeof SmartFunction);
+                return new Response(typeof SmartFunction);
+            }"#,
+        );
+        let mismatches = check(host.rt(), vec![request]).await.unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].index, 0);
+        assert!(mismatches[0].response_differs());
+        assert!(mismatches[0].storage_differs());
+        assert!(!mismatches[0].v1.storage_updates.is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-#[cfg(not(feature = "v2_runtime"))]
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[cfg(any(feature = "v1_runtime", not(feature = "v2_runtime")))]
 pub mod v1;
 #[cfg(not(feature = "v2_runtime"))]
 pub use v1::{run_toplevel_fetch, Kv, KvValue, LogRecord, ParsedCode, LOG_PREFIX};
@@ -10,3 +14,25 @@ pub use v2::{
     fetch::fetch_handler::ProtoFetchHandler, protocol_context::*, run_toplevel_fetch, Kv,
     KvValue, LogRecord, ParsedCode, LOG_PREFIX, SNAPSHOT,
 };
+
+#[cfg(all(feature = "v1_runtime", feature = "v2_runtime"))]
+pub mod compat;
+
+/// Runtime a smart function was deployed for. The v1 and v2 runtimes expose different
+/// host APIs, so a smart function always runs on the runtime it was deployed for.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode, ToSchema,
+)]
+pub enum RuntimeVersion {
+    V1,
+    V2,
+}
+
+impl RuntimeVersion {
+    /// Runtime of the smart functions deployed by this build
+    pub const CURRENT: RuntimeVersion = if cfg!(feature = "v2_runtime") {
+        RuntimeVersion::V2
+    } else {
+        RuntimeVersion::V1
+    };
+}
@@ -3,8 +3,14 @@ use std::ops::Deref;
 use bincode::error::{DecodeError, EncodeError};
 use bincode::{de::Decoder, enc::Encoder, Decode, Encode};
 use boa_engine::{
-    js_string, object::ObjectInitializer, property::Attribute, Context, JsArgs, JsData,
-    JsError, JsNativeError, JsResult, JsString, JsValue, NativeFunction,
+    js_string,
+    object::{
+        builtins::{JsArrayBuffer, JsUint8Array},
+        ObjectInitializer,
+    },
+    property::Attribute,
+    Context, JsArgs, JsData, JsError, JsNativeError, JsResult, JsString, JsValue,
+    NativeFunction,
 };
 use boa_gc::{Finalize, Trace};
 use jstz_core::kv::transaction::Guarded;
@@ -14,26 +20,42 @@ use serde::{Deserialize, Serialize};
 use tezos_smart_rollup::storage::path::{self, OwnedPath, RefPath};
 use utoipa::ToSchema;
 
+use crate::context::{account::Account, protocol_params::ProtocolParams};
+
 #[derive(Debug, Trace, Finalize, JsData)]
 pub struct Kv {
     prefix: String,
 }
 
 const KV_PATH: RefPath = RefPath::assert_from(b"/jstz_kv");
+// Number of bytes accounted for each key. Keys stored before storage was accounted
+// have no entry.
+const SIZES_PATH: RefPath = RefPath::assert_from(b"/jstz_kv_size");
 
-// TODO: Figure out a more effective way of serializing values using json
-/// A value stored in the Key-Value store. Always valid JSON.
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+/// A value stored in the Key-Value store, either JSON or bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
 #[schema(value_type = Value)]
-pub struct KvValue(pub serde_json::Value);
+pub enum KvValue {
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+}
+
+// Byte values are stored behind a zero byte. JSON values are stored as JSON text, which
+// never starts with a zero byte, so values stored before byte values were supported
+// still decode as JSON.
+const BYTES_TAG: u8 = 0;
 
 impl Decode for KvValue {
     fn decode<D: Decoder>(decoder: &mut D) -> std::result::Result<KvValue, DecodeError> {
         let bytes: Vec<u8> = Decode::decode(decoder)?;
+        if let Some((&BYTES_TAG, bytes)) = bytes.split_first() {
+            return Ok(Self::Bytes(bytes.to_vec()));
+        }
         let value = serde_json::from_slice(&bytes).map_err(|e| {
             DecodeError::OtherString(format!("error deserializing kv value: {e}"))
         })?;
-        Ok(Self(value))
+        Ok(Self::Json(value))
     }
 }
 
@@ -42,13 +64,38 @@ impl Encode for KvValue {
         &self,
         encoder: &mut E,
     ) -> std::result::Result<(), EncodeError> {
-        let bytes = serde_json::to_vec(&self.0).map_err(|e| {
-            EncodeError::OtherString(format!("error serializing kv value: {e}"))
-        })?;
+        let bytes = match self {
+            KvValue::Json(value) => serde_json::to_vec(value).map_err(|e| {
+                EncodeError::OtherString(format!("error serializing kv value: {e}"))
+            })?,
+            KvValue::Bytes(bytes) => [&[BYTES_TAG], bytes.as_slice()].concat(),
+        };
         Encode::encode(&bytes, encoder)
     }
 }
 
+impl KvValue {
+    /// Number of bytes used to store the value
+    pub fn stored_len(&self) -> usize {
+        match self {
+            KvValue::Json(value) => value.to_string().len(),
+            KvValue::Bytes(bytes) => bytes.len() + 1,
+        }
+    }
+
+    /// Converts the value into a JS value. Byte values become `Uint8Array`s.
+    pub fn to_js(&self, context: &mut Context) -> JsResult<JsValue> {
+        match self {
+            KvValue::Json(value) => JsValue::from_json(value, context),
+            KvValue::Bytes(bytes) => Ok(JsUint8Array::from_array_buffer(
+                JsArrayBuffer::from_byte_block(bytes.clone(), context)?,
+                context,
+            )?
+            .into()),
+        }
+    }
+}
+
 impl Kv {
     pub fn new(prefix: String) -> Self {
         Self { prefix }
@@ -60,7 +107,14 @@ impl Kv {
         Ok(path::concat(&KV_PATH, &key_path)?)
     }
 
+    fn size_path(&self, key: &str) -> jstz_core::Result<OwnedPath> {
+        let key_path = OwnedPath::try_from(format!("/{}/{}", self.prefix, key))?;
+
+        Ok(path::concat(&SIZES_PATH, &key_path)?)
+    }
+
     pub fn set(&self, tx: &mut Transaction, key: &str, value: KvValue) -> Result<()> {
+        tx.insert(self.size_path(key)?, Self::entry_size(key, &value))?;
         tx.insert(self.key_path(key)?, value)
     }
 
@@ -74,9 +128,50 @@ impl Kv {
     }
 
     pub fn delete(&self, tx: &mut Transaction, key: &str) -> Result<()> {
+        tx.remove(self.size_path(key)?)?;
         tx.remove(self.key_path(key)?)
     }
 
+    /// Returns the number of bytes accounted for `key`, 0 if it is not set or was
+    /// stored before storage was accounted
+    pub fn size(
+        &self,
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        key: &str,
+    ) -> Result<u64> {
+        Ok(tx
+            .get::<u64>(hrt, self.size_path(key)?)?
+            .map(|size| *size)
+            .unwrap_or(0))
+    }
+
+    /// Number of bytes accounted for storing `value` at `key`
+    pub fn entry_size(key: &str, value: &KvValue) -> u64 {
+        (key.len() + value.stored_len()) as u64
+    }
+
+    /// Accounts for the Kv store changing by `delta` bytes with the storage
+    /// parameters of the protocol
+    fn update_storage_usage(
+        &self,
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        delta: i64,
+    ) -> crate::Result<()> {
+        let address = SmartFunctionHash::from_base58(&self.prefix)?;
+        let params = ProtocolParams::load(hrt)?;
+        Account::update_storage_usage(
+            hrt,
+            tx,
+            &address,
+            delta,
+            params.storage_quota,
+            params.storage_deposit_per_byte,
+        )?;
+        Ok(())
+    }
+
     pub fn has(
         &self,
         hrt: &impl HostRuntime,
@@ -120,9 +215,14 @@ impl KvApi {
     fn set(this: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
         preamble!(this, args, key);
 
-        let value = KvValue(args.get_or_undefined(1).to_json(context)?);
+        let value = KvValue::Json(args.get_or_undefined(1).to_json(context)?);
 
-        runtime::with_js_tx(|tx| this.set(tx, &key, value))?;
+        runtime::with_js_hrt_and_tx(|hrt, tx| -> crate::Result<()> {
+            let previous = this.size(hrt.deref(), tx, &key)?;
+            let delta = Kv::entry_size(&key, &value) as i64 - previous as i64;
+            this.update_storage_usage(hrt.deref(), tx, delta)?;
+            Ok(this.set(tx, &key, value)?)
+        })?;
 
         Ok(JsValue::undefined())
     }
@@ -132,7 +232,7 @@ impl KvApi {
 
         runtime::with_js_hrt_and_tx(|hrt, tx| -> JsResult<JsValue> {
             match this.get(hrt.deref(), tx, &key)? {
-                Some(value) => JsValue::from_json(&value.0, context),
+                Some(value) => value.to_js(context),
                 None => Ok(JsValue::null()),
             }
         })
@@ -145,7 +245,13 @@ impl KvApi {
     ) -> JsResult<JsValue> {
         preamble!(this, args, key);
 
-        runtime::with_js_tx(|tx| this.delete(tx, &key))?;
+        runtime::with_js_hrt_and_tx(|hrt, tx| -> crate::Result<()> {
+            let previous = this.size(hrt.deref(), tx, &key)?;
+            if previous > 0 {
+                this.update_storage_usage(hrt.deref(), tx, -(previous as i64))?;
+            }
+            Ok(this.delete(tx, &key)?)
+        })?;
 
         Ok(JsValue::undefined())
     }
@@ -197,24 +303,24 @@ mod tests {
     fn test_kv_value_roundtrip() {
         let test_cases = vec![
             // Null
-            KvValue(json!(null)),
+            KvValue::Json(json!(null)),
             // Boolean
-            KvValue(json!(true)),
-            KvValue(json!(false)),
+            KvValue::Json(json!(true)),
+            KvValue::Json(json!(false)),
             // Numbers
-            KvValue(json!(42)),
-            KvValue(json!(-17.5)),
-            KvValue(json!(0)),
+            KvValue::Json(json!(42)),
+            KvValue::Json(json!(-17.5)),
+            KvValue::Json(json!(0)),
             // String
-            KvValue(json!("hello world")),
-            KvValue(json!("")),
+            KvValue::Json(json!("hello world")),
+            KvValue::Json(json!("")),
             // Array
-            KvValue(json!([])),
-            KvValue(json!([1, 2, 3])),
-            KvValue(json!(["a", "b", null, true, 1.5])),
+            KvValue::Json(json!([])),
+            KvValue::Json(json!([1, 2, 3])),
+            KvValue::Json(json!(["a", "b", null, true, 1.5])),
             // Object
-            KvValue(json!({})),
-            KvValue(json!({
+            KvValue::Json(json!({})),
+            KvValue::Json(json!({
                 "string": "value",
                 "number": 42,
                 "bool": true,
@@ -225,6 +331,9 @@ mod tests {
                     "c": [true, null]
                 }
             })),
+            // Bytes
+            KvValue::Bytes(vec![]),
+            KvValue::Bytes(vec![0, 1, 2]),
         ];
 
         for value in test_cases {
@@ -233,9 +342,9 @@ mod tests {
             let decoded =
                 <KvValue as BinEncodable>::decode(&bytes).expect("Failed to decode");
             assert_eq!(
-                value.0, decoded.0,
+                value, decoded,
                 "Value did not match after roundtrip: {:?}",
-                value.0
+                value
             );
         }
     }
@@ -17,11 +17,14 @@ use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use crate::{
     context::account::{Account, Address, Amount},
     error::Result,
+    executor::withdraw::{execute_smart_function_withdraw, Withdrawal},
 };
 
 // Ledger.selfAddress
 // Ledger.balance(pkh)
 // Ledger.transfer(dst, amount)
+// Ledger.withdraw(receiver, amount)
+// Ledger.outboxCapacity()
 
 #[derive(JsData)]
 struct Ledger {
@@ -60,6 +63,25 @@ impl Ledger {
 
         Ok(())
     }
+
+    fn withdraw(
+        &self,
+        rt: &mut impl HostRuntime,
+        tx: &mut Transaction,
+        receiver: Address,
+        amount: Amount,
+    ) -> Result<()> {
+        execute_smart_function_withdraw(
+            rt,
+            tx,
+            &self.address,
+            Withdrawal { amount, receiver },
+        )
+    }
+
+    fn outbox_capacity(rt: &mut impl HostRuntime, tx: &mut Transaction) -> Result<u32> {
+        Ok(tx.outbox_capacity(rt)?)
+    }
 }
 
 pub struct LedgerApi {
@@ -135,6 +157,36 @@ impl LedgerApi {
 
         Ok(JsValue::undefined())
     }
+
+    fn withdraw(
+        this: &JsValue,
+        args: &[JsValue],
+        _context: &mut Context,
+    ) -> JsResult<JsValue> {
+        let ledger = Ledger::try_from_js(this)?;
+        let receiver = js_value_to_pkh(args.get_or_undefined(0))?;
+        let amount = args
+            .get_or_undefined(1)
+            .as_number()
+            .ok_or_else(JsNativeError::typ)?;
+
+        runtime::with_js_hrt_and_tx(|hrt, tx| {
+            ledger.withdraw(hrt, tx, receiver, amount as Amount)
+        })?;
+
+        Ok(JsValue::undefined())
+    }
+
+    fn outbox_capacity(
+        _this: &JsValue,
+        _args: &[JsValue],
+        _context: &mut Context,
+    ) -> JsResult<JsValue> {
+        let capacity =
+            runtime::with_js_hrt_and_tx(|hrt, tx| Ledger::outbox_capacity(hrt, tx))?;
+
+        Ok(capacity.into())
+    }
 }
 
 impl jstz_core::Api for LedgerApi {
@@ -163,6 +215,16 @@ impl jstz_core::Api for LedgerApi {
             js_string!("transfer"),
             3,
         )
+        .function(
+            NativeFunction::from_fn_ptr(Self::withdraw),
+            js_string!("withdraw"),
+            2,
+        )
+        .function(
+            NativeFunction::from_fn_ptr(Self::outbox_capacity),
+            js_string!("outboxCapacity"),
+            0,
+        )
         .build();
 
         context
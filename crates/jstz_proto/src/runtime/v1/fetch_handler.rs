@@ -1,7 +1,8 @@
 use boa_engine::{
-    object::FunctionObjectBuilder, Context, JsArgs, JsError, JsNativeError, JsResult,
-    JsValue, NativeFunction,
+    object::{ErasedObject, FunctionObjectBuilder},
+    Context, JsArgs, JsError, JsNativeError, JsResult, JsValue, NativeFunction,
 };
+use boa_gc::GcRefMut;
 use jstz_api::http::{
     body::{Body, BodyWithType, HttpBody},
     header::Headers,
@@ -9,18 +10,28 @@ use jstz_api::http::{
     response::{Response, ResponseBuilder, ResponseClass, ResponseOptions},
 };
 use jstz_core::{native::JsNativeObject, runtime, Runtime};
-use std::ops::Deref;
+use std::{cell::RefCell, ops::Deref};
 
 use crate::{
     context::account::{Account, Address, Addressable},
     error::{self, Result},
-    executor::smart_function::{JSTZ_HOST, NOOP_PATH},
+    executor::{
+        l1_message::is_reserved_path,
+        smart_function::{JSTZ_HOST, NOOP_PATH},
+    },
     logger::{log_request_end, log_request_start},
     operation::{OperationHash, RunFunction},
     receipt::RunFunctionReceipt,
+    runtime::RuntimeVersion,
     Error,
 };
 
+#[cfg(feature = "v2_runtime")]
+use {
+    super::host_script::run_function_from_request,
+    jstz_crypto::smart_function_hash::SmartFunctionHash,
+};
+
 use super::{
     api::{ProtocolApi, WebApi},
     host_script::HostScript,
@@ -145,6 +156,9 @@ pub fn fetch(
             let dest_address = Address::from_base58(dest_address).map_err(|_| {
                 JsError::from_native(JsNativeError::error().with_message("Invalid host"))
             })?;
+            if is_reserved_path(request_deref.url().path()) {
+                return Err(error::Error::ReservedPath.into());
+            }
 
             runtime::with_js_tx(|tx| tx.begin());
 
@@ -166,26 +180,46 @@ pub fn fetch(
                     // 5. Set the referrer of the request to the current smart function address
                     headers::test_and_set_referrer(&request_deref, source_address)?;
 
-                    // 6. Load, init and run the smart function
-                    let src_code =
-                        runtime::with_js_hrt_and_tx(|hrt, tx| -> Result<ParsedCode> {
-                            Ok(ParsedCode(
-                                Account::function_code(hrt, tx, &dest_address)?
-                                    .deref()
-                                    .to_string(),
-                            ))
-                        })?;
+                    // 6. Load, init and run the smart function on the runtime it was
+                    // deployed for
+                    let version = runtime::with_js_hrt_and_tx(|hrt, tx| {
+                        Account::runtime_version(hrt, tx, &dest_address)
+                    })?;
 
                     log_request_start(dest_address.clone(), operation_hash.to_string());
-                    let response = Script::load_init_run(
-                        &src_code,
-                        ProtocolApi {
-                            operation_hash: operation_hash.clone(),
-                            address: dest_address.clone(),
-                        },
-                        request.inner(),
-                        context,
-                    );
+                    let response = match version {
+                        RuntimeVersion::V1 => {
+                            let src_code = runtime::with_js_hrt_and_tx(
+                                |hrt, tx| -> Result<ParsedCode> {
+                                    Ok(ParsedCode(
+                                        Account::function_code(hrt, tx, &dest_address)?
+                                            .deref()
+                                            .to_string(),
+                                    ))
+                                },
+                            )?;
+                            Script::load_init_run(
+                                &src_code,
+                                ProtocolApi {
+                                    operation_hash: operation_hash.clone(),
+                                    address: dest_address.clone(),
+                                },
+                                request.inner(),
+                                context,
+                            )
+                        }
+                        #[cfg(feature = "v2_runtime")]
+                        RuntimeVersion::V2 => run_v2(
+                            &operation_hash,
+                            &dest_address,
+                            &mut request_deref,
+                            context,
+                        ),
+                        #[cfg(not(feature = "v2_runtime"))]
+                        RuntimeVersion::V2 => {
+                            Err(Error::UnsupportedRuntimeVersion.into())
+                        }
+                    };
 
                     // TODO: avoid cloning
                     // https://linear.app/tezos/issue/JSTZ-331/avoid-cloning-for-address-in-proto
@@ -250,6 +284,50 @@ pub fn fetch(
     }
 }
 
+thread_local! {
+    /// User address that initiated the running operation
+    static SOURCE: RefCell<Option<Address>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `source` as the user address that initiated the running operation
+pub fn with_source<R>(source: Address, f: impl FnOnce() -> R) -> R {
+    let previous = SOURCE.with(|cell| cell.replace(Some(source)));
+    let result = f();
+    SOURCE.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+// Runs a smart function deployed for the v2 runtime with the gas left to the calling
+// runtime. Transfers, headers and the transaction of the call are handled by `fetch`.
+#[cfg(feature = "v2_runtime")]
+fn run_v2(
+    operation_hash: &OperationHash,
+    address: &SmartFunctionHash,
+    request: &mut GcRefMut<'_, ErasedObject, Request>,
+    context: &mut Context,
+) -> JsResult<JsValue> {
+    let source = SOURCE
+        .with(|cell| cell.borrow().clone())
+        .ok_or(Error::InvalidAddress)?;
+    let run = run_function_from_request(request, context.instructions_remaining())?;
+    // Release the host context before running, as the v2 smart function may call
+    // back into the v1 runtime
+    let (mut hrt, mut tx) =
+        runtime::with_js_hrt_and_tx(|hrt, tx| (hrt.reborrow(), tx.clone()));
+    let receipt = futures::executor::block_on(crate::runtime::v2::run_nested_fetch(
+        &mut hrt,
+        &mut tx,
+        source,
+        address.clone(),
+        run,
+        operation_hash.clone(),
+    ))?;
+    let response = response_from_run_receipt(receipt, context)?;
+    Ok(JsNativeObject::new::<ResponseClass>(response, context)?
+        .inner()
+        .clone())
+}
+
 fn create_http_request(
     uri: http::Uri,
     method: http::Method,
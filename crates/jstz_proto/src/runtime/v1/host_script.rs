@@ -128,7 +128,7 @@ impl HostScript {
     }
 }
 
-fn run_function_from_request(
+pub(super) fn run_function_from_request(
     request_deref: &mut GcRefMut<'_, ErasedObject, Request>,
     gas_limit: usize,
 ) -> JsResult<RunFunction> {
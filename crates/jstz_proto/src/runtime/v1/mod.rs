@@ -9,12 +9,18 @@ use fetch_handler::{fetch, runtime_and_request_from_run_operation};
 pub use js_logger::{LogLevel, LogRecord, LOG_PREFIX};
 pub use script::ParsedCode;
 
-use jstz_api::http::response::Response;
-use jstz_core::{host::HostRuntime, kv::Transaction, runtime};
+use boa_engine::{Context, JsResult, JsValue};
+use jstz_api::http::{request::Request, response::Response};
+use jstz_core::{host::HostRuntime, kv::Transaction, native::JsNativeObject, runtime};
 use tezos_smart_rollup::prelude::debug_msg;
 
+#[cfg(feature = "v2_runtime")]
+use {
+    crate::context::account::Account, jstz_crypto::smart_function_hash::SmartFunctionHash,
+};
+
 use crate::{
-    context::account::Addressable,
+    context::account::{Address, Addressable},
     error::Result,
     operation::{OperationHash, RunFunction},
     receipt::RunFunctionReceipt,
@@ -27,6 +33,46 @@ pub async fn run_toplevel_fetch(
     source_address: &(impl Addressable + 'static),
     run_operation: RunFunction,
     operation_hash: OperationHash,
+) -> Result<RunFunctionReceipt> {
+    let source = source_address.clone().into();
+    run(hrt, tx, source, run_operation, |request, context| {
+        fetch(source_address, operation_hash, request, context)
+    })
+}
+
+/// Runs the v1 smart function at `address` for a request made from the v2 runtime.
+/// The caller is responsible for the transfers, headers and transaction of the call.
+#[cfg(feature = "v2_runtime")]
+pub fn run_nested_fetch(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: &Address,
+    address: &SmartFunctionHash,
+    run_operation: RunFunction,
+    operation_hash: OperationHash,
+) -> Result<RunFunctionReceipt> {
+    let src_code = ParsedCode(Account::function_code(hrt, tx, address)?.to_string());
+    let api = ProtocolApi {
+        address: address.clone(),
+        operation_hash,
+    };
+    run(
+        hrt,
+        tx,
+        source.clone(),
+        run_operation,
+        |request, context| {
+            script::Script::load_init_run(&src_code, api, request.inner(), context)
+        },
+    )
+}
+
+fn run(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: Address,
+    run_operation: RunFunction,
+    f: impl FnOnce(&JsNativeObject<Request>, &mut Context) -> JsResult<JsValue>,
 ) -> Result<RunFunctionReceipt> {
     let gas_limit = run_operation.gas_limit;
     let (mut rt, request) = runtime_and_request_from_run_operation(run_operation)?;
@@ -34,19 +80,24 @@ pub async fn run_toplevel_fetch(
     let result = {
         let rt = &mut rt;
         runtime::enter_js_host_context(hrt, tx, || {
-            let result = fetch(source_address, operation_hash, &request, rt)?;
-            rt.blocking_resolve_value(&result)
+            fetch_handler::with_source(source, || {
+                let result = f(&request, rt)?;
+                rt.blocking_resolve_value(&result)
+            })
         })
     }
     .map_err(|err| {
         if rt.instructions_remaining() == 0 {
-            Error::GasLimitExceeded
+            Error::GasLimitExceeded {
+                gas_used: gas_limit as u64,
+            }
         } else {
             err.into()
         }
     })?;
 
-    debug_msg!(hrt, "🚀 Smart function executed successfully with value: {:?} (in {:?} instructions)\n", result, gas_limit - rt.instructions_remaining());
+    let gas_used = gas_limit - rt.instructions_remaining();
+    debug_msg!(hrt, "🚀 Smart function executed successfully with value: {:?} (in {:?} instructions)\n", result, gas_used);
 
     let response = Response::try_from_js(&result)?;
     let (http_parts, body) = Response::to_http_response(&response).into_parts();
@@ -54,5 +105,7 @@ pub async fn run_toplevel_fetch(
         body: body.into(),
         status_code: http_parts.status,
         headers: http_parts.headers,
+        withdrawals: vec![],
+        gas_used: gas_used as u64,
     })
 }
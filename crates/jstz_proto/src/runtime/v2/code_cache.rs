@@ -0,0 +1,241 @@
+//! Cache of compiled smart function and library code.
+//!
+//! Entries are keyed by the hash of the deployed code, so a code change never hits a
+//! stale entry. Each entry holds the code instrumented for gas metering and, once the
+//! module has been compiled, the V8 code cache of the module. The V8 code cache only
+//! speeds up compilation; if V8 rejects it (e.g. after a V8 upgrade), the module is
+//! compiled from source and the entry is replaced.
+//!
+//! The cache lives in memory and is never written to durable storage, since its
+//! content depends on the V8 build and must not affect the rollup state.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, LazyLock},
+};
+
+use deno_core::SourceCodeCacheInfo;
+use jstz_crypto::hash::Blake2b;
+use jstz_runtime::gas::{self, InstrumentError};
+use parking_lot::Mutex;
+
+pub type CodeHash = Blake2b;
+
+/// Default number of entries kept in [`CODE_CACHE`]
+pub const DEFAULT_CODE_CACHE_CAPACITY: usize = 256;
+
+pub static CODE_CACHE: LazyLock<CodeCache> =
+    LazyLock::new(|| CodeCache::new(DEFAULT_CODE_CACHE_CAPACITY));
+
+#[derive(Debug, Clone)]
+struct Entry {
+    instrumented: Arc<str>,
+    compiled: Option<Arc<[u8]>>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<CodeHash, Entry>,
+    // Use order, least recently used first
+    order: VecDeque<CodeHash>,
+}
+
+impl Entries {
+    /// Returns the entry of `hash` and marks it as the most recently used
+    fn get(&mut self, hash: &CodeHash) -> Option<&Entry> {
+        let entry = self.entries.get(hash)?;
+        if let Some(i) = self.order.iter().position(|h| h == hash) {
+            if let Some(hash) = self.order.remove(i) {
+                self.order.push_back(hash);
+            }
+        }
+        Some(entry)
+    }
+}
+
+/// A bounded cache of instrumented and compiled code. When full, the least recently
+/// used entry is evicted.
+#[derive(Debug)]
+pub struct CodeCache {
+    capacity: usize,
+    inner: Mutex<Entries>,
+}
+
+impl CodeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    /// Returns the hash of `code` and `code` instrumented for gas metering. Code is
+    /// only instrumented on a cache miss.
+    pub fn instrument(
+        &self,
+        code: &str,
+    ) -> Result<(CodeHash, Arc<str>), InstrumentError> {
+        let hash = CodeHash::from(code.as_bytes());
+        if let Some(entry) = self.inner.lock().get(&hash) {
+            return Ok((hash, entry.instrumented.clone()));
+        }
+        let instrumented: Arc<str> = gas::instrument(code)?.into();
+        self.insert(
+            hash.clone(),
+            Entry {
+                instrumented: instrumented.clone(),
+                compiled: None,
+            },
+        );
+        Ok((hash, instrumented))
+    }
+
+    /// Returns the V8 code cache info of the code with `hash` to be passed on to V8
+    /// when the module is compiled
+    pub fn source_code_cache_info(&self, hash: &CodeHash) -> SourceCodeCacheInfo {
+        let data = self
+            .inner
+            .lock()
+            .entries
+            .get(hash)
+            .and_then(|entry| entry.compiled.clone())
+            .map(|compiled| compiled.to_vec().into());
+        SourceCodeCacheInfo {
+            hash: Self::short_hash(hash),
+            data,
+        }
+    }
+
+    /// Stores the V8 code cache of the code with `hash`. Ignored if the code is not
+    /// cached.
+    pub fn set_compiled(&self, hash: &CodeHash, compiled: &[u8]) {
+        if let Some(entry) = self.inner.lock().entries.get_mut(hash) {
+            entry.compiled = Some(compiled.into());
+        }
+    }
+
+    /// Returns true if the V8 code cache of the code with `hash` is cached
+    pub fn is_compiled(&self, hash: &CodeHash) -> bool {
+        self.inner
+            .lock()
+            .entries
+            .get(hash)
+            .is_some_and(|entry| entry.compiled.is_some())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        *self.inner.lock() = Default::default();
+    }
+
+    /// The 64 bit hash V8 associates with a code cache
+    pub fn short_hash(hash: &CodeHash) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&hash.as_array()[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn insert(&self, hash: CodeHash, entry: Entry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock();
+        if inner.entries.contains_key(&hash) {
+            return;
+        }
+        while inner.entries.len() >= self.capacity {
+            match inner.order.pop_front() {
+                Some(least_recent) => {
+                    inner.entries.remove(&least_recent);
+                }
+                None => break,
+            }
+        }
+        inner.order.push_back(hash.clone());
+        inner.entries.insert(hash, entry);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_runtime::gas;
+
+    use super::{CodeCache, CodeHash};
+
+    #[test]
+    fn instrument_caches_instrumented_code() {
+        let cache = CodeCache::new(2);
+        let code = "export default () => 42";
+        let (hash, instrumented) = cache.instrument(code).unwrap();
+        assert_eq!(hash, CodeHash::from(code.as_bytes()));
+        assert_eq!(&*instrumented, gas::instrument(code).unwrap());
+        assert_eq!(cache.len(), 1);
+
+        let (_, cached) = cache.instrument(code).unwrap();
+        assert_eq!(instrumented, cached);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn set_compiled() {
+        let cache = CodeCache::new(2);
+        let (hash, _) = cache.instrument("export default () => 42").unwrap();
+        assert!(!cache.is_compiled(&hash));
+        assert!(cache.source_code_cache_info(&hash).data.is_none());
+
+        cache.set_compiled(&hash, &[1, 2, 3]);
+        assert!(cache.is_compiled(&hash));
+        let info = cache.source_code_cache_info(&hash);
+        assert_eq!(info.hash, CodeCache::short_hash(&hash));
+        assert_eq!(info.data.as_deref(), Some([1, 2, 3].as_slice()));
+
+        // Unknown code is ignored
+        let unknown = CodeHash::from("unknown".as_bytes());
+        cache.set_compiled(&unknown, &[1]);
+        assert!(!cache.is_compiled(&unknown));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let cache = CodeCache::new(2);
+        let (first, _) = cache.instrument("export default () => 1").unwrap();
+        let (second, _) = cache.instrument("export default () => 2").unwrap();
+        cache.set_compiled(&first, &[1]);
+        cache.set_compiled(&second, &[2]);
+        // Using the first entry makes the second one the least recently used
+        cache.instrument("export default () => 1").unwrap();
+        cache.instrument("export default () => 3").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.is_compiled(&first));
+        assert!(!cache.is_compiled(&second));
+    }
+
+    #[test]
+    fn evicts_oldest_entry() {
+        let cache = CodeCache::new(2);
+        let (first, _) = cache.instrument("export default () => 1").unwrap();
+        let (second, _) = cache.instrument("export default () => 2").unwrap();
+        cache.set_compiled(&first, &[1]);
+        let (third, _) = cache.instrument("export default () => 3").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.is_compiled(&first));
+        cache.set_compiled(&second, &[2]);
+        cache.set_compiled(&third, &[3]);
+        assert!(cache.is_compiled(&second));
+        assert!(cache.is_compiled(&third));
+    }
+
+    #[test]
+    fn invalid_code_is_not_cached() {
+        let cache = CodeCache::new(2);
+        assert!(cache.instrument("export default () => {").is_err());
+        assert!(cache.is_empty());
+    }
+}
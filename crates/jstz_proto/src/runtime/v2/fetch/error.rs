@@ -2,7 +2,11 @@ use std::borrow::Cow;
 
 use deno_error::JsErrorClass as _;
 use jstz_crypto::smart_function_hash::SmartFunctionHash;
-use jstz_runtime::error::RuntimeError;
+use jstz_runtime::{
+    error::RuntimeError,
+    gas::{InstrumentError, OutOfGas},
+    heap::OutOfMemory,
+};
 use serde::Serialize;
 
 use crate::runtime::v2::oracle::OracleError;
@@ -50,6 +54,18 @@ pub enum FetchError {
     #[class(syntax)]
     #[error("Smart function '{address}' has no code")]
     EmptyCode { address: SmartFunctionHash },
+    #[class(not_supported)]
+    #[error("Smart function '{address}' was deployed for the v1 runtime and cannot run on the v2 runtime")]
+    UnsupportedRuntimeVersion { address: SmartFunctionHash },
+    #[class(inherit)]
+    #[error(transparent)]
+    OutOfGas(#[from] OutOfGas),
+    #[class(inherit)]
+    #[error(transparent)]
+    InstrumentError(#[from] InstrumentError),
+    #[class(inherit)]
+    #[error(transparent)]
+    OutOfMemory(#[from] OutOfMemory),
 }
 
 #[derive(Serialize)]
@@ -1,22 +1,30 @@
-use crate::executor::smart_function::{FA_WITHDRAW_PATH, NOOP_PATH, WITHDRAW_PATH};
+use crate::executor::{
+    l1_message::is_reserved_path,
+    smart_function::{FA_WITHDRAW_PATH, NOOP_PATH, WITHDRAW_PATH},
+};
 use crate::logger::{
     log_request_end_with_host, log_request_start_with_host, log_response_status_code,
 };
 use crate::operation::OperationHash;
+use crate::runtime::v2::code_cache::CODE_CACHE;
 use crate::runtime::v2::fetch::error::{FetchError, Result};
 use crate::runtime::v2::fetch::http::Request;
 use crate::runtime::v2::ledger;
+use crate::runtime::v2::library::LibraryModuleLoader;
 use crate::runtime::v2::protocol_context::PROTOCOL_CONTEXT;
-use crate::runtime::SNAPSHOT;
+use crate::runtime::{RuntimeVersion, SNAPSHOT};
 
 use deno_core::error::CoreError;
 use deno_core::{
-    resolve_import, v8, ByteString, JsBuffer, OpState, ResourceId, StaticModuleLoader,
+    resolve_import, v8, ByteString, Extension, JsBuffer, NoopModuleLoader, OpState,
+    ResourceId,
 };
 use deno_fetch_base::{FetchHandler, FetchResponse, FetchReturn};
 use futures::FutureExt;
 use jstz_crypto::public_key_hash::PublicKeyHash;
-use jstz_runtime::runtime::{AsyncEntered, Limiter, MAX_SMART_FUNCTION_CALL_COUNT};
+use jstz_runtime::gas::{self, cost, OutOfGas};
+use jstz_runtime::heap::OutOfMemory;
+use jstz_runtime::runtime::{AsyncEntered, Limiter};
 use std::future::Future;
 use std::pin::Pin;
 use std::{cell::RefCell, rc::Rc};
@@ -42,6 +50,11 @@ use super::http::HostName;
 use super::http::{Body, Response, SupportedScheme};
 use std::num::NonZeroU64;
 use std::str::FromStr;
+#[cfg(feature = "v1_runtime")]
+use {
+    super::http::convert_header_map, crate::operation::RunFunction,
+    jstz_runtime::gas::GasMeter,
+};
 
 /// Provides the backend for Deno's [fetch](https://docs.deno.com/api/web/~/fetch) which structures
 /// its implementation into two steps to allow an [abort handler](https://github.com/jstz-dev/deno/blob/v2.1.10-jstz/ext/fetch_base/26_fetch.js#L182)
@@ -133,6 +146,7 @@ fn fetch(
     headers: Vec<(ByteString, ByteString)>,
     body: Option<Body>,
 ) -> Result<FetchReturn> {
+    gas::charge(state, cost::FETCH)?;
     let url = Url::try_from(url.as_str())?;
     let (tx, from, host, limiter) = {
         let rt_context = state.borrow_mut::<RuntimeContext>();
@@ -173,7 +187,8 @@ fn fetch(
 /// returns a response.
 ///
 /// A new transaction snapshot is created before dispatching the run function and
-/// committed/rolledback when it completes.
+/// committed/rolledback when it completes. The snapshot is always rolled back once
+/// the operation runs out of gas.
 ///
 /// Callers should not process the response further other than converting it into
 /// the expected response type.This function is agnostic of the context in which it
@@ -198,6 +213,7 @@ pub async fn process_and_dispatch_request(
     limiter: Limiter,
 ) -> Response {
     let scheme = SupportedScheme::try_from(&url);
+    let gas = limiter.gas().clone();
     let source = match SourceAddress::try_from(source) {
         Ok(ok) => ok,
         Err(e) => return e.into(),
@@ -221,8 +237,11 @@ pub async fn process_and_dispatch_request(
                 limiter,
             )
             .await;
-            let _ =
-                commit_or_rollback(&mut host, &mut tx, is_successful && result.is_ok());
+            let _ = commit_or_rollback(
+                &mut host,
+                &mut tx,
+                is_successful && result.is_ok() && !gas.is_exhausted(),
+            );
             result.into()
         }
         Ok(SupportedScheme::Http) | Ok(SupportedScheme::Https) => {
@@ -353,6 +372,16 @@ async fn dispatch_run(
 ) -> Result<Response> {
     let to = url.try_into();
     match to {
+        // Smart functions cannot impersonate the protocol, which makes
+        // top-level requests to reserved paths itself
+        Ok(HostName::Address(_)) if !is_run_function && is_reserved_path(url.path()) => {
+            Ok(Response {
+                status: 403,
+                status_text: "Forbidden".into(),
+                headers: Vec::with_capacity(0),
+                body: "The path is reserved for requests made by the protocol".into(),
+            })
+        }
         Ok(HostName::Address(to)) => {
             log_event(host, operation_hash, LogEvent::RequestStart(&to));
             let response = handle_address(
@@ -391,7 +420,9 @@ async fn dispatch_run(
                 body: "Unsupported HostScript endpoint".into(),
             })
         }
-        Ok(HostName::JstzHost) => HostScript::route(host, tx, from, method, url).await,
+        Ok(HostName::JstzHost) => {
+            HostScript::route(host, tx, from, method, url, data).await
+        }
         Err(e) => Err(e),
     }
 }
@@ -498,7 +529,7 @@ async fn load_and_run(
         let err: CoreError = deno_error::JsErrorBox::range_error(
             format!(
                 "Too many smart function calls (max: {})",
-                MAX_SMART_FUNCTION_CALL_COUNT
+                limiter.max_call_depth()
             )
             .to_string(),
         )
@@ -506,6 +537,27 @@ async fn load_and_run(
         FetchError::JstzError(err.to_string())
     })?;
     let mut body = body;
+    let gas = slot.gas().clone();
+    let heap = slot.heap().clone();
+
+    #[cfg(feature = "v1_runtime")]
+    if Account::runtime_version(host, tx, &address)
+        .map_err(|err| FetchError::JstzError(err.to_string()))?
+        == RuntimeVersion::V1
+    {
+        return run_v1(
+            host,
+            tx,
+            operation_hash,
+            &source,
+            &address,
+            method,
+            url,
+            headers,
+            body,
+            &gas,
+        );
+    }
 
     // 0. Prepare Protocol
     let mut proto = RuntimeContext::new(
@@ -517,14 +569,18 @@ async fn load_and_run(
     );
     // 1. Load script
     let script = { load_script(tx, &mut proto.host, &proto.address)? };
+    let script = CODE_CACHE.instrument(&script)?;
     // 2. Prepare runtime
     let path = format!("jstz://{}", address);
     // `resolve_import` will panic without pinning
     let path = std::pin::Pin::new(path.as_str());
     let specifier = resolve_import(&path, "").unwrap();
-    // TODO: Investigate if its possible to replace moodule loader with explicit module loading
-    // from raw, precompiled or cached script
-    let module_loader = StaticModuleLoader::with(specifier.clone(), script);
+    let module_loader = LibraryModuleLoader::new(
+        JsHostRuntime::new(host),
+        tx.clone(),
+        specifier.clone(),
+        script,
+    );
     let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
         module_loader: Rc::new(module_loader),
         fetch: ProtoFetchHandler,
@@ -533,6 +589,8 @@ async fn load_and_run(
         snapshot: SNAPSHOT.get().map(|v| *v),
     });
     runtime.set_state(source);
+    // Lets a local debugger break before the smart function is loaded
+    jstz_runtime::debugger::attach(&mut runtime, &address);
 
     // 3. Prepare request
     let request = {
@@ -553,16 +611,146 @@ async fn load_and_run(
 
     // 4. Run
     let args = [request];
-    let id = runtime.execute_main_module(&specifier).await?;
-    let result = runtime.call_default_handler(id, &args).await?;
-    let response = {
+    let response: Result<Response> = async {
+        let id = runtime.execute_main_module(&specifier).await?;
+        let result = runtime.call_default_handler(id, &args).await?;
         AsyncEntered::new(&mut runtime, |runtime| {
             convert_js_to_response(runtime, result)
         })
         .await
-        .map_err(|_| FetchError::InvalidResponseType)?
+        .map_err(|_| FetchError::InvalidResponseType)
+    }
+    .await;
+    heap.record(runtime.heap_size());
+    // The runtime was terminated by the gas or heap limit
+    if gas.is_exhausted() {
+        return Err(OutOfGas.into());
+    }
+    if runtime.is_out_of_memory() {
+        return Err(OutOfMemory.into());
+    }
+    response
+}
+
+/// Runs the v2 smart function at `address` for a request made from the v1 runtime.
+/// The caller is responsible for the transfers, headers and transaction of the call.
+#[cfg(feature = "v1_runtime")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_nested(
+    host: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    operation_hash: &OperationHash,
+    source: Address,
+    address: SmartFunctionHash,
+    method: ByteString,
+    url: &Url,
+    headers: Vec<(ByteString, ByteString)>,
+    body: Option<Body>,
+    limiter: Limiter,
+) -> Result<Response> {
+    let source = SourceAddress::try_from(source)?;
+    load_and_run(
+        host,
+        tx,
+        Some(operation_hash),
+        source,
+        address,
+        method,
+        url,
+        headers,
+        body,
+        limiter,
+    )
+    .await
+}
+
+// Runs a smart function deployed for the v1 runtime with the gas left to the call.
+// The gas used by the v1 runtime is charged to the call.
+#[cfg(feature = "v1_runtime")]
+#[allow(clippy::too_many_arguments)]
+fn run_v1(
+    host: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    operation_hash: Option<&OperationHash>,
+    source: &SourceAddress,
+    address: &SmartFunctionHash,
+    method: ByteString,
+    url: &Url,
+    headers: Vec<(ByteString, ByteString)>,
+    body: Option<Body>,
+    gas: &GasMeter,
+) -> Result<Response> {
+    let mut header_map = http::HeaderMap::with_capacity(headers.len());
+    for (key, value) in headers {
+        let key = http::HeaderName::from_bytes(&key)
+            .map_err(|_| FetchError::InvalidHeaderType)?;
+        let value = http::HeaderValue::from_bytes(&value)
+            .map_err(|_| FetchError::InvalidHeaderType)?;
+        header_map.append(key, value);
+    }
+    let run = RunFunction {
+        uri: url
+            .as_str()
+            .parse()
+            .map_err(|_| FetchError::JstzError("Invalid URI".to_string()))?,
+        method: http::Method::from_bytes(&method)
+            .map_err(|_| FetchError::JstzError("Invalid method".to_string()))?,
+        headers: header_map,
+        body: body.map(|body| body.to_vec()).into(),
+        gas_limit: gas.limit().saturating_sub(gas.used()) as usize,
     };
-    Ok(response)
+    let receipt = crate::runtime::v1::run_nested_fetch(
+        host,
+        tx,
+        &source.0,
+        address,
+        run,
+        operation_hash.cloned().unwrap_or_default(),
+    )
+    .map_err(|err| match err {
+        crate::Error::GasLimitExceeded { .. } => {
+            let _ = gas.consume(gas.limit());
+            FetchError::OutOfGas(OutOfGas)
+        }
+        err => FetchError::JstzError(err.to_string()),
+    })?;
+    gas.consume(receipt.gas_used)?;
+    Ok(Response {
+        status: receipt.status_code.as_u16(),
+        status_text: receipt
+            .status_code
+            .canonical_reason()
+            .unwrap_or_default()
+            .to_string(),
+        headers: convert_header_map(receipt.headers),
+        body: receipt.body.0.unwrap_or_default().into(),
+    })
+}
+
+/// Bootstraps a runtime in the context of the smart function at `address` without
+/// running its code, for evaluating scripts interactively. The runtime has the protocol
+/// APIs of smart functions, with `extensions` registered on top, and sends its
+/// `fetch` calls from `source`.
+pub fn new_repl_runtime(
+    host: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: PublicKeyHash,
+    address: SmartFunctionHash,
+    extensions: Vec<Extension>,
+) -> JstzRuntime {
+    let slot = Limiter::default()
+        .try_acquire()
+        .expect("A new limiter has free slots");
+    let proto = RuntimeContext::new(host, tx, address, String::new(), slot);
+    let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+        module_loader: Rc::new(NoopModuleLoader),
+        fetch: ProtoFetchHandler,
+        protocol: Some(proto),
+        extensions: [vec![ledger::jstz_ledger::init_ops_and_esm()], extensions].concat(),
+        snapshot: SNAPSHOT.get().map(|v| *v),
+    });
+    runtime.set_state(SourceAddress(Address::User(source)));
+    runtime
 }
 
 fn load_script(
@@ -570,6 +758,13 @@ fn load_script(
     host: &impl HostRuntime,
     address: &SmartFunctionHash,
 ) -> Result<String> {
+    let version = Account::runtime_version(host, tx, address)
+        .map_err(|err| FetchError::JstzError(err.to_string()))?;
+    if version != RuntimeVersion::V2 {
+        return Err(FetchError::UnsupportedRuntimeVersion {
+            address: address.clone(),
+        });
+    }
     let code = Account::function_code(host, tx, address)
         .map(|s| s.to_string())
         .map_err(|err| FetchError::JstzError(err.to_string()))?;
@@ -792,12 +987,16 @@ impl TryFrom<Address> for SourceAddress {
 mod test {
     use super::ProtoFetchHandler;
     use crate::runtime::v2::{
-        fetch::fetch_handler::process_and_dispatch_request, oracle::OracleRequest,
-        protocol_context::ProtocolContext,
+        code_cache::{CodeHash, CODE_CACHE},
+        fetch::fetch_handler::process_and_dispatch_request,
+        library::Library,
+        oracle::OracleRequest,
+        protocol_context::{ProtocolContext, ProtocolParams},
     };
-    use crate::runtime::ParsedCode;
+    use crate::runtime::{ParsedCode, RuntimeVersion};
     use crate::{
-        context::account::{Account, Address},
+        context::account::{Account, Address, StorageUsage},
+        executor::l1_call::{L1Caller, L1Destination, L1_CALLERS_PATH},
         tests::DebugLogSink,
     };
     use crate::{
@@ -820,12 +1019,13 @@ mod test {
         smart_function_hash::SmartFunctionHash,
     };
     use jstz_runtime::{
-        runtime::Limiter, JstzRuntime, JstzRuntimeOptions, RuntimeContext,
+        runtime::Limiter, JstzRuntime, JstzRuntimeOptions, KvValue, RuntimeContext,
     };
     use jstz_utils::test_util::TOKIO;
     use serde_json::{json, Value as JsonValue};
     use std::rc::Rc;
     use std::{collections::HashMap, str::FromStr};
+    use tezos_smart_rollup::storage::path::RefPath;
     use url::Url;
 
     // Script simply fetches the smart function given in the path param
@@ -873,6 +1073,288 @@ mod test {
         });
     }
 
+    // A REPL runtime runs smart functions with `fetch` and the ledger of its address.
+    #[test]
+    fn repl_runtime_fetches_smart_function() {
+        TOKIO.block_on(async {
+            // Code
+            let remote = r#"export default async (_req) => new Response("hello world")"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(&mut host, [remote]);
+            let remote_address = hashes[0].clone();
+            let mut runtime = super::new_repl_runtime(
+                &mut host,
+                &mut tx,
+                source_address,
+                remote_address.clone(),
+                vec![],
+            );
+
+            // Run
+            let result = runtime
+                .execute_and_resolve(&format!(
+                    "fetch('jstz://{remote_address}').then((res) => res.text()).then((text) => text + ' from ' + Ledger.selfAddress)"
+                ))
+                .await
+                .unwrap();
+
+            // Assert
+            let scope = &mut runtime.handle_scope();
+            let result = result.open(scope).to_rust_string_lossy(scope);
+            assert_eq!(format!("hello world from {remote_address}"), result);
+        });
+    }
+
+    // Running a smart function caches its compiled code, which is reused by later calls
+    #[test]
+    fn fetch_caches_compiled_code() {
+        TOKIO.block_on(async {
+            // Code
+            let run = r#"export default async (_req) => new Response("cached code")"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, tx, source_address, hashes) = setup(&mut host, [run]);
+            let run_address = hashes[0].clone();
+            let code_hash = CodeHash::from(run.as_bytes());
+
+            for _ in 0..2 {
+                // Run
+                let response = process_and_dispatch_request(
+                    JsHostRuntime::new(&mut host),
+                    tx.clone(),
+                    false,
+                    None,
+                    source_address.clone().into(),
+                    source_address.clone().into(),
+                    "GET".into(),
+                    Url::parse(format!("jstz://{}", run_address).as_str()).unwrap(),
+                    vec![],
+                    None,
+                    Limiter::default(),
+                )
+                .await;
+
+                // Assert
+                assert_eq!(
+                    "cached code",
+                    String::from_utf8(response.body.into()).unwrap()
+                );
+                assert!(CODE_CACHE.is_compiled(&code_hash));
+            }
+        });
+    }
+
+    // Smart functions deployed for the v1 runtime do not run on the v2 runtime
+    #[cfg(not(feature = "v1_runtime"))]
+    #[test]
+    fn fetch_rejects_v1_smart_function() {
+        TOKIO.block_on(async {
+            // Code
+            let run = r#"export default async (_req) => new Response("hello world")"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(&mut host, [run]);
+            let run_address = hashes[0].clone();
+            Account::set_runtime_version(
+                &host,
+                &mut tx,
+                &run_address,
+                RuntimeVersion::V1,
+            )
+            .unwrap();
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx,
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", run_address).as_str()).unwrap(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(response.status, 500);
+            let body =
+                serde_json::from_slice::<JsonValue>(response.body.to_vec().as_slice())
+                    .unwrap();
+            assert_eq!(
+                body["message"],
+                format!(
+                    "Smart function '{run_address}' was deployed for the v1 runtime and cannot run on the v2 runtime"
+                )
+            );
+        });
+    }
+
+    // Kv writes are accounted for in the storage usage of the smart function
+    // Builds with both runtimes run smart functions deployed for the v1 runtime
+    // from the v2 runtime and the other way around
+    #[cfg(feature = "v1_runtime")]
+    #[test]
+    fn fetch_calls_across_runtimes() {
+        TOKIO.block_on(async {
+            // Code
+            let call = r#"export default (req) => fetch(new Request(`jstz://${req.headers.get("callee")}/`, { headers: { callee: req.headers.get("next") ?? "" } }))"#;
+            let reply = r#"export default (req) => new Response(`hello from ${req.headers.get("referer")}`)"#;
+
+            // Setup: a v2 smart function calls a v1 one, which calls a v2 one
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, hashes) =
+                setup(&mut host, [call, call, reply]);
+            Account::set_runtime_version(&host, &mut tx, &hashes[1], RuntimeVersion::V1)
+                .unwrap();
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx,
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", hashes[0]).as_str()).unwrap(),
+                vec![
+                    ("callee".into(), hashes[1].to_string().into()),
+                    ("next".into(), hashes[2].to_string().into()),
+                ],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(response.status, 200);
+            assert_eq!(
+                String::from_utf8(response.body.to_vec()).unwrap(),
+                format!("hello from {}", hashes[1])
+            );
+        });
+    }
+
+    #[test]
+    fn fetch_accounts_kv_storage() {
+        TOKIO.block_on(async {
+            // Code
+            let run = r#"export default async (req) => {
+                const size = Number(new URL(req.url).pathname.substring(1));
+                Kv.set("key", "x".repeat(size));
+                return new Response();
+            }"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(&mut host, [run]);
+            let run_address = hashes[0].clone();
+            Account::add_balance(&host, &mut tx, &run_address, 1000).unwrap();
+            let params = ProtocolParams {
+                storage_quota: 100,
+                storage_deposit_per_byte: 2,
+                ..Default::default()
+            };
+
+            for (size, status) in [(10, 200), (200, 500)] {
+                // Run
+                let response = process_and_dispatch_request(
+                    JsHostRuntime::new(&mut host),
+                    tx.clone(),
+                    false,
+                    None,
+                    source_address.clone().into(),
+                    source_address.clone().into(),
+                    "GET".into(),
+                    Url::parse(format!("jstz://{}/{}", run_address, size).as_str())
+                        .unwrap(),
+                    vec![],
+                    None,
+                    params.limiter(u64::MAX),
+                )
+                .await;
+
+                // Assert
+                assert_eq!(response.status, status);
+                // `key` takes 3 bytes and `"xxxxxxxxxx"` 12 bytes
+                assert_eq!(
+                    Account::storage_usage(&host, &tx, &run_address).unwrap(),
+                    StorageUsage {
+                        bytes: 15,
+                        deposit: 30
+                    }
+                );
+                assert_eq!(Account::balance(&host, &mut tx, &run_address).unwrap(), 970);
+            }
+        });
+    }
+
+    // Smart functions import deployed libraries, which import other libraries
+    #[test]
+    fn fetch_imports_libraries() {
+        TOKIO.block_on(async {
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, []) = setup(&mut host, []);
+            let add = Library::deploy(
+                &host,
+                &mut tx,
+                "export const add = (a, b) => a + b;".to_string(),
+            )
+            .unwrap();
+            let double = Library::deploy(
+                &host,
+                &mut tx,
+                format!(
+                    r#"import {{ add }} from "{}";
+                    export const double = (a) => add(a, a);"#,
+                    Library::specifier(&add)
+                ),
+            )
+            .unwrap();
+            let run = format!(
+                r#"import {{ double }} from "{}";
+                export default () => new Response(`${{double(21)}}`);"#,
+                Library::specifier(&double)
+            );
+            let [run_address] = deploy_smart_functions(
+                [run.as_str()],
+                &host,
+                &mut tx,
+                &source_address,
+                0,
+            );
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.clone().into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}/", run_address).as_str()).unwrap(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(response.status, 200);
+            assert_eq!(String::from_utf8(response.body.to_vec()).unwrap(), "42");
+        });
+    }
+
     // Fetch rejects unsupported schemes runs a smart function.
     #[test]
     fn fetch_rejects_unsupported_scheme() {
@@ -1243,9 +1725,8 @@ mod test {
                 .get(&mut host, &mut tx, "value")
                 .unwrap()
                 .unwrap()
-                .0
                 .clone();
-            assert_eq!(2, serde_json::from_value::<usize>(result).unwrap());
+            assert_eq!(result, KvValue::Json(json!(2)));
         });
     }
 
@@ -1719,6 +2200,55 @@ mod test {
         });
     }
 
+    #[test]
+    fn transaction_rolled_back_when_out_of_memory() {
+        TOKIO.block_on(async {
+            // Code
+            let remote = r#"export default async (_req) => {
+            Kv.set("test", 123)
+            const chunks = [];
+            while (true) {
+                chunks.push(new Array(1024 * 1024).fill(42));
+            }
+        }"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, tx, _, hashes) = setup(&mut host, [remote]);
+            let remote_address = hashes[0].clone();
+            let limiter = Limiter::default().with_heap_limit(16 * 1024 * 1024);
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                jstz_mock::account1().into(),
+                jstz_mock::account1().into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", remote_address).as_str()).unwrap(),
+                vec![],
+                None,
+                limiter.clone(),
+            )
+            .await;
+
+            assert_eq!(500, response.status);
+            assert_eq!(
+                json!({"class":"RangeError","message":"Out of memory"}),
+                serde_json::from_slice::<JsonValue>(response.body.to_vec().as_slice())
+                    .unwrap()
+            );
+            assert!(limiter.heap().peak() >= 16 * 1024 * 1024);
+
+            let kv = crate::runtime::Kv::new(remote_address.to_string());
+            let mut tx = tx;
+            let result = kv.get(&mut host, &mut tx, "test").unwrap();
+            assert!(result.is_none())
+        });
+    }
+
     // Error behaviour
 
     // Errors that are a result of evaluating the request (server side issues) are converted
@@ -1775,7 +2305,7 @@ mod test {
 
         let mut tx = jstz_core::kv::Transaction::default();
         tx.begin();
-        let limiter: Limiter<5> = Limiter::default();
+        let limiter = Limiter::default();
         let protocol = Some(RuntimeContext::new(
             &mut host,
             &mut tx,
@@ -2182,6 +2712,100 @@ mod test {
         });
     }
 
+    #[test]
+    fn handle_l1_call_endpoint() {
+        TOKIO.block_on(async {
+            // Code
+            let run = SIMPLE_REMOTE_CALLER;
+            let remote = r#"export default async (req) => {
+                const response = await fetch(`jstz://jstz/l1-call`, {
+                    method: 'POST',
+                    body: JSON.stringify({
+                        destination: "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV",
+                        entrypoint: "default",
+                        parameters: { prim: "Pair", args: [{ int: "1" }, { string: "foo" }] },
+                    }),
+                });
+                return response;
+            }"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, tx, _source_address, hashes) = setup(&mut host, [run, remote]);
+            let run_address = hashes[0].clone();
+            let remote_address = hashes[1].clone();
+            let url =
+                Url::parse(format!("jstz://{}/{}", run_address, remote_address).as_str())
+                    .unwrap();
+
+            // Run as an unauthorised caller
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                jstz_mock::account1().into(),
+                jstz_mock::account1().into(),
+                "GET".into(),
+                url.clone(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            assert_eq!(400, response.status);
+            assert!(String::from_utf8(response.body.to_vec())
+                .unwrap()
+                .contains("UnauthorisedCaller"));
+
+            // Run as an authorised caller
+            Storage::insert(
+                &mut host,
+                &RefPath::assert_from(L1_CALLERS_PATH.as_bytes()),
+                &vec![L1Caller {
+                    address: remote_address.clone(),
+                    destinations: vec![L1Destination {
+                        address: SmartFunctionHash::from_base58(
+                            "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV",
+                        )
+                        .unwrap()
+                        .0
+                        .into(),
+                        entrypoint: "default".to_string(),
+                        parameter_type: serde_json::from_value(json!({
+                            "prim": "pair",
+                            "args": [{ "prim": "int" }, { "prim": "string" }]
+                        }))
+                        .unwrap(),
+                    }],
+                }],
+            )
+            .unwrap();
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                jstz_mock::account1().into(),
+                jstz_mock::account1().into(),
+                "GET".into(),
+                url,
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            assert_eq!(200, response.status);
+            let receipt =
+                serde_json::from_slice::<JsonValue>(response.body.to_vec().as_slice())
+                    .unwrap();
+            assert_eq!(receipt["source"], json!(remote_address.to_string()));
+            assert_eq!(receipt["entrypoint"], json!("default"));
+        });
+    }
+
     #[test]
     fn unsupported_host_endpoint_returns_404() {
         TOKIO.block_on(async {
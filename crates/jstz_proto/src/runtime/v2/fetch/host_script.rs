@@ -6,6 +6,7 @@ use jstz_core::{host::HostRuntime, kv::Transaction};
 use url::Url;
 
 use crate::context::account::{Account, Address};
+use crate::executor::{l1_call::L1Call, smart_function::L1_CALL_PATH};
 
 pub struct HostScript;
 
@@ -16,11 +17,15 @@ impl HostScript {
         from: Address,
         method: ByteString,
         url: &Url,
+        data: Option<Body>,
     ) -> Result<Response> {
         let path = url.path();
         if path.starts_with("/balances") {
             return Self::handle_balance(host, tx, from, method, url).await;
         }
+        if path == L1_CALL_PATH {
+            return Self::handle_l1_call(host, tx, from, method, data);
+        }
 
         // Return 404 for all other paths
         Ok(Response {
@@ -84,4 +89,49 @@ impl HostScript {
         Account::balance(host, tx, &target_address)
             .map_err(|e| FetchError::JstzError(e.to_string()))
     }
+
+    /// Queues a call to an L1 contract on behalf of `self_address`. See [L1Call].
+    pub fn handle_l1_call(
+        host: &mut impl HostRuntime,
+        tx: &mut Transaction,
+        self_address: Address,
+        method: ByteString,
+        data: Option<Body>,
+    ) -> Result<Response> {
+        if method != "POST".into() {
+            return Ok(Response {
+                status: 405,
+                status_text: "Method Not Allowed".to_string(),
+                headers: vec![],
+                body: Body::Vector("Only POST method is allowed".as_bytes().to_vec()),
+            });
+        }
+
+        let l1_call = data
+            .ok_or_else(|| "Missing request body".to_string())
+            .and_then(|data| {
+                serde_json::from_slice::<L1Call>(&data.to_vec())
+                    .map_err(|e| e.to_string())
+            });
+        let receipt = l1_call.and_then(|l1_call| {
+            let receipt = l1_call
+                .execute(host, tx, &self_address)
+                .map_err(|e| e.to_string())?;
+            serde_json::to_string(&receipt).map_err(|e| e.to_string())
+        });
+        match receipt {
+            Ok(receipt) => Ok(Response {
+                status: 200,
+                status_text: "OK".to_string(),
+                headers: vec![],
+                body: receipt.into(),
+            }),
+            Err(e) => Ok(Response {
+                status: 400,
+                status_text: "Bad Request".to_string(),
+                headers: vec![],
+                body: e.into(),
+            }),
+        }
+    }
 }
@@ -10,6 +10,18 @@ class Ledger {
   static transfer(dst, amount) {
     return globalThis.Deno.core.ops.op_transfer(dst, amount);
   }
+
+  static withdraw(receiver, amount) {
+    return globalThis.Deno.core.ops.op_withdraw(receiver, amount);
+  }
+
+  static selfDestruct(beneficiary) {
+    return globalThis.Deno.core.ops.op_self_destruct(beneficiary);
+  }
+
+  static outboxCapacity() {
+    return globalThis.Deno.core.ops.op_outbox_capacity();
+  }
 }
 
 Object.defineProperties(globalThis, {
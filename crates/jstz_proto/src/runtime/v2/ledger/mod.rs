@@ -1,9 +1,15 @@
 use deno_core::{extension, op2, OpState};
 
 use jstz_crypto::hash::Hash;
-use jstz_runtime::RuntimeContext;
+use jstz_runtime::{
+    gas::{self, cost, OutOfGas},
+    RuntimeContext,
+};
 
-use crate::context::account::{Account, Address};
+use crate::{
+    context::account::{Account, Address},
+    executor::withdraw::{execute_smart_function_withdraw, Withdrawal},
+};
 
 #[op2]
 #[string]
@@ -15,6 +21,7 @@ fn op_self_address(state: &mut OpState) -> String {
 #[op2(fast)]
 #[number]
 fn op_balance(state: &mut OpState, #[string] address: String) -> Result<u64> {
+    gas::charge(state, cost::LEDGER)?;
     let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
     let address = Address::from_base58(&address)?;
     Ok(Account::balance(host, tx, &address)?)
@@ -26,6 +33,7 @@ fn op_transfer(
     #[string] dest_address: String,
     #[number] amount: u64,
 ) -> Result<()> {
+    gas::charge(state, cost::LEDGER)?;
     let RuntimeContext {
         host, tx, address, ..
     } = state.borrow_mut::<RuntimeContext>();
@@ -33,6 +41,50 @@ fn op_transfer(
     Ok(Account::transfer(host, tx, address, &dest, amount)?)
 }
 
+#[op2(fast)]
+fn op_withdraw(
+    state: &mut OpState,
+    #[string] receiver: String,
+    #[number] amount: u64,
+) -> Result<()> {
+    gas::charge(state, cost::LEDGER)?;
+    let RuntimeContext {
+        host, tx, address, ..
+    } = state.borrow_mut::<RuntimeContext>();
+    let receiver = Address::from_base58(&receiver)?;
+    Ok(execute_smart_function_withdraw(
+        host,
+        tx,
+        address,
+        Withdrawal { amount, receiver },
+    )?)
+}
+
+#[op2(fast)]
+#[number]
+fn op_self_destruct(state: &mut OpState, #[string] beneficiary: String) -> Result<u64> {
+    gas::charge(state, cost::LEDGER)?;
+    let RuntimeContext {
+        host, tx, address, ..
+    } = state.borrow_mut::<RuntimeContext>();
+    let beneficiary = Address::from_base58(&beneficiary)?;
+    Ok(Account::delete_smart_function(
+        host,
+        tx,
+        address,
+        &beneficiary,
+    )?)
+}
+
+#[op2(fast)]
+fn op_outbox_capacity(state: &mut OpState) -> Result<u32> {
+    gas::charge(state, cost::LEDGER)?;
+    let RuntimeContext { host, tx, .. } = state.borrow_mut::<RuntimeContext>();
+    Ok(tx
+        .outbox_capacity(host)
+        .map_err(crate::error::Error::from)?)
+}
+
 pub type Result<T> = std::result::Result<T, LedgerError>;
 
 #[derive(Debug, thiserror::Error, deno_error::JsError)]
@@ -45,6 +97,10 @@ pub enum LedgerError {
     #[class(generic)]
     #[error("{0}")]
     V1Error(String),
+
+    #[class(inherit)]
+    #[error(transparent)]
+    OutOfGas(#[from] OutOfGas),
 }
 
 impl From<crate::error::Error> for LedgerError {
@@ -55,7 +111,14 @@ impl From<crate::error::Error> for LedgerError {
 
 extension!(
     jstz_ledger,
-    ops = [op_self_address, op_balance, op_transfer],
+    ops = [
+        op_self_address,
+        op_balance,
+        op_transfer,
+        op_withdraw,
+        op_self_destruct,
+        op_outbox_capacity
+    ],
     esm_entry_point = "ext:jstz_ledger/ledger.js",
     esm = [dir "src/runtime/v2/ledger", "ledger.js"]
 );
@@ -67,8 +130,11 @@ mod test {
     use jstz_utils::test_util::TOKIO_MULTI_THREAD;
     use url::Url;
 
+    use jstz_mock::host::JstzMockHost;
+
     use crate::{
         context::account::Account,
+        executor::withdraw::{take_withdrawal_log, WithdrawalReceipt},
         runtime::v2::{
             fetch::fetch_handler::process_and_dispatch_request, test_utils::*,
         },
@@ -195,4 +261,145 @@ mod test {
             )
         })
     }
+    #[test]
+    fn self_destruct() {
+        TOKIO_MULTI_THREAD.block_on(async {
+            // Code
+            let run = r#"export default async (request) => {
+                let referer = request.headers.get("referer");
+                return new Response(Ledger.selfDestruct(referer))
+            }"#;
+
+            // Setup
+            let mut host = tezos_smart_rollup_mock::MockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(&mut host, [run]);
+            let run_address = hashes[0].clone();
+            Account::add_balance(&host, &mut tx, &run_address, 1_000_000_000).unwrap();
+            let source_balance =
+                Account::balance(&host, &mut tx, &source_address).unwrap();
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.clone().into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", run_address).as_str()).unwrap(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(
+                "1000000000",
+                String::from_utf8(response.body.to_vec()).unwrap()
+            );
+            assert!(!Account::exists(&host, &tx, &run_address).unwrap());
+            assert!(Account::is_deleted(&host, &tx, &run_address).unwrap());
+            assert_eq!(
+                source_balance + 1_000_000_000,
+                Account::balance(&host, &mut tx, &source_address).unwrap()
+            )
+        })
+    }
+
+    #[test]
+    fn withdraw() {
+        TOKIO_MULTI_THREAD.block_on(async {
+            // Code
+            let run = r#"export default async (request) => {
+                let referer = request.headers.get("referer");
+                Ledger.withdraw(referer, 500 * 1000000);
+                return new Response()
+            }"#;
+
+            // Setup
+            let mut mock_host = JstzMockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(mock_host.rt(), [run]);
+            let run_address = hashes[0].clone();
+            Account::add_balance(&host, &mut tx, &run_address, 1_000_000_000).unwrap();
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx.clone(),
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.clone().into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", run_address).as_str()).unwrap(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(200, response.status);
+            assert_eq!(
+                500_000_000,
+                Account::balance(&host, &mut tx, &run_address).unwrap()
+            );
+            assert_eq!(
+                vec![WithdrawalReceipt {
+                    amount: 500_000_000,
+                    receiver: source_address.into(),
+                    outbox_message_id: None
+                }],
+                take_withdrawal_log(&host, &mut tx).unwrap()
+            );
+            tx.commit(&mut host).unwrap();
+            let level = mock_host.rt().run_level(|_| {});
+            assert_eq!(1, mock_host.rt().outbox_at(level).len());
+        })
+    }
+
+    #[test]
+    fn outbox_capacity() {
+        TOKIO_MULTI_THREAD.block_on(async {
+            // Code
+            let run = r#"export default async (request) => {
+                let referer = request.headers.get("referer");
+                Ledger.withdraw(referer, 1000000);
+                return new Response(Ledger.outboxCapacity())
+            }"#;
+
+            // Setup
+            let mut mock_host = JstzMockHost::default();
+            let (mut host, mut tx, source_address, hashes) = setup(mock_host.rt(), [run]);
+            let run_address = hashes[0].clone();
+            Account::add_balance(&host, &mut tx, &run_address, 1_000_000_000).unwrap();
+
+            // Run
+            let response = process_and_dispatch_request(
+                JsHostRuntime::new(&mut host),
+                tx,
+                false,
+                None,
+                source_address.clone().into(),
+                source_address.into(),
+                "GET".into(),
+                Url::parse(format!("jstz://{}", run_address).as_str()).unwrap(),
+                vec![],
+                None,
+                Limiter::default(),
+            )
+            .await;
+
+            // Assert
+            assert_eq!(
+                u16::MAX as u32 - 1,
+                String::from_utf8(response.body.to_vec())
+                    .unwrap()
+                    .parse::<u32>()
+                    .unwrap()
+            )
+        })
+    }
 }
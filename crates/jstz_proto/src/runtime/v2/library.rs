@@ -0,0 +1,250 @@
+//! Library modules shared between smart functions.
+//!
+//! A library is an ES module deployed with [`DeployLibrary`] and addressed by the
+//! hash of its code. Smart functions and other libraries import it with
+//! `import lib from "jstz-lib:<hash>"`. Deploying the same code twice stores it once.
+//!
+//! [`DeployLibrary`]: crate::operation::DeployLibrary
+
+use std::{cell::RefCell, collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use bincode::{Decode, Encode};
+use deno_core::{
+    resolve_import, ModuleLoadResponse, ModuleLoader, ModuleSource, ModuleSourceCode,
+    ModuleSpecifier, ModuleType, RequestedModuleType, ResolutionKind,
+};
+use deno_error::JsErrorBox;
+use jstz_core::{
+    host::{HostRuntime, JsHostRuntime},
+    kv::Transaction,
+};
+use jstz_crypto::hash::Blake2b;
+use tezos_smart_rollup::storage::path::{self, OwnedPath, RefPath};
+
+use crate::error::Result;
+
+use super::{
+    code_cache::{CodeHash, CODE_CACHE},
+    ParsedCode,
+};
+
+pub type LibraryHash = Blake2b;
+
+const LIBRARIES_PATH: RefPath = RefPath::assert_from(b"/jstz_library");
+
+/// Scheme of library import specifiers, distinct from the `jstz` scheme of
+/// smart function URLs
+pub const LIBRARY_SCHEME: &str = "jstz-lib";
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Library {
+    pub code: String,
+}
+
+impl Library {
+    fn path(hash: &LibraryHash) -> Result<OwnedPath> {
+        let library_path = OwnedPath::try_from(format!("/{}", hash))?;
+        Ok(path::concat(&LIBRARIES_PATH, &library_path)?)
+    }
+
+    /// Stores `code` as a library and returns its hash. Fails if `code` is not
+    /// a valid library.
+    pub fn deploy(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        code: String,
+    ) -> Result<LibraryHash> {
+        ParsedCode::parse_library(&code).map_err(super::Error::from)?;
+        let hash = LibraryHash::from(code.as_bytes());
+        let path = Self::path(&hash)?;
+        if !tx.contains_key(hrt, &path)? {
+            tx.insert(path, Library { code })?;
+        }
+        Ok(hash)
+    }
+
+    /// Returns true if a library with `hash` is deployed
+    pub fn is_deployed(
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        hash: &LibraryHash,
+    ) -> Result<bool> {
+        Ok(tx.contains_key(hrt, &Self::path(hash)?)?)
+    }
+
+    /// Returns the code of the library with `hash`, if any
+    pub fn code(
+        hrt: &impl HostRuntime,
+        tx: &Transaction,
+        hash: &LibraryHash,
+    ) -> Result<Option<String>> {
+        let is_dirty = tx.get_dirty();
+        let result = tx
+            .get::<Library>(hrt, Self::path(hash)?)?
+            .map(|library| library.code.clone());
+        tx.set_dirty(is_dirty);
+        Ok(result)
+    }
+
+    /// Returns the specifier used to import the library with `hash`
+    pub fn specifier(hash: &LibraryHash) -> String {
+        format!("{LIBRARY_SCHEME}:{hash}")
+    }
+
+    /// Returns the library hash of an import specifier of the form `jstz-lib:<hash>`
+    pub fn hash_from_specifier(specifier: &str) -> Option<LibraryHash> {
+        let hash = specifier.strip_prefix(LIBRARY_SCHEME)?.strip_prefix(':')?;
+        LibraryHash::try_parse(hash.to_string()).ok()
+    }
+}
+
+/// Loads the main module of a smart function along with the libraries it imports.
+///
+/// Library code is instrumented for gas metering like smart function code. Compiled
+/// modules are stored in the [`CODE_CACHE`].
+pub struct LibraryModuleLoader {
+    host: JsHostRuntime<'static>,
+    tx: Transaction,
+    main_specifier: ModuleSpecifier,
+    main_code: (CodeHash, Arc<str>),
+    // Code hashes of the loaded modules
+    loaded: RefCell<HashMap<ModuleSpecifier, CodeHash>>,
+}
+
+impl LibraryModuleLoader {
+    /// `main_code` is the hash of the smart function code and the code instrumented
+    /// for gas metering, as returned by [`CODE_CACHE`]
+    pub fn new(
+        host: JsHostRuntime<'static>,
+        tx: Transaction,
+        main_specifier: ModuleSpecifier,
+        main_code: (CodeHash, Arc<str>),
+    ) -> Self {
+        Self {
+            host,
+            tx,
+            main_specifier,
+            main_code,
+            loaded: Default::default(),
+        }
+    }
+
+    fn load_code(
+        &self,
+        specifier: &ModuleSpecifier,
+    ) -> std::result::Result<(CodeHash, Arc<str>), JsErrorBox> {
+        if specifier == &self.main_specifier {
+            return Ok(self.main_code.clone());
+        }
+        let not_found =
+            || JsErrorBox::type_error(format!("Module not found \"{specifier}\""));
+        let hash =
+            Library::hash_from_specifier(specifier.as_str()).ok_or_else(not_found)?;
+        let code = Library::code(&self.host, &self.tx, &hash)
+            .map_err(|err| JsErrorBox::generic(err.to_string()))?
+            .ok_or_else(not_found)?;
+        CODE_CACHE.instrument(&code).map_err(JsErrorBox::from_err)
+    }
+}
+
+impl ModuleLoader for LibraryModuleLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        referrer: &str,
+        _kind: ResolutionKind,
+    ) -> std::result::Result<ModuleSpecifier, JsErrorBox> {
+        resolve_import(specifier, referrer).map_err(JsErrorBox::from_err)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<&ModuleSpecifier>,
+        _is_dyn_import: bool,
+        _requested_module_type: RequestedModuleType,
+    ) -> ModuleLoadResponse {
+        let source = self.load_code(module_specifier).map(|(hash, code)| {
+            let code_cache = CODE_CACHE.source_code_cache_info(&hash);
+            self.loaded
+                .borrow_mut()
+                .insert(module_specifier.clone(), hash);
+            ModuleSource::new(
+                ModuleType::JavaScript,
+                ModuleSourceCode::String(code.to_string().into()),
+                module_specifier,
+                Some(code_cache),
+            )
+        });
+        ModuleLoadResponse::Sync(source)
+    }
+
+    fn code_cache_ready(
+        &self,
+        module_specifier: ModuleSpecifier,
+        _hash: u64,
+        code_cache: &[u8],
+    ) -> Pin<Box<dyn Future<Output = ()>>> {
+        if let Some(hash) = self.loaded.borrow().get(&module_specifier) {
+            CODE_CACHE.set_compiled(hash, code_cache);
+        }
+        Box::pin(async {})
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jstz_core::kv::Transaction;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{Library, LibraryHash};
+
+    #[test]
+    fn deploy_library() {
+        let host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+
+        let code = "export const add = (a, b) => a + b;".to_string();
+        let hash = Library::deploy(&host, &mut tx, code.clone()).unwrap();
+        assert_eq!(hash, LibraryHash::from(code.as_bytes()));
+        assert_eq!(
+            Library::code(&host, &tx, &hash).unwrap(),
+            Some(code.clone())
+        );
+
+        // Deploying the same code again is idempotent
+        assert_eq!(Library::deploy(&host, &mut tx, code).unwrap(), hash);
+    }
+
+    #[test]
+    fn deploy_invalid_library_fails() {
+        let host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+
+        assert!(Library::deploy(&host, &mut tx, "invalid js".to_string()).is_err());
+        assert!(Library::deploy(
+            &host,
+            &mut tx,
+            "import foo from \"foo\"; export default foo;".to_string()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn library_specifier() {
+        let hash = LibraryHash::from("code".as_bytes());
+        let specifier = Library::specifier(&hash);
+        assert_eq!(Library::hash_from_specifier(&specifier), Some(hash));
+        assert_eq!(
+            Library::hash_from_specifier("jstz://KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton"),
+            None
+        );
+        assert_eq!(
+            Library::hash_from_specifier(&format!("jstz://{hash}")),
+            None
+        );
+        assert_eq!(Library::hash_from_specifier("foo"), None);
+    }
+}
@@ -15,13 +15,23 @@ use jstz_core::{
     kv::Transaction,
 };
 use jstz_runtime::runtime::Limiter;
+use protocol_context::ProtocolParams;
+use tezos_smart_rollup::prelude::debug_msg;
 use url::Url;
+#[cfg(feature = "v1_runtime")]
+use {
+    crate::context::account::Address, fetch::http::Response,
+    jstz_crypto::smart_function_hash::SmartFunctionHash,
+};
 pub mod fetch;
+pub use fetch::fetch_handler::new_repl_runtime;
 pub use jstz_core::log_record::{LogRecord, LOG_PREFIX};
 pub use jstz_runtime::{Kv, KvValue};
+pub mod code_cache;
 mod parsed_code;
 pub use parsed_code::ParsedCode;
 mod ledger;
+pub mod library;
 pub mod oracle;
 pub mod protocol_context;
 
@@ -34,7 +44,22 @@ pub async fn run_toplevel_fetch(
     run_operation: RunFunction,
     operation_hash: OperationHash,
 ) -> Result<RunFunctionReceipt, crate::Error> {
-    Ok(run(hrt, tx, source_address, run_operation, operation_hash).await?)
+    let limiter = ProtocolParams::load(hrt)?.limiter(run_operation.gas_limit as u64);
+    let receipt = run(
+        hrt,
+        tx,
+        source_address,
+        run_operation,
+        operation_hash,
+        limiter.clone(),
+    )
+    .await?;
+    if limiter.gas().is_exhausted() {
+        return Err(crate::Error::GasLimitExceeded {
+            gas_used: limiter.gas().used(),
+        });
+    }
+    Ok(receipt)
 }
 
 async fn run(
@@ -43,6 +68,7 @@ async fn run(
     source_address: &(impl Addressable + 'static),
     run_operation: RunFunction,
     operation_hash: OperationHash,
+    limiter: Limiter,
 ) -> Result<RunFunctionReceipt, Error> {
     let RunFunction {
         uri,
@@ -51,6 +77,9 @@ async fn run(
         headers,
         gas_limit: _,
     } = run_operation;
+    let gas = limiter.gas().clone();
+    let heap = limiter.heap().clone();
+    let hash = operation_hash.to_string();
 
     let url = Url::parse(uri.to_string().as_str()).map_err(FetchError::from)?;
     let body = body.0.map(Body::Vector);
@@ -65,14 +94,74 @@ async fn run(
         url,
         convert_header_map(headers),
         body,
-        Limiter::default(),
+        limiter,
     )
     .await
     .into();
+    // The peak heap size depends on garbage collection, so it is only logged
+    // rather than stored in the receipt
+    debug_msg!(
+        hrt,
+        "[📜] Peak heap size of operation {}: {} bytes\n",
+        hash,
+        heap.peak()
+    );
     Ok(RunFunctionReceipt {
         body: response.body().clone().into(),
         status_code: response.status().clone(),
         headers: response.headers().clone(),
+        withdrawals: vec![],
+        gas_used: gas.used(),
+    })
+}
+
+/// Runs the v2 smart function at `address` for a request made from the v1 runtime.
+/// The caller is responsible for the transfers, headers and transaction of the call.
+#[cfg(feature = "v1_runtime")]
+pub async fn run_nested_fetch(
+    hrt: &mut impl HostRuntime,
+    tx: &mut Transaction,
+    source: Address,
+    address: SmartFunctionHash,
+    run_operation: RunFunction,
+    operation_hash: OperationHash,
+) -> Result<RunFunctionReceipt, crate::Error> {
+    let RunFunction {
+        uri,
+        body,
+        method,
+        headers,
+        gas_limit,
+    } = run_operation;
+    let limiter = ProtocolParams::load(hrt)?.limiter(gas_limit as u64);
+    let gas = limiter.gas().clone();
+    let url = Url::parse(uri.to_string().as_str())
+        .map_err(|err| Error::FetchError(err.into()))?;
+    let response = fetch::fetch_handler::run_nested(
+        hrt,
+        tx,
+        &operation_hash,
+        source,
+        address,
+        method.to_string().into(),
+        &url,
+        convert_header_map(headers),
+        body.0.map(Body::Vector),
+        limiter,
+    )
+    .await;
+    let response: http::Response<Option<Vec<u8>>> = Response::from(response).into();
+    if gas.is_exhausted() {
+        return Err(crate::Error::GasLimitExceeded {
+            gas_used: gas.used(),
+        });
+    }
+    Ok(RunFunctionReceipt {
+        body: response.body().clone().into(),
+        status_code: response.status(),
+        headers: response.headers().clone(),
+        withdrawals: vec![],
+        gas_used: gas.used(),
     })
 }
 
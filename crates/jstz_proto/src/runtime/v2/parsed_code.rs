@@ -9,6 +9,8 @@ use jstz_runtime::{sys::FromV8, JstzRuntime, JstzRuntimeOptions};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use super::library::Library;
+
 #[derive(
     Clone,
     Debug,
@@ -67,8 +69,12 @@ impl ParsedCode {
      * ```
      */
     /// Parses the given JavaScript code. Checks that the code is valid JavaScript
-    /// syntax, compiles into an ES Module, and checks that the module has a default
-    /// export handler
+    /// syntax, compiles into an ES Module once instrumented for gas metering, and
+    /// checks that the module has a default export handler
+    ///
+    /// Code may only import libraries (`jstz-lib:<hash>`). Since libraries are resolved
+    /// when the smart function runs, code with imports is only checked for syntax and
+    /// for a default export.
     ///
     /// Note that even if code is parsable, it may not be a valid smart function if it
     /// does not have the correct argument and return types
@@ -80,21 +86,15 @@ impl ParsedCode {
         });
         let scope = &mut runtime.handle_scope();
 
-        let script_origin = script_origin(scope, "code".to_string()).unwrap();
-        let local_code = v8::String::new(scope, &code).unwrap();
-        let mut source =
-            v8::script_compiler::Source::new(local_code, Some(&script_origin));
-
-        let tc_scope = &mut v8::TryCatch::new(scope);
-
-        // Compiles the source code, catching syntax and reference errors
-        let module = v8::script_compiler::compile_module(tc_scope, &mut source);
-        if tc_scope.has_caught() {
-            let exception = tc_scope.exception().unwrap();
-            return Err(CompileModuleError::from_caught(tc_scope, exception)?.into());
+        let module = compile_module(scope, &code)?;
+        if has_library_imports(scope, &module)? {
+            if !jstz_runtime::gas::has_default_export(&code)? {
+                return Err(ParseError::NoDefaultHandler);
+            }
+            return Ok(ParsedCode(code));
         }
 
-        let module = module.unwrap();
+        let tc_scope = &mut v8::TryCatch::new(scope);
 
         // By creating a scope from the previous scope, we tell rust that
         // &module will outlive handles created in the new scope.
@@ -112,14 +112,69 @@ impl ParsedCode {
 
         Ok(ParsedCode(code))
     }
+
+    /// Parses the given JavaScript library code. Checks that the code is valid
+    /// JavaScript syntax, compiles into an ES Module once instrumented for gas
+    /// metering, and only imports other libraries
+    pub fn parse_library(code: &str) -> Result<()> {
+        let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+            // Explicitly switch off protocol
+            protocol: None,
+            ..Default::default()
+        });
+        let scope = &mut runtime.handle_scope();
+
+        let module = compile_module(scope, code)?;
+        has_library_imports(scope, &module)?;
+        Ok(())
+    }
+}
+
+/// Compiles `code` into an ES Module once instrumented for gas metering, catching
+/// syntax and reference errors
+fn compile_module<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    code: &str,
+) -> Result<v8::Local<'s, v8::Module>> {
+    let script_origin = script_origin(scope, "code".to_string()).unwrap();
+    let instrumented_code = jstz_runtime::gas::instrument(code)?;
+    let local_code = v8::String::new(scope, &instrumented_code).unwrap();
+    let mut source = v8::script_compiler::Source::new(local_code, Some(&script_origin));
+
+    let tc_scope = &mut v8::TryCatch::new(scope);
+    let module = v8::script_compiler::compile_module(tc_scope, &mut source);
+    if tc_scope.has_caught() {
+        let exception = tc_scope.exception().unwrap();
+        return Err(CompileModuleError::from_caught(tc_scope, exception)?.into());
+    }
+    Ok(module.unwrap())
+}
+
+/// Returns true if the module imports libraries. Fails if the module imports
+/// anything else
+fn has_library_imports(
+    scope: &mut v8::HandleScope,
+    module: &v8::Local<v8::Module>,
+) -> Result<bool> {
+    let requests = module.get_module_requests();
+    for i in 0..requests.length() {
+        let request =
+            v8::Local::<v8::ModuleRequest>::try_from(requests.get(scope, i).unwrap())
+                .map_err(|_| ParseError::InstantiationFailed)?;
+        let specifier = request.get_specifier().to_rust_string_lossy(scope);
+        if Library::hash_from_specifier(&specifier).is_none() {
+            return Err(ParseError::ImportsNotSupported);
+        }
+    }
+    Ok(requests.length() > 0)
 }
 
 /// Flag to detect import
 #[derive(Deref, DerefMut)]
 struct ImportDetected(bool);
 
-/// Instantiation normally involves resolving module dependencies but since Jstz
-/// only supports bundled modules, we error if module resolution is requested
+/// Instantiation normally involves resolving module dependencies but since modules
+/// without library imports are bundled, we error if module resolution is requested
 fn instantiate_module<'s>(
     scope: &mut v8::HandleScope<'s>,
     module: &v8::Local<v8::Module>,
@@ -254,12 +309,18 @@ pub enum ParseError {
     Other(#[from] jstz_runtime::error::RuntimeError),
 
     #[class(not_supported)]
-    #[error("Import specifiers are not supported")]
+    #[error(
+        "Import specifiers other than libraries (jstz-lib:<hash>) are not supported"
+    )]
     ImportsNotSupported,
 
     #[class(generic)]
     #[error("Failed to instantiate module")]
     InstantiationFailed,
+
+    #[class(inherit)]
+    #[error(transparent)]
+    InstrumentError(#[from] jstz_runtime::gas::InstrumentError),
 }
 
 #[derive(Debug, thiserror::Error, deno_error::JsError)]
@@ -385,7 +446,51 @@ mod test {
         println!("{:?}", error);
         assert!(matches!(error, ParseError::ImportsNotSupported));
         assert_eq!(error.get_class(), "NotSupported");
-        assert_eq!(error.get_message(), "Import specifiers are not supported");
+        assert_eq!(
+            error.get_message(),
+            "Import specifiers other than libraries (jstz-lib:<hash>) are not supported"
+        );
+    }
+
+    #[test]
+    fn parse_with_library_imports_succeeds() {
+        let hash = Library::specifier(&"lib".as_bytes().into());
+        let code = format!(
+            r#"
+            import {{ add }} from "{hash}";
+            export default () => add(1, 2)
+        "#
+        );
+        let parsed_code = ParsedCode::parse(code.clone()).unwrap();
+        assert_eq!(parsed_code.0, code);
+
+        let code = format!(
+            r#"import {{ add }} from "{hash}"; export default () => return add(1, 2)"#
+        );
+        let error = ParsedCode::parse(code).unwrap_err();
+        assert!(matches!(error, ParseError::CompileModuleError(_)));
+
+        let code = format!(r#"import {{ add }} from "{hash}"; export const f = add"#);
+        let error = ParsedCode::parse(code).unwrap_err();
+        assert!(matches!(error, ParseError::NoDefaultHandler));
+    }
+
+    #[test]
+    fn parse_library() {
+        let hash = Library::specifier(&"lib".as_bytes().into());
+        let code = format!(
+            r#"
+            import {{ add }} from "{hash}";
+            export const double = (a) => add(a, a);
+        "#
+        );
+        ParsedCode::parse_library(&code).unwrap();
+
+        let error = ParsedCode::parse_library("invalid js").unwrap_err();
+        assert!(matches!(error, ParseError::CompileModuleError(_)));
+
+        let error = ParsedCode::parse_library(r#"export * from "./foo.js""#).unwrap_err();
+        assert!(matches!(error, ParseError::ImportsNotSupported));
     }
 
     #[test]
@@ -420,4 +525,17 @@ mod test {
         assert_eq!(error.get_class(), "CompileModuleError");
         assert_eq!(error.get_message(), "Error: Uncaught just a string");
     }
+
+    #[test]
+    fn parse_reserved_gas_function_fails() {
+        let code = r#"
+        const __jstz_gas__ = () => {};
+        export default () => 42;
+    "#;
+
+        let error = ParsedCode::parse(code.to_string()).unwrap_err();
+
+        assert!(matches!(error, ParseError::InstrumentError(_)));
+        assert_eq!(error.get_class(), "SyntaxError");
+    }
 }
@@ -1,17 +1,73 @@
 use std::sync::{Arc, OnceLock};
 
-use jstz_core::{host::HostRuntime, kv::Storage};
-use jstz_crypto::public_key::PublicKey;
+use jstz_core::{
+    host::{HostRuntime, JsHostRuntime},
+    kv::{Storage, Transaction},
+};
+use jstz_crypto::{public_key::PublicKey, smart_function_hash::SmartFunctionHash};
+use jstz_runtime::{
+    runtime::Limiter,
+    storage::{StorageAccounting, StorageError},
+};
 use parking_lot::Mutex;
 use tezos_smart_rollup::storage::path::RefPath;
 
-use crate::{storage::ORACLE_PUBLIC_KEY_PATH, BlockLevel};
+pub use crate::context::protocol_params::ProtocolParams;
+use crate::{
+    context::account::{Account, Amount},
+    storage::ORACLE_PUBLIC_KEY_PATH,
+    BlockLevel,
+};
 
 use super::oracle::{Oracle, OracleError};
 
 /// Holds stateful globals required by the protocol
 pub static PROTOCOL_CONTEXT: OnceLock<ProtocolContext> = OnceLock::new();
 
+impl ProtocolParams {
+    /// Creates the limiter of an operation with `gas_limit`
+    pub fn limiter(&self, gas_limit: u64) -> Limiter {
+        Limiter::default()
+            .with_max_call_depth(self.max_call_depth)
+            .with_gas_limit(gas_limit)
+            .with_heap_limit(self.heap_limit as usize)
+            .with_storage(Arc::new(AccountStorage {
+                quota: self.storage_quota,
+                deposit_per_byte: self.storage_deposit_per_byte,
+            }))
+    }
+}
+
+/// Accounts for the Kv storage of smart functions in their [`StorageUsage`]
+///
+/// [`StorageUsage`]: crate::context::account::StorageUsage
+#[derive(Debug)]
+struct AccountStorage {
+    quota: u64,
+    deposit_per_byte: Amount,
+}
+
+impl StorageAccounting for AccountStorage {
+    fn update(
+        &self,
+        host: &mut JsHostRuntime<'static>,
+        tx: &mut Transaction,
+        address: &SmartFunctionHash,
+        delta: i64,
+    ) -> Result<(), StorageError> {
+        Account::update_storage_usage(
+            &*host,
+            tx,
+            address,
+            delta,
+            self.quota,
+            self.deposit_per_byte,
+        )
+        .map(|_| ())
+        .map_err(|err| StorageError(err.to_string()))
+    }
+}
+
 pub struct ProtocolContext {
     oracle: Arc<Mutex<Oracle>>,
     current_level: Arc<Mutex<BlockLevel>>,
@@ -75,3 +131,35 @@ pub enum ProtocolContextError {
     #[error(transparent)]
     OracleFailedToInitialize(#[from] OracleError),
 }
+
+#[cfg(test)]
+mod test {
+    use jstz_runtime::{
+        heap::DEFAULT_HEAP_LIMIT, runtime::MAX_SMART_FUNCTION_CALL_COUNT,
+    };
+
+    use super::ProtocolParams;
+
+    #[test]
+    fn default_protocol_params_match_runtime() {
+        let params = ProtocolParams::default();
+        assert_eq!(params.max_call_depth, MAX_SMART_FUNCTION_CALL_COUNT);
+        assert_eq!(params.heap_limit, DEFAULT_HEAP_LIMIT as u64);
+    }
+
+    #[test]
+    fn protocol_params_limiter() {
+        let params = ProtocolParams {
+            max_call_depth: 2,
+            heap_limit: 1024,
+            storage_quota: 4096,
+            storage_deposit_per_byte: 1,
+            ..Default::default()
+        };
+        let limiter = params.limiter(100);
+        assert_eq!(limiter.max_call_depth(), 2);
+        assert_eq!(limiter.gas().limit(), 100);
+        assert_eq!(limiter.heap().limit(), 1024);
+        assert!(limiter.try_acquire().unwrap().storage().is_some());
+    }
+}
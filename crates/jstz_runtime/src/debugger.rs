@@ -0,0 +1,134 @@
+//! Hooks to debug smart functions with the V8 inspector.
+//!
+//! A [`Debugger`] set with [`set_debugger`] enables the inspector of the runtimes
+//! created afterwards on the same thread. The protocol passes the runtime of every
+//! smart function call to [`attach`] before running it, so that the debugger can
+//! connect a DevTools session to it.
+
+use std::{cell::RefCell, rc::Rc};
+
+use jstz_crypto::smart_function_hash::SmartFunctionHash;
+
+use crate::JstzRuntime;
+
+thread_local! {
+    static DEBUGGER: RefCell<Option<Rc<dyn Debugger>>> = const { RefCell::new(None) };
+}
+
+/// Debugger of the smart function calls run on the current thread
+pub trait Debugger {
+    /// Called with the runtime of a call to the smart function at `address`, before
+    /// the smart function is loaded
+    fn attach(&self, runtime: &mut JstzRuntime, address: &SmartFunctionHash);
+}
+
+/// Sets the debugger of the current thread, or removes it with `None`
+pub fn set_debugger(debugger: Option<Rc<dyn Debugger>>) {
+    DEBUGGER.with(|cell| *cell.borrow_mut() = debugger);
+}
+
+/// Returns true if the current thread has a debugger
+pub fn is_enabled() -> bool {
+    DEBUGGER.with(|cell| cell.borrow().is_some())
+}
+
+/// Attaches the debugger of the current thread, if any, to `runtime`
+pub fn attach(runtime: &mut JstzRuntime, address: &SmartFunctionHash) {
+    // Released before attaching, since the debugger may create runtimes itself
+    let debugger = DEBUGGER.with(|cell| cell.borrow().clone());
+    if let Some(debugger) = debugger {
+        debugger.attach(runtime, address);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc, thread};
+
+    use deno_core::{
+        futures::{channel::mpsc, executor::block_on_stream},
+        InspectorMsg, InspectorSessionKind, InspectorSessionProxy,
+    };
+    use jstz_crypto::smart_function_hash::SmartFunctionHash;
+
+    use super::{attach, is_enabled, set_debugger, Debugger};
+    use crate::{init_test_setup, JstzRuntime};
+
+    #[derive(Default)]
+    struct Recorder(RefCell<Vec<SmartFunctionHash>>);
+
+    impl Debugger for Recorder {
+        fn attach(&self, _runtime: &mut JstzRuntime, address: &SmartFunctionHash) {
+            self.0.borrow_mut().push(address.clone());
+        }
+    }
+
+    #[test]
+    fn attaches_runtimes_to_debugger() {
+        let recorder = Rc::new(Recorder::default());
+        set_debugger(Some(recorder.clone()));
+        assert!(is_enabled());
+
+        init_test_setup! {
+            runtime = runtime;
+            address = address;
+        };
+        attach(&mut runtime, &address);
+        assert_eq!(*recorder.0.borrow(), vec![address.clone()]);
+
+        set_debugger(None);
+        assert!(!is_enabled());
+        attach(&mut runtime, &address);
+        assert_eq!(recorder.0.borrow().len(), 1);
+    }
+
+    #[test]
+    fn waits_for_debugger_and_pauses() {
+        set_debugger(Some(Rc::new(Recorder::default())));
+        init_test_setup! {
+            runtime = runtime;
+        };
+        set_debugger(None);
+
+        let (outbound_tx, outbound_rx) = mpsc::unbounded::<InspectorMsg>();
+        let (inbound_tx, inbound_rx) = mpsc::unbounded::<String>();
+        runtime
+            .inspector()
+            .borrow()
+            .get_session_sender()
+            .unbounded_send(InspectorSessionProxy {
+                tx: outbound_tx,
+                rx: inbound_rx,
+                kind: InspectorSessionKind::NonBlocking {
+                    wait_for_disconnect: false,
+                },
+            })
+            .unwrap();
+        for message in [
+            r#"{"id":1,"method":"Debugger.enable"}"#,
+            r#"{"id":2,"method":"Runtime.runIfWaitingForDebugger"}"#,
+        ] {
+            inbound_tx.unbounded_send(message.to_string()).unwrap();
+        }
+        // Resumes every pause, like DevTools would
+        let session = thread::spawn(move || {
+            let mut pauses = 0;
+            for message in block_on_stream(outbound_rx) {
+                if message.content.contains(r#""method":"Debugger.paused""#) {
+                    pauses += 1;
+                    let resume = r#"{"id":3,"method":"Debugger.resume"}"#;
+                    inbound_tx.unbounded_send(resume.to_string()).unwrap();
+                }
+            }
+            pauses
+        });
+
+        runtime.wait_for_debugger();
+        let result = runtime.execute_with_result::<u32>("1 + 1").unwrap();
+        assert_eq!(result, 2);
+
+        // Dropping the runtime closes the session
+        drop(runtime);
+        assert_eq!(session.join().unwrap(), 1);
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{ext::NotSupported, runtime::RuntimeContext};
+use crate::{
+    ext::NotSupported,
+    gas::{self, cost},
+    runtime::RuntimeContext,
+};
 use deno_core::*;
 use jstz_core::log_record::LogLevel;
 use tezos_smart_rollup::prelude::debug_msg;
@@ -25,6 +29,10 @@ pub fn op_debug_msg(
     #[string] msg: &str,
     level: u32,
 ) -> Result<(), NotSupported> {
+    if gas::charge(op_state, cost::CONSOLE).is_err() {
+        // Execution is terminated
+        return Ok(());
+    }
     let proto = op_state.try_borrow_mut::<RuntimeContext>();
     match proto {
         Some(proto) => {
@@ -0,0 +1,9 @@
+import { op_jstz_gas } from "ext:core/ops";
+
+// Called by instrumented smart function code. See `jstz_runtime::gas::instrument`
+Object.defineProperty(globalThis, "__jstz_gas__", {
+  value: op_jstz_gas,
+  enumerable: false,
+  configurable: false,
+  writable: false,
+});
@@ -0,0 +1,15 @@
+use deno_core::{extension, op2, OpState};
+
+use crate::gas::{self, cost, OutOfGas};
+
+#[op2(fast)]
+fn op_jstz_gas(state: &mut OpState) -> Result<(), OutOfGas> {
+    gas::charge(state, cost::TICK)
+}
+
+extension!(
+    jstz_gas,
+    ops = [op_jstz_gas],
+    esm_entry_point = "ext:jstz_gas/gas.js",
+    esm = [dir "src/ext/jstz_gas", "gas.js"],
+);
@@ -1,4 +1,42 @@
-import { Kv } from "ext:core/ops";
+import {
+  Kv as KvOps,
+  op_jstz_kv_contains_of,
+  op_jstz_kv_get_of,
+  op_jstz_kv_set_bytes,
+} from "ext:core/ops";
+
+// Returns the bytes of `ArrayBuffer` and `ArrayBufferView` values, null otherwise
+function asBytes(value) {
+  if (value instanceof ArrayBuffer) {
+    return new Uint8Array(value);
+  }
+  if (ArrayBuffer.isView(value)) {
+    return new Uint8Array(value.buffer, value.byteOffset, value.byteLength);
+  }
+  return null;
+}
+
+const Kv = {
+  get: (key) => KvOps.get(key),
+  set: (key, value) => {
+    const bytes = asBytes(value);
+    if (bytes === null) {
+      KvOps.set(key, value);
+    } else {
+      op_jstz_kv_set_bytes(key, bytes);
+    }
+  },
+  delete: (key) => KvOps.delete(key),
+  contains: (key) => KvOps.contains(key),
+  publish: (prefix) => KvOps.publish(prefix),
+  unpublish: (prefix) => KvOps.unpublish(prefix),
+  // Read-only view of the Kv of the smart function at `address`
+  of: (address) =>
+    Object.freeze({
+      get: (key) => op_jstz_kv_get_of(address, key),
+      contains: (key) => op_jstz_kv_contains_of(address, key),
+    }),
+};
 
 Object.freeze(Kv);
 
@@ -22,20 +22,45 @@ pub struct Kv {
 }
 
 const KV_PATH: RefPath = RefPath::assert_from(b"/jstz_kv");
+const PUBLISHED_PATH: RefPath = RefPath::assert_from(b"/jstz_kv_published");
+// Number of bytes accounted for each key. Keys stored before storage was accounted
+// have no entry.
+const SIZES_PATH: RefPath = RefPath::assert_from(b"/jstz_kv_size");
 
-// TODO: Figure out a more effective way of serializing values using json
-/// A value stored in the Key-Value store. Always valid JSON.
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+/// A value stored in the Key-Value store, either JSON or bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
 #[schema(value_type = Value)]
-pub struct KvValue(pub serde_json::Value);
+pub enum KvValue {
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+}
+
+// Byte values are stored behind a zero byte. JSON values are stored as JSON text, which
+// never starts with a zero byte, so values stored before byte values were supported
+// still decode as JSON.
+const BYTES_TAG: u8 = 0;
+
+impl KvValue {
+    /// Number of bytes used to store the value
+    pub fn stored_len(&self) -> usize {
+        match self {
+            KvValue::Json(value) => value.to_string().len(),
+            KvValue::Bytes(bytes) => bytes.len() + 1,
+        }
+    }
+}
 
 impl Decode for KvValue {
     fn decode<D: Decoder>(decoder: &mut D) -> std::result::Result<KvValue, DecodeError> {
         let bytes: Vec<u8> = Decode::decode(decoder)?;
+        if let Some((&BYTES_TAG, bytes)) = bytes.split_first() {
+            return Ok(Self::Bytes(bytes.to_vec()));
+        }
         let value = serde_json::from_slice(&bytes).map_err(|e| {
             DecodeError::OtherString(format!("error deserializing kv value: {e}"))
         })?;
-        Ok(Self(value))
+        Ok(Self::Json(value))
     }
 }
 
@@ -44,9 +69,12 @@ impl Encode for KvValue {
         &self,
         encoder: &mut E,
     ) -> std::result::Result<(), EncodeError> {
-        let bytes = serde_json::to_vec(&self.0).map_err(|e| {
-            EncodeError::OtherString(format!("error serializing kv value: {e}"))
-        })?;
+        let bytes = match self {
+            KvValue::Json(value) => serde_json::to_vec(value).map_err(|e| {
+                EncodeError::OtherString(format!("error serializing kv value: {e}"))
+            })?,
+            KvValue::Bytes(bytes) => [&[BYTES_TAG], bytes.as_slice()].concat(),
+        };
         Encode::encode(&bytes, encoder)
     }
 }
@@ -61,7 +89,13 @@ impl Kv {
         Ok(path::concat(&KV_PATH, &key_path)?)
     }
 
+    fn size_path(&self, key: &str) -> Result<OwnedPath> {
+        let key_path = OwnedPath::try_from(format!("/{}/{}", self.prefix, key))?;
+        Ok(path::concat(&SIZES_PATH, &key_path)?)
+    }
+
     pub fn set(&self, tx: &mut Transaction, key: &str, value: KvValue) -> Result<()> {
+        tx.insert(self.size_path(key)?, Self::entry_size(key, &value))?;
         tx.insert(self.key_path(key)?, value)
     }
 
@@ -75,9 +109,29 @@ impl Kv {
     }
 
     pub fn delete(&self, tx: &mut Transaction, key: &str) -> Result<()> {
+        tx.remove(self.size_path(key)?)?;
         tx.remove(self.key_path(key)?)
     }
 
+    /// Returns the number of bytes accounted for `key`, 0 if it is not set or was
+    /// stored before storage was accounted
+    pub fn size(
+        &self,
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        key: &str,
+    ) -> Result<u64> {
+        Ok(tx
+            .get::<u64>(hrt, self.size_path(key)?)?
+            .map(|size| *size)
+            .unwrap_or(0))
+    }
+
+    /// Number of bytes accounted for storing `value` at `key`
+    pub fn entry_size(key: &str, value: &KvValue) -> u64 {
+        (key.len() + value.stored_len()) as u64
+    }
+
     pub fn has(
         &self,
         hrt: &impl HostRuntime,
@@ -86,6 +140,51 @@ impl Kv {
     ) -> Result<bool> {
         tx.contains_key(hrt, &self.key_path(key)?)
     }
+
+    fn published_path(&self) -> Result<OwnedPath> {
+        let path = OwnedPath::try_from(format!("/{}", self.prefix))?;
+        Ok(path::concat(&PUBLISHED_PATH, &path)?)
+    }
+
+    /// Key prefixes that other smart functions can read
+    pub fn published(
+        &self,
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+    ) -> Result<Vec<String>> {
+        Ok(tx
+            .get::<Vec<String>>(hrt, self.published_path()?)?
+            .map(|prefixes| prefixes.clone())
+            .unwrap_or_default())
+    }
+
+    pub fn set_published(
+        &self,
+        tx: &mut Transaction,
+        prefixes: Vec<String>,
+    ) -> Result<()> {
+        if prefixes.is_empty() {
+            tx.remove(self.published_path()?)
+        } else {
+            tx.insert(self.published_path()?, prefixes)
+        }
+    }
+
+    /// Returns true if other smart functions can read `key`, i.e. if `key` is a
+    /// published prefix or a subkey of one. The empty prefix publishes all keys.
+    pub fn is_published(
+        &self,
+        hrt: &impl HostRuntime,
+        tx: &mut Transaction,
+        key: &str,
+    ) -> Result<bool> {
+        Ok(self.published(hrt, tx)?.iter().any(|prefix| {
+            prefix.is_empty()
+                || key
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        }))
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -97,24 +196,24 @@ mod tests {
     fn test_kv_value_roundtrip() {
         let test_cases = vec![
             // Null
-            KvValue(json!(null)),
+            KvValue::Json(json!(null)),
             // Boolean
-            KvValue(json!(true)),
-            KvValue(json!(false)),
+            KvValue::Json(json!(true)),
+            KvValue::Json(json!(false)),
             // Numbers
-            KvValue(json!(42)),
-            KvValue(json!(-17.5)),
-            KvValue(json!(0)),
+            KvValue::Json(json!(42)),
+            KvValue::Json(json!(-17.5)),
+            KvValue::Json(json!(0)),
             // String
-            KvValue(json!("hello world")),
-            KvValue(json!("")),
+            KvValue::Json(json!("hello world")),
+            KvValue::Json(json!("")),
             // Array
-            KvValue(json!([])),
-            KvValue(json!([1, 2, 3])),
-            KvValue(json!(["a", "b", null, true, 1.5])),
+            KvValue::Json(json!([])),
+            KvValue::Json(json!([1, 2, 3])),
+            KvValue::Json(json!(["a", "b", null, true, 1.5])),
             // Object
-            KvValue(json!({})),
-            KvValue(json!({
+            KvValue::Json(json!({})),
+            KvValue::Json(json!({
                 "string": "value",
                 "number": 42,
                 "bool": true,
@@ -133,13 +232,74 @@ mod tests {
             let decoded =
                 <KvValue as BinEncodable>::decode(&bytes).expect("Failed to decode");
             assert_eq!(
-                value.0, decoded.0,
+                value, decoded,
                 "Value did not match after roundtrip: {:?}",
-                value.0
+                value
             );
         }
     }
 
+    #[test]
+    fn test_kv_value_bytes() {
+        for bytes in [vec![], vec![0], vec![1, 2, 3], b"{\"json\": true}".to_vec()] {
+            let value = KvValue::Bytes(bytes);
+            let encoded = <KvValue as BinEncodable>::encode(&value).unwrap();
+            let decoded = <KvValue as BinEncodable>::decode(&encoded).unwrap();
+            assert_eq!(value, decoded);
+        }
+        assert_eq!(KvValue::Bytes(vec![1, 2, 3]).stored_len(), 4);
+        assert_eq!(KvValue::Json(json!("abc")).stored_len(), 5);
+    }
+
+    #[test]
+    fn test_kv_published() {
+        let host = tezos_smart_rollup_mock::MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let kv = Kv::new("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton".to_string());
+        assert!(kv.published(&host, &mut tx).unwrap().is_empty());
+        assert!(!kv.is_published(&host, &mut tx, "public").unwrap());
+
+        kv.set_published(&mut tx, vec!["public".to_string(), "a/b".to_string()])
+            .unwrap();
+        for key in ["public", "public/x", "a/b", "a/b/c"] {
+            assert!(kv.is_published(&host, &mut tx, key).unwrap(), "{key}");
+        }
+        for key in ["publicity", "a", "a/bc", "private"] {
+            assert!(!kv.is_published(&host, &mut tx, key).unwrap(), "{key}");
+        }
+
+        kv.set_published(&mut tx, vec![String::new()]).unwrap();
+        assert!(kv.is_published(&host, &mut tx, "private").unwrap());
+
+        kv.set_published(&mut tx, vec![]).unwrap();
+        assert!(!kv.is_published(&host, &mut tx, "private").unwrap());
+    }
+
+    #[test]
+    fn test_kv_size() {
+        let host = tezos_smart_rollup_mock::MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let kv = Kv::new("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton".to_string());
+        assert_eq!(kv.size(&host, &mut tx, "key").unwrap(), 0);
+
+        let value = KvValue::Json(json!("value"));
+        kv.set(&mut tx, "key", value.clone()).unwrap();
+        assert_eq!(
+            kv.size(&host, &mut tx, "key").unwrap(),
+            Kv::entry_size("key", &value)
+        );
+
+        kv.delete(&mut tx, "key").unwrap();
+        assert_eq!(kv.size(&host, &mut tx, "key").unwrap(), 0);
+
+        // Values stored before storage was accounted have no accounted size
+        tx.insert(kv.key_path("legacy").unwrap(), value).unwrap();
+        assert!(kv.has(&host, &mut tx, "legacy").unwrap());
+        assert_eq!(kv.size(&host, &mut tx, "legacy").unwrap(), 0);
+    }
+
     #[test]
     fn test_kv_value_decode_error() {
         let invalid_bytes = b"invalid";
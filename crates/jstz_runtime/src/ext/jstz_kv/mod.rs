@@ -1,27 +1,89 @@
 pub mod kv;
 pub(crate) mod extension {
-    use super::kv::KvValue;
-    use crate::{ext::NotSupported, runtime::RuntimeContext};
-    use deno_core::{extension, op2, OpState};
+    use super::kv::{Kv as KvStore, KvValue};
+    use crate::{
+        ext::NotSupported,
+        gas::{self, cost, OutOfGas},
+        runtime::RuntimeContext,
+        storage::StorageError,
+    };
+    use deno_core::{extension, op2, OpState, ToJsBuffer};
+    use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
     use thiserror;
     struct Kv;
 
     const NOT_SUPPORTED_ERROR: NotSupported = NotSupported { name: "Kv" };
+
+    /// A [`KvValue`] as returned to smart functions. Byte values are returned as
+    /// `Uint8Array`s.
+    #[derive(serde::Serialize)]
+    #[serde(untagged)]
+    enum JsKvValue {
+        Json(serde_json::Value),
+        Bytes(ToJsBuffer),
+    }
+
+    impl From<KvValue> for JsKvValue {
+        fn from(value: KvValue) -> Self {
+            match value {
+                KvValue::Json(value) => JsKvValue::Json(value),
+                KvValue::Bytes(bytes) => JsKvValue::Bytes(bytes.into()),
+            }
+        }
+    }
+
+    fn set_value(op_state: &mut OpState, key: &str, value: KvValue) -> Result<()> {
+        let stored = KvStore::entry_size(key, &value);
+        gas::charge(
+            op_state,
+            cost::KV_WRITE + cost::KV_READ + stored * cost::KV_BYTE,
+        )?;
+        let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
+        match maybe_proto {
+            Some(RuntimeContext {
+                host,
+                tx,
+                kv,
+                address,
+                slot,
+                ..
+            }) => {
+                if let Some(storage) = slot.storage() {
+                    let previous = kv
+                        .size(host, tx, key)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
+                    storage.update(host, tx, address, stored as i64 - previous as i64)?;
+                }
+                kv.set(tx, key, value)
+                    .map_err(|e| KvError::JstzCoreError(e.to_string()))
+            }
+            None => Err(NOT_SUPPORTED_ERROR)?,
+        }
+    }
+
+    // Called by `Kv.set` in kv.js for `ArrayBuffer` and `ArrayBufferView` values
+    #[op2(fast)]
+    fn op_jstz_kv_set_bytes(
+        op_state: &mut OpState,
+        #[string] key: &str,
+        #[buffer] value: &[u8],
+    ) -> Result<()> {
+        set_value(op_state, key, KvValue::Bytes(value.to_vec()))
+    }
+
     #[op2]
     impl Kv {
         #[static_method]
         #[serde]
-        fn get(
-            op_state: &mut OpState,
-            #[string] key: &str,
-        ) -> Result<Option<serde_json::Value>> {
+        fn get(op_state: &mut OpState, #[string] key: &str) -> Result<Option<JsKvValue>> {
+            gas::charge(op_state, cost::KV_READ)?;
             let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
             match maybe_proto {
                 Some(RuntimeContext { host, tx, kv, .. }) => {
                     let maybe_value = kv
                         .get(host, tx, key)
                         .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
-                    Ok(maybe_value.map(|v| v.0.clone()))
+                    Ok(maybe_value.map(|v| v.clone().into()))
                 }
                 None => Err(NOT_SUPPORTED_ERROR)?,
             }
@@ -33,23 +95,34 @@ pub(crate) mod extension {
             #[string] key: &str,
             #[serde] value: serde_json::Value,
         ) -> Result<()> {
-            let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
-            match maybe_proto {
-                Some(RuntimeContext { tx, kv, .. }) => kv
-                    .set(tx, key, KvValue(value))
-                    .map_err(|e| KvError::JstzCoreError(e.to_string())),
-                None => Err(NOT_SUPPORTED_ERROR)?,
-            }
+            set_value(op_state, key, KvValue::Json(value))
         }
 
         #[fast]
         #[static_method]
         fn delete(op_state: &mut OpState, #[string] key: &str) -> Result<()> {
+            gas::charge(op_state, cost::KV_WRITE + cost::KV_READ)?;
             let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
             match maybe_proto {
-                Some(RuntimeContext { tx, kv, .. }) => kv
-                    .delete(tx, key)
-                    .map_err(|e| KvError::JstzCoreError(e.to_string())),
+                Some(RuntimeContext {
+                    host,
+                    tx,
+                    kv,
+                    address,
+                    slot,
+                    ..
+                }) => {
+                    if let Some(storage) = slot.storage() {
+                        let previous = kv
+                            .size(host, tx, key)
+                            .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
+                        if previous > 0 {
+                            storage.update(host, tx, address, -(previous as i64))?;
+                        }
+                    }
+                    kv.delete(tx, key)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))
+                }
                 None => Err(NOT_SUPPORTED_ERROR)?,
             }
         }
@@ -57,6 +130,7 @@ pub(crate) mod extension {
         #[fast]
         #[static_method]
         fn contains(op_state: &mut OpState, #[string] key: &str) -> Result<bool> {
+            gas::charge(op_state, cost::KV_READ)?;
             let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
             match maybe_proto {
                 Some(RuntimeContext { tx, kv, host, .. }) => kv
@@ -65,6 +139,132 @@ pub(crate) mod extension {
                 None => Err(NOT_SUPPORTED_ERROR)?,
             }
         }
+
+        #[static_method]
+        fn publish(op_state: &mut OpState, #[string] prefix: String) -> Result<()> {
+            gas::charge(
+                op_state,
+                cost::KV_WRITE + prefix.len() as u64 * cost::KV_BYTE,
+            )?;
+            let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
+            match maybe_proto {
+                Some(RuntimeContext {
+                    host,
+                    tx,
+                    kv,
+                    address,
+                    slot,
+                    ..
+                }) => {
+                    let mut published = kv
+                        .published(host, tx)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
+                    if published.contains(&prefix) {
+                        return Ok(());
+                    }
+                    if let Some(storage) = slot.storage() {
+                        storage.update(host, tx, address, prefix.len() as i64)?;
+                    }
+                    published.push(prefix);
+                    kv.set_published(tx, published)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))
+                }
+                None => Err(NOT_SUPPORTED_ERROR)?,
+            }
+        }
+
+        #[static_method]
+        fn unpublish(op_state: &mut OpState, #[string] prefix: String) -> Result<()> {
+            gas::charge(op_state, cost::KV_WRITE)?;
+            let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
+            match maybe_proto {
+                Some(RuntimeContext {
+                    host,
+                    tx,
+                    kv,
+                    address,
+                    slot,
+                    ..
+                }) => {
+                    let mut published = kv
+                        .published(host, tx)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
+                    let Some(index) = published.iter().position(|p| *p == prefix) else {
+                        return Ok(());
+                    };
+                    if let Some(storage) = slot.storage() {
+                        storage.update(host, tx, address, -(prefix.len() as i64))?;
+                    }
+                    published.remove(index);
+                    kv.set_published(tx, published)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))
+                }
+                None => Err(NOT_SUPPORTED_ERROR)?,
+            }
+        }
+    }
+
+    /// Returns the Kv of `address` after checking that the calling smart function can
+    /// read `key` from it. Smart functions can read all of their own keys, and the
+    /// keys other smart functions publish.
+    fn readable_kv(op_state: &mut OpState, address: &str, key: &str) -> Result<KvStore> {
+        gas::charge(op_state, cost::KV_READ)?;
+        let maybe_proto = op_state.try_borrow_mut::<RuntimeContext>();
+        match maybe_proto {
+            Some(RuntimeContext {
+                host,
+                tx,
+                address: self_address,
+                ..
+            }) => {
+                let address = SmartFunctionHash::from_base58(address)
+                    .map_err(|_| KvError::InvalidAddress(address.to_string()))?;
+                let kv = KvStore::new(address.to_base58());
+                if address != *self_address
+                    && !kv
+                        .is_published(host, tx, key)
+                        .map_err(|e| KvError::JstzCoreError(e.to_string()))?
+                {
+                    return Err(KvError::NotPublished {
+                        address: address.to_base58(),
+                        key: key.to_string(),
+                    });
+                }
+                Ok(kv)
+            }
+            None => Err(NOT_SUPPORTED_ERROR)?,
+        }
+    }
+
+    // Called by `Kv.of(address).get` in kv.js
+    #[op2]
+    #[serde]
+    fn op_jstz_kv_get_of(
+        op_state: &mut OpState,
+        #[string] address: &str,
+        #[string] key: &str,
+    ) -> Result<Option<JsKvValue>> {
+        let kv = readable_kv(op_state, address, key)?;
+        gas::charge(op_state, cost::KV_READ)?;
+        let RuntimeContext { host, tx, .. } = op_state.borrow_mut::<RuntimeContext>();
+        let maybe_value = kv
+            .get(host, tx, key)
+            .map_err(|e| KvError::JstzCoreError(e.to_string()))?;
+        Ok(maybe_value.map(|v| v.clone().into()))
+    }
+
+    // Called by `Kv.of(address).contains` in kv.js
+    #[op2(fast)]
+    fn op_jstz_kv_contains_of(
+        op_state: &mut OpState,
+        #[string] address: &str,
+        #[string] key: &str,
+    ) -> Result<bool> {
+        let kv = readable_kv(op_state, address, key)?;
+        gas::charge(op_state, cost::KV_READ)?;
+        let RuntimeContext { host, tx, .. } = op_state.borrow_mut::<RuntimeContext>();
+        kv.has(host, tx, key)
+            .map_err(|e| KvError::JstzCoreError(e.to_string()))
     }
 
     #[derive(Debug, thiserror::Error, deno_error::JsError)]
@@ -76,12 +276,29 @@ pub(crate) mod extension {
         #[class(inherit)]
         #[error(transparent)]
         UnsupportedError(#[from] NotSupported),
+
+        #[class(inherit)]
+        #[error(transparent)]
+        OutOfGas(#[from] OutOfGas),
+
+        #[class(inherit)]
+        #[error(transparent)]
+        StorageError(#[from] StorageError),
+
+        #[class(type)]
+        #[error("Invalid smart function address '{0}'")]
+        InvalidAddress(String),
+
+        #[class(generic)]
+        #[error("Key '{key}' of smart function '{address}' is not published")]
+        NotPublished { address: String, key: String },
     }
 
     type Result<T> = std::result::Result<T, KvError>;
 
     extension!(
         jstz_kv,
+        ops = [op_jstz_kv_set_bytes, op_jstz_kv_get_of, op_jstz_kv_contains_of],
         objects = [Kv],
         esm_entry_point = "ext:jstz_kv/kv.js",
         esm = [dir "src/ext/jstz_kv", "kv.js"]
@@ -89,9 +306,21 @@ pub(crate) mod extension {
 
     #[cfg(test)]
     mod test {
+        use std::sync::{Arc, Mutex};
+
         use deno_error::JsErrorClass;
+        use jstz_core::{host::JsHostRuntime, kv::Transaction};
+        use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+        use serde_json::json;
+        use tezos_smart_rollup_mock::MockHost;
 
-        use crate::{init_test_setup, JstzRuntime, JstzRuntimeOptions};
+        use super::{KvStore, KvValue};
+        use crate::{
+            init_test_setup,
+            runtime::Limiter,
+            storage::{StorageAccounting, StorageError},
+            JstzRuntime, JstzRuntimeOptions, RuntimeContext,
+        };
 
         #[test]
         fn kv() {
@@ -116,6 +345,164 @@ pub(crate) mod extension {
             assert!(!has_value_after_delete);
         }
 
+        #[test]
+        fn kv_bytes() {
+            init_test_setup! {
+                runtime = runtime;
+            };
+            let code = r#"
+                Kv.set("array", new Uint8Array([1, 2, 3]));
+                Kv.set("buffer", new Uint16Array([0x0201, 0x0403]).buffer);
+                Kv.set("view", new DataView(new Uint8Array([0, 5, 6, 0]).buffer, 1, 2));
+                Kv.set("json", [1, 2, 3]);
+                [Kv.get("array"), Kv.get("buffer"), Kv.get("view")].every(
+                    (value) => value instanceof Uint8Array
+                ) && !(Kv.get("json") instanceof Uint8Array)
+            "#;
+            assert!(runtime.execute_with_result::<bool>(code).unwrap());
+            let code = r#"
+                [Kv.get("array"), Kv.get("buffer"), Kv.get("view")].map(
+                    (value) => Array.from(value)
+                )
+            "#;
+            let values = runtime.execute_with_result::<Vec<Vec<u8>>>(code).unwrap();
+            assert_eq!(values, vec![vec![1, 2, 3], vec![1, 2, 3, 4], vec![5, 6]]);
+        }
+
+        #[derive(Debug, Default)]
+        struct RecordingStorage {
+            deltas: Mutex<Vec<i64>>,
+        }
+
+        impl StorageAccounting for RecordingStorage {
+            fn update(
+                &self,
+                _host: &mut JsHostRuntime<'static>,
+                _tx: &mut Transaction,
+                _address: &SmartFunctionHash,
+                delta: i64,
+            ) -> Result<(), StorageError> {
+                if delta > 100 {
+                    return Err(StorageError("Storage quota exceeded".to_string()));
+                }
+                self.deltas.lock().unwrap().push(delta);
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn kv_storage_accounting() {
+            let mut host = MockHost::default();
+            let mut tx = Transaction::default();
+            tx.begin();
+            let address =
+                SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton")
+                    .unwrap();
+            let storage = Arc::new(RecordingStorage::default());
+            let limiter = Limiter::default().with_storage(storage.clone());
+            let protocol = RuntimeContext::new(
+                &mut host,
+                &mut tx,
+                address,
+                String::new(),
+                limiter.try_acquire().unwrap(),
+            );
+            let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+                protocol: Some(protocol),
+                ..Default::default()
+            });
+
+            let code = r#"
+                Kv.set("key", "value");
+                Kv.set("key", "longer value");
+                Kv.delete("key");
+                Kv.delete("key");
+            "#;
+            runtime.execute(code).unwrap();
+            // `key` takes 3 bytes and `"value"` 7 bytes
+            assert_eq!(*storage.deltas.lock().unwrap(), vec![10, 7, -17]);
+
+            let code = r#"Kv.set("key", "x".repeat(200))"#;
+            let err = runtime.execute(code).unwrap_err();
+            assert_eq!(err.get_class(), "RangeError");
+            assert!(err.get_message().contains("Storage quota exceeded"));
+            assert_eq!(storage.deltas.lock().unwrap().len(), 3);
+        }
+
+        #[test]
+        fn kv_of() {
+            let mut host = MockHost::default();
+            let mut tx = Transaction::default();
+            tx.begin();
+            let other = KvStore::new("KT1RycYvM4EVs6BAXWEsGXaAaRqiMP53KT4w".to_string());
+            other
+                .set(&mut tx, "public/value", KvValue::Json(json!(42)))
+                .unwrap();
+            other
+                .set(&mut tx, "private", KvValue::Json(json!(0)))
+                .unwrap();
+            other
+                .set_published(&mut tx, vec!["public".to_string()])
+                .unwrap();
+            let address =
+                SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton")
+                    .unwrap();
+            let protocol = RuntimeContext::new(
+                &mut host,
+                &mut tx,
+                address.clone(),
+                String::new(),
+                Limiter::default().try_acquire().unwrap(),
+            );
+            let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+                protocol: Some(protocol),
+                ..Default::default()
+            });
+
+            let code = r#"
+                const other = Kv.of("KT1RycYvM4EVs6BAXWEsGXaAaRqiMP53KT4w");
+                [
+                    other.get("public/value"),
+                    other.contains("public/value"),
+                    other.contains("public/missing"),
+                    other.set === undefined && other.delete === undefined,
+                ]
+            "#;
+            let result = runtime
+                .execute_with_result::<(i32, bool, bool, bool)>(code)
+                .unwrap();
+            assert_eq!(result, (42, true, false, true));
+
+            let code = r#"Kv.of("KT1RycYvM4EVs6BAXWEsGXaAaRqiMP53KT4w").get("private")"#;
+            let err = runtime.execute(code).unwrap_err();
+            assert_eq!(err.get_class(), "Error");
+            assert!(err.get_message().contains("is not published"));
+
+            let code = r#"Kv.of("invalid").get("private")"#;
+            let err = runtime.execute(code).unwrap_err();
+            assert_eq!(err.get_class(), "TypeError");
+
+            // Smart functions can read all of their own keys
+            let code = r#"
+                Kv.set("mine", 1);
+                Kv.of("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton").get("mine")
+            "#;
+            assert_eq!(runtime.execute_with_result::<i32>(code).unwrap(), 1);
+
+            let code = r#"
+                Kv.publish("a");
+                Kv.publish("b");
+                Kv.publish("a");
+                Kv.unpublish("b");
+            "#;
+            runtime.execute(code).unwrap();
+            let own = KvStore::new(address.to_base58());
+            assert_eq!(
+                own.published(&host, &mut tx).unwrap(),
+                vec!["a".to_string()]
+            );
+        }
+
         #[test]
         fn kv_not_supported() {
             let mut runtime = JstzRuntime::new(JstzRuntimeOptions::default());
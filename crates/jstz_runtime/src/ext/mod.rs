@@ -1,5 +1,6 @@
 pub(crate) mod jstz_console;
 pub(crate) mod jstz_fetch;
+pub(crate) mod jstz_gas;
 pub mod jstz_kv;
 pub(crate) mod jstz_main;
 
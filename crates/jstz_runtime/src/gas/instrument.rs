@@ -0,0 +1,1005 @@
+//! Source instrumentation for metering JS execution.
+//!
+//! The instrumentation works on tokens rather than on a full AST. It inserts a
+//! call to [`GAS_FUNCTION`]
+//!
+//! * in the condition of `while`, `do..while` and `for(;;)` loops,
+//!   e.g. `while (x)` becomes `while (__jstz_gas__()&&(x))`
+//! * at the start of the body of `for..in` and `for..of` loops, which is wrapped
+//!   in a block if needed, e.g. `for (x of xs) f(x)` becomes
+//!   `for (x of xs) {__jstz_gas__();f(x)}`. Iterators are not necessarily
+//!   metered, e.g. `Array(2**32-1).keys()`
+//! * at the start of function, method and arrow function bodies,
+//!   e.g. `x => x + 1` becomes `x =>(__jstz_gas__(), x + 1)`
+use std::collections::HashSet;
+
+/// Global function charging [`super::cost::TICK`]. Reserved in smart function code.
+pub const GAS_FUNCTION: &str = "__jstz_gas__";
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error, deno_error::JsError)]
+#[class(syntax)]
+pub enum InstrumentError {
+    #[error("Unterminated literal at offset {0}")]
+    UnterminatedLiteral(usize),
+    #[error("Unbalanced brackets at offset {0}")]
+    UnbalancedBrackets(usize),
+    #[error("'__jstz_gas__' is a reserved identifier")]
+    ReservedIdentifier,
+    #[error("Ambiguous '/' at offset {0}, wrap it in parentheses")]
+    AmbiguousSlash(usize),
+}
+
+type Result<T> = std::result::Result<T, InstrumentError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Punct,
+    Literal,
+    /// Template literal chunk. `opens` if it ends with `${` and
+    /// `closes` if it starts with `}`
+    Template {
+        opens: bool,
+        closes: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+    newline_before: bool,
+}
+
+const PUNCTUATORS: [&str; 50] = [
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>",
+    "==", "!=", "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=",
+    "%=", "&=", "|=", "^=", "**", "<<", ">>", "{", "}", "(", ")", "[", "]", ";", ",",
+    "<", ">", "+", "-", "*", "%", "&", "|", "^",
+];
+
+/// Keywords after which a `/` starts a regular expression
+const EXPRESSION_KEYWORDS: [&str; 14] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+/// Keywords followed by a parenthesized head and a statement
+const CONTROL_KEYWORDS: [&str; 6] = ["if", "for", "while", "switch", "catch", "with"];
+
+/// Context opened by a `{`, which decides how a `/` after the matching `}` is lexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Brace {
+    /// Template substitution, closed by a template chunk
+    Template,
+    /// Block statement, after which `/` starts a regular expression
+    Block,
+    /// Object literal, after which `/` is a division
+    Expression,
+    /// Function or class body, which may be a declaration or an expression
+    Unknown,
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '_' | '$' | '#' | '\\') || !c.is_ascii()
+}
+
+fn is_ident_part(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '$' | '\\' | '\u{200c}' | '\u{200d}')
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    tokens: Vec<Token>,
+    /// Context of each open brace
+    braces: Vec<Brace>,
+    /// `true` for each open parenthesis following a control keyword
+    parens: Vec<bool>,
+    /// Context of the last closed brace
+    closed_brace: Brace,
+    /// `true` if the last closed parenthesis followed a control keyword
+    closed_control: bool,
+    newline_before: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn push(&mut self, kind: Kind, start: usize) {
+        self.tokens.push(Token {
+            kind,
+            start,
+            end: self.pos,
+            newline_before: self.newline_before,
+        });
+        self.newline_before = false;
+    }
+
+    fn text(&self, token: &Token) -> &'a str {
+        &self.src[token.start..token.end]
+    }
+
+    /// Returns the text of the `n`th token before the current position
+    fn prev_text(&self, n: usize) -> Option<&'a str> {
+        let i = self.tokens.len().checked_sub(n + 1)?;
+        Some(self.text(&self.tokens[i]))
+    }
+
+    fn regex_allowed(&self) -> Result<bool> {
+        let Some(prev) = self.tokens.last() else {
+            return Ok(true);
+        };
+        Ok(match prev.kind {
+            Kind::Ident => EXPRESSION_KEYWORDS.contains(&self.text(prev)),
+            Kind::Literal => false,
+            Kind::Template { opens, .. } => opens,
+            Kind::Punct => match self.text(prev) {
+                // `if (x) /re/` but `f(x) / 2`
+                ")" => self.closed_control,
+                // `{} /re/` but `({} / 2)`
+                "}" => match self.closed_brace {
+                    Brace::Block => true,
+                    Brace::Expression => false,
+                    Brace::Template | Brace::Unknown => {
+                        return Err(InstrumentError::AmbiguousSlash(self.pos))
+                    }
+                },
+                "]" | "++" | "--" => false,
+                _ => true,
+            },
+        })
+    }
+
+    /// Returns the context of a `{` at the current position
+    fn brace_context(&self) -> Brace {
+        let Some(prev) = self.tokens.last() else {
+            return Brace::Block;
+        };
+        let text = self.text(prev);
+        match prev.kind {
+            Kind::Ident if matches!(text, "else" | "do" | "try" | "finally") => {
+                Brace::Block
+            }
+            Kind::Ident if EXPRESSION_KEYWORDS.contains(&text) => Brace::Expression,
+            Kind::Ident => Brace::Unknown,
+            Kind::Punct => match text {
+                ";" | "{" | "}" | "=>" => Brace::Block,
+                ")" if self.closed_control => Brace::Block,
+                // Function bodies, and labels or cases versus property values
+                ")" | ":" => Brace::Unknown,
+                _ => Brace::Expression,
+            },
+            Kind::Literal | Kind::Template { .. } => Brace::Expression,
+        }
+    }
+
+    /// Returns true if a `(` at the current position opens the head of a
+    /// control statement, e.g. `if (`
+    fn is_control_head(&self) -> bool {
+        match self.prev_text(0) {
+            Some("await") => self.prev_text(1) == Some("for"),
+            Some(keyword) => {
+                CONTROL_KEYWORDS.contains(&keyword)
+                    && !matches!(self.prev_text(1), Some(".") | Some("?."))
+            }
+            None => false,
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>> {
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            if is_line_terminator(c) {
+                self.bump();
+                self.newline_before = true;
+            } else if c.is_whitespace() || c == '\u{feff}' {
+                self.bump();
+            } else if self.src[start..].starts_with("//") {
+                while self.peek().is_some_and(|c| !is_line_terminator(c)) {
+                    self.bump();
+                }
+            } else if self.src[start..].starts_with("/*") {
+                let len = self.src[start + 2..]
+                    .find("*/")
+                    .ok_or(InstrumentError::UnterminatedLiteral(start))?;
+                let comment = &self.src[start..start + len + 4];
+                self.newline_before |= comment.chars().any(is_line_terminator);
+                self.pos += comment.len();
+            } else if c == '"' || c == '\'' {
+                self.bump();
+                self.string(c, start)?;
+                self.push(Kind::Literal, start);
+            } else if c == '`' {
+                self.bump();
+                self.template(start, false)?;
+            } else if c.is_ascii_digit()
+                || (c == '.' && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()))
+            {
+                self.number();
+                self.push(Kind::Literal, start);
+            } else if is_ident_start(c) {
+                self.ident_char();
+                while self.peek().is_some_and(is_ident_part) {
+                    self.ident_char();
+                }
+                self.push(Kind::Ident, start);
+            } else if c == '/' && self.regex_allowed()? {
+                self.regex(start)?;
+                self.push(Kind::Literal, start);
+            } else if c == '{' {
+                let context = self.brace_context();
+                self.bump();
+                self.braces.push(context);
+                self.push(Kind::Punct, start);
+            } else if c == '}' {
+                match self.braces.pop() {
+                    Some(Brace::Template) => self.template(start, true)?,
+                    context => {
+                        self.bump();
+                        self.closed_brace = context.unwrap_or(Brace::Unknown);
+                        self.push(Kind::Punct, start);
+                    }
+                }
+            } else {
+                let control = self.is_control_head();
+                self.punctuator();
+                self.push(Kind::Punct, start);
+                match &self.src[start..self.pos] {
+                    "(" => self.parens.push(control),
+                    ")" => self.closed_control = self.parens.pop().unwrap_or(false),
+                    _ => (),
+                }
+            }
+        }
+        Ok(self.tokens)
+    }
+
+    /// Lexes a character of an identifier, including escape sequences such as
+    /// `\u{5f}`
+    fn ident_char(&mut self) {
+        if self.bump() == Some('\\') && self.src[self.pos..].starts_with("u{") {
+            while self.bump().is_some_and(|c| c != '}') {}
+        }
+    }
+
+    fn string(&mut self, quote: char, start: usize) -> Result<()> {
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some(c) if c == quote => return Ok(()),
+                Some(c) if c == '\n' || c == '\r' => {
+                    return Err(InstrumentError::UnterminatedLiteral(start))
+                }
+                Some(_) => (),
+                None => return Err(InstrumentError::UnterminatedLiteral(start)),
+            }
+        }
+    }
+
+    /// Lexes a template chunk, starting after the opening backtick or at the `}`
+    /// closing a substitution
+    fn template(&mut self, start: usize, closes: bool) -> Result<()> {
+        if closes {
+            self.bump();
+        }
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('`') => {
+                    self.push(
+                        Kind::Template {
+                            opens: false,
+                            closes,
+                        },
+                        start,
+                    );
+                    return Ok(());
+                }
+                Some('$') if self.peek() == Some('{') => {
+                    self.bump();
+                    self.braces.push(Brace::Template);
+                    self.push(
+                        Kind::Template {
+                            opens: true,
+                            closes,
+                        },
+                        start,
+                    );
+                    return Ok(());
+                }
+                Some(_) => (),
+                None => return Err(InstrumentError::UnterminatedLiteral(start)),
+            }
+        }
+    }
+
+    fn number(&mut self) {
+        let is_decimal = !self.src[self.pos..].starts_with("0x")
+            && !self.src[self.pos..].starts_with("0X");
+        while let Some(c) = self.peek() {
+            let prev = self.src[..self.pos].chars().next_back();
+            if c.is_ascii_alphanumeric()
+                || c == '_'
+                || c == '.'
+                || (matches!(c, '+' | '-')
+                    && is_decimal
+                    && matches!(prev, Some('e') | Some('E')))
+            {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn regex(&mut self, start: usize) -> Result<()> {
+        self.bump();
+        let mut in_class = false;
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('[') => in_class = true,
+                Some(']') => in_class = false,
+                Some('/') if !in_class => break,
+                Some(c) if !is_line_terminator(c) => (),
+                _ => return Err(InstrumentError::UnterminatedLiteral(start)),
+            }
+        }
+        while self.peek().is_some_and(is_ident_part) {
+            self.bump();
+        }
+        Ok(())
+    }
+
+    fn punctuator(&mut self) {
+        let rest = &self.src[self.pos..];
+        let len = PUNCTUATORS
+            .iter()
+            .find(|p| rest.starts_with(*p))
+            // `a?.5:b` is a conditional expression
+            .filter(|p| {
+                **p != "?."
+                    || !rest[2..].chars().next().is_some_and(|c| c.is_ascii_digit())
+            })
+            .map(|p| p.len());
+        match len {
+            Some(len) => self.pos += len,
+            None => {
+                self.bump();
+            }
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>> {
+    Lexer {
+        src,
+        pos: 0,
+        tokens: vec![],
+        braces: vec![],
+        parens: vec![],
+        closed_brace: Brace::Unknown,
+        closed_control: false,
+        newline_before: false,
+    }
+    .tokenize()
+}
+
+struct Instrumenter<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    /// Index of the closing token of each opening token and vice versa
+    pairs: Vec<Option<usize>>,
+    class_bodies: HashSet<usize>,
+    insertions: Vec<Insertion>,
+}
+
+#[derive(Debug)]
+struct Insertion {
+    offset: usize,
+    text: String,
+    closes: bool,
+}
+
+impl<'a> Instrumenter<'a> {
+    fn new(src: &'a str, tokens: Vec<Token>) -> Result<Self> {
+        let mut pairs = vec![None; tokens.len()];
+        let mut stack = vec![];
+        for (i, token) in tokens.iter().enumerate() {
+            let text = &src[token.start..token.end];
+            let (closes, opens) = match token.kind {
+                Kind::Punct => (
+                    matches!(text, ")" | "]" | "}"),
+                    matches!(text, "(" | "[" | "{"),
+                ),
+                Kind::Template { opens, closes } => (closes, opens),
+                _ => (false, false),
+            };
+            if closes {
+                let open: usize = stack
+                    .pop()
+                    .ok_or(InstrumentError::UnbalancedBrackets(token.start))?;
+                pairs[open] = Some(i);
+                if !opens {
+                    pairs[i] = Some(open);
+                }
+            }
+            if opens {
+                stack.push(i);
+            }
+        }
+        if let Some(open) = stack.pop() {
+            return Err(InstrumentError::UnbalancedBrackets(tokens[open].start));
+        }
+        Ok(Self {
+            src,
+            tokens,
+            pairs,
+            class_bodies: HashSet::new(),
+            insertions: vec![],
+        })
+    }
+
+    fn text(&self, i: usize) -> &'a str {
+        &self.src[self.tokens[i].start..self.tokens[i].end]
+    }
+
+    fn is(&self, i: usize, text: &str) -> bool {
+        i < self.tokens.len() && self.text(i) == text
+    }
+
+    fn is_opening(&self, i: usize) -> bool {
+        match self.tokens[i].kind {
+            Kind::Punct => matches!(self.text(i), "(" | "[" | "{"),
+            Kind::Template { opens, .. } => opens,
+            _ => false,
+        }
+    }
+
+    fn is_closing(&self, i: usize) -> bool {
+        match self.tokens[i].kind {
+            Kind::Punct => matches!(self.text(i), ")" | "]" | "}"),
+            Kind::Template { closes, .. } => closes,
+            _ => false,
+        }
+    }
+
+    /// Returns the index of the token closing the group opened at `i`
+    fn skip_group(&self, mut i: usize) -> usize {
+        while self.is_opening(i) {
+            // Brackets are balanced
+            i = self.pairs[i].unwrap();
+        }
+        i
+    }
+
+    /// Returns true if the keyword at `i` is used as a keyword rather than as a
+    /// property name, e.g. `x.for`
+    fn is_keyword(&self, i: usize, keyword: &str) -> bool {
+        self.tokens[i].kind == Kind::Ident
+            && self.text(i) == keyword
+            && !(i > 0 && (self.is(i - 1, ".") || self.is(i - 1, "?.")))
+            && !self.is(i + 1, ":")
+    }
+
+    fn insert(&mut self, offset: usize, text: &str, closes: bool) {
+        self.insertions.push(Insertion {
+            offset,
+            text: text.to_string(),
+            closes,
+        });
+    }
+
+    fn instrument(mut self) -> String {
+        for i in 0..self.tokens.len() {
+            if self.is_keyword(i, "class") {
+                self.mark_class_body(i);
+            } else if self.is_keyword(i, "while") && self.is(i + 1, "(") {
+                let close = self.pairs[i + 1].unwrap();
+                self.insert(
+                    self.tokens[i + 1].end,
+                    &format!("{GAS_FUNCTION}()&&("),
+                    false,
+                );
+                self.insert(self.tokens[close].start, ")", true);
+            } else if self.is_keyword(i, "for") {
+                self.instrument_for(i);
+            } else if self.is(i, "{") && self.is_function_body(i) {
+                self.insert(self.tokens[i].end, &format!("{GAS_FUNCTION}();"), false);
+            } else if self.tokens[i].kind == Kind::Punct
+                && self.is(i, "=>")
+                && i + 1 < self.tokens.len()
+                && !self.is(i + 1, "{")
+            {
+                let end = self.expression_end(i + 1);
+                self.insert(self.tokens[i].end, &format!("({GAS_FUNCTION}(),"), false);
+                self.insert(self.tokens[end].end, ")", true);
+            }
+        }
+        self.apply()
+    }
+
+    fn mark_class_body(&mut self, class: usize) {
+        let mut i = class + 1;
+        while i < self.tokens.len() {
+            if self.is(i, "{") {
+                self.class_bodies.insert(i);
+                return;
+            }
+            if self.is_opening(i) {
+                i = self.skip_group(i);
+            }
+            i += 1;
+        }
+    }
+
+    fn instrument_for(&mut self, i: usize) {
+        let open = if self.is(i + 1, "await") {
+            i + 2
+        } else {
+            i + 1
+        };
+        if !self.is(open, "(") {
+            return;
+        }
+        let close = self.pairs[open].unwrap();
+        let mut semicolons = vec![];
+        let mut k = open + 1;
+        while k < close {
+            if self.is(k, ";") {
+                semicolons.push(k);
+            }
+            k = self.skip_group(k) + 1;
+        }
+        match semicolons[..] {
+            [init, condition] if condition == init + 1 => {
+                self.insert(self.tokens[init].end, &format!("{GAS_FUNCTION}()"), false);
+            }
+            [init, condition] => {
+                self.insert(
+                    self.tokens[init].end,
+                    &format!("{GAS_FUNCTION}()&&("),
+                    false,
+                );
+                self.insert(self.tokens[condition].start, ")", true);
+            }
+            // `for..in` and `for..of` loops
+            [] if close + 1 < self.tokens.len() => self.instrument_loop_body(close + 1),
+            _ => (),
+        }
+    }
+
+    /// Charges gas at the start of the loop body starting at `body`
+    fn instrument_loop_body(&mut self, body: usize) {
+        if self.is(body, "{") {
+            self.insert(self.tokens[body].end, &format!("{GAS_FUNCTION}();"), false);
+        } else {
+            let end = self.statement_end(body);
+            self.insert(
+                self.tokens[body].start,
+                &format!("{{{GAS_FUNCTION}();"),
+                false,
+            );
+            self.insert(self.tokens[end].end, "}", true);
+        }
+    }
+
+    /// Returns the index of the last token of the statement starting at `start`
+    fn statement_end(&self, start: usize) -> usize {
+        let last = self.tokens.len() - 1;
+        // Returns the end of the statement following the parenthesized head at `i`
+        let after_head = |i: usize| {
+            if self.is(i, "(") {
+                self.statement_end((self.pairs[i].unwrap() + 1).min(last))
+            } else {
+                i.min(last)
+            }
+        };
+        if self.is(start, "{") {
+            return self.pairs[start].unwrap();
+        }
+        if self.tokens[start].kind == Kind::Ident && self.is(start + 1, ":") {
+            // Labelled statement
+            return self.statement_end((start + 2).min(last));
+        }
+        match self.text(start) {
+            "if" => {
+                let end = after_head(start + 1);
+                if self.is(end + 1, "else") {
+                    self.statement_end((end + 2).min(last))
+                } else {
+                    end
+                }
+            }
+            "for" if self.is(start + 1, "await") => after_head(start + 2),
+            "for" | "while" | "with" => after_head(start + 1),
+            "switch" => {
+                let open = self.skip_group(start + 1) + 1;
+                self.skip_group(open.min(last))
+            }
+            "do" => {
+                // `do` is followed by its body and `while (..)`
+                let end = self.statement_end((start + 1).min(last));
+                let close = self.skip_group((end + 2).min(last));
+                if self.is(close + 1, ";") {
+                    close + 1
+                } else {
+                    close
+                }
+            }
+            "try" => {
+                let mut end = self.skip_group((start + 1).min(last));
+                if self.is(end + 1, "catch") {
+                    end = self.skip_group((end + 2).min(last));
+                    if self.is(end, ")") {
+                        end = self.skip_group((end + 1).min(last));
+                    }
+                }
+                if self.is(end + 1, "finally") {
+                    end = self.skip_group((end + 2).min(last));
+                }
+                end
+            }
+            _ => {
+                let mut end = start;
+                let mut i = start;
+                while i <= last {
+                    if self.is(i, ";") {
+                        return i;
+                    }
+                    if self.is_closing(i)
+                        || (i > start
+                            && self.tokens[i].newline_before
+                            && self.ends_expression(end)
+                            && self.starts_statement(i))
+                    {
+                        break;
+                    }
+                    end = self.skip_group(i);
+                    i = end + 1;
+                }
+                end
+            }
+        }
+    }
+
+    fn is_function_body(&self, i: usize) -> bool {
+        if i == 0 || self.class_bodies.contains(&i) {
+            return false;
+        }
+        if self.is(i - 1, "=>") {
+            return true;
+        }
+        if !self.is(i - 1, ")") {
+            return false;
+        }
+        // Brackets are balanced
+        let open = self.pairs[i - 1].unwrap();
+        if open == 0 {
+            return true;
+        }
+        let statement = CONTROL_KEYWORDS
+            .iter()
+            .any(|keyword| self.is_keyword(open - 1, keyword));
+        let for_await =
+            open > 1 && self.is(open - 1, "await") && self.is_keyword(open - 2, "for");
+        !statement && !for_await
+    }
+
+    /// Returns the index of the last token of the expression starting at `start`
+    fn expression_end(&self, start: usize) -> usize {
+        let mut conditionals = 0;
+        let mut last = start;
+        let mut i = start;
+        while i < self.tokens.len() {
+            if i > start
+                && self.tokens[i].newline_before
+                && self.ends_expression(last)
+                && self.starts_statement(i)
+            {
+                break;
+            }
+            if self.is_closing(i) {
+                break;
+            }
+            if self.tokens[i].kind == Kind::Punct {
+                match self.text(i) {
+                    "," | ";" => break,
+                    "?" => conditionals += 1,
+                    ":" if conditionals == 0 => break,
+                    ":" => conditionals -= 1,
+                    _ => (),
+                }
+            }
+            last = self.skip_group(i);
+            i = last + 1;
+        }
+        last
+    }
+
+    fn ends_expression(&self, i: usize) -> bool {
+        match self.tokens[i].kind {
+            Kind::Ident | Kind::Literal => true,
+            Kind::Template { opens, .. } => !opens,
+            Kind::Punct => matches!(self.text(i), ")" | "]" | "}" | "++" | "--"),
+        }
+    }
+
+    fn starts_statement(&self, i: usize) -> bool {
+        match self.tokens[i].kind {
+            Kind::Ident => !matches!(self.text(i), "in" | "instanceof"),
+            Kind::Literal => true,
+            Kind::Template { .. } => false,
+            Kind::Punct => matches!(self.text(i), "++" | "--" | "!" | "~"),
+        }
+    }
+
+    fn apply(mut self) -> String {
+        // At the same offset, closing insertions come first, innermost first
+        let mut insertions: Vec<(usize, Insertion)> =
+            self.insertions.drain(..).enumerate().collect();
+        insertions.sort_by_key(|(seq, insertion)| {
+            let rank = if insertion.closes {
+                (0, usize::MAX - seq)
+            } else {
+                (1, *seq)
+            };
+            (insertion.offset, rank)
+        });
+        let extra: usize = insertions.iter().map(|(_, i)| i.text.len()).sum();
+        let mut output = String::with_capacity(self.src.len() + extra);
+        let mut offset = 0;
+        for (_, insertion) in insertions {
+            output.push_str(&self.src[offset..insertion.offset]);
+            output.push_str(&insertion.text);
+            offset = insertion.offset;
+        }
+        output.push_str(&self.src[offset..]);
+        output
+    }
+}
+
+/// Returns the name of an identifier with its unicode escape sequences decoded,
+/// e.g. `\u005f_jstz_gas__` or `\u{5f}_jstz_gas__`. Invalid escape sequences
+/// are kept as they are, since they are syntax errors anyway.
+fn identifier_name(text: &str) -> std::borrow::Cow<'_, str> {
+    if !text.contains('\\') {
+        return text.into();
+    }
+    let mut name = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('\\') {
+        name.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let (hex, len) = match rest.strip_prefix("\\u{") {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 4),
+                None => ("", 0),
+            },
+            None => (rest.get(2..6).unwrap_or(""), 6),
+        };
+        match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            Some(c) if rest.starts_with("\\u") => {
+                name.push(c);
+                rest = &rest[len..];
+            }
+            _ => {
+                name.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    name.push_str(rest);
+    name.into()
+}
+
+/// Instruments `code` to charge gas on loop iterations and function calls
+pub fn instrument(code: &str) -> Result<String> {
+    let tokens = tokenize(code)?;
+    if tokens.iter().any(|t| {
+        t.kind == Kind::Ident && identifier_name(&code[t.start..t.end]) == GAS_FUNCTION
+    }) {
+        return Err(InstrumentError::ReservedIdentifier);
+    }
+    Ok(Instrumenter::new(code, tokens)?.instrument())
+}
+
+/// Returns true if `code` has a default export, i.e. `export default` or
+/// `export { x as default }`
+pub fn has_default_export(code: &str) -> Result<bool> {
+    let tokens = tokenize(code)?;
+    Ok(tokens.windows(2).any(|pair| {
+        pair.iter().all(|t| t.kind == Kind::Ident)
+            && matches!(&code[pair[0].start..pair[0].end], "export" | "as")
+            && &code[pair[1].start..pair[1].end] == "default"
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{has_default_export, instrument, InstrumentError};
+
+    fn check(code: &str, expected: &str) {
+        assert_eq!(
+            instrument(code).unwrap(),
+            expected.replace('@', "__jstz_gas__")
+        );
+    }
+
+    #[test]
+    fn instruments_loops() {
+        check("while (x) y();", "while (@()&&(x)) y();");
+        check("do { y() } while (x)", "do { y() } while (@()&&(x))");
+        check(
+            "for (let i = 0; i < n; i++) {}",
+            "for (let i = 0;@()&&( i < n); i++) {}",
+        );
+        check("for (;;) x++", "for (;@();) x++");
+        check("for (const x of xs) {}", "for (const x of xs) {@();}");
+        check(
+            "for await (const x of xs) {}",
+            "for await (const x of xs) {@();}",
+        );
+        check("for (k in o) f(k); g()", "for (k in o) {@();f(k);} g()");
+        check("for (x of xs) f(x)\ng()", "for (x of xs) {@();f(x)}\ng()");
+        check(
+            "for (a of as) for (b of bs) if (a) f(a, b); else g()",
+            "for (a of as) {@();for (b of bs) {@();if (a) f(a, b); else g()}}",
+        );
+        check(
+            "for (x of xs) do x++; while (x < 3); f()",
+            "for (x of xs) {@();do x++; while (@()&&(x < 3));} f()",
+        );
+        check(
+            "l: for (x of xs) try { f(x) } catch { continue l }",
+            "l: for (x of xs) {@();try { f(x) } catch { continue l }}",
+        );
+        check("x.while(y); ({ for: 1 })", "x.while(y); ({ for: 1 })");
+    }
+
+    #[test]
+    fn instruments_functions() {
+        check(
+            "function f(a) { return a }",
+            "function f(a) {@(); return a }",
+        );
+        check("const f = async () => { }", "const f = async () => {@(); }");
+        check(
+            "class A extends mixin(B) { constructor() { super() } get x() { return 1 } }",
+            "class A extends mixin(B) { constructor() {@(); super() } get x() {@(); return 1 } }",
+        );
+        check("if (x) { } else { }", "if (x) { } else { }");
+        check(
+            "switch (x) { case 1: break }",
+            "switch (x) { case 1: break }",
+        );
+        check("try { } catch (e) { }", "try { } catch (e) { }");
+    }
+
+    #[test]
+    fn instruments_arrow_expression_bodies() {
+        check("xs.map(x => x + 1)", "xs.map(x =>(@(), x + 1))");
+        check("f(x => g(x), 2)", "f(x =>(@(), g(x)), 2)");
+        check("a ? x => 1 : 2", "a ? x =>(@(), 1) : 2");
+        check("x => x ? 1 : 2;", "x =>(@(), x ? 1 : 2);");
+        check("x => y => x", "x =>(@(), y =>(@(), x))");
+        check("const f = x => x\nf(1)", "const f = x =>(@(), x)\nf(1)");
+        check("x => x\n.y", "x =>(@(), x\n.y)");
+        check("({ a: x => 1 })", "({ a: x =>(@(), 1) })");
+    }
+
+    #[test]
+    fn ignores_literals_and_comments() {
+        check(
+            r#"'while (x)'; "for (;;)"; // x => x
+            /* function() {} */ /[/(]while/.test(x); `${a => 1} x => x`"#,
+            r#"'while (x)'; "for (;;)"; // x => x
+            /* function() {} */ /[/(]while/.test(x); `${a =>(@(), 1)} x => x`"#,
+        );
+        check("a / b / c", "a / b / c");
+    }
+
+    #[test]
+    fn lexes_slashes_after_brackets() {
+        check(
+            "x = {} / 1; while (x) {}",
+            "x = {} / 1; while (@()&&(x)) {}",
+        );
+        check(
+            "if (x) {} /'/.test(y); while (x) {}",
+            "if (x) {} /'/.test(y); while (@()&&(x)) {}",
+        );
+        check(
+            "if (x) /'/.test(y); while (x) {}",
+            "if (x) /'/.test(y); while (@()&&(x)) {}",
+        );
+        check("f(x) / 2; while (x) {}", "f(x) / 2; while (@()&&(x)) {}");
+    }
+
+    #[test]
+    fn detects_default_export() {
+        assert!(has_default_export("export default () => 1").unwrap());
+        assert!(has_default_export("const f = 1; export { f as default }").unwrap());
+        assert!(!has_default_export("export const f = 1").unwrap());
+        assert!(!has_default_export("// export default\n'export default'").unwrap());
+    }
+
+    #[test]
+    fn rejects_ambiguous_slash() {
+        assert_eq!(
+            instrument("function f() {}\n/x/.test(y)"),
+            Err(InstrumentError::AmbiguousSlash(16))
+        );
+        assert!(instrument("function f() {}\n(/x/).test(y)").is_ok());
+    }
+
+    #[test]
+    fn rejects_reserved_identifier() {
+        assert_eq!(
+            instrument("const __jstz_gas__ = () => {}"),
+            Err(InstrumentError::ReservedIdentifier)
+        );
+        assert!(instrument("'__jstz_gas__'").is_ok());
+        assert_eq!(
+            instrument(r"const \u005f_jstz_gas__ = () => true"),
+            Err(InstrumentError::ReservedIdentifier)
+        );
+        assert_eq!(
+            instrument(r"const __jstz_\u{67}as__ = () => true"),
+            Err(InstrumentError::ReservedIdentifier)
+        );
+        assert!(instrument(r"const \u0066 = 1").is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_code() {
+        assert_eq!(
+            instrument("'abc"),
+            Err(InstrumentError::UnterminatedLiteral(0))
+        );
+        assert_eq!(
+            instrument("f(x"),
+            Err(InstrumentError::UnbalancedBrackets(1))
+        );
+        assert_eq!(
+            instrument("f(x))"),
+            Err(InstrumentError::UnbalancedBrackets(4))
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Deterministic gas metering for smart functions.
+//!
+//! V8 does not count instructions, so gas is charged at well defined points
+//! instead:
+//!
+//! * JS execution - smart function code is instrumented (see [`instrument`]) so that
+//!   every loop iteration and every function call charges [`cost::TICK`]
+//! * Host operations - Kv, Ledger, fetch and console ops charge a fixed cost,
+//!   and writes additionally charge per byte stored
+//!
+//! All smart function calls of an operation share a single [`GasMeter`]. Once the
+//! meter runs out of gas, the running isolate is terminated. The error is uncatchable
+//! and callers up the stack are terminated as soon as they charge gas again.
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use deno_core::{v8, OpState};
+
+use crate::RuntimeContext;
+
+mod instrument;
+pub use instrument::{has_default_export, instrument, InstrumentError, GAS_FUNCTION};
+
+/// Gas charged by the protocol
+pub mod cost {
+    /// Loop iteration or function call
+    pub const TICK: u64 = 10;
+    /// `Kv.get` and `Kv.contains`. Reads through `Kv.of` cost one more read to check
+    /// that the key is readable, and `Kv.set` and `Kv.delete` one read of the size of
+    /// the previous value
+    pub const KV_READ: u64 = 100;
+    /// `Kv.set`, `Kv.delete`, `Kv.publish` and `Kv.unpublish`
+    pub const KV_WRITE: u64 = 200;
+    /// Per byte of key and value stored by `Kv.set`, and of prefix published by
+    /// `Kv.publish`
+    pub const KV_BYTE: u64 = 1;
+    /// Ledger operations
+    pub const LEDGER: u64 = 100;
+    /// `fetch` request
+    pub const FETCH: u64 = 100;
+    /// Console message
+    pub const CONSOLE: u64 = 10;
+}
+
+#[derive(Debug, thiserror::Error, deno_error::JsError)]
+#[class(range)]
+#[error("Out of gas")]
+pub struct OutOfGas;
+
+#[derive(Debug, Default)]
+struct GasState {
+    used: AtomicU64,
+    exhausted: AtomicBool,
+}
+
+/// A shareable gas meter. Clones share the same gas counter.
+#[derive(Debug, Clone)]
+pub struct GasMeter {
+    limit: u64,
+    state: Arc<GasState>,
+}
+
+impl Default for GasMeter {
+    /// Returns an unlimited gas meter
+    fn default() -> Self {
+        Self::new(u64::MAX)
+    }
+}
+
+impl GasMeter {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            state: Default::default(),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn used(&self) -> u64 {
+        self.state.used.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if consuming gas failed at least once
+    pub fn is_exhausted(&self) -> bool {
+        self.state.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Consumes `amount` gas. If the remaining gas is insufficient, the meter
+    /// is exhausted and all the remaining gas is consumed.
+    pub fn consume(&self, amount: u64) -> Result<(), OutOfGas> {
+        let result =
+            self.state
+                .used
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                    used.checked_add(amount).filter(|used| *used <= self.limit)
+                });
+        if result.is_err() {
+            self.state.used.store(self.limit, Ordering::Relaxed);
+            self.state.exhausted.store(true, Ordering::Relaxed);
+            return Err(OutOfGas);
+        }
+        Ok(())
+    }
+}
+
+/// Charges `amount` gas to the operation running in `state`. When out of gas,
+/// the execution of the isolate is terminated.
+///
+/// Runtimes without protocol are not metered.
+pub fn charge(state: &OpState, amount: u64) -> Result<(), OutOfGas> {
+    let Some(proto) = state.try_borrow::<RuntimeContext>() else {
+        return Ok(());
+    };
+    let result = proto.slot.gas().consume(amount);
+    if result.is_err() {
+        if let Some(isolate) = state.try_borrow::<v8::IsolateHandle>() {
+            isolate.terminate_execution();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use deno_core::{resolve_import, StaticModuleLoader};
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+    use jstz_utils::test_util::TOKIO;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::{cost, instrument, GasMeter, InstrumentError};
+    use crate::{runtime::Limiter, JstzRuntime, JstzRuntimeOptions, RuntimeContext};
+
+    #[test]
+    fn consume_gas() {
+        let gas = GasMeter::new(100);
+        gas.consume(60).unwrap();
+        gas.clone().consume(40).unwrap();
+        assert_eq!(gas.used(), 100);
+        assert!(!gas.is_exhausted());
+
+        assert!(gas.consume(1).is_err());
+        assert_eq!(gas.used(), 100);
+        assert!(gas.is_exhausted());
+    }
+
+    #[test]
+    fn consume_gas_does_not_overflow() {
+        let gas = GasMeter::default();
+        gas.consume(u64::MAX).unwrap();
+        assert!(gas.consume(1).is_err());
+    }
+
+    fn run(code: &str, gas_limit: u64) -> (GasMeter, bool) {
+        let code = instrument(code).unwrap();
+        TOKIO.block_on(async {
+            let mut host = MockHost::default();
+            let mut tx = Transaction::default();
+            tx.begin();
+            let address =
+                SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton")
+                    .unwrap();
+            let specifier =
+                resolve_import("file://jstz/accounts/root", "//sf/main.js").unwrap();
+            let limiter = Limiter::default().with_gas_limit(gas_limit);
+            let gas = limiter.gas().clone();
+            let protocol = RuntimeContext::new(
+                &mut host,
+                &mut tx,
+                address,
+                String::new(),
+                limiter.try_acquire().unwrap(),
+            );
+            let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+                protocol: Some(protocol),
+                module_loader: Rc::new(StaticModuleLoader::with(specifier.clone(), code)),
+                ..Default::default()
+            });
+            let id = runtime.execute_main_module(&specifier).await.unwrap();
+            let result = runtime.call_default_handler(id, &[]).await;
+            (gas, result.is_ok())
+        })
+    }
+
+    #[test]
+    fn metered_execution_is_deterministic() {
+        let code = r#"
+            export default () => {
+                let sum = 0;
+                for (let i = 0; i < 10; i++) {
+                    sum += i;
+                }
+                return sum;
+            }
+        "#;
+        let (gas, ok) = run(code, 1000);
+        assert!(ok);
+        // handler call + 10 iterations
+        assert_eq!(gas.used(), 11 * cost::TICK);
+        assert_eq!(run(code, 1000).0.used(), gas.used());
+    }
+
+    #[test]
+    fn infinite_loop_runs_out_of_gas() {
+        let code = r#"
+            export default () => {
+                while (true) {
+                    try {
+                        for (;;) {}
+                    } catch {}
+                }
+            }
+        "#;
+        let (gas, ok) = run(code, 100_000);
+        assert!(!ok);
+        assert!(gas.is_exhausted());
+        assert_eq!(gas.used(), 100_000);
+    }
+
+    #[test]
+    fn infinite_iterator_runs_out_of_gas() {
+        let code = r#"
+            export default () => {
+                for (const i of Array(2**32-1).keys()) {}
+            }
+        "#;
+        let (gas, ok) = run(code, 100_000);
+        assert!(!ok);
+        assert!(gas.is_exhausted());
+    }
+
+    #[test]
+    fn escaped_gas_function_cannot_be_shadowed() {
+        let code = r#"
+            const \u005f_jstz_gas__ = () => true;
+            export default () => {
+                while (true) {}
+            }
+        "#;
+        assert_eq!(instrument(code), Err(InstrumentError::ReservedIdentifier));
+
+        // Other escaped identifiers are metered like any other
+        let code = r#"
+            const \u0066 = () => {
+                while (true) {}
+            };
+            export default () => f()
+        "#;
+        let (gas, ok) = run(code, 100_000);
+        assert!(!ok);
+        assert!(gas.is_exhausted());
+    }
+
+    #[test]
+    fn code_generation_from_strings_is_disallowed() {
+        assert!(!run("export default () => eval('1')", 1000).1);
+        assert!(!run("export default () => new Function('return 1')()", 1000).1);
+        assert!(run("export default () => 1", 1000).1);
+    }
+
+    #[test]
+    fn host_operations_are_metered() {
+        let code = r#"
+            export default () => {
+                Kv.set("key", "value");
+                Kv.get("key");
+                console.log("hello");
+            }
+        "#;
+        let (gas, ok) = run(code, 1000);
+        assert!(ok);
+        // "key" + "\"value\""
+        let stored = 3 + 7;
+        assert_eq!(
+            gas.used(),
+            cost::TICK
+                + cost::KV_WRITE
+                + cost::KV_READ
+                + stored * cost::KV_BYTE
+                + cost::KV_READ
+                + cost::CONSOLE
+        );
+
+        let (gas, ok) = run(code, 300);
+        assert!(!ok);
+        assert!(gas.is_exhausted());
+    }
+}
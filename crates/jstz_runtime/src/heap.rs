@@ -0,0 +1,137 @@
+//! Heap accounting for smart functions.
+//!
+//! Every smart function call runs in its own isolate whose heap is limited to
+//! [`HeapMeter::limit`] bytes. When an isolate gets close to its limit, its execution
+//! is terminated and the call fails with [`OutOfMemory`]. Unlike running out of gas,
+//! only the offending call fails; its caller receives an error response.
+//!
+//! The meter is shared by all the smart function calls of an operation and records
+//! the peak heap size they reached.
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Default heap limit of a smart function call, in bytes
+pub const DEFAULT_HEAP_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error, deno_error::JsError)]
+#[class(range)]
+#[error("Out of memory")]
+pub struct OutOfMemory;
+
+/// A shareable heap meter. Clones share the same peak heap size.
+#[derive(Debug, Clone)]
+pub struct HeapMeter {
+    limit: usize,
+    peak: Arc<AtomicUsize>,
+}
+
+impl Default for HeapMeter {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEAP_LIMIT)
+    }
+}
+
+impl HeapMeter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            peak: Default::default(),
+        }
+    }
+
+    /// Heap limit of each smart function call, in bytes
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Largest heap size recorded, in bytes
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Records a heap size of `size` bytes
+    pub fn record(&self, size: usize) {
+        self.peak.fetch_max(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use deno_core::{resolve_import, StaticModuleLoader};
+    use jstz_core::kv::Transaction;
+    use jstz_crypto::{hash::Hash, smart_function_hash::SmartFunctionHash};
+    use jstz_utils::test_util::TOKIO;
+    use tezos_smart_rollup_mock::MockHost;
+
+    use super::HeapMeter;
+    use crate::{runtime::Limiter, JstzRuntime, JstzRuntimeOptions, RuntimeContext};
+
+    #[test]
+    fn record_peak() {
+        let heap = HeapMeter::new(100);
+        heap.record(60);
+        heap.clone().record(40);
+        assert_eq!(heap.peak(), 60);
+        assert_eq!(heap.limit(), 100);
+    }
+
+    fn run(code: &'static str, heap_limit: usize) -> (JstzRuntime, bool) {
+        TOKIO.block_on(async {
+            let mut host = MockHost::default();
+            let mut tx = Transaction::default();
+            tx.begin();
+            let address =
+                SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton")
+                    .unwrap();
+            let specifier =
+                resolve_import("file://jstz/accounts/root", "//sf/main.js").unwrap();
+            let limiter = Limiter::default().with_heap_limit(heap_limit);
+            let protocol = RuntimeContext::new(
+                &mut host,
+                &mut tx,
+                address,
+                String::new(),
+                limiter.try_acquire().unwrap(),
+            );
+            let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
+                protocol: Some(protocol),
+                module_loader: Rc::new(StaticModuleLoader::with(specifier.clone(), code)),
+                ..Default::default()
+            });
+            let id = runtime.execute_main_module(&specifier).await.unwrap();
+            let result = runtime.call_default_handler(id, &[]).await;
+            (runtime, result.is_ok())
+        })
+    }
+
+    #[test]
+    fn heap_limit_terminates_execution() {
+        let code = r#"
+            export default () => {
+                const chunks = [];
+                while (true) {
+                    chunks.push(new Array(1024 * 1024).fill(42));
+                }
+            }
+        "#;
+        let (mut runtime, ok) = run(code, 16 * 1024 * 1024);
+        assert!(!ok);
+        assert!(runtime.is_out_of_memory());
+        assert!(runtime.heap_size() > 0);
+    }
+
+    #[test]
+    fn heap_limit_is_not_reached() {
+        let code = r#"
+            export default () => new Array(1024).fill(42).length
+        "#;
+        let (mut runtime, ok) = run(code, 16 * 1024 * 1024);
+        assert!(ok);
+        assert!(!runtime.is_out_of_memory());
+        assert!(runtime.heap_size() <= 16 * 1024 * 1024);
+    }
+}
@@ -1,8 +1,12 @@
+pub mod debugger;
 pub mod error;
 pub mod ext;
+pub mod gas;
+pub mod heap;
 pub use ext::jstz_kv::kv::*;
 
 pub mod runtime;
+pub mod storage;
 pub mod sys;
 
 #[cfg(feature = "wpt")]
@@ -103,7 +107,7 @@ mod test_utils {
             let request_id = String::new();
             $(let request_id = $request_id.to_string();)?
             #[allow(unused)]
-            let protocol  = Some($crate::RuntimeContext::new(&mut init_host, &mut init_tx, init_addr.clone(), request_id, $crate::runtime::Limiter::<5>::default().try_acquire().unwrap()));
+            let protocol  = Some($crate::RuntimeContext::new(&mut init_host, &mut init_tx, init_addr.clone(), request_id, $crate::runtime::Limiter::default().try_acquire().unwrap()));
             #[allow(unused)]
             let mut $runtime = $crate::JstzRuntime::new($crate::JstzRuntimeOptions {
                 protocol,
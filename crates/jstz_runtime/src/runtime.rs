@@ -1,3 +1,4 @@
+use crate::debugger;
 use crate::error::Result;
 use crate::ext::jstz_fetch::FetchAPI;
 use crate::ext::jstz_fetch::NotSupportedFetch;
@@ -15,6 +16,7 @@ use jstz_crypto::hash::Hash;
 use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use pin_project::pin_project;
 use serde::Deserialize;
+use std::cell::Cell;
 use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
 use std::result::Result as StdResult;
@@ -29,14 +31,16 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::ext::{jstz_console, jstz_kv, jstz_kv::kv::Kv, jstz_main};
+use crate::ext::{jstz_console, jstz_gas, jstz_kv, jstz_kv::kv::Kv, jstz_main};
+use crate::gas::GasMeter;
+use crate::heap::HeapMeter;
+use crate::storage::SharedStorageAccounting;
 use deno_console;
 use deno_url;
 use deno_web::TimersPermission;
 use deno_webidl;
 
-// This is just a placeholder to reduce the risk of stack overflows
-// TODO: Track the memory usage of the smart function and explore a reasonable limit.
+/// Default maximum depth of nested smart function calls
 pub const MAX_SMART_FUNCTION_CALL_COUNT: u8 = 5;
 
 /// Returns the default object of the specified JavaScript namespace (Object).
@@ -57,6 +61,8 @@ fn get_default_export<'s>(
 /// and protocol capabilities
 pub struct JstzRuntime {
     runtime: std::mem::ManuallyDrop<JsRuntime>,
+    /// Set when the execution is terminated for reaching the heap limit
+    out_of_memory: Rc<Cell<bool>>,
 }
 
 impl Drop for JstzRuntime {
@@ -167,27 +173,75 @@ impl JstzRuntime {
         snapshot: Option<&'static [u8]>,
     ) -> Self {
         let v8_platform = Some(new_single_threaded_default_platform(false).make_shared());
+        // Smart functions are limited by the heap meter of their operation
+        let metered = protocol.is_some();
+        let heap = protocol.as_ref().map(|proto| proto.slot.heap().clone());
+        let create_params = heap
+            .as_ref()
+            .map(|heap| v8::CreateParams::default().heap_limits(0, heap.limit()));
         // Construct Runtime options
         let js_runtime_options = RuntimeOptions {
+            create_params,
             extensions,
             module_loader: Some(module_loader),
             v8_platform,
             startup_snapshot: snapshot,
             skip_op_registration: false,
+            // A debugger needs the inspector to connect DevTools sessions
+            inspector: debugger::is_enabled(),
+            is_main: debugger::is_enabled(),
             ..Default::default()
         };
 
         // SAFETY: See `impl Drop for JstzRuntime`
         let mut runtime = ManuallyDrop::new(JsRuntime::new(js_runtime_options));
+        let isolate = runtime.v8_isolate().thread_safe_handle();
+        let out_of_memory = Rc::new(Cell::new(false));
+        if let Some(heap) = heap {
+            let isolate = isolate.clone();
+            let out_of_memory = out_of_memory.clone();
+            runtime.add_near_heap_limit_callback(move |current_limit, _initial_limit| {
+                heap.record(current_limit);
+                out_of_memory.set(true);
+                isolate.terminate_execution();
+                // Leave room for the terminated execution to unwind
+                current_limit * 2
+            });
+        }
+        if metered {
+            // Code generated from strings would escape the gas instrumentation
+            let context = runtime.main_context();
+            let scope = &mut runtime.handle_scope();
+            let context = v8::Local::new(scope, context);
+            context.set_allow_generation_from_strings(false);
+        }
         unsafe { runtime.v8_isolate().exit() };
         // Give protocol access to the running script
         let op_state = runtime.op_state();
+        // Allows terminating the execution when out of gas
+        op_state.borrow_mut().put(isolate);
         if let Some(protocol) = protocol {
             op_state.borrow_mut().put(protocol);
         };
         op_state.borrow_mut().put(JstzPermissions);
 
-        Self { runtime }
+        Self {
+            runtime,
+            out_of_memory,
+        }
+    }
+
+    /// Returns true if the execution was terminated for reaching the heap limit
+    pub fn is_out_of_memory(&self) -> bool {
+        self.out_of_memory.get()
+    }
+
+    /// Returns the size of the heap in bytes
+    pub fn heap_size(&mut self) -> usize {
+        let mut this = Entered::new(self);
+        let mut stats = v8::HeapStatistics::default();
+        this.v8_isolate().get_heap_statistics(&mut stats);
+        stats.total_heap_size()
     }
 
     pub fn set_state<S: 'static>(&mut self, state: S) {
@@ -219,6 +273,35 @@ impl JstzRuntime {
         Ok(result)
     }
 
+    /// Blocks until a DevTools session connects to the inspector, then pauses on
+    /// the next statement. The runtime must have been created with a debugger set,
+    /// see [`crate::debugger`].
+    pub fn wait_for_debugger(&mut self) {
+        let entered = Entered::new(self);
+        entered
+            .runtime
+            .inspector()
+            .borrow_mut()
+            .wait_for_session_and_break_on_next_statement();
+    }
+
+    /// Executes traditional, non-ECMAScript-module JavaScript code, running the
+    /// event loop until its result resolves if it is a promise
+    pub async fn execute_and_resolve(
+        &mut self,
+        code: &str,
+    ) -> Result<v8::Global<v8::Value>> {
+        let code = code.to_string();
+        let fut = AsyncEntered::new(self, |runtime| async move {
+            let value = runtime.execute_script("jstz://run", code)?;
+            let promise = runtime.resolve(value);
+            Ok(runtime
+                .with_event_loop_promise(promise, Default::default())
+                .await?)
+        });
+        fut.await
+    }
+
     /// Loads and instantiated specified JavaScript module as the "main" module.
     /// The module is "main" in the sense that [`import.meta.main`] is set to [`true`].
     pub async fn preload_main_module(
@@ -425,14 +508,37 @@ pub enum LimiterError {
 #[derive(Debug)]
 pub struct Slot {
     slots: Arc<AtomicU8>,
+    max_call_depth: u8,
+    gas: GasMeter,
+    heap: HeapMeter,
+    storage: Option<SharedStorageAccounting>,
 }
 
 impl Slot {
     pub fn limiter(&self) -> Limiter {
         Limiter {
             slots_in_use: Arc::clone(&self.slots),
+            max_call_depth: self.max_call_depth,
+            gas: self.gas.clone(),
+            heap: self.heap.clone(),
+            storage: self.storage.clone(),
         }
     }
+
+    /// Gas meter shared by all the smart function calls of the operation
+    pub fn gas(&self) -> &GasMeter {
+        &self.gas
+    }
+
+    /// Heap meter shared by all the smart function calls of the operation
+    pub fn heap(&self) -> &HeapMeter {
+        &self.heap
+    }
+
+    /// Storage accounting of the operation, if any
+    pub fn storage(&self) -> Option<&SharedStorageAccounting> {
+        self.storage.as_ref()
+    }
 }
 
 impl Drop for Slot {
@@ -441,25 +547,89 @@ impl Drop for Slot {
     }
 }
 
-/// A shareable limiter that allows at most `LIMIT` concurrent counts.
+/// A shareable limiter that allows at most `max_call_depth` concurrent counts.
 ///
 /// Each successful call to [`Limiter::try_acquire`] returns a [`Slot`].
 /// The slot frees up once it is dropped.
-#[derive(Debug, Clone, Default)]
-pub struct Limiter<const LIMIT: u8 = MAX_SMART_FUNCTION_CALL_COUNT> {
+///
+/// The limiter also carries the [`GasMeter`], [`HeapMeter`] and storage accounting of
+/// the operation. The default limiter allows [`MAX_SMART_FUNCTION_CALL_COUNT`] calls,
+/// has unlimited gas, the default heap limit and no storage accounting.
+#[derive(Debug, Clone)]
+pub struct Limiter {
     slots_in_use: Arc<AtomicU8>,
+    max_call_depth: u8,
+    gas: GasMeter,
+    heap: HeapMeter,
+    storage: Option<SharedStorageAccounting>,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self {
+            slots_in_use: Default::default(),
+            max_call_depth: MAX_SMART_FUNCTION_CALL_COUNT,
+            gas: Default::default(),
+            heap: Default::default(),
+            storage: None,
+        }
+    }
 }
 
-impl<const LIMIT: u8> Limiter<LIMIT> {
+impl Limiter {
+    /// Sets the maximum depth of nested smart function calls
+    pub fn with_max_call_depth(self, max_call_depth: u8) -> Self {
+        Self {
+            max_call_depth,
+            ..self
+        }
+    }
+
+    /// Sets a gas meter of `gas_limit`
+    pub fn with_gas_limit(self, gas_limit: u64) -> Self {
+        Self {
+            gas: GasMeter::new(gas_limit),
+            ..self
+        }
+    }
+
+    /// Sets a heap meter limiting each smart function call to `heap_limit` bytes
+    pub fn with_heap_limit(self, heap_limit: usize) -> Self {
+        Self {
+            heap: HeapMeter::new(heap_limit),
+            ..self
+        }
+    }
+
+    /// Sets the storage accounting of Kv writes
+    pub fn with_storage(self, storage: SharedStorageAccounting) -> Self {
+        Self {
+            storage: Some(storage),
+            ..self
+        }
+    }
+
+    pub fn max_call_depth(&self) -> u8 {
+        self.max_call_depth
+    }
+
+    pub fn gas(&self) -> &GasMeter {
+        &self.gas
+    }
+
+    pub fn heap(&self) -> &HeapMeter {
+        &self.heap
+    }
+
     /// Attempts to acquire a slot.
     ///
     /// Returns:
-    /// - `Ok(Slot)` if the current usage is below `LIMIT`. The Slot frees up once it is dropped.
+    /// - `Ok(Slot)` if the current usage is below `max_call_depth`. The Slot frees up once it is dropped.
     /// - `Err(LimiterError::LimitExceeded)` if the limit has been reached.
     pub fn try_acquire(&self) -> StdResult<Slot, LimiterError> {
         self.slots_in_use
             .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |val| {
-                if val < LIMIT {
+                if val < self.max_call_depth {
                     Some(val + 1)
                 } else {
                     None
@@ -468,6 +638,10 @@ impl<const LIMIT: u8> Limiter<LIMIT> {
             .map_err(|_| LimiterError::LimitExceeded)?;
         Ok(Slot {
             slots: Arc::clone(&self.slots_in_use),
+            max_call_depth: self.max_call_depth,
+            gas: self.gas.clone(),
+            heap: self.heap.clone(),
+            storage: self.storage.clone(),
         })
     }
 
@@ -492,6 +666,7 @@ fn init_base_extensions_ops_and_esm<F: FetchAPI>() -> Vec<Extension> {
         deno_webidl::deno_webidl::init_ops_and_esm(),
         deno_console::deno_console::init_ops_and_esm(),
         jstz_console::jstz_console::init_ops_and_esm(),
+        jstz_gas::jstz_gas::init_ops_and_esm(),
         deno_url::deno_url::init_ops_and_esm(),
         jstz_kv::jstz_kv::init_ops_and_esm(),
         deno_web::deno_web::init_ops_and_esm::<JstzPermissions>(Default::default(), None),
@@ -507,6 +682,7 @@ fn init_base_extensions_ops<F: FetchAPI>() -> Vec<Extension> {
         deno_webidl::deno_webidl::init_ops(),
         deno_console::deno_console::init_ops(),
         jstz_console::jstz_console::init_ops(),
+        jstz_gas::jstz_gas::init_ops(),
         deno_url::deno_url::init_ops(),
         jstz_kv::jstz_kv::init_ops(),
         deno_web::deno_web::init_ops::<JstzPermissions>(Default::default(), None),
@@ -658,6 +834,23 @@ export default handler;
         })
     }
 
+    #[test]
+    fn execute_and_resolve_awaits_promises() {
+        TOKIO.block_on(async {
+            init_test_setup! {
+                runtime = rt;
+            };
+            let result = rt
+                .execute_and_resolve(
+                    "new Promise((resolve) => setTimeout(() => resolve(42), 0))",
+                )
+                .await;
+            let scope = &mut rt.handle_scope();
+            let result_i64 = result.unwrap().open(scope).integer_value(scope).unwrap();
+            assert_eq!(result_i64, 42);
+        })
+    }
+
     #[test]
     #[ignore = "Will run forever"]
     fn test_infinite_loop() {
@@ -708,9 +901,7 @@ export default handler;
                 &mut tx,
                 init_addr.clone(),
                 String::new(),
-                Limiter::<MAX_SMART_FUNCTION_CALL_COUNT>::default()
-                    .try_acquire()
-                    .unwrap(),
+                Limiter::default().try_acquire().unwrap(),
             );
             let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
                 protocol: Some(protocol),
@@ -730,7 +921,7 @@ export default handler;
 
     #[tokio::test]
     async fn test_limiter() {
-        let limiter = Limiter::<2>::default();
+        let limiter = Limiter::default().with_max_call_depth(2);
         assert_eq!(limiter.in_use(), 0);
         // Acquiring a slot should succeed
         {
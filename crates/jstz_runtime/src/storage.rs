@@ -0,0 +1,31 @@
+//! Storage accounting for smart functions.
+//!
+//! Kv writes change the durable storage used by the writing smart function. The
+//! protocol accounts for it through the [`StorageAccounting`] of the operation's
+//! [`Limiter`], e.g. to enforce storage quotas or to charge storage deposits. When the
+//! limiter has none, storage is not accounted for.
+//!
+//! [`Limiter`]: crate::runtime::Limiter
+use std::{fmt::Debug, sync::Arc};
+
+use jstz_core::{host::JsHostRuntime, kv::Transaction};
+use jstz_crypto::smart_function_hash::SmartFunctionHash;
+
+#[derive(Debug, thiserror::Error, deno_error::JsError)]
+#[class(range)]
+#[error("{0}")]
+pub struct StorageError(pub String);
+
+pub trait StorageAccounting: Debug + Send + Sync {
+    /// Accounts for the storage used by `address` changing by `delta` bytes. Fails
+    /// if `address` cannot use the additional storage.
+    fn update(
+        &self,
+        host: &mut JsHostRuntime<'static>,
+        tx: &mut Transaction,
+        address: &SmartFunctionHash,
+        delta: i64,
+    ) -> Result<(), StorageError>;
+}
+
+pub type SharedStorageAccounting = Arc<dyn StorageAccounting>;
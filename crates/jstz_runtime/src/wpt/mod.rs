@@ -3,10 +3,7 @@
 //! Provides test status enums, result and report structures, runtime operations and integration
 //! for running WPT tests within the JSTZ kernel.
 
-use crate::{
-    runtime::{Limiter, MAX_SMART_FUNCTION_CALL_COUNT},
-    JstzRuntime, JstzRuntimeOptions, RuntimeContext,
-};
+use crate::{runtime::Limiter, JstzRuntime, JstzRuntimeOptions, RuntimeContext};
 use deno_core::{
     convert::Smi,
     op2,
@@ -297,7 +294,7 @@ pub fn init_runtime(host: &mut impl HostRuntime, tx: &mut Transaction) -> JstzRu
     options
         .extensions
         .push(test_harness_api::init_ops_and_esm());
-    let limiter = Limiter::<MAX_SMART_FUNCTION_CALL_COUNT>::default();
+    let limiter = Limiter::default();
     let mut runtime = JstzRuntime::new(JstzRuntimeOptions {
         protocol: Some(RuntimeContext::new(
             host,
@@ -23,7 +23,7 @@ async fn test() {
     let mut host = MockHost::default();
     let address =
         SmartFunctionHash::from_base58("KT1RJ6PbjHpwc3M5rw5s2Nbmefwbuwbdxton").unwrap();
-    let limiter = Limiter::<5>::default();
+    let limiter = Limiter::default();
     let mut rt = JstzRuntime::new(JstzRuntimeOptions {
         protocol: Some(RuntimeContext::new(
             &mut host,
@@ -18,8 +18,8 @@ use tezos_smart_rollup::{
     inbox::{ExternalMessageFrame, InboxMessage, InternalInboxMessage, Transfer},
     michelson::{
         ticket::{FA2_1Ticket, Ticket},
-        Michelson, MichelsonContract, MichelsonNat, MichelsonOption, MichelsonOr,
-        MichelsonPair, MichelsonUnit,
+        Michelson, MichelsonBytes, MichelsonContract, MichelsonNat, MichelsonOption,
+        MichelsonOr, MichelsonPair, MichelsonUnit,
     },
     types::{Contract, PublicKeyHash, SmartRollupAddress},
     utils::inbox::file::{InboxFile, Message},
@@ -33,11 +33,14 @@ const MNEMONIC: &str =
     "donate kidney style loyal nose core inflict cup symptom speed giant polar";
 // FIXME: JSTZ-854
 type DepositInboxMsgPayloadType = MichelsonOr<
-    MichelsonPair<MichelsonContract, FA2_1Ticket>,
-    MichelsonPair<
-        MichelsonContract,
-        MichelsonPair<MichelsonOption<MichelsonContract>, FA2_1Ticket>,
+    MichelsonOr<
+        MichelsonPair<MichelsonContract, FA2_1Ticket>,
+        MichelsonPair<
+            MichelsonContract,
+            MichelsonPair<MichelsonOption<MichelsonContract>, FA2_1Ticket>,
+        >,
     >,
+    MichelsonPair<MichelsonContract, MichelsonBytes>,
 >;
 
 pub struct Account {
@@ -192,7 +195,7 @@ impl InboxBuilder {
         account: &Account,
         amount_mutez: u64,
     ) -> DepositInboxMsgPayloadType {
-        MichelsonOr::Left(MichelsonPair(
+        MichelsonOr::Left(MichelsonOr::Left(MichelsonPair(
             MichelsonContract(Contract::Implicit(
                 PublicKeyHash::from_b58check(&account.address.to_string())
                     .expect("serialised address should be parsable"),
@@ -203,7 +206,7 @@ impl InboxBuilder {
                 amount_mutez,
             )
             .expect("ticket creation from ticketer should work"),
-        ))
+        )))
     }
 
     pub fn deposit_from_l1(
@@ -542,7 +545,10 @@ mod tests {
                     InboxMessage::Internal(InternalInboxMessage::Transfer(transfer)) => {
                         assert_eq!(transfer.destination, builder.rollup_address);
                         assert_eq!(transfer.sender, builder.ticketer_address.unwrap());
-                        assert!(matches!(transfer.payload, MichelsonOr::Left(_)));
+                        assert!(matches!(
+                            transfer.payload,
+                            MichelsonOr::Left(MichelsonOr::Left(_))
+                        ));
                     }
                     _ => panic!("should be internal message"),
                 }
@@ -1,24 +1,17 @@
 use anyhow::Result;
-use jstz_crypto::{
-    public_key::PublicKey, secret_key::SecretKey, smart_function_hash::SmartFunctionHash,
-};
-use jstz_kernel::{INJECTOR, TICKETER};
+use jstz_crypto::{public_key::PublicKey, secret_key::SecretKey};
 use std::{
     collections::HashMap,
     env, fs,
     path::{Path, PathBuf},
 };
-use tezos_crypto_rs::hash::ContractKt1Hash;
-use tezos_smart_rollup::storage::path::OwnedPath;
-use tezos_smart_rollup_installer::{
-    installer, preimages, KERNEL_BOOT_PATH, PREPARE_KERNEL_PATH,
-};
-use tezos_smart_rollup_installer_config::binary::owned::{
-    OwnedBytes, OwnedConfigInstruction, OwnedConfigProgram,
-};
+use tezos_smart_rollup_installer::preimages;
 
 include!("build_config.rs");
 
+#[path = "src/installer.rs"]
+mod installer;
+
 /// The jstz kernel path used to generate the rollup installer / preimages.
 /// generated by running `make build build-jstzd-kernel`
 const JSTZ_KERNEL_PATH: &str = "./resources/jstz_rollup/jstz_kernel.wasm";
@@ -46,6 +39,9 @@ const ROLLUP_OPERATOR_BOOTSTRAP_ACCOUNT_ALIAS: &str = "rollup_operator";
 /// - kernel_installer_path(): Path to the kernel installer hex file
 /// - parameters_ty_path(): Path to the parameters type JSON file
 /// - preimages_path(): Path to the preimages directory
+///
+/// and the `KERNEL_ROOT_HASH` constant, the hex-encoded root hash of the kernel
+/// preimages, with which jstzd builds kernel installers at runtime.
 fn main() {
     println!("cargo:rerun-if-changed={JSTZ_KERNEL_PATH}");
     println!("cargo:rerun-if-changed={JSTZ_PARAMETERS_TY_PATH}");
@@ -67,7 +63,7 @@ fn main() {
         .get(INJECTOR_BOOTSTRAP_ACCOUNT_ALIAS)
         .expect("injector bootstrap account should exist")
         .clone();
-    let kernel_installer = make_kernel_installer(
+    let (kernel_installer, kernel_root_hash) = make_kernel_installer(
         PathBuf::from(JSTZ_KERNEL_PATH).as_path(),
         &preimages_dir,
         injector_pk,
@@ -79,7 +75,7 @@ fn main() {
         .expect("Failed to write kernel_installer.hex");
 
     // 5. Generate path getter code in OUT_DIR
-    generate_code(&out_dir);
+    generate_code(&out_dir, &kernel_root_hash);
 
     println!(
         "cargo:warning=Build script output directory: {}",
@@ -118,12 +114,12 @@ fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result
 /// * `preimages_dir` - Directory where preimages will be saved
 ///
 /// # Returns
-/// Hex-encoded kernel installer string
+/// Hex-encoded kernel installer string and hex-encoded root hash of the preimages
 fn make_kernel_installer(
     kernel_file: &Path,
     preimages_dir: &Path,
     injector_pk: PublicKey,
-) -> Result<String> {
+) -> Result<(String, String)> {
     if !kernel_file.exists() {
         return Err(anyhow::anyhow!(
             "kernel file not found: {}",
@@ -132,37 +128,9 @@ fn make_kernel_installer(
     }
     let content = fs::read(kernel_file)?;
     let root_hash = preimages::content_to_preimages(content, preimages_dir)?;
-    let installer_program = OwnedConfigProgram(vec![
-        // 1. Prepare kernel installer
-        OwnedConfigInstruction::reveal_instr(
-            root_hash,
-            OwnedPath::from(PREPARE_KERNEL_PATH),
-        ),
-        OwnedConfigInstruction::move_instr(
-            OwnedPath::from(PREPARE_KERNEL_PATH),
-            OwnedPath::from(KERNEL_BOOT_PATH),
-        ),
-        // 2. Set `jstz` ticketer as the bridge contract address
-        OwnedConfigInstruction::set_instr(
-            OwnedBytes(bincode::encode_to_vec(
-                SmartFunctionHash(
-                    ContractKt1Hash::from_base58_check(EXCHANGER_ADDRESS)?.into(),
-                ),
-                bincode::config::legacy(),
-            )?),
-            OwnedPath::from(TICKETER),
-        ),
-        // 3. Set `jstz` injector as the `jstz_node` account
-        OwnedConfigInstruction::set_instr(
-            OwnedBytes(bincode::encode_to_vec(
-                injector_pk,
-                bincode::config::legacy(),
-            )?),
-            OwnedPath::from(INJECTOR),
-        ),
-    ]);
-    let installer = installer::with_config_program(installer_program);
-    Ok(hex::encode(&installer))
+    let root_hash_hex = hex::encode(root_hash.as_ref());
+    let installer = installer::make_installer(root_hash, injector_pk, vec![])?;
+    Ok((installer, root_hash_hex))
 }
 
 /// Generates Rust code for path getters to access files in OUT_DIR
@@ -171,8 +139,14 @@ fn make_kernel_installer(
 /// - kernel_installer_path(): Path to the kernel installer hex file
 /// - parameters_ty_path(): Path to the parameters type JSON file
 /// - preimages_path(): Path to the preimages directory
-fn generate_code(out_dir: &Path) {
-    let mut code = String::new();
+///
+/// and the `KERNEL_ROOT_HASH` constant.
+fn generate_code(out_dir: &Path, kernel_root_hash: &str) {
+    let mut code = format!(
+        r#"
+        pub const KERNEL_ROOT_HASH: &str = "{kernel_root_hash}";
+        "#
+    );
     code.push_str(&generate_path_getter_code(
         out_dir,
         "kernel_installer",
@@ -10,6 +10,7 @@ use octez::r#async::node_config::{OctezNodeHistoryMode, OctezNodeRunOptionsBuild
 use rust_embed::Embed;
 use tempfile::NamedTempFile;
 
+use crate::genesis::Genesis;
 use crate::task::jstzd::JstzdConfig;
 use crate::user_config::UserJstzNodeConfig;
 #[cfg(feature = "oracle")]
@@ -27,6 +28,7 @@ use octez::r#async::protocol::{
 use octez::r#async::{
     baker::{BakerBinaryPath, OctezBakerConfig, OctezBakerConfigBuilder},
     client::{OctezClientConfig, OctezClientConfigBuilder},
+    directory::Directory,
     file::FileWrapper,
     node_config::{OctezNodeConfig, OctezNodeConfigBuilder},
     protocol::{BootstrapAccount, ProtocolParameterBuilder},
@@ -76,6 +78,8 @@ pub struct Config {
     oracle_node: UserOracleNodeConfig,
     #[serde(default)]
     protocol: ProtocolParameterBuilder,
+    #[serde(default)]
+    genesis: Genesis,
 }
 
 async fn parse_config(path: &str) -> Result<Config> {
@@ -118,7 +122,13 @@ pub(crate) async fn build_config_from_path(
     config_path: &Option<String>,
 ) -> Result<(u16, JstzdConfig)> {
     let config = match config_path {
-        Some(p) => parse_config(p).await?,
+        Some(p) => {
+            let mut config = parse_config(p).await?;
+            if let Some(dir) = Path::new(p).parent() {
+                config.genesis.resolve_paths(dir);
+            }
+            config
+        }
         None => Config::default(),
     };
     build_config(config).await
@@ -133,7 +143,15 @@ pub async fn build_config(mut config: Config) -> Result<(u16, JstzdConfig)> {
         None => OctezClientConfigBuilder::new(octez_node_config.rpc_endpoint.clone()),
     }
     .build()?;
-    let protocol_params = build_protocol_params(config.protocol).await?;
+    let injector = find_injector_account(builtin_bootstrap_accounts()?)
+        .context("failed to retrieve injector account")?;
+    let genesis_dir = Directory::default();
+    let (kernel_installer_path, preimages_dir) = config
+        .genesis
+        .make_kernel_installer(&injector.0, &PathBuf::from(&genesis_dir))
+        .context("failed to build genesis")?;
+    let protocol_params =
+        build_protocol_params(config.protocol, &kernel_installer_path).await?;
     let baker_config = populate_baker_config(
         config.octez_baker,
         &octez_node_config,
@@ -163,13 +181,13 @@ pub async fn build_config(mut config: Config) -> Result<(u16, JstzdConfig)> {
             rollup_builder.set_operator(ROLLUP_OPERATOR_ACCOUNT_ALIAS.to_string());
     }
     if !rollup_builder.has_boot_sector_file() {
-        rollup_builder = rollup_builder
-            .set_boot_sector_file(jstz_rollup_path::kernel_installer_path());
+        rollup_builder =
+            rollup_builder.set_boot_sector_file(kernel_installer_path.clone());
     }
 
     let octez_rollup_config = rollup_builder
         .set_data_dir(RollupDataDir::TempWithPreImages {
-            preimages_dir: jstz_rollup_path::preimages_path(),
+            preimages_dir: preimages_dir.clone(),
         })
         .set_kernel_debug_file(kernel_debug_file)
         .build()
@@ -179,6 +197,7 @@ pub async fn build_config(mut config: Config) -> Result<(u16, JstzdConfig)> {
     let jstz_node_config = build_jstz_node_config(
         config.jstz_node,
         &octez_rollup_config.rpc_endpoint,
+        &preimages_dir,
         &kernel_debug_file_path,
     )
     .context("failed to build jstz node config")?;
@@ -207,13 +226,15 @@ pub async fn build_config(mut config: Config) -> Result<(u16, JstzdConfig)> {
                 false => Some(jstz_node_config),
             },
             protocol_params,
-        ),
+        )
+        .with_genesis_dir(genesis_dir),
     ))
 }
 
 fn build_jstz_node_config(
     config: UserJstzNodeConfig,
     rollup_rpc_endpoint: &Endpoint,
+    preimages_dir: &Path,
     kernel_debug_file_path: &Path,
 ) -> Result<JstzNodeConfig> {
     let jstz_node_rpc_endpoint =
@@ -236,7 +257,7 @@ fn build_jstz_node_config(
     Ok(JstzNodeConfig::new(
         &jstz_node_rpc_endpoint,
         rollup_rpc_endpoint,
-        &jstz_rollup_path::preimages_path(),
+        preimages_dir,
         kernel_debug_file_path,
         injector.clone(),
         run_mode_builder.build()?,
@@ -365,6 +386,7 @@ async fn read_bootstrap_contracts() -> Result<Vec<BootstrapContract>> {
 
 async fn build_protocol_params(
     mut builder: ProtocolParameterBuilder,
+    kernel_installer_path: &Path,
 ) -> Result<ProtocolParameter> {
     // User contracts whose addresses collide with those reserved for jstz contracts
     // will overwrite jstz contracts. This aligns with the current implementation
@@ -400,7 +422,7 @@ async fn build_protocol_params(
         .set_bootstrap_smart_rollups([BootstrapSmartRollup::new(
             JSTZ_ROLLUP_ADDRESS,
             SmartRollupPvmKind::Wasm,
-            &tokio::fs::read_to_string(jstz_rollup_path::kernel_installer_path()).await?,
+            &tokio::fs::read_to_string(kernel_installer_path).await?,
             serde_json::from_slice(
                 &BootstrapRollupFile::get("parameters_ty.json")
                     .ok_or(anyhow::anyhow!("file not found"))?
@@ -868,6 +890,45 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn build_config_with_genesis() {
+        let config = serde_json::from_value::<Config>(serde_json::json!({
+            "genesis": {
+                "accounts": {
+                    "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx": { "balance": 1000 }
+                }
+            }
+        }))
+        .unwrap();
+        let (_, config) = super::build_config(config).await.unwrap();
+
+        let boot_sector_file = &config.octez_rollup_config().boot_sector_file;
+        assert_ne!(boot_sector_file, &jstz_rollup_path::kernel_installer_path());
+        let installer = std::fs::read_to_string(boot_sector_file).unwrap();
+        assert!(
+            installer.len()
+                > std::fs::read_to_string(jstz_rollup_path::kernel_installer_path())
+                    .unwrap()
+                    .len()
+        );
+
+        let RollupDataDir::TempWithPreImages { preimages_dir } =
+            &config.octez_rollup_config().data_dir
+        else {
+            panic!("unexpected rollup data dir");
+        };
+        assert_eq!(
+            &config.jstz_node_config().unwrap().rollup_preimages_dir,
+            preimages_dir
+        );
+        assert_eq!(
+            std::fs::read_dir(preimages_dir).unwrap().count(),
+            std::fs::read_dir(jstz_rollup_path::preimages_path())
+                .unwrap()
+                .count()
+        );
+    }
+
     #[tokio::test]
     async fn build_config_skip_nodes() {
         let mut tmp_file = NamedTempFile::new().unwrap();
@@ -902,9 +963,13 @@ mod tests {
             storage_sync: false,
             skipped: false,
         };
-        let jstz_node_config =
-            super::build_jstz_node_config(config, &Endpoint::default(), &PathBuf::new())
-                .unwrap();
+        let jstz_node_config = super::build_jstz_node_config(
+            config,
+            &Endpoint::default(),
+            &jstz_rollup_path::preimages_path(),
+            &PathBuf::new(),
+        )
+        .unwrap();
         assert_eq!(
             jstz_node_config.mode,
             RunMode::Sequencer {
@@ -924,6 +989,7 @@ mod tests {
         assert!(super::build_jstz_node_config(
             bad_config,
             &Endpoint::default(),
+            &jstz_rollup_path::preimages_path(),
             &PathBuf::new(),
         )
         .is_err());
@@ -1024,7 +1090,12 @@ mod tests {
             40_000_000_000,
         )
         .unwrap()]);
-        let params = super::build_protocol_params(builder).await.unwrap();
+        let params = super::build_protocol_params(
+            builder,
+            &jstz_rollup_path::kernel_installer_path(),
+        )
+        .await
+        .unwrap();
         let mut addresses = read_bootstrap_contracts_from_param_file(
             &params.parameter_file().path().to_path_buf(),
         )
@@ -1055,7 +1126,12 @@ mod tests {
             )
             .unwrap()])
             .set_bootstrap_contracts([dummy_contract.clone()]);
-        let params = super::build_protocol_params(builder).await.unwrap();
+        let params = super::build_protocol_params(
+            builder,
+            &jstz_rollup_path::kernel_installer_path(),
+        )
+        .await
+        .unwrap();
         let mut contracts = read_bootstrap_contracts_from_param_file(
             &params.parameter_file().path().to_path_buf(),
         )
@@ -0,0 +1,524 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use jstz_core::{
+    kv::{storage_update::StorageUpdate, Transaction},
+    BinEncodable,
+};
+use jstz_crypto::{public_key::PublicKey, public_key_hash::PublicKeyHash};
+use jstz_proto::{
+    context::{
+        account::{Account, Address},
+        protocol_params::{ProtocolParams, VersionedProtocolParams},
+        ticket_table::TicketTable,
+    },
+    executor::l1_call::{L1Caller, L1_CALLERS_PATH},
+    runtime::{Kv, KvValue, ParsedCode, RuntimeVersion},
+    storage::PROTOCOL_PARAMS_PATH,
+};
+use serde::Deserialize;
+use tezos_crypto_rs::hash::ContractKt1Hash;
+use tezos_smart_rollup::{
+    dac::PreimageHash,
+    michelson::{
+        ticket::{FA2_1Ticket, TicketHash},
+        MichelsonBytes, MichelsonNat, MichelsonOption, MichelsonPair,
+    },
+    storage::path::OwnedPath,
+    types::Contract,
+};
+use tezos_smart_rollup_installer::preimages;
+use tezos_smart_rollup_installer_config::binary::owned::{
+    OwnedBytes, OwnedConfigInstruction,
+};
+use tezos_smart_rollup_mock::MockHost;
+
+use crate::{installer::make_installer, jstz_rollup_path};
+
+/// Values larger than this are revealed from preimages by the kernel installer
+/// rather than embedded in it, which keeps the boot sector small.
+const MAX_INLINE_VALUE_SIZE: usize = 1024;
+
+/// Jstz state written into the durable storage of the rollup by the kernel
+/// installer, so that the sandbox starts with funded accounts and deployed smart
+/// functions.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Genesis {
+    /// User accounts, by address.
+    #[serde(default)]
+    pub accounts: BTreeMap<String, GenesisAccount>,
+    #[serde(default)]
+    pub smart_functions: Vec<GenesisSmartFunction>,
+    #[serde(default)]
+    pub tickets: Vec<GenesisTicket>,
+    /// Smart functions allowed to call L1 contracts, with the entrypoints they
+    /// may call.
+    #[serde(default)]
+    pub l1_callers: Vec<L1Caller>,
+    /// Protocol parameters, which default to the parameters of the kernel.
+    #[serde(default)]
+    pub params: Option<ProtocolParams>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisAccount {
+    /// Balance in mutez.
+    #[serde(default)]
+    pub balance: u64,
+}
+
+/// A smart function deployed at the address given by `jstz account address --predict`
+/// for `deployer`, `salt` and `code`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisSmartFunction {
+    /// Path to the function code, or the code itself.
+    pub code: String,
+    /// Address of the account that deploys the smart function.
+    pub deployer: String,
+    pub salt: String,
+    /// Runtime the smart function runs on, which must be supported by the kernel.
+    pub runtime_version: RuntimeVersion,
+    /// Balance in mutez.
+    #[serde(default)]
+    pub balance: u64,
+    /// Initial entries of the key-value store of the smart function.
+    #[serde(default)]
+    pub kv: BTreeMap<String, serde_json::Value>,
+}
+
+/// Balance of an FA2.1 ticket, identified by its ticketer, token id and content.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisTicket {
+    /// Address of the account or smart function owning the tickets.
+    pub owner: String,
+    pub ticketer: String,
+    #[serde(default)]
+    pub token_id: u32,
+    /// Hex-encoded content of the ticket.
+    pub content: Option<String>,
+    pub amount: u64,
+}
+
+impl GenesisTicket {
+    fn hash(&self) -> Result<TicketHash> {
+        let content = self
+            .content
+            .as_ref()
+            .map(hex::decode)
+            .transpose()
+            .context("ticket content must be hex-encoded")?;
+        let ticket = FA2_1Ticket::new(
+            Contract::Originated(ContractKt1Hash::from_base58_check(&self.ticketer)?),
+            MichelsonPair(
+                MichelsonNat::from(self.token_id),
+                MichelsonOption(content.map(MichelsonBytes)),
+            ),
+            self.amount,
+        )?;
+        Ok(ticket.hash()?)
+    }
+}
+
+impl Genesis {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+            && self.smart_functions.is_empty()
+            && self.tickets.is_empty()
+            && self.l1_callers.is_empty()
+            && self.params.is_none()
+    }
+
+    /// Resolves the relative paths to smart function code against `base`, the
+    /// directory of the configuration file.
+    pub(crate) fn resolve_paths(&mut self, base: &Path) {
+        for function in &mut self.smart_functions {
+            let path = base.join(&function.code);
+            if Path::new(&function.code).is_relative() && path.is_file() {
+                function.code = path.to_string_lossy().to_string();
+            }
+        }
+    }
+
+    /// Returns the values written to the durable storage, by path. They are
+    /// computed by the protocol against an empty storage, so they are encoded
+    /// exactly as the kernel expects them.
+    fn storage(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut host = MockHost::default();
+        let mut tx = Transaction::default();
+        tx.begin();
+        let params = self.params.clone().unwrap_or_default();
+
+        for (address, account) in &self.accounts {
+            let address = PublicKeyHash::from_base58(address)
+                .with_context(|| format!("invalid account address '{address}'"))?;
+            Account::add_balance(&host, &mut tx, &address, account.balance)?;
+        }
+
+        for function in &self.smart_functions {
+            let deployer =
+                Address::from_base58(&function.deployer).with_context(|| {
+                    format!("invalid deployer address '{}'", function.deployer)
+                })?;
+            let code = match Path::new(&function.code).is_file() {
+                true => fs::read_to_string(&function.code).with_context(|| {
+                    format!("failed to read smart function code '{}'", function.code)
+                })?,
+                false => function.code.clone(),
+            };
+            let code = ParsedCode::try_from(code).with_context(|| {
+                format!("invalid code of the smart function '{}'", function.salt)
+            })?;
+            let address = Account::create_salted_smart_function(
+                &host,
+                &mut tx,
+                &deployer,
+                function.balance,
+                code,
+                &function.salt,
+            )?;
+            Account::set_runtime_version(
+                &host,
+                &mut tx,
+                &address,
+                function.runtime_version,
+            )?;
+            let kv = Kv::new(address.to_string());
+            for (key, value) in &function.kv {
+                let value = KvValue::Json(value.clone());
+                // Initial entries are accounted like entries set by the smart function
+                Account::update_storage_usage(
+                    &host,
+                    &mut tx,
+                    &address,
+                    Kv::entry_size(key, &value) as i64,
+                    params.storage_quota,
+                    params.storage_deposit_per_byte,
+                )
+                .with_context(|| {
+                    format!(
+                        "failed to store the kv of the smart function '{}'",
+                        function.salt
+                    )
+                })?;
+                kv.set(&mut tx, key, value)?;
+            }
+        }
+
+        for ticket in &self.tickets {
+            let owner = Address::from_base58(&ticket.owner)
+                .with_context(|| format!("invalid ticket owner '{}'", ticket.owner))?;
+            TicketTable::add(&mut host, &mut tx, &owner, &ticket.hash()?, ticket.amount)?;
+        }
+
+        let mut storage: Vec<_> = tx
+            .storage_updates()?
+            .into_iter()
+            .filter_map(|update| match update {
+                StorageUpdate::Insert { key, value } => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+        if !self.l1_callers.is_empty() {
+            storage.push((L1_CALLERS_PATH.to_string(), self.l1_callers.encode()?));
+        }
+        if let Some(params) = &self.params {
+            let params = VersionedProtocolParams::from(params.clone());
+            storage.push((PROTOCOL_PARAMS_PATH.to_string(), params.encode()?));
+        }
+        Ok(storage)
+    }
+
+    /// Returns the installer instructions writing the genesis state. Large values
+    /// are saved as preimages in `preimages_dir`.
+    fn instructions(&self, preimages_dir: &Path) -> Result<Vec<OwnedConfigInstruction>> {
+        self.storage()?
+            .into_iter()
+            .map(|(path, value)| {
+                let to = OwnedPath::try_from(path)?;
+                Ok(if value.len() <= MAX_INLINE_VALUE_SIZE {
+                    OwnedConfigInstruction::set_instr(OwnedBytes(value), to)
+                } else {
+                    let root_hash =
+                        preimages::content_to_preimages(value, preimages_dir)?;
+                    OwnedConfigInstruction::reveal_instr(root_hash, to)
+                })
+            })
+            .collect()
+    }
+
+    /// Builds the kernel installer writing the genesis state in `dir` and returns
+    /// the path of the installer file and of the preimages directory of the rollup.
+    /// Without genesis state, these are the built-in kernel installer and preimages.
+    pub(crate) fn make_kernel_installer(
+        &self,
+        injector: &PublicKey,
+        dir: &Path,
+    ) -> Result<(PathBuf, PathBuf)> {
+        if self.is_empty() {
+            return Ok((
+                jstz_rollup_path::kernel_installer_path(),
+                jstz_rollup_path::preimages_path(),
+            ));
+        }
+
+        let preimages_dir = dir.join("preimages");
+        fs::create_dir_all(&preimages_dir)?;
+        for entry in fs::read_dir(jstz_rollup_path::preimages_path())? {
+            let entry = entry?;
+            fs::copy(entry.path(), preimages_dir.join(entry.file_name()))?;
+        }
+
+        let root_hash: [u8; 33] = hex::decode(jstz_rollup_path::KERNEL_ROOT_HASH)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("invalid kernel root hash"))?;
+        let installer = make_installer(
+            PreimageHash::from(&root_hash),
+            injector.clone(),
+            self.instructions(&preimages_dir)?,
+        )?;
+
+        let path = dir.join("kernel_installer.hex");
+        fs::write(&path, installer)?;
+        Ok((path, preimages_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use jstz_crypto::smart_function_hash::SmartFunctionHash;
+    use tempfile::NamedTempFile;
+    use tezos_smart_rollup::host::Runtime;
+
+    use super::*;
+
+    const DEPLOYER: &str = "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx";
+    const CODE: &str = "export default () => new Response('hello');";
+
+    fn genesis() -> Genesis {
+        serde_json::from_value(serde_json::json!({
+            "accounts": { DEPLOYER: { "balance": 1000 } },
+            "smart_functions": [{
+                "code": CODE,
+                "deployer": DEPLOYER,
+                "salt": "hello",
+                "runtime_version": "V1",
+                "balance": 20,
+                "kv": { "count": 1 }
+            }],
+            "tickets": [{
+                "owner": DEPLOYER,
+                "ticketer": "KT1F3MuqvT9Yz57TgCS3EkDcKNZe9HpiavUJ",
+                "content": "cafe",
+                "amount": 5
+            }],
+            "l1_callers": [{
+                "address": "KT1RycYvM4EVs6BAXWEsGXaAaRqiMP53KT4w",
+                "destinations": [{
+                    "address": "KT1QgfSE4C1dX9UqrPAXjUaFQ36F9eB4nNkV",
+                    "entrypoint": "default",
+                    "parameter_type": { "prim": "pair", "args": [{ "prim": "int" }, { "prim": "string" }] }
+                }]
+            }],
+            "params": {
+                "heap_limit": 1024,
+                "storage_quota": 4096,
+                "storage_deposit_per_byte": 1,
+                "max_reveal_size": 1048576
+            }
+        }))
+        .unwrap()
+    }
+
+    fn commit(genesis: &Genesis) -> MockHost {
+        let mut host = MockHost::default();
+        for (path, value) in genesis.storage().unwrap() {
+            host.store_write_all(&OwnedPath::try_from(path).unwrap(), &value)
+                .unwrap();
+        }
+        host
+    }
+
+    #[test]
+    fn deserialize_genesis() {
+        assert!(Genesis::default().is_empty());
+        assert!(!genesis().is_empty());
+        assert!(serde_json::from_value::<Genesis>(serde_json::json!({
+            "accounts": { DEPLOYER: { "amount": 1 } }
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn genesis_storage() {
+        let genesis = genesis();
+        let mut host = commit(&genesis);
+        let mut tx = Transaction::default();
+        tx.begin();
+
+        let deployer = PublicKeyHash::from_base58(DEPLOYER).unwrap();
+        assert_eq!(Account::balance(&host, &mut tx, &deployer).unwrap(), 1000);
+
+        let address: SmartFunctionHash =
+            Account::salted_address(&deployer, "hello", CODE).unwrap();
+        assert_eq!(
+            Account::runtime_version(&host, &tx, &address).unwrap(),
+            RuntimeVersion::V1
+        );
+        // The deposit for the kv entry is taken from the balance of the smart function
+        let kv_size = Kv::entry_size("count", &KvValue::Json(serde_json::json!(1)));
+        assert_eq!(
+            Account::balance(&host, &mut tx, &address).unwrap(),
+            20 - kv_size
+        );
+        assert_eq!(
+            Account::storage_usage(&host, &tx, &address).unwrap().bytes,
+            kv_size
+        );
+        assert_eq!(
+            Kv::new(address.to_string())
+                .size(&host, &mut tx, "count")
+                .unwrap(),
+            kv_size
+        );
+        assert_eq!(
+            &*Account::function_code(&host, &mut tx, &address).unwrap(),
+            CODE
+        );
+        assert_eq!(
+            Kv::new(address.to_string())
+                .get(&host, &mut tx, "count")
+                .unwrap()
+                .unwrap()
+                .clone(),
+            KvValue::Json(serde_json::json!(1))
+        );
+
+        assert_eq!(
+            TicketTable::get_balance(
+                &mut host,
+                &mut tx,
+                &deployer,
+                &genesis.tickets[0].hash().unwrap()
+            )
+            .unwrap(),
+            5
+        );
+
+        assert_eq!(
+            jstz_proto::executor::l1_call::l1_callers(&host).unwrap(),
+            genesis.l1_callers
+        );
+
+        assert_eq!(
+            ProtocolParams::load(&host).unwrap(),
+            ProtocolParams {
+                heap_limit: 1024,
+                storage_quota: 4096,
+                storage_deposit_per_byte: 1,
+                max_reveal_size: 1024 * 1024,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn genesis_code_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(CODE.as_bytes()).unwrap();
+        let mut genesis = genesis();
+        genesis.smart_functions[0].code = file.path().to_str().unwrap().to_string();
+        let host = commit(&genesis);
+
+        let address = Account::salted_address(
+            &PublicKeyHash::from_base58(DEPLOYER).unwrap(),
+            "hello",
+            CODE,
+        )
+        .unwrap();
+        let mut tx = Transaction::default();
+        tx.begin();
+        assert_eq!(
+            &*Account::function_code(&host, &mut tx, &address).unwrap(),
+            CODE
+        );
+    }
+
+    #[test]
+    fn genesis_code_relative_to_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("counter.js"), CODE).unwrap();
+        let mut genesis = genesis();
+        genesis.smart_functions[0].code = "counter.js".to_string();
+        genesis.smart_functions.push(GenesisSmartFunction {
+            salt: "inline".to_string(),
+            ..genesis.smart_functions[0].clone()
+        });
+        genesis.smart_functions[1].code = CODE.to_string();
+
+        genesis.resolve_paths(dir.path());
+        assert_eq!(
+            Path::new(&genesis.smart_functions[0].code),
+            dir.path().join("counter.js")
+        );
+        assert_eq!(genesis.smart_functions[1].code, CODE);
+    }
+
+    #[test]
+    fn genesis_kv_exceeding_quota() {
+        let mut genesis = genesis();
+        genesis.params = Some(ProtocolParams {
+            storage_quota: 1,
+            ..Default::default()
+        });
+        assert!(genesis
+            .storage()
+            .unwrap_err()
+            .to_string()
+            .contains("failed to store the kv of the smart function 'hello'"));
+    }
+
+    #[test]
+    fn genesis_instructions_reveal_large_values() {
+        let mut genesis = Genesis::default();
+        genesis.smart_functions.push(GenesisSmartFunction {
+            code: format!("{CODE}//{}", "a".repeat(MAX_INLINE_VALUE_SIZE)),
+            deployer: DEPLOYER.to_string(),
+            salt: "large".to_string(),
+            runtime_version: RuntimeVersion::V1,
+            balance: 0,
+            kv: BTreeMap::new(),
+        });
+        let preimages_dir = tempfile::tempdir().unwrap();
+        let instructions = genesis.instructions(preimages_dir.path()).unwrap();
+
+        assert!(instructions
+            .iter()
+            .any(|instr| matches!(instr, OwnedConfigInstruction::Reveal(_))));
+        assert!(fs::read_dir(preimages_dir.path()).unwrap().next().is_some());
+    }
+
+    #[test]
+    fn invalid_genesis() {
+        let mut genesis = genesis();
+        genesis.smart_functions[0].deployer = "foo".to_string();
+        assert!(genesis
+            .storage()
+            .unwrap_err()
+            .to_string()
+            .contains("invalid deployer address 'foo'"));
+
+        let mut genesis = self::genesis();
+        genesis.tickets[0].content = Some("not hex".to_string());
+        assert!(genesis.storage().is_err());
+    }
+}
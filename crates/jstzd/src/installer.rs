@@ -0,0 +1,53 @@
+// Config program of the jstz kernel installer. This module is shared with the build
+// script, which builds the default kernel installer.
+
+use anyhow::Result;
+use jstz_crypto::{public_key::PublicKey, smart_function_hash::SmartFunctionHash};
+use jstz_kernel::{INJECTOR, TICKETER};
+use tezos_crypto_rs::hash::ContractKt1Hash;
+use tezos_smart_rollup::{dac::PreimageHash, storage::path::OwnedPath};
+use tezos_smart_rollup_installer::{installer, KERNEL_BOOT_PATH, PREPARE_KERNEL_PATH};
+use tezos_smart_rollup_installer_config::binary::owned::{
+    OwnedBytes, OwnedConfigInstruction, OwnedConfigProgram,
+};
+
+/// Builds the hex-encoded installer of the kernel whose preimages have the root hash
+/// `kernel_root_hash`. The installer runs `instructions` after setting up the kernel.
+pub fn make_installer(
+    kernel_root_hash: PreimageHash,
+    injector_pk: PublicKey,
+    instructions: Vec<OwnedConfigInstruction>,
+) -> Result<String> {
+    let mut program = vec![
+        // 1. Prepare kernel installer
+        OwnedConfigInstruction::reveal_instr(
+            kernel_root_hash,
+            OwnedPath::from(PREPARE_KERNEL_PATH),
+        ),
+        OwnedConfigInstruction::move_instr(
+            OwnedPath::from(PREPARE_KERNEL_PATH),
+            OwnedPath::from(KERNEL_BOOT_PATH),
+        ),
+        // 2. Set `jstz` ticketer as the bridge contract address
+        OwnedConfigInstruction::set_instr(
+            OwnedBytes(bincode::encode_to_vec(
+                SmartFunctionHash(
+                    ContractKt1Hash::from_base58_check(crate::EXCHANGER_ADDRESS)?.into(),
+                ),
+                bincode::config::legacy(),
+            )?),
+            OwnedPath::from(TICKETER),
+        ),
+        // 3. Set `jstz` injector as the `jstz_node` account
+        OwnedConfigInstruction::set_instr(
+            OwnedBytes(bincode::encode_to_vec(
+                injector_pk,
+                bincode::config::legacy(),
+            )?),
+            OwnedPath::from(INJECTOR),
+        ),
+    ];
+    program.extend(instructions);
+    let installer = installer::with_config_program(OwnedConfigProgram(program));
+    Ok(hex::encode(&installer))
+}
@@ -1,5 +1,7 @@
 mod config;
 pub mod docker;
+mod genesis;
+mod installer;
 pub mod task;
 mod user_config;
 
@@ -1,9 +1,18 @@
+use anyhow::bail;
 use async_dropper_simple::{AsyncDrop, AsyncDropper};
 use async_trait::async_trait;
+use nix::{
+    sys::signal::{self, Signal::SIGTERM},
+    unistd::Pid,
+};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::process::Child;
 use tokio::sync::RwLock;
 
+/// How long a child process may take to exit after SIGTERM.
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub type Shared<T> = Arc<RwLock<T>>;
 
 pub type SharedChildWrapper = Shared<AsyncDropper<ChildWrapper>>;
@@ -28,6 +37,25 @@ impl ChildWrapper {
         }
         Ok(())
     }
+    /// Sends SIGTERM to the child process and waits for it to exit, so that it can
+    /// flush its data to disk. The process is killed if it is still running after
+    /// `timeout`.
+    pub async fn terminate(&mut self, timeout: Duration) -> anyhow::Result<()> {
+        let Some(mut child) = self.inner.take() else {
+            return Ok(());
+        };
+        // The id is only missing if the process has already exited
+        let Some(pid) = child.id() else {
+            return Ok(());
+        };
+        signal::kill(Pid::from_raw(pid as i32), SIGTERM)?;
+        if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+            child.kill().await?;
+            bail!("process {pid} did not exit within {timeout:?} of SIGTERM");
+        }
+        Ok(())
+    }
+
     /// Check if the child process is running
     /// mutable borrow because the process id could be reaped if it exited
     pub async fn is_running(&mut self) -> bool {
@@ -59,4 +87,24 @@ mod test {
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
         assert!(!wrapper.write().await.is_running().await);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn terminate_waits_for_exit() {
+        let child = tokio::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let mut wrapper = ChildWrapper::new(child);
+        wrapper.terminate(Duration::from_secs(5)).await.unwrap();
+        assert!(!wrapper.is_running().await);
+
+        // A process ignoring SIGTERM is killed
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 10"])
+            .spawn()
+            .unwrap();
+        let mut wrapper = ChildWrapper::new(child);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(wrapper.terminate(Duration::from_millis(200)).await.is_err());
+    }
 }
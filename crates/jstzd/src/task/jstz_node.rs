@@ -21,6 +21,8 @@ impl Task for JstzNode {
 
     async fn kill(&mut self) -> Result<()> {
         self.handle.abort();
+        // Wait for the node to shut down so that its databases are closed
+        let _ = (&mut self.handle).await;
         Ok(())
     }
 
@@ -11,7 +11,7 @@ use super::{
     octez_baker::OctezBaker,
     octez_node::OctezNode,
     octez_rollup::OctezRollup,
-    utils::{get_block_level, retry},
+    utils::{copy_dir, get_block_level, retry},
     Task,
 };
 use anyhow::{bail, Context, Result};
@@ -31,17 +31,21 @@ use jstz_oracle_node::OracleNodeConfig;
 use octez::r#async::{
     baker::OctezBakerConfig,
     client::{Address, OctezClient, OctezClientConfig},
+    directory::Directory,
     endpoint::Endpoint,
     node_config::OctezNodeConfig,
     protocol::{BootstrapAccount, ProtocolParameter},
-    rollup::OctezRollupConfig,
+    rollup::{OctezRollupConfig, RollupDataDir},
 };
 use prettytable::{format::consts::FORMAT_DEFAULT, Cell, Row, Table};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::{
     collections::HashMap,
+    fs,
     io::{stdout, Write},
+    os::unix::fs::symlink,
+    path::PathBuf,
 };
 use tokio::{
     net::TcpListener,
@@ -50,6 +54,13 @@ use tokio::{
     time::{sleep, Duration},
 };
 
+// Names of the state directories of the sandbox, in the state directory of the
+// server and in snapshots
+const OCTEZ_NODE_DIR: &str = "octez-node";
+const OCTEZ_ROLLUP_DIR: &str = "octez-rollup";
+const OCTEZ_CLIENT_DIR: &str = "octez-client";
+const JSTZ_NODE_DIR: &str = "jstz-node";
+
 trait IntoShared {
     fn into_shared(self) -> Shared<Self>;
 }
@@ -86,6 +97,10 @@ pub struct JstzdConfig {
     oracle_node_config: Option<OracleNodeConfig>,
     #[serde(skip_serializing)]
     protocol_params: ProtocolParameter,
+    // holds the TempDir instance so that the kernel installer and preimages built
+    // for the genesis state are not deleted while the sandbox runs
+    #[serde(skip_serializing)]
+    _genesis_dir: Option<Arc<Directory>>,
 }
 
 impl JstzdConfig {
@@ -107,9 +122,17 @@ impl JstzdConfig {
             #[cfg(feature = "oracle")]
             oracle_node_config,
             protocol_params,
+            _genesis_dir: None,
         }
     }
 
+    /// Keeps `dir`, holding the kernel installer and preimages of the rollup, for
+    /// as long as the config is used.
+    pub(crate) fn with_genesis_dir(mut self, dir: Directory) -> Self {
+        self._genesis_dir = Some(Arc::new(dir));
+        self
+    }
+
     pub fn octez_node_config(&self) -> &OctezNodeConfig {
         &self.octez_node_config
     }
@@ -149,18 +172,25 @@ impl Task for Jstzd {
         let octez_client = OctezClient::new(config.octez_client_config.clone());
         Self::wait_for_node(&octez_node).await?;
 
-        Self::import_accounts(
-            &octez_client,
-            HashMap::from_iter(
-                // cannot use config.protocol_params().bootstrap_accounts() here because
-                // we need secret keys
-                builtin_bootstrap_accounts()?
-                    .into_iter()
-                    .map(|(alias, _, sk, _)| (alias, sk)),
-            ),
-        )
-        .await?;
-        Self::activate_protocol(&octez_client, &config.protocol_params).await?;
+        // A node resuming a chain from its data directory (e.g. a restored snapshot)
+        // already has the protocol activated, and the client already knows the accounts
+        let resumed = get_block_level(&config.octez_node_config.rpc_endpoint.to_string())
+            .await?
+            > 0;
+        if !resumed {
+            Self::import_accounts(
+                &octez_client,
+                HashMap::from_iter(
+                    // cannot use config.protocol_params().bootstrap_accounts() here because
+                    // we need secret keys
+                    builtin_bootstrap_accounts()?
+                        .into_iter()
+                        .map(|(alias, _, sk, _)| (alias, sk)),
+                ),
+            )
+            .await?;
+            Self::activate_protocol(&octez_client, &config.protocol_params).await?;
+        }
         let baker = OctezBaker::spawn(config.baker_config.clone()).await?;
         Self::wait_for_block_level(&config.octez_node_config.rpc_endpoint, 3).await?;
         let rollup = OctezRollup::spawn(config.octez_rollup_config.clone()).await?;
@@ -223,6 +253,37 @@ impl Task for Jstzd {
 }
 
 impl Jstzd {
+    /// Stops jstzd gracefully, waiting for every process to exit so that their
+    /// data directories are consistent. The processes are stopped one after
+    /// another, starting with those depending on the others.
+    async fn stop(&mut self) -> Result<()> {
+        if let Some(n) = self.jstz_node.take() {
+            n.write().await.kill().await?;
+        }
+        #[cfg(feature = "oracle")]
+        if let Some(n) = self.oracle_node.take() {
+            n.write().await.kill().await?;
+        }
+        self.baker
+            .write()
+            .await
+            .stop()
+            .await
+            .context("failed to stop the baker")?;
+        self.rollup
+            .write()
+            .await
+            .stop()
+            .await
+            .context("failed to stop the rollup node")?;
+        self.octez_node
+            .write()
+            .await
+            .stop()
+            .await
+            .context("failed to stop the octez node")
+    }
+
     async fn health_check_inner(&self) -> (Result<bool>, Vec<Result<bool>>) {
         let mut healthy = true;
         let mut err = vec![];
@@ -339,6 +400,8 @@ pub struct JstzdServerInner {
 
 #[derive(Default)]
 struct ServerState {
+    // holds the TempDir instance so that the data of the processes survives restarts
+    state_dir: Option<Directory>,
     jstzd_config: Option<JstzdConfig>,
     jstzd_config_json: serde_json::Map<String, serde_json::Value>,
     jstzd: Option<Jstzd>,
@@ -371,6 +434,7 @@ impl JstzdServer {
                         .as_object()
                         .unwrap()
                         .to_owned(),
+                    state_dir: None,
                     jstzd_config: Some(config),
                     jstzd: None,
                     server_handle: None,
@@ -398,22 +462,22 @@ impl JstzdServer {
     }
 
     pub async fn run(&mut self, print_info: bool) -> Result<()> {
-        let jstzd = Self::spawn_jstzd(
-            self.inner
-                .state
-                .read()
-                .await
-                .jstzd_config
-                .as_ref()
-                .ok_or(anyhow::anyhow!(
-                    // shouldn't really reach this branch since jstzd config is required at instantiation
-                    // unless someone calls `run` after calling `stop`
-                    "cannot run jstzd server without jstzd config"
-                ))?
-                .clone(),
-            print_info,
-        )
-        .await?;
+        let config = {
+            let mut state = self.inner.state.write().await;
+            let state_dir = Directory::default();
+            let config = state.jstzd_config.as_mut().ok_or(anyhow::anyhow!(
+                // shouldn't really reach this branch since jstzd config is required at instantiation
+                // unless someone calls `run` after calling `stop`
+                "cannot run jstzd server without jstzd config"
+            ))?;
+            // `/config` keeps serving the user config since these directories are
+            // internal to jstzd
+            use_persistent_dirs(config, &PathBuf::from(&state_dir))?;
+            let config = config.clone();
+            state.state_dir.replace(state_dir);
+            config
+        };
+        let jstzd = Self::spawn_jstzd(config, print_info).await?;
         self.inner.state.write().await.jstzd.replace(jstzd);
 
         let router = Router::new()
@@ -422,7 +486,13 @@ impl JstzdServer {
             .route("/config/:config_type", get(config_handler))
             .route("/config/", get(all_config_handler))
             .route("/contract_call", post(call_contract_handler))
+            .route(
+                "/execute_outbox_message",
+                post(execute_outbox_message_handler),
+            )
             .route("/l1_alias/:alias", get(l1_alias_handler))
+            .route("/snapshot/save", post(save_snapshot_handler))
+            .route("/snapshot/load", post(load_snapshot_handler))
             .with_state(self.inner.state.clone());
         let listener = TcpListener::bind(("0.0.0.0", self.port)).await?;
 
@@ -499,6 +569,63 @@ impl JstzdServer {
     }
 }
 
+/// Points the processes without a configured data directory to directories in
+/// `state_dir`, so that their data survives restarts and can be snapshotted.
+fn use_persistent_dirs(
+    config: &mut JstzdConfig,
+    state_dir: &std::path::Path,
+) -> Result<()> {
+    if config.octez_node_config.data_dir.is_none() {
+        let dir = state_dir.join(OCTEZ_NODE_DIR);
+        fs::create_dir_all(&dir)?;
+        config.octez_node_config.data_dir = Some(dir);
+    }
+
+    let rollup_config = &mut config.octez_rollup_config;
+    if !matches!(rollup_config.data_dir, RollupDataDir::Path { .. }) {
+        let dir = state_dir.join(OCTEZ_ROLLUP_DIR);
+        fs::create_dir_all(&dir)?;
+        if let RollupDataDir::TempWithPreImages { preimages_dir } =
+            &rollup_config.data_dir
+        {
+            symlink(preimages_dir, dir.join(rollup_config.pvm_kind.to_string()))?;
+        }
+        rollup_config.data_dir = RollupDataDir::Path { data_dir: dir };
+    }
+
+    if let Some(jstz_node_config) = &mut config.jstz_node_config {
+        if jstz_node_config.data_dir.is_none() {
+            let dir = state_dir.join(JSTZ_NODE_DIR);
+            fs::create_dir_all(&dir)?;
+            jstz_node_config.data_dir = Some(dir);
+        }
+    }
+    Ok(())
+}
+
+/// Directories holding the state of the sandbox, by the name of their copy in
+/// snapshots.
+fn snapshot_dirs(config: &JstzdConfig) -> Vec<(&'static str, PathBuf)> {
+    let mut dirs = vec![(
+        OCTEZ_CLIENT_DIR,
+        PathBuf::from(config.octez_client_config.base_dir()),
+    )];
+    if let Some(dir) = &config.octez_node_config.data_dir {
+        dirs.push((OCTEZ_NODE_DIR, dir.clone()));
+    }
+    if let RollupDataDir::Path { data_dir } = &config.octez_rollup_config.data_dir {
+        dirs.push((OCTEZ_ROLLUP_DIR, data_dir.clone()));
+    }
+    if let Some(dir) = config
+        .jstz_node_config
+        .as_ref()
+        .and_then(|c| c.data_dir.as_ref())
+    {
+        dirs.push((JSTZ_NODE_DIR, dir.clone()));
+    }
+    dirs
+}
+
 fn collect_progress(individual_results: Vec<Result<bool>>) -> u64 {
     individual_results
         .into_iter()
@@ -689,6 +816,96 @@ fn handle_show_address_response(res: Result<Address>) -> impl IntoResponse {
     }
 }
 
+#[derive(Deserialize)]
+struct SnapshotRequest {
+    path: PathBuf,
+}
+
+async fn save_snapshot_handler(
+    state: State<Shared<ServerState>>,
+    Json(payload): Json<SnapshotRequest>,
+) -> impl IntoResponse {
+    let mut lock = state.write().await;
+    match snapshot(&mut lock, |dir, name| {
+        copy_dir(dir, &payload.path.join(name))
+    })
+    .await
+    {
+        Ok(_) => http::StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            (http::StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+async fn load_snapshot_handler(
+    state: State<Shared<ServerState>>,
+    Json(payload): Json<SnapshotRequest>,
+) -> impl IntoResponse {
+    let mut lock = state.write().await;
+    if let Some(config) = &lock.jstzd_config {
+        if let Some((name, _)) = snapshot_dirs(config)
+            .into_iter()
+            .find(|(name, _)| !payload.path.join(name).is_dir())
+        {
+            return (
+                http::StatusCode::BAD_REQUEST,
+                format!("snapshot is missing '{name}'"),
+            )
+                .into_response();
+        }
+    }
+    match snapshot(&mut lock, |dir, name| {
+        copy_dir(&payload.path.join(name), dir)
+    })
+    .await
+    {
+        Ok(_) => http::StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            (http::StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+/// Stops jstzd, calls `f` on each of its state directories and restarts jstzd
+/// from the resulting state.
+async fn snapshot(
+    state: &mut ServerState,
+    f: impl Fn(&std::path::Path, &str) -> Result<()>,
+) -> Result<()> {
+    let config = state
+        .jstzd_config
+        .clone()
+        .ok_or(anyhow::anyhow!("jstzd is not running"))?;
+    // The state is only copied once every process has exited cleanly
+    let result = match state.jstzd.take() {
+        Some(mut jstzd) => jstzd.stop().await.context("failed to stop jstzd"),
+        None => Ok(()),
+    }
+    .and_then(|_| {
+        snapshot_dirs(&config)
+            .into_iter()
+            .try_for_each(|(name, dir)| {
+                f(&dir, name).with_context(|| format!("failed to copy '{name}'"))
+            })
+    });
+    match JstzdServer::spawn_jstzd(config, false).await {
+        Ok(jstzd) => {
+            state.jstzd.replace(jstzd);
+            result
+        }
+        Err(e) => {
+            // The sandbox stays down, so report it on the server console as well
+            eprintln!("failed to restart jstzd: {e:?}");
+            let e = e.context("failed to restart jstzd, the sandbox is stopped");
+            Err(match result {
+                Ok(_) => e,
+                Err(snapshot_err) => anyhow::anyhow!("{snapshot_err:#}; {e:#}"),
+            })
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct TransferRequest {
     from: String,
@@ -720,6 +937,38 @@ async fn call_contract_handler(
     }
 }
 
+#[derive(Deserialize)]
+struct ExecuteOutboxMessageRequest {
+    commitment: String,
+    proof: String,
+    burn_cap: Option<f64>,
+}
+
+// Any account can execute an outbox message, so the sandbox uses a funded bootstrap
+// account instead of requiring the key of the message's receiver
+const OUTBOX_EXECUTOR_ALIAS: &str = "bootstrap1";
+
+async fn execute_outbox_message_handler(
+    state: State<Shared<ServerState>>,
+    Json(payload): Json<ExecuteOutboxMessageRequest>,
+) -> http::StatusCode {
+    let lock = state.read().await;
+    let config = lock.jstzd_config.as_ref().unwrap();
+    match OctezClient::new(config.octez_client_config().clone())
+        .execute_rollup_outbox_message(
+            &config.octez_rollup_config().address,
+            OUTBOX_EXECUTOR_ALIAS,
+            &payload.commitment,
+            &format!("0x{}", payload.proof),
+            Some(payload.burn_cap.unwrap_or(100f64)),
+        )
+        .await
+    {
+        Ok(_) => http::StatusCode::OK,
+        _ => http::StatusCode::BAD_REQUEST,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use axum::{body::to_bytes, response::IntoResponse};
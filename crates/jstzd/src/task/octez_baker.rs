@@ -1,5 +1,5 @@
 use super::{
-    child_wrapper::{ChildWrapper, SharedChildWrapper},
+    child_wrapper::{ChildWrapper, SharedChildWrapper, STOP_TIMEOUT},
     Task,
 };
 use anyhow::Result;
@@ -11,6 +11,14 @@ pub struct OctezBaker {
     inner: SharedChildWrapper,
 }
 
+impl OctezBaker {
+    /// Stops the baker gracefully, waiting for it to exit.
+    pub async fn stop(&mut self) -> Result<()> {
+        let mut lock = self.inner.write().await;
+        lock.terminate(STOP_TIMEOUT).await
+    }
+}
+
 #[async_trait]
 impl Task for OctezBaker {
     type Config = OctezBakerConfig;
@@ -1,4 +1,4 @@
-use super::child_wrapper::{ChildWrapper, SharedChildWrapper};
+use super::child_wrapper::{ChildWrapper, SharedChildWrapper, STOP_TIMEOUT};
 use super::Task;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -28,6 +28,13 @@ impl OctezNode {
     pub fn data_dir(&self) -> PathBuf {
         self._data_dir.as_ref().into()
     }
+
+    /// Stops the node gracefully, waiting for it to exit so that its data
+    /// directory is consistent.
+    pub async fn stop(&mut self) -> Result<()> {
+        let mut inner = self.inner.write().await;
+        inner.inner_mut().terminate(STOP_TIMEOUT).await
+    }
 }
 
 #[async_trait]
@@ -50,25 +57,32 @@ impl Task for OctezNode {
             log_file: log_file.clone(),
         };
 
-        let status = node.generate_identity().await?.wait().await?;
-        match status.code() {
-            Some(0) => (),
-            _ => return Err(anyhow::anyhow!("failed to generate node identity")),
+        // A data directory from a previous run (e.g. a restored snapshot) already
+        // holds the node identity and config
+        let data_dir_path: PathBuf = (&data_dir).into();
+        if !data_dir_path.join("identity.json").exists() {
+            let status = node.generate_identity().await?.wait().await?;
+            match status.code() {
+                Some(0) => (),
+                _ => return Err(anyhow::anyhow!("failed to generate node identity")),
+            }
         }
 
-        let status = node
-            .config_init(
-                &config.network,
-                &config.rpc_endpoint,
-                &config.p2p_address,
-                0,
-            )
-            .await?
-            .wait()
-            .await?;
-        match status.code() {
-            Some(0) => (),
-            _ => return Err(anyhow::anyhow!("failed to initialize node config")),
+        if !data_dir_path.join("config.json").exists() {
+            let status = node
+                .config_init(
+                    &config.network,
+                    &config.rpc_endpoint,
+                    &config.p2p_address,
+                    0,
+                )
+                .await?
+                .wait()
+                .await?;
+            match status.code() {
+                Some(0) => (),
+                _ => return Err(anyhow::anyhow!("failed to initialize node config")),
+            }
         }
 
         Ok(OctezNode {
@@ -6,7 +6,10 @@ use std::{
 
 use crate::task::child_wrapper::ChildWrapper;
 
-use super::{child_wrapper::SharedChildWrapper, Task};
+use super::{
+    child_wrapper::{SharedChildWrapper, STOP_TIMEOUT},
+    Task,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use octez::r#async::{
@@ -28,6 +31,13 @@ impl OctezRollup {
     pub fn rpc_endpoint(&self) -> &Endpoint {
         &self.config.rpc_endpoint
     }
+
+    /// Stops the rollup node gracefully, waiting for it to exit so that its data
+    /// directory is consistent.
+    pub async fn stop(&mut self) -> Result<()> {
+        let mut inner = self.inner.write().await;
+        inner.inner_mut().terminate(STOP_TIMEOUT).await
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Result};
 use jstz_utils::poll;
 use serde_json::Value;
+use std::{fs, os::unix::fs::symlink, path::Path};
 
 pub async fn retry<'a, F>(
     retries: u16,
@@ -35,6 +36,28 @@ pub async fn get_block_level(rpc_endpoint: &str) -> Result<i64> {
         .ok_or_else(|| anyhow!("Level is not a valid i64"))
 }
 
+/// Replaces the contents of `to` with a copy of `from`. Symlinks are copied as
+/// symlinks.
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        fs::remove_dir_all(to)?;
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_symlink() {
+            symlink(fs::read_link(entry.path())?, target)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -67,4 +90,26 @@ mod tests {
             !super::retry(2, 1, || async { check(locked.clone(), true).await }).await
         );
     }
+
+    #[test]
+    fn copy_dir() {
+        let from = tempfile::tempdir().unwrap();
+        std::fs::create_dir(from.path().join("nested")).unwrap();
+        std::fs::write(from.path().join("nested/file"), "foo").unwrap();
+        std::os::unix::fs::symlink("/preimages", from.path().join("link")).unwrap();
+
+        let to = tempfile::tempdir().unwrap();
+        std::fs::write(to.path().join("stale"), "bar").unwrap();
+
+        super::copy_dir(from.path(), to.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(to.path().join("nested/file")).unwrap(),
+            "foo"
+        );
+        assert_eq!(
+            std::fs::read_link(to.path().join("link")).unwrap(),
+            std::path::PathBuf::from("/preimages")
+        );
+        assert!(!to.path().join("stale").exists());
+    }
 }
@@ -1,11 +1,14 @@
 use std::error::Error;
 
-use jstz_core::{host::WriteDebug, BinEncodable};
+use jstz_core::{
+    host::WriteDebug, kv::outbox::set_outbox_level, micheline::Micheline, BinEncodable,
+};
 use jstz_crypto::hash::Hash;
 use jstz_crypto::public_key_hash::PublicKeyHash;
+use jstz_crypto::smart_function_hash::SmartFunctionHash;
 use jstz_proto::context::account::Address;
 use jstz_proto::operation::{
-    internal::{Deposit, InboxId},
+    internal::{Deposit, InboxId, L1Message},
     InternalOperation, SignedOperation,
 };
 use num_traits::ToPrimitive;
@@ -42,7 +45,21 @@ pub type MichelsonFaDeposit = MichelsonPair<
     MichelsonPair<MichelsonOption<MichelsonContract>, FA2_1Ticket>,
 >;
 
-pub type RollupType = MichelsonOr<MichelsonNativeDeposit, MichelsonFaDeposit>;
+/// Message from an L1 contract to a smart function. The payload is expected
+/// to be a packed Michelson value
+pub type MichelsonL1Message = MichelsonPair<MichelsonContract, MichelsonBytes>;
+
+/// Parameter type of the rollup. Deposits are sent to the `%default` entrypoint,
+/// whose type is unchanged since bridge contracts were deployed, and L1 messages
+/// to the `%call` entrypoint
+pub type RollupType = MichelsonOr<
+    MichelsonOr<MichelsonNativeDeposit, MichelsonFaDeposit>,
+    MichelsonL1Message,
+>;
+
+/// Parameter type of rollups originated before L1 messages were supported.
+/// Transfers to these rollups only carry deposits
+pub type LegacyRollupType = MichelsonOr<MichelsonNativeDeposit, MichelsonFaDeposit>;
 
 // tag + 20 byte address
 const EXTERNAL_FRAME_SIZE: usize = 21;
@@ -58,6 +75,10 @@ pub fn read_message(
     ticketer: &ContractKt1Hash,
 ) -> Option<ParsedInboxMessageWrapper> {
     let input = rt.read_input().ok()??;
+    // Outbox messages written while processing this input are assigned ids at its level
+    if let Err(e) = set_outbox_level(rt, input.level) {
+        debug_msg!(rt, "Failed to set outbox level: {e:?}\n");
+    }
     let jstz_rollup_address = rt.reveal_metadata().address();
     let inbox_id = InboxId {
         l1_level: input.level,
@@ -101,7 +122,10 @@ pub fn parse_inbox_message(
     ticketer: &ContractKt1Hash,
     jstz_rollup_address: &SmartRollupHash,
 ) -> Option<ParsedInboxMessageWrapper> {
-    let (_, message) = InboxMessage::<RollupType>::parse(inbox_msg).ok()?;
+    let message = match InboxMessage::<RollupType>::parse(inbox_msg) {
+        Ok((_, message)) => message,
+        Err(_) => parse_legacy_transfer(inbox_msg)?,
+    };
 
     let content = match message {
         InboxMessage::Internal(InternalInboxMessage::StartOfLevel) => {
@@ -170,6 +194,23 @@ pub fn parse_inbox_message(
     Some(ParsedInboxMessageWrapper { inbox_id, content })
 }
 
+/// Parses a transfer to a rollup originated with the [LegacyRollupType]. Its
+/// deposits are read like deposits sent to the `%default` entrypoint of the
+/// [RollupType].
+fn parse_legacy_transfer(inbox_msg: &[u8]) -> Option<InboxMessage<'_, RollupType>> {
+    match InboxMessage::<LegacyRollupType>::parse(inbox_msg).ok()?.1 {
+        InboxMessage::Internal(InternalInboxMessage::Transfer(transfer)) => Some(
+            InboxMessage::Internal(InternalInboxMessage::Transfer(Transfer {
+                payload: MichelsonOr::Left(transfer.payload),
+                sender: transfer.sender,
+                source: transfer.source,
+                destination: transfer.destination,
+            })),
+        ),
+        _ => None,
+    }
+}
+
 fn is_valid_native_deposit(
     logger: &impl WriteDebug,
     ticket: &FA2_1Ticket,
@@ -214,7 +255,7 @@ fn read_transfer(
         }
     };
     match transfer.payload {
-        MichelsonOr::Left(tez_ticket) => {
+        MichelsonOr::Left(MichelsonOr::Left(tez_ticket)) => {
             let ticket = tez_ticket.1;
 
             if is_valid_native_deposit(logger, &ticket, ticketer) {
@@ -233,7 +274,7 @@ fn read_transfer(
                 None
             }
         }
-        MichelsonOr::Right(fa_ticket) => {
+        MichelsonOr::Left(MichelsonOr::Right(fa_ticket)) => {
             let ticket = fa_ticket.1 .1;
             let receiver = fa_ticket.0;
             let proxy = fa_ticket.1 .0 .0;
@@ -241,6 +282,34 @@ fn read_transfer(
                 try_parse_fa_deposit(inbox_id, ticket, source, receiver, proxy).ok()?;
             Some(Message::Internal(InternalMessage::FaDeposit(fa_deposit)))
         }
+        MichelsonOr::Right(MichelsonPair(receiver, payload)) => {
+            let receiver = match SmartFunctionHash::from_base58(&receiver.0.to_b58check())
+            {
+                Ok(receiver) => receiver,
+                Err(_) => {
+                    logger.write_debug(
+                        "L1 message ignored because the receiver is not a smart function\n",
+                    );
+                    return None;
+                }
+            };
+            let payload = match Micheline::unpack(&payload.0).and_then(|p| p.to_json()) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    logger.write_debug(&format!("L1 message ignored: {e}\n"));
+                    return None;
+                }
+            };
+            let content = L1Message {
+                inbox_id,
+                sender: transfer.sender,
+                source,
+                receiver,
+                payload,
+            };
+            logger.write_debug(format!("L1 message: {content:?}\n").as_str());
+            Some(Message::Internal(InternalMessage::L1Message(content)))
+        }
     }
 }
 
@@ -303,7 +372,10 @@ mod test {
     };
     use jstz_mock::{
         host::JstzMockHost,
-        message::{fa_deposit::MockFaDeposit, native_deposit::MockNativeDeposit},
+        message::{
+            fa_deposit::MockFaDeposit, l1_message::MockL1Message,
+            native_deposit::MockNativeDeposit,
+        },
     };
     use jstz_proto::{
         context::account::{Address, Addressable, Nonce},
@@ -313,11 +385,15 @@ mod test {
         },
     };
     use tezos_crypto_rs::hash::{ContractKt1Hash, HashTrait};
-    use tezos_smart_rollup::types::SmartRollupAddress;
+    use tezos_smart_rollup::{
+        michelson::MichelsonOr,
+        types::{Contract, SmartRollupAddress},
+    };
+    use tezos_smart_rollup_mock::TransferMetadata;
 
     use crate::inbox::ParsedInboxMessage;
 
-    use super::{read_message, InternalMessage, Message};
+    use super::{read_message, InternalMessage, Message, RollupType};
 
     struct DummyLogger;
     impl WriteDebug for DummyLogger {
@@ -369,6 +445,57 @@ mod test {
         }
     }
 
+    #[test]
+    fn read_message_legacy_native_deposit_succeeds() {
+        let mut host = JstzMockHost::new(true);
+        let deposit = MockNativeDeposit::default();
+        let ticketer = host.get_ticketer();
+        // Deposits to rollups originated with the legacy parameter type are not
+        // wrapped in the `%default` entrypoint
+        let MichelsonOr::Left(payload) = RollupType::from(&deposit) else {
+            panic!("Expected deposit payload")
+        };
+        host.add_transfer(
+            payload,
+            &TransferMetadata::new(deposit.sender.clone(), deposit.source.clone()),
+        );
+        let message = read_message(host.rt(), &ticketer)
+            .expect("Expected message but non received");
+        if let ParsedInboxMessage::JstzMessage(Message::Internal(
+            InternalMessage::Deposit(internal::Deposit {
+                amount, receiver, ..
+            }),
+        )) = message.content
+        {
+            assert_eq!(amount, 100);
+            assert_eq!(receiver.to_base58(), deposit.receiver.to_b58check());
+        } else {
+            panic!("Expected deposit message")
+        }
+    }
+
+    #[test]
+    fn read_message_legacy_fa_deposit_succeeds() {
+        let mut host = JstzMockHost::new(true);
+        let fa_deposit = MockFaDeposit::default();
+        let ticketer = host.get_ticketer();
+        let MichelsonOr::Left(payload) = RollupType::from(&fa_deposit) else {
+            panic!("Expected deposit payload")
+        };
+        host.add_transfer(
+            payload,
+            &TransferMetadata::new(fa_deposit.sender.clone(), fa_deposit.source.clone()),
+        );
+        let message = read_message(host.rt(), &ticketer)
+            .expect("Expected message but non received");
+        assert!(matches!(
+            message.content,
+            ParsedInboxMessage::JstzMessage(Message::Internal(
+                InternalMessage::FaDeposit(_)
+            ))
+        ));
+    }
+
     #[test]
     fn read_message_native_deposit_ignored_different_ticketer() {
         let mut host = JstzMockHost::new(true);
@@ -449,6 +576,70 @@ mod test {
         }
     }
 
+    #[test]
+    fn read_message_l1_message_succeeds() {
+        let mut host = JstzMockHost::new(true);
+        let l1_message = MockL1Message::default();
+        let ticketer = host.get_ticketer();
+        host.add_internal_message(&l1_message);
+
+        if let ParsedInboxMessage::JstzMessage(Message::Internal(
+            InternalMessage::L1Message(internal::L1Message {
+                sender,
+                source,
+                receiver,
+                payload,
+                ..
+            }),
+        )) = read_message(host.rt(), &ticketer)
+            .expect("Expected L1 message")
+            .content
+        {
+            assert_eq!(l1_message.sender, sender);
+            assert_eq!(
+                l1_message.source.to_b58check(),
+                Addressable::to_base58(&source)
+            );
+            assert_eq!(
+                SmartFunctionHash::from_base58(jstz_mock::host::MOCK_PROXY).unwrap(),
+                receiver
+            );
+            assert_eq!(
+                serde_json::json!({
+                    "prim": "Pair",
+                    "args": [{ "int": "1" }, { "string": "foo" }]
+                }),
+                payload
+            );
+        } else {
+            panic!("Expected L1 message")
+        }
+    }
+
+    #[test]
+    fn read_message_l1_message_ignored_on_invalid_payload() {
+        let mut host = JstzMockHost::new(true);
+        let ticketer = host.get_ticketer();
+        let l1_message = MockL1Message {
+            payload: b"not packed".to_vec(),
+            ..MockL1Message::default()
+        };
+        host.add_internal_message(&l1_message);
+        assert_eq!(read_message(host.rt(), &ticketer), None);
+    }
+
+    #[test]
+    fn read_message_l1_message_ignored_on_user_receiver() {
+        let mut host = JstzMockHost::new(true);
+        let ticketer = host.get_ticketer();
+        let l1_message = MockL1Message {
+            receiver: Contract::from_b58check(jstz_mock::host::MOCK_RECEIVER).unwrap(),
+            ..MockL1Message::default()
+        };
+        host.add_internal_message(&l1_message);
+        assert_eq!(read_message(host.rt(), &ticketer), None);
+    }
+
     #[test]
     fn encode_signed_operation_round_trip() {
         let ticketer_addr =
@@ -233,7 +233,7 @@ mod test {
                 method: http::Method::GET,
                 headers: http::HeaderMap::new(),
                 body: HttpBody::empty(),
-                gas_limit: 0,
+                gas_limit: 550000,
             };
             set_transfer_header(&mut run_fn, 10);
             let op = Operation {
@@ -156,6 +156,43 @@ impl OctezClient {
         ]))
     }
 
+    /// Execute the outbox message of the smart rollup `rollup` using account `source`.
+    /// `proof` is the output proof of the message against the cemented `commitment`.
+    /// `burn_cap` bounds the fees burnt for storage, using octez-client's default if unset.
+    pub fn execute_outbox_message(
+        &self,
+        rollup: &str,
+        source: &str,
+        commitment: &str,
+        proof: &str,
+        burn_cap: Option<&Decimal>,
+    ) -> Result<()> {
+        let mut command = self.command();
+        command.args([
+            "execute",
+            "outbox",
+            "message",
+            "of",
+            "smart",
+            "rollup",
+            rollup,
+            "from",
+            source,
+            "for",
+            "commitment",
+            "hash",
+            commitment,
+            "and",
+            "output",
+            "proof",
+            proof,
+        ]);
+        if let Some(burn_cap) = burn_cap {
+            command.args(["--burn-cap", &burn_cap.to_string()]);
+        }
+        run_command(&mut command)
+    }
+
     /// Run arbitrary RPC command
     pub fn rpc(&self, options: &[&str]) -> Result<String> {
         run_command_with_output(self.command().arg("rpc").args(options))
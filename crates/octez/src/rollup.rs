@@ -98,6 +98,15 @@ enum ValueResponse {
 #[derive(Debug, Deserialize)]
 struct SubkeysResponse(Vec<String>);
 
+/// Proof that an outbox message is part of a cemented commitment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxProof {
+    /// Hash of the cemented commitment
+    pub commitment: String,
+    /// Hex-encoded output proof
+    pub proof: String,
+}
+
 impl OctezRollupClient {
     pub fn new(endpoint: String) -> Self {
         Self {
@@ -187,6 +196,30 @@ impl OctezRollupClient {
         }
     }
 
+    /// Get the proof of the outbox message at `index` in the outbox of `level`.
+    /// Returns `None` if the level has not been cemented yet or if the message
+    /// does not exist. Other errors of the rollup node are returned as is.
+    pub async fn get_outbox_proof(
+        &self,
+        level: u32,
+        index: u32,
+    ) -> Result<Option<OutboxProof>> {
+        let res = self
+            .client
+            .get(format!(
+                "{}/global/block/cemented/helpers/proofs/outbox/{}/messages?index={}",
+                self.endpoint, level, index
+            ))
+            .send()
+            .await?;
+
+        match res.status().as_u16() {
+            200 => Ok(Some(res.json::<OutboxProof>().await?)),
+            404 => Ok(None),
+            status => Err(anyhow!("Unhandled response status: {}", status)),
+        }
+    }
+
     pub async fn get_rollup_address(&self) -> Result<SmartRollupAddress> {
         let res = self
             .client
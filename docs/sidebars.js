@@ -29,6 +29,7 @@ const sidebars = {
       items: [
         "functions/overview",
         "functions/building",
+        "functions/testing",
         "functions/deploying",
         "functions/requests",
         "functions/data_storage",
@@ -186,11 +186,19 @@ declare var console: Console;
 
 declare type Address = string;
 
+declare interface KvReader {
+  get<T = unknown>(key: string): T | null;
+  contains(key: string): boolean;
+}
+
 declare interface Kv {
   get<T = unknown>(key: string): T | null;
   set(key: string, value: unknown): void;
   delete(key: string): void;
   has(key: string): boolean;
+  publish(prefix: string): void;
+  unpublish(prefix: string): void;
+  of(address: Address): KvReader;
 }
 
 declare var Kv: Kv;
@@ -201,6 +209,9 @@ declare interface Ledger {
   readonly selfAddress: Address;
   balance(address: Address): Mutez;
   transfer(address: Address, amount: Mutez): void;
+  withdraw(receiver: Address, amount: Mutez): void;
+  selfDestruct(beneficiary: Address): Mutez;
+  outboxCapacity(): number;
 }
 
 declare var Ledger: Ledger;